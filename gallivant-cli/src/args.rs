@@ -2,6 +2,8 @@ use std::path::PathBuf;
 
 use clap::Parser;
 
+use gallivant::{Encoding, ErrorKind};
+
 ////////////////////////////////////////////////////////////////
 // types
 ////////////////////////////////////////////////////////////////
@@ -18,8 +20,92 @@ pub struct Args {
     #[arg(short, long)]
     pub printer: Option<String>,
 
+    /// Port of the reference device used by REFTEST commands.
+    #[arg(long)]
+    pub reference: Option<String>,
+
+    /// Port to try if the TCU fails to open. May be given more than once to try several in order.
+    #[arg(long)]
+    pub tcu_fallback: Vec<String>,
+
+    /// Port to try if the printer fails to open. May be given more than once.
+    #[arg(long)]
+    pub printer_fallback: Vec<String>,
+
+    /// Port to try if the reference device fails to open. May be given more than once.
+    #[arg(long)]
+    pub reference_fallback: Vec<String>,
+
     #[arg(short, long)]
     pub debug: bool,
+
+    /// Skip any lines of the script that can't be parsed. Not for use in production.
+    #[arg(long)]
+    pub permissive: bool,
+
+    /// Symbol to define, including the script's IFDEF sections for it. May be given more than
+    /// once.
+    #[arg(short = 'D', long, conflicts_with = "permissive")]
+    pub define: Vec<String>,
+
+    /// Record the measurements taken by test commands to a CSV file instead of testing them.
+    #[arg(short, long)]
+    pub record: Option<PathBuf>,
+
+    /// Number of boards to run the script against when recording.
+    #[arg(short, long, default_value_t = 1, requires = "record")]
+    pub boards: u32,
+
+    /// Keep the printer open from one board to the next rather than reopening it for each.
+    #[arg(long, requires = "record")]
+    pub keep_open: bool,
+
+    /// Encoding that printed text is converted to. One of passthrough, latin1, cp1252, cp437 or
+    /// cp850.
+    #[arg(long, default_value_t = Encoding::Passthrough)]
+    pub encoding: Encoding,
+
+    /// Byte, in decimal, sent to the TCU once it's echoed a test command to prompt it to take the
+    /// measurement. For devices that won't sample until polled.
+    #[arg(long)]
+    pub measurement_trigger: Option<u8>,
+
+    /// Number of times a command is re-sent after a comms error, i.e. a timeout or an incorrect
+    /// echo, before the run fails. Separate from a test's retries.
+    #[arg(long, default_value_t = 0)]
+    pub comms_retries: u32,
+
+    /// Write the transactions the script will perform to a JSON file before running it.
+    #[arg(long)]
+    pub plan: Option<PathBuf>,
+
+    /// Log every device opened or closed during the run to a CSV file.
+    #[arg(long)]
+    pub device_log: Option<PathBuf>,
+
+    /// Number of times to retry a board's entire run if it fails with a transient error.
+    #[arg(long, default_value_t = 0)]
+    pub run_retries: u32,
+
+    /// Kinds of error considered transient by --run-retries. e.g. timeout,comms
+    #[arg(long, value_delimiter = ',', default_values_t = [ErrorKind::Timeout, ErrorKind::Comms])]
+    pub transient: Vec<ErrorKind>,
+
+    /// Stop the run when a WAITDIALOG's timeout expires rather than continuing it.
+    #[arg(long)]
+    pub fail_dialog_timeouts: bool,
+
+    /// Run tests annotated as @independent in a random order.
+    #[arg(long)]
+    pub shuffle: bool,
+
+    /// Seed for --shuffle, to reproduce a previous run's order. Random if not given.
+    #[arg(long, requires = "shuffle")]
+    pub seed: Option<u64>,
+
+    /// Directory that artifacts of the run, such as recordings, are written to.
+    #[arg(short, long)]
+    pub output_dir: Option<PathBuf>,
 }
 
 ////////////////////////////////////////////////////////////////
@@ -1,13 +1,21 @@
 use std::{
     io::{ErrorKind, Write},
-    time::Duration,
+    path::Path,
+    sync::{
+        mpsc::{self, Receiver, RecvTimeoutError},
+        Mutex, OnceLock,
+    },
+    time::{Duration, Instant, SystemTime},
 };
 
 use ariadne::{Report, Source};
 use clap::Parser;
 use serialport::{self, SerialPort};
 
-use gallivant::{FrontendRequest, Interpreter, Transaction, TransactionStatus};
+use gallivant::{
+    CrossCheckStatus, Device, DeviceLog, FrontendRequest, Interpreter, Recording, Routing,
+    RunRetry, Session, Shuffle, TimeoutAction, Transaction, TransactionStatus,
+};
 use gallivant_serial::{CommPort, MockTCUPort};
 
 mod args;
@@ -37,42 +45,230 @@ impl From<gallivant::Error> for Error {
 fn main() {
     let args = Args::parse();
 
-    let mut tcu = args.tcu.map(|port| {
+    let mut routing = Routing::builder();
+    if let Some(port) = &args.tcu {
+        routing = routing.route(Device::TCU, port);
+    }
+    if let Some(port) = &args.printer {
+        routing = routing.route(Device::USB, port);
+    }
+    if let Some(port) = &args.reference {
+        routing = routing.route(Device::Reference, port);
+    }
+    for port in &args.tcu_fallback {
+        routing = routing.fallback(Device::TCU, port);
+    }
+    for port in &args.printer_fallback {
+        routing = routing.fallback(Device::USB, port);
+    }
+    for port in &args.reference_fallback {
+        routing = routing.fallback(Device::Reference, port);
+    }
+    let routing = routing.build();
+
+    let open_serial = |port: &str| {
+        serialport::new(port, 9600)
+            .timeout(Duration::from_millis(100))
+            .open()
+    };
+
+    let mut tcu = args.tcu.as_ref().map(|port| {
         if port == "mock" {
             CommPort::Open(Box::new(MockTCUPort::new()))
         } else {
-            CommPort::from(
-                serialport::new(port, 9600)
-                    .timeout(Duration::from_millis(100))
-                    .open()
-                    .expect("Failed to open TCU port"),
-            )
+            CommPort::from(open_port(&routing, Device::TCU, open_serial))
         }
     });
 
-    let mut printer = args.printer.map(|port| {
-        CommPort::from(CommPort::builder(port, 9600).timeout(Duration::from_millis(100)))
-    });
+    let mut printer = args
+        .printer
+        .as_ref()
+        .map(|port| CommPort::from(CommPort::builder(port, 9600)));
+
+    let mut reference = args
+        .reference
+        .as_ref()
+        .map(|_| CommPort::from(open_port(&routing, Device::Reference, open_serial)));
 
     let script = std::fs::read_to_string(&args.script).expect("Failed to read script");
 
-    let run_script = |i| run_script(i, args.debug, &mut tcu, &mut printer);
+    let mut recording = Recording::new();
+    let boards = if args.record.is_some() {
+        args.boards
+    } else {
+        1
+    };
+
+    let mut run_boards = |interpreter: Interpreter| {
+        for board in 0..boards {
+            if board > 0 {
+                wait_for_next_board(board + 1, boards);
+            }
+
+            run_script(
+                interpreter.clone(),
+                args.debug,
+                &mut tcu,
+                &mut printer,
+                &mut reference,
+                &mut recording,
+            )?;
+        }
 
-    match gallivant::Interpreter::try_from_str(&script)
+        Ok(interpreter)
+    };
+
+    let output_dir = |interpreter: Interpreter| match &args.output_dir {
+        Some(directory) => interpreter.with_output_directory(directory),
+        None => Ok(interpreter),
+    };
+
+    let device_log = DeviceLog::new();
+    let session = Session::new();
+
+    let interpreter = if args.permissive {
+        Interpreter::try_from_str_permissive(&script)
+    } else {
+        // Included scripts are found relative to the directory of the script being run.
+        let directory = args.script.parent().unwrap_or(Path::new("")).to_owned();
+        Interpreter::try_from_str_with_includes(&script, args.define.iter().cloned(), |path| {
+            std::fs::read_to_string(directory.join(path)).ok()
+        })
+    };
+
+    let trigger = |interpreter: Interpreter| match args.measurement_trigger {
+        Some(trigger) => interpreter.with_measurement_trigger(trigger),
+        None => interpreter,
+    };
+
+    let session_mode = |interpreter: Interpreter| {
+        if args.keep_open {
+            interpreter.with_session(session.clone())
+        } else {
+            interpreter
+        }
+    };
+
+    let shuffle = |interpreter: Interpreter| {
+        if !args.shuffle {
+            return interpreter;
+        }
+
+        let seed = args.seed.unwrap_or_else(|| {
+            SystemTime::now()
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .map_or(0, |time| time.as_nanos() as u64)
+        });
+
+        println!("SHUFFLE: Independent tests shuffled with seed {seed}");
+        interpreter.with_shuffle(Shuffle::new(seed))
+    };
+
+    let interpreter = match interpreter
+        .map(trigger)
+        .map(shuffle)
+        .map(session_mode)
+        .map(|interpreter| {
+            interpreter
+                .with_record_mode(args.record.is_some())
+                .with_encoding(args.encoding)
+                .with_comms_retries(args.comms_retries)
+                .with_dialog_timeout_action(match args.fail_dialog_timeouts {
+                    true => TimeoutAction::Fail,
+                    false => TimeoutAction::Continue,
+                })
+                .with_routing(routing)
+                .with_script_path(&args.script)
+                .with_device_log(device_log.clone())
+                .with_run_retry(
+                    RunRetry::new(args.run_retries).with_transient(args.transient.clone()),
+                )
+        })
         .map_err(Error::from)
-        .and_then(run_script)
+        .and_then(|interpreter| output_dir(interpreter).map_err(Error::from))
     {
-        Ok(()) => (),
-        Err(Error::ParseErrors(errors)) => {
+        Ok(interpreter) => interpreter,
+        Err(error) => return report(error, &script),
+    };
+
+    if interpreter.is_permissive() {
+        println!("WARNING: PERMISSIVE RUN - unparseable lines are skipped. Results aren't valid.");
+
+        for diagnostic in interpreter.skipped_statements() {
+            let line = script[..diagnostic.span().start].matches('\n').count() + 1;
+            println!("WARNING: Line {line}: {}", diagnostic.message());
+        }
+    }
+
+    if let Some(path) = &args.plan {
+        match interpreter.create_artifact(path) {
+            Ok(file) => interpreter
+                .write_plan_json(file)
+                .expect("Failed to write plan"),
+            Err(error) => return report(error.into(), &script),
+        }
+    }
+
+    let result = run_boards(interpreter.clone()).and_then(|interpreter| match &args.record {
+        Some(path) => {
+            let file = interpreter.create_artifact(path)?;
+            recording
+                .write_csv(&script, file)
+                .expect("Failed to write recording");
+            Ok(())
+        }
+        None => Ok(()),
+    });
+
+    // Devices kept open between boards are closed once every board has run.
+    let routing = interpreter.routing().cloned().unwrap_or_default();
+    for request in session.close_all() {
+        if let Err(error) = handle_request(
+            request,
+            args.debug,
+            &routing,
+            &mut tcu,
+            &mut printer,
+            &mut reference,
+            &mut recording,
+        ) {
+            report(error, &script);
+        }
+    }
+
+    // The device log is written regardless of how the run ended.
+    if let Some(path) = &args.device_log {
+        match interpreter.create_artifact(path) {
+            Ok(file) => device_log
+                .write_csv(&script, file)
+                .expect("Failed to write device log"),
+            Err(error) => report(error.into(), &script),
+        }
+    }
+
+    if let Err(error) = result {
+        report(error, &script);
+    }
+
+    if interpreter.is_permissive() {
+        println!("WARNING: PERMISSIVE RUN - results aren't valid.");
+    }
+}
+
+////////////////////////////////////////////////////////////////
+
+fn report(error: Error, script: &str) {
+    match error {
+        Error::ParseErrors(errors) => {
             for error in errors {
                 Report::from(error)
-                    .eprint(Source::from(&script))
+                    .eprint(Source::from(script))
                     .expect("Failed to create error report");
             }
         }
-        Err(Error::RuntimeError(error)) => {
+        Error::RuntimeError(error) => {
             Report::from(error)
-                .eprint(Source::from(&script))
+                .eprint(Source::from(script))
                 .expect("Failed to create error report");
         }
     }
@@ -81,16 +277,110 @@ fn main() {
 ////////////////////////////////////////////////////////////////
 
 fn run_script(
-    interpreter: Interpreter,
+    mut interpreter: Interpreter,
+    debug: bool,
+    tcu: &mut Option<CommPort>,
+    printer: &mut Option<CommPort>,
+    reference: &mut Option<CommPort>,
+    recording: &mut Recording,
+) -> Result<(), Error> {
+    let routing = interpreter.routing().cloned().unwrap_or_default();
+
+    loop {
+        // Measurements recorded by a failed attempt are discarded.
+        let mut attempt = recording.clone();
+
+        let error = match run_attempt(
+            &mut interpreter,
+            debug,
+            &routing,
+            tcu,
+            printer,
+            reference,
+            &mut attempt,
+        ) {
+            Ok(()) => {
+                *recording = attempt;
+                return Ok(());
+            }
+            Err(Error::RuntimeError(error)) => error,
+            Err(error) => return Err(error),
+        };
+
+        run_cleanup(
+            &mut interpreter,
+            debug,
+            &routing,
+            tcu,
+            printer,
+            reference,
+            recording,
+        );
+
+        let Some(cleanup) = interpreter.retry_run(&error) else {
+            for request in interpreter.close_all() {
+                handle_request(request, debug, &routing, tcu, printer, reference, recording)?;
+            }
+            return Err(error.into());
+        };
+
+        println!(
+            "RETRY:   Run failed ({}). Retrying, attempt {}",
+            error.kind(),
+            interpreter.run_retries() + 1
+        );
+
+        for request in cleanup {
+            handle_request(request, debug, &routing, tcu, printer, reference, recording)?;
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////
+
+fn run_attempt(
+    interpreter: &mut Interpreter,
     debug: bool,
+    routing: &Routing,
     tcu: &mut Option<CommPort>,
     printer: &mut Option<CommPort>,
+    reference: &mut Option<CommPort>,
+    recording: &mut Recording,
 ) -> Result<(), Error> {
-    for current_request in interpreter {
-        let mut current_request = Some(current_request?);
+    while let Some(current_request) = interpreter.next() {
+        let current_request = current_request?;
+
+        if let FrontendRequest::GuiDialogue {
+            kind: gallivant::Dialog::Confirmation,
+            message,
+        } = &current_request
+        {
+            interpreter.confirm(confirm(message));
+            continue;
+        }
+
+        if let FrontendRequest::GuiDialogue {
+            kind: gallivant::Dialog::Input,
+            message,
+        } = &current_request
+        {
+            interpreter.enter_input(input(message));
+            continue;
+        }
+
+        let mut current_request = Some(current_request);
 
         while let Some(request) = current_request {
-            current_request = handle_request(request, debug, tcu, printer)?;
+            let result =
+                handle_request(request, debug, routing, tcu, printer, reference, recording);
+            current_request = match result {
+                Ok(request) => request,
+                Err(Error::RuntimeError(error)) => {
+                    interpreter.recover(error)?;
+                    None
+                }
+                Err(error) => return Err(error),
+            };
         }
     }
 
@@ -99,33 +389,211 @@ fn run_script(
 
 ////////////////////////////////////////////////////////////////
 
+/// Run the script's ONABORT blocks after a failed attempt. Errors are reported without stopping
+/// the rest of the cleanup, or replacing the error that caused it.
+///
+fn run_cleanup(
+    interpreter: &mut Interpreter,
+    debug: bool,
+    routing: &Routing,
+    tcu: &mut Option<CommPort>,
+    printer: &mut Option<CommPort>,
+    reference: &mut Option<CommPort>,
+    recording: &mut Recording,
+) {
+    interpreter.abort();
+
+    while let Some(request) = interpreter.next() {
+        let mut current_request = match request {
+            Ok(request) => Some(request),
+            Err(error) => {
+                println!("ABORT:   Cleanup failed - {error}");
+                continue;
+            }
+        };
+
+        if let Some(FrontendRequest::GuiDialogue {
+            kind: gallivant::Dialog::Input,
+            message,
+        }) = &current_request
+        {
+            interpreter.enter_input(input(message));
+            continue;
+        }
+
+        while let Some(request) = current_request {
+            current_request =
+                match handle_request(request, debug, routing, tcu, printer, reference, recording) {
+                    Ok(request) => request,
+                    Err(Error::RuntimeError(error)) => {
+                        if let Err(error) = interpreter.recover(error) {
+                            println!("ABORT:   Cleanup failed - {error}");
+                        }
+                        None
+                    }
+                    // Only parsing produces these.
+                    Err(Error::ParseErrors(_)) => None,
+                };
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////
+
+fn confirm(message: &str) -> bool {
+    print!("CONFIRM: {message} [y/N] ");
+    std::io::stdout().flush().expect("std out flush error");
+
+    let input = read_input(None).unwrap_or_default();
+    matches!(input.trim(), "y" | "Y" | "yes" | "YES")
+}
+
+////////////////////////////////////////////////////////////////
+
+fn input(message: &str) -> String {
+    print!("INPUT:   {message}: ");
+    std::io::stdout().flush().expect("std out flush error");
+
+    let input = read_input(None).unwrap_or_default();
+    input.trim_end_matches(['\r', '\n']).to_owned()
+}
+
+////////////////////////////////////////////////////////////////
+
+fn wait_for_next_board(board: u32, boards: u32) {
+    println!("DIALOG:  Insert board {board} of {boards} and press enter");
+    read_input(None);
+}
+
+////////////////////////////////////////////////////////////////
+
+/// Read a line of the operator's input. Lines are read by a separate thread so that a read can
+/// time out without the line later entered being lost to it.
+///
+/// # Returns
+/// The line, empty once the input has ended, or None if the timeout expired first.
+///
+fn read_input(timeout: Option<Duration>) -> Option<String> {
+    static INPUT: OnceLock<Mutex<Receiver<String>>> = OnceLock::new();
+
+    let input = INPUT.get_or_init(|| {
+        let (sender, receiver) = mpsc::channel();
+        std::thread::spawn(move || loop {
+            let mut line = String::new();
+            let read = std::io::stdin()
+                .read_line(&mut line)
+                .expect("Dialog input error");
+
+            if read == 0 || sender.send(line).is_err() {
+                break;
+            }
+        });
+        Mutex::new(receiver)
+    });
+
+    let input = input.lock().expect("Dialog input error");
+    match timeout {
+        Some(timeout) => match input.recv_timeout(timeout) {
+            Ok(line) => Some(line),
+            Err(RecvTimeoutError::Timeout) => None,
+            Err(RecvTimeoutError::Disconnected) => Some(String::new()),
+        },
+        None => Some(input.recv().unwrap_or_default()),
+    }
+}
+
+////////////////////////////////////////////////////////////////
+
 fn handle_request(
     request: FrontendRequest,
     debug: bool,
+    routing: &Routing,
     tcu: &mut Option<CommPort>,
     printer: &mut Option<CommPort>,
+    reference: &mut Option<CommPort>,
+    recording: &mut Recording,
 ) -> Result<Option<FrontendRequest>, Error> {
     if debug {
         println!("{request:?}")
     }
 
     match request {
-        FrontendRequest::None => (),
+        FrontendRequest::None | FrontendRequest::Progress { .. } | FrontendRequest::Paused => (),
+        FrontendRequest::Skipped { reason, .. } => println!("SKIPPED: {reason}"),
+        FrontendRequest::Step(description) => println!("STEP:    {description}..."),
+        FrontendRequest::Beep => println!("BEEP:    \x07"),
+        FrontendRequest::Protocol { device, dump } => println!("TX:      {device} - {dump}"),
+        FrontendRequest::Ratio {
+            span,
+            ratio,
+            recorded,
+        } => {
+            let sign = if ratio < 0 { "-" } else { "" };
+            let magnitude = ratio.unsigned_abs();
+            println!(
+                "RATIO:   {sign}{}.{:03}",
+                magnitude / 1000,
+                magnitude % 1000
+            );
+
+            if recorded {
+                recording.add(span, ratio);
+            }
+        }
+        FrontendRequest::Timed {
+            span,
+            name,
+            elapsed,
+            recorded,
+        } => {
+            println!("TIMER:   {name} took {}ms", elapsed.as_millis());
+
+            if recorded {
+                let elapsed = u32::try_from(elapsed.as_millis()).unwrap_or(u32::MAX);
+                recording.add(span, elapsed);
+            }
+        }
+        FrontendRequest::Latency {
+            span,
+            name,
+            latency,
+            samples,
+            recorded,
+        } => {
+            println!(
+                "LATENCY: {name} was {}ms over {samples} responses",
+                latency.as_millis()
+            );
+
+            if recorded {
+                let latency = u32::try_from(latency.as_millis()).unwrap_or(u32::MAX);
+                recording.add(span, latency);
+            }
+        }
         FrontendRequest::Wait(time) => std::thread::sleep(time),
 
         FrontendRequest::GuiPrint(message) => println!("COMMENT: {message}"),
         FrontendRequest::GuiDialogue { kind, message } => match kind {
-            gallivant::Dialog::ManualInput => {
+            gallivant::Dialog::ManualInput { timeout } => {
                 println!("DIALOG:  {message}");
+                let deadline = timeout.map(|timeout| Instant::now() + timeout.duration);
 
                 loop {
                     print!("INPUT:   ");
                     std::io::stdout().flush().expect("std out flush error");
 
-                    let mut input = String::new();
-                    std::io::stdin()
-                        .read_line(&mut input)
-                        .expect("Dialog input error");
+                    let remaining =
+                        deadline.map(|deadline| deadline.saturating_duration_since(Instant::now()));
+                    let Some(input) = read_input(remaining) else {
+                        println!();
+                        match timeout.map(|timeout| timeout.action) {
+                            Some(TimeoutAction::Fail) => panic!("Test cancelled by timeout"),
+                            _ => {
+                                println!("DIALOG:  Timed out, continuing");
+                                break;
+                            }
+                        }
+                    };
 
                     let input = input.trim();
                     if input.starts_with("STOP") || input.starts_with(['S', 's']) {
@@ -141,11 +609,14 @@ fn handle_request(
                 }
             }
             gallivant::Dialog::Notification => println!("DIALOG:  {message}"),
+            gallivant::Dialog::Input | gallivant::Dialog::Confirmation => {
+                unreachable!("Inputs and confirmations are handled by run_script")
+            }
         },
 
         FrontendRequest::TCUTransact(transaction) => {
             if let Some(CommPort::Open(tcu)) = tcu {
-                handle_transaction(transaction, tcu)?;
+                handle_transaction(transaction, tcu, recording)?;
             } else {
                 panic!("TCU port required but none given");
             }
@@ -167,13 +638,16 @@ fn handle_request(
             }
         }
 
-        FrontendRequest::PrinterOpen => {
-            if let Some(port) = printer {
-                port.open().expect("Failed to open printer comm port");
-            } else {
-                panic!("Printer port required but none given");
+        FrontendRequest::PrinterOpen => match printer {
+            Some(CommPort::Open(_)) => (),
+            Some(CommPort::Closed(_)) => {
+                *printer = Some(open_port(routing, Device::USB, |path| {
+                    let mut port = CommPort::from(CommPort::builder(path, 9600));
+                    port.open().map(|_| port)
+                }));
             }
-        }
+            None => panic!("Printer port required but none given"),
+        },
 
         FrontendRequest::PrinterClose => {
             if let Some(port) = printer {
@@ -185,7 +659,7 @@ fn handle_request(
 
         FrontendRequest::PrinterTransact(transaction) => match printer {
             Some(CommPort::Open(port)) => {
-                handle_transaction(transaction, port)?;
+                handle_transaction(transaction, port, recording)?;
             }
 
             Some(CommPort::Closed(_)) => {
@@ -193,6 +667,33 @@ fn handle_request(
             }
             None => panic!("Printer port required but none given"),
         },
+
+        FrontendRequest::Reconfigure { baud } => match printer {
+            Some(port) => port
+                .set_baud_rate(baud)
+                .unwrap_or_else(|_| panic!("Failed to set printer baud rate to {baud}")),
+            None => panic!("Printer port required but none given"),
+        },
+
+        FrontendRequest::FetchAndProgram { url, device } => {
+            panic!("Programming firmware isn't supported. Can't program the {device} from {url}")
+        }
+
+        FrontendRequest::CrossCheck(mut check) => match (tcu, reference) {
+            (Some(CommPort::Open(tcu)), Some(CommPort::Open(reference))) => loop {
+                check = match check.process(tcu, reference)? {
+                    CrossCheckStatus::Success => break,
+                    CrossCheckStatus::Ongoing(check) => check,
+                    CrossCheckStatus::Recorded { span, measurement } => {
+                        recording.add(span, measurement);
+                        break;
+                    }
+                }
+            },
+
+            (None, _) => panic!("TCU port required but none given"),
+            _ => panic!("Reference port required but none given"),
+        },
     }
 
     Ok(None)
@@ -200,15 +701,46 @@ fn handle_request(
 
 ////////////////////////////////////////////////////////////////
 
+/// Open a device on it's assigned port or, failing that, the first of it's fallback ports that
+/// opens. Reports the port used if it isn't the assigned one.
+///
+fn open_port<T>(
+    routing: &Routing,
+    device: Device,
+    open: impl FnMut(&str) -> Result<T, serialport::Error>,
+) -> T {
+    match routing.open_with(device, open) {
+        Ok((port, opened)) => {
+            if let Some(assigned) = routing.get(device).filter(|assigned| *assigned != port) {
+                println!("PORT:    {device} failed to open on {assigned}. Using fallback {port}");
+            }
+            opened
+        }
+        Err(failures) => {
+            for (port, error) in failures {
+                eprintln!("Failed to open {device} on {port}: {error}");
+            }
+            panic!("Failed to open {device} port");
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////
+
 fn handle_transaction(
     mut transaction: Transaction,
     port: &mut Box<dyn SerialPort>,
+    recording: &mut Recording,
 ) -> Result<(), Error> {
     // Send bytes.
     loop {
         transaction = match transaction.process(port)? {
-            TransactionStatus::Success => break,
+            TransactionStatus::Success { .. } => break,
             TransactionStatus::Ongoing(transaction) => transaction,
+            TransactionStatus::Recorded { span, measurement } => {
+                recording.add(span, measurement);
+                break;
+            }
         }
     }
 
@@ -27,6 +27,12 @@ impl MockTCUPort {
     }
 }
 
+impl Default for MockTCUPort {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 ////////////////////////////////////////////////////////////////
 
 fn tcu_decode_byte(bytes: &[u8]) -> u8 {
@@ -0,0 +1,473 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::{
+    diagnostic::{Diagnostic, Severity},
+    execution::{Device, FrontendRequest},
+    syntax::{Annotation, Expr, ExprKind, ParsedExpr},
+};
+
+////////////////////////////////////////////////////////////////
+// checks
+////////////////////////////////////////////////////////////////
+
+/// Check that everything the script opens or starts is later closed or stopped, and that nothing
+/// is closed or stopped without first being opened or started. Blocks are checked as if run once.
+///
+/// # Returns
+/// A warning for each unmatched command, in script order.
+///
+pub(crate) fn balance(ast: &[ParsedExpr]) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    let mut open = BTreeMap::new();
+    check_balance(ast, &mut open, &mut diagnostics);
+
+    for (resource, opened_by) in open {
+        diagnostics.push(Diagnostic::warning(
+            opened_by.span().clone(),
+            format!("{resource} is never {}", resource.closed()),
+        ));
+    }
+
+    diagnostics.sort_by_key(|diagnostic| diagnostic.span().start);
+    diagnostics
+}
+
+////////////////////////////////////////////////////////////////
+
+/// Check for statements that can't be reached because a preceding test can never pass, ending the
+/// run.
+///
+/// # Arguments
+/// * `ast` - Statements of the script.
+/// * `requests` - Requests from evaluating the script. Only test transactions are checked.
+///
+/// # Returns
+/// A single warning spanning every unreachable statement, if any.
+///
+pub(crate) fn unreachable(ast: &[ParsedExpr], requests: &[FrontendRequest]) -> Vec<Diagnostic> {
+    let failing = requests.iter().find_map(|request| match request {
+        FrontendRequest::TCUTransact(transaction)
+        | FrontendRequest::PrinterTransact(transaction) => transaction
+            .test()
+            .is_some_and(|test| !test.expected.is_satisfiable())
+            .then(|| transaction.span().clone()),
+        _ => None,
+    });
+
+    let Some(failing) = failing else {
+        return Vec::new();
+    };
+
+    let mut statements = Vec::new();
+    flatten(ast, &mut statements);
+
+    let unreachable: Vec<&ParsedExpr> = statements
+        .into_iter()
+        .filter(|expr| !matches!(expr.expression(), Expr::ScriptComment(_)))
+        .filter(|expr| expr.span().start >= failing.end)
+        .collect();
+
+    let (Some(first), Some(last)) = (unreachable.first(), unreachable.last()) else {
+        return Vec::new();
+    };
+
+    vec![Diagnostic::warning(
+        first.span().start..last.span().end,
+        "Unreachable as a preceding test can never pass",
+    )]
+}
+
+////////////////////////////////////////////////////////////////
+
+/// Check that no traceability ID is used by more than one statement.
+///
+/// # Arguments
+/// * `ast` - Statements of the script.
+/// * `severity` - Severity of the diagnostics for any duplicates.
+///
+/// # Returns
+/// A diagnostic for every occurrence of each duplicated ID.
+///
+pub(crate) fn duplicate_ids(ast: &[ParsedExpr], severity: Severity) -> Vec<Diagnostic> {
+    let mut ids: BTreeMap<&str, Vec<&ParsedExpr>> = BTreeMap::new();
+    for expr in blocks_and_statements(ast) {
+        for annotation in expr.annotations() {
+            if let Annotation::Id(id) = annotation {
+                ids.entry(id).or_default().push(expr);
+            }
+        }
+    }
+
+    let mut diagnostics: Vec<Diagnostic> = ids
+        .into_iter()
+        .filter(|(_, exprs)| exprs.len() > 1)
+        .flat_map(|(id, exprs)| {
+            let count = exprs.len();
+            exprs.into_iter().map(move |expr| {
+                Diagnostic::new(
+                    severity,
+                    expr.span().clone(),
+                    format!("Traceability ID '{id}' is used by {count} statements"),
+                )
+            })
+        })
+        .collect();
+
+    diagnostics.sort_by_key(|diagnostic| diagnostic.span().start);
+    diagnostics
+}
+
+////////////////////////////////////////////////////////////////
+
+/// Check that commands depending on the state of a device, such as a dialog instructing the
+/// operator once the device is ready, don't run before the device is opened. The USB printer is
+/// opened by USBOPEN and the TCU, along with the printer reached through it, by a TCUOPEN of any
+/// channel. The reference device is opened by
+/// the frontend so is always open. Blocks are checked as if run once.
+///
+/// # Arguments
+/// * `ast` - Statements of the script.
+/// * `dependencies` - Device each state dependent kind of command depends on.
+///
+/// # Returns
+/// A warning for each command that runs while it's device isn't open, in script order.
+///
+pub(crate) fn opened_before(
+    ast: &[ParsedExpr],
+    dependencies: &BTreeMap<ExprKind, Device>,
+) -> Vec<Diagnostic> {
+    let mut statements = Vec::new();
+    flatten(ast, &mut statements);
+
+    let mut usb = false;
+    let mut channels = BTreeSet::new();
+    let mut diagnostics = Vec::new();
+    for expr in statements {
+        match expr.expression() {
+            Expr::USBOpen => usb = true,
+            Expr::USBClose => usb = false,
+            Expr::TCUOpen(channel) => {
+                if let Expr::UInt(channel) = channel.expression() {
+                    channels.insert(*channel);
+                }
+            }
+            Expr::TCUClose(channel) => {
+                if let Expr::UInt(channel) = channel.expression() {
+                    channels.remove(channel);
+                }
+            }
+            _ => (),
+        }
+
+        let Some(device) = dependencies.get(&expr.expression_kind()) else {
+            continue;
+        };
+
+        let open = match device {
+            Device::TCU | Device::Printer => !channels.is_empty(),
+            Device::USB => usb,
+            Device::Reference => true,
+        };
+
+        if !open {
+            diagnostics.push(Diagnostic::warning(
+                expr.span().clone(),
+                format!(
+                    "{} depends on the {device} but runs while it isn't open",
+                    expr.expression_kind().name()
+                ),
+            ));
+        }
+    }
+
+    diagnostics
+}
+
+////////////////////////////////////////////////////////////////
+// helpers
+////////////////////////////////////////////////////////////////
+
+/// Something that must be closed once opened.
+///
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum Resource {
+    Printer,
+    Channel(u32),
+    Timer(String),
+}
+
+impl Resource {
+    fn is_timer(&self) -> bool {
+        matches!(self, Resource::Timer(_))
+    }
+
+    fn closed(&self) -> &'static str {
+        match self {
+            Resource::Printer | Resource::Channel(_) => "closed",
+            Resource::Timer(_) => "stopped",
+        }
+    }
+}
+
+impl std::fmt::Display for Resource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Resource::Printer => write!(f, "The printer"),
+            Resource::Channel(channel) => write!(f, "TCU channel {channel}"),
+            Resource::Timer(name) => write!(f, "Timer '{name}'"),
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////
+
+fn check_balance<'a>(
+    ast: &'a [ParsedExpr],
+    open: &mut BTreeMap<Resource, &'a ParsedExpr>,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    for expr in ast {
+        let (resource, opening) = match expr.expression() {
+            Expr::USBOpen => (Resource::Printer, true),
+            Expr::USBClose => (Resource::Printer, false),
+            Expr::TCUOpen(channel) | Expr::TCUClose(channel) => {
+                let Expr::UInt(channel) = channel.expression() else {
+                    continue;
+                };
+                let opening = matches!(expr.expression(), Expr::TCUOpen(_));
+                (Resource::Channel(*channel), opening)
+            }
+            Expr::StartTimer(name) | Expr::StopTimer { name, .. } => {
+                let Expr::String(name) = name.expression() else {
+                    continue;
+                };
+                let opening = matches!(expr.expression(), Expr::StartTimer(_));
+                (Resource::Timer(name.to_owned()), opening)
+            }
+            Expr::RetryBlock { body, .. }
+            | Expr::RepeatBlock { body, .. }
+            | Expr::BoardBlock { body, .. } => {
+                check_balance(body, open, diagnostics);
+                continue;
+            }
+            // Only one branch is run, so each is checked from the resources open before it. Any
+            // left open by either are open after it.
+            Expr::If {
+                then_body,
+                else_body,
+                ..
+            } => {
+                let mut else_open = open.clone();
+                check_balance(then_body, open, diagnostics);
+                check_balance(else_body, &mut else_open, diagnostics);
+                open.extend(else_open);
+                continue;
+            }
+            _ => continue,
+        };
+
+        if opening {
+            // Restarting a timer is allowed, re-opening a device isn't.
+            if let (Some(_), false) = (open.insert(resource.clone(), expr), resource.is_timer()) {
+                diagnostics.push(Diagnostic::warning(
+                    expr.span().clone(),
+                    format!("{resource} is already open"),
+                ));
+            }
+        } else if open.remove(&resource).is_none() {
+            let action = if resource.is_timer() {
+                "started"
+            } else {
+                "opened"
+            };
+            diagnostics.push(Diagnostic::warning(
+                expr.span().clone(),
+                format!("{resource} is {} without being {action}", resource.closed()),
+            ));
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////
+
+/// Collect every statement, including those within blocks, in script order.
+///
+fn flatten<'a>(ast: &'a [ParsedExpr], statements: &mut Vec<&'a ParsedExpr>) {
+    for expr in ast {
+        match expr.expression() {
+            Expr::RetryBlock { body, .. }
+            | Expr::RepeatBlock { body, .. }
+            | Expr::BoardBlock { body, .. } => flatten(body, statements),
+            Expr::If {
+                then_body,
+                else_body,
+                ..
+            } => {
+                flatten(then_body, statements);
+                flatten(else_body, statements);
+            }
+            _ => statements.push(expr),
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////
+
+/// Collect every statement and block, including those within blocks, in script order.
+///
+fn blocks_and_statements(ast: &[ParsedExpr]) -> Vec<&ParsedExpr> {
+    ast.iter()
+        .flat_map(|expr| {
+            let body = match expr.expression() {
+                Expr::RetryBlock { body, .. }
+                | Expr::RepeatBlock { body, .. }
+                | Expr::BoardBlock { body, .. }
+                | Expr::AbortBlock { body } => blocks_and_statements(body),
+                Expr::If {
+                    then_body,
+                    else_body,
+                    ..
+                } => [then_body, else_body]
+                    .into_iter()
+                    .flat_map(|body| blocks_and_statements(body))
+                    .collect(),
+                _ => Vec::new(),
+            };
+            std::iter::once(expr).chain(body)
+        })
+        .collect()
+}
+
+////////////////////////////////////////////////////////////////
+// tests
+////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::syntax::parse_from_str;
+
+    ////////////////////////////////////////////////////////////////
+
+    fn messages(diagnostics: Vec<Diagnostic>) -> Vec<String> {
+        diagnostics
+            .iter()
+            .map(|diagnostic| diagnostic.message().to_owned())
+            .collect()
+    }
+
+    ////////////////////////////////////////////////////////////////
+
+    #[test]
+    fn test_balanced() {
+        let script = r#"
+USBOPEN
+TCUOPEN 1
+STARTTIMER "boot"
+RETRY 2
+    TCUCLOSE 1
+ENDRETRY
+STOPTIMER "boot", <= 100, "Slow"
+USBCLOSE
+"#;
+        let ast = parse_from_str(script).unwrap();
+        assert!(balance(&ast).is_empty());
+    }
+
+    ////////////////////////////////////////////////////////////////
+
+    #[test]
+    fn test_unbalanced() {
+        let script = r#"
+USBOPEN
+TCUCLOSE 2
+TCUOPEN 1
+TCUOPEN 1
+STOPTIMER "boot", <= 100, "Slow"
+"#;
+        let ast = parse_from_str(script).unwrap();
+        assert_eq!(
+            messages(balance(&ast)),
+            [
+                "The printer is never closed",
+                "TCU channel 2 is closed without being opened",
+                "TCU channel 1 is already open",
+                "TCU channel 1 is never closed",
+                "Timer 'boot' is stopped without being started",
+            ]
+        );
+    }
+
+    ////////////////////////////////////////////////////////////////
+
+    #[test]
+    fn test_duplicate_ids() {
+        let script = r#"
+@id "REQ-1"
+TCUTEST 1, 0, 10, 0, "a"
+@id "REQ-2"
+RETRY 2
+    @id "REQ-1"
+    TCUTEST 2, 0, 10, 0, "b"
+ENDRETRY
+@id "REQ-3"
+TCUTEST 3, 0, 10, 0, "c"
+"#;
+        let ast = parse_from_str(script).unwrap();
+        let diagnostics = duplicate_ids(&ast, Severity::Error);
+
+        assert_eq!(
+            messages(diagnostics.clone()),
+            [
+                "Traceability ID 'REQ-1' is used by 2 statements",
+                "Traceability ID 'REQ-1' is used by 2 statements",
+            ]
+        );
+        assert_eq!(diagnostics[0].span(), ast[0].span());
+        assert!(diagnostics
+            .iter()
+            .all(|diagnostic| diagnostic.severity() == Severity::Error));
+
+        assert!(duplicate_ids(&ast[2..], Severity::Warning).is_empty());
+    }
+
+    ////////////////////////////////////////////////////////////////
+
+    #[test]
+    fn test_opened_before() {
+        let script = r#"
+OPENDIALOG "Check the printer is ready"
+USBOPEN
+OPENDIALOG "Check the printer is ready"
+WAITDIALOG "Powering the board"
+TCUOPEN 1
+RETRY 2
+    WAITDIALOG "Powering the board"
+ENDRETRY
+TCUCLOSE 1
+USBCLOSE
+OPENDIALOG "Check the printer is ready"
+"#;
+        let ast = parse_from_str(script).unwrap();
+        let dependencies = BTreeMap::from([
+            (ExprKind::OpenDialog, Device::USB),
+            (ExprKind::WaitDialog, Device::TCU),
+        ]);
+        let diagnostics = opened_before(&ast, &dependencies);
+
+        assert_eq!(
+            messages(diagnostics.clone()),
+            [
+                "Command: 'OPENDIALOG' depends on the USB printer but runs while it isn't open",
+                "Command: 'WAITDIALOG' depends on the TCU but runs while it isn't open",
+                "Command: 'OPENDIALOG' depends on the USB printer but runs while it isn't open",
+            ]
+        );
+        assert_eq!(diagnostics[0].span(), ast[0].span());
+        assert_eq!(diagnostics[1].span(), ast[3].span());
+
+        assert!(opened_before(&ast, &BTreeMap::new()).is_empty());
+    }
+}
+
+////////////////////////////////////////////////////////////////
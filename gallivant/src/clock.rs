@@ -0,0 +1,32 @@
+use chrono::{Local, NaiveDateTime};
+
+////////////////////////////////////////////////////////////////
+// types
+////////////////////////////////////////////////////////////////
+
+/// Source of the current date and time used while evaluating a script.
+///
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Clock {
+    /// The local time according to the OS.
+    #[default]
+    System,
+
+    /// A pinned time. Primarily intended for use in testing.
+    Fixed(NaiveDateTime),
+}
+
+////////////////////////////////////////////////////////////////
+// methods
+////////////////////////////////////////////////////////////////
+
+impl Clock {
+    pub fn now(&self) -> NaiveDateTime {
+        match self {
+            Clock::System => Local::now().naive_local(),
+            Clock::Fixed(datetime) => *datetime,
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////
@@ -0,0 +1,173 @@
+use std::ops::Range;
+
+use crate::error::Error;
+
+////////////////////////////////////////////////////////////////
+// types
+////////////////////////////////////////////////////////////////
+
+/// A finding about a script that doesn't necessarily prevent it from being run.
+///
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Diagnostic {
+    severity: Severity,
+    message: String,
+    span: Range<usize>,
+}
+
+////////////////////////////////////////////////////////////////
+
+/// Every finding of an analysis of a script, in script order.
+///
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Diagnostics {
+    diagnostics: Vec<Diagnostic>,
+}
+
+////////////////////////////////////////////////////////////////
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    /// The script can't be run as written.
+    Error,
+
+    /// The script can be run but likely won't behave as intended.
+    #[default]
+    Warning,
+}
+
+////////////////////////////////////////////////////////////////
+// construction / conversion
+////////////////////////////////////////////////////////////////
+
+impl Diagnostic {
+    pub fn new(severity: Severity, span: Range<usize>, message: impl Into<String>) -> Self {
+        Self {
+            severity,
+            message: message.into(),
+            span,
+        }
+    }
+
+    pub fn warning(span: Range<usize>, message: impl Into<String>) -> Self {
+        Self::new(Severity::Warning, span, message)
+    }
+}
+
+////////////////////////////////////////////////////////////////
+
+impl From<&Error> for Diagnostic {
+    fn from(error: &Error) -> Self {
+        Self::new(
+            Severity::Error,
+            error.span().unwrap_or_default(),
+            error.reason().message(),
+        )
+    }
+}
+
+////////////////////////////////////////////////////////////////
+
+impl FromIterator<Diagnostic> for Diagnostics {
+    /// Collect diagnostics, ordering them by where they occur in the script.
+    ///
+    fn from_iter<T: IntoIterator<Item = Diagnostic>>(iter: T) -> Self {
+        let mut diagnostics: Vec<Diagnostic> = iter.into_iter().collect();
+        diagnostics.sort_by_key(|diagnostic| (diagnostic.span.start, diagnostic.span.end));
+        Self { diagnostics }
+    }
+}
+
+////////////////////////////////////////////////////////////////
+
+impl IntoIterator for Diagnostics {
+    type Item = Diagnostic;
+    type IntoIter = std::vec::IntoIter<Diagnostic>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.diagnostics.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a Diagnostics {
+    type Item = &'a Diagnostic;
+    type IntoIter = std::slice::Iter<'a, Diagnostic>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.diagnostics.iter()
+    }
+}
+
+////////////////////////////////////////////////////////////////
+// field access
+////////////////////////////////////////////////////////////////
+
+impl Diagnostic {
+    pub fn severity(&self) -> Severity {
+        self.severity
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    pub fn span(&self) -> &Range<usize> {
+        &self.span
+    }
+}
+
+////////////////////////////////////////////////////////////////
+
+impl Diagnostics {
+    pub fn iter(&self) -> std::slice::Iter<'_, Diagnostic> {
+        self.diagnostics.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.diagnostics.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.diagnostics.is_empty()
+    }
+
+    /// Return the diagnostics of the given severity.
+    ///
+    pub fn with_severity(&self, severity: Severity) -> impl Iterator<Item = &Diagnostic> {
+        self.iter()
+            .filter(move |diagnostic| diagnostic.severity == severity)
+    }
+
+    /// Return true if any of the diagnostics are errors. i.e. The script can't be run as written.
+    ///
+    pub fn has_errors(&self) -> bool {
+        self.with_severity(Severity::Error).next().is_some()
+    }
+}
+
+////////////////////////////////////////////////////////////////
+// ...
+////////////////////////////////////////////////////////////////
+
+impl std::fmt::Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Severity::Error => write!(f, "error"),
+            Severity::Warning => write!(f, "warning"),
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////
+
+impl std::fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}: {} ({}..{})",
+            self.severity, self.message, self.span.start, self.span.end
+        )
+    }
+}
+
+////////////////////////////////////////////////////////////////
@@ -1,8 +1,10 @@
+use std::{ops::Range, path::PathBuf, time::Duration};
+
 use ariadne::{Config, Label, Report, ReportKind};
 
 use crate::{
-    execution::FailedTest,
-    syntax::{self, Expr, ParsedExpr},
+    execution::{Device, Encoding, Expected, FailedTest, MeasurementError, Transport},
+    syntax::{self, Expr, ExprKind, Operator, ParsedExpr},
 };
 
 ////////////////////////////////////////////////////////////////
@@ -11,7 +13,7 @@ use crate::{
 
 #[derive(Debug)]
 pub struct Error {
-    reason: ErrorReason,
+    reason: Box<ErrorReason>,
     notes: Vec<ErrorNote>,
 }
 
@@ -28,6 +30,205 @@ pub enum ErrorReason {
         expression: ParsedExpr,
         error: std::io::Error,
     },
+
+    /// A command required a device that the frontend hasn't assigned.
+    Unrouted {
+        expression: ParsedExpr,
+        device: Device,
+    },
+
+    /// A command wrote to a device that the script hasn't opened.
+    DeviceNotOpen {
+        expression: ParsedExpr,
+        device: Device,
+    },
+
+    /// A command opened a device that the script had already opened.
+    DeviceAlreadyOpen {
+        expression: ParsedExpr,
+        device: Device,
+    },
+
+    /// A command opened a TCU relay that the script had already opened.
+    RelayAlreadyOpen {
+        expression: ParsedExpr,
+        relay: u32,
+    },
+
+    /// A command can't be used with the active transport.
+    Unsupported {
+        expression: ParsedExpr,
+        kind: ExprKind,
+        transport: Transport,
+    },
+
+    /// A file referenced by the script couldn't be read.
+    AssetError {
+        expression: ParsedExpr,
+        path: PathBuf,
+        error: std::io::Error,
+    },
+
+    /// A file referenced by the script exceeds the size that can be sent.
+    AssetTooLarge {
+        expression: ParsedExpr,
+        path: PathBuf,
+        size: u64,
+        limit: u64,
+    },
+
+    /// The operator didn't confirm a CONFIRM command's safety check.
+    Unconfirmed {
+        expression: ParsedExpr,
+    },
+
+    /// A CONFIRM command followed other commands, so it's check couldn't precede them.
+    LateConfirmation {
+        expression: ParsedExpr,
+    },
+
+    /// A command referred to a measurement that hasn't been stored.
+    UnknownMeasurement {
+        expression: ParsedExpr,
+        name: String,
+    },
+
+    /// A command referred to the text of an INPUTDIALOG that the operator didn't enter.
+    InputNotEntered {
+        expression: ParsedExpr,
+        name: String,
+    },
+
+    /// The denominator of a ratio test was zero.
+    ZeroDenominator {
+        expression: ParsedExpr,
+        name: String,
+    },
+
+    /// A timer was stopped without having been started.
+    TimerNotStarted {
+        expression: ParsedExpr,
+        name: String,
+    },
+
+    /// Latencies were tested without any having been collected.
+    LatencyNotCollected {
+        expression: ParsedExpr,
+        name: String,
+    },
+
+    /// A device's echo of a command didn't match the command sent.
+    EchoMismatch {
+        expression: ParsedExpr,
+        expected: Vec<u8>,
+        received: Vec<u8>,
+    },
+
+    /// A device's response couldn't be parsed as a measurement in the expected format.
+    InvalidMeasurement {
+        expression: ParsedExpr,
+        format: &'static str,
+        response: Vec<u8>,
+        error: MeasurementError,
+    },
+
+    /// A device didn't send anything within a transaction's response timeout.
+    Timeout {
+        expression: ParsedExpr,
+        elapsed: Duration,
+    },
+
+    /// A BOARD block was started within another.
+    NestedBoard {
+        expression: ParsedExpr,
+        outer: String,
+    },
+
+    /// A BREAK command was run outside of any block, so there's nothing for it to exit.
+    BreakOutsideBlock {
+        expression: ParsedExpr,
+    },
+
+    /// A GOTO command's label isn't in it's block or any enclosing it.
+    UndefinedLabel {
+        expression: ParsedExpr,
+        label: String,
+    },
+
+    /// A GOTO command would exceed the maximum number of jumps a run may make. Most likely an
+    /// infinite loop.
+    JumpLimitExceeded {
+        expression: ParsedExpr,
+        limit: u32,
+    },
+
+    /// Text to be printed contains a character the printer's encoding can't represent.
+    Unencodable {
+        expression: ParsedExpr,
+        argument: ParsedExpr,
+        character: char,
+        encoding: Encoding,
+    },
+
+    /// An arithmetic operation's result couldn't be represented. i.e. It overflowed, was negative
+    /// or was a division by zero.
+    ArithmeticError {
+        expression: ParsedExpr,
+        operation: ParsedExpr,
+        lhs: u32,
+        rhs: u32,
+    },
+
+    /// A computed setting doesn't fit in a byte.
+    SettingOutOfRange {
+        expression: ParsedExpr,
+        setting: ParsedExpr,
+        value: i64,
+    },
+
+    /// An output file or directory couldn't be created.
+    OutputError {
+        path: PathBuf,
+        error: std::io::Error,
+    },
+}
+
+////////////////////////////////////////////////////////////////
+
+/// Broad category of an error. Used to decide how an error is handled without matching on every
+/// [`ErrorReason`]. e.g. Whether a run that ended with the error is worth retrying.
+///
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ErrorKind {
+    /// The script couldn't be parsed.
+    Syntax,
+
+    /// The script is invalid in a way only found while running it.
+    Script,
+
+    /// A test's measurement was outside of it's expected range.
+    TestFailure,
+
+    /// A value computed from measurements couldn't be used.
+    Measurement,
+
+    /// A device didn't respond in time.
+    Timeout,
+
+    /// Communication with a device failed.
+    Comms,
+
+    /// The frontend or its configuration can't run the script.
+    Configuration,
+
+    /// A file referenced by the script couldn't be used.
+    Asset,
+
+    /// The operator stopped the run.
+    Operator,
+
+    /// An output of the run couldn't be written.
+    Output,
 }
 
 ////////////////////////////////////////////////////////////////
@@ -43,16 +244,292 @@ pub enum ErrorNote {
 ////////////////////////////////////////////////////////////////
 
 impl Error {
+    pub(crate) fn from_syntax_error(reason: syntax::ErrorReason, notes: Vec<ErrorNote>) -> Self {
+        Self {
+            reason: Box::new(ErrorReason::SyntaxError(reason)),
+            notes,
+        }
+    }
+
     pub fn from_io_error(expression: ParsedExpr, error: std::io::Error) -> Self {
         Self {
-            reason: ErrorReason::IOError { expression, error },
+            reason: Box::new(ErrorReason::IOError { expression, error }),
             notes: Vec::new(),
         }
     }
 
     pub fn from_failed_test(expression: ParsedExpr, test: FailedTest) -> Self {
         Self {
-            reason: ErrorReason::TestFailure { expression, test },
+            reason: Box::new(ErrorReason::TestFailure { expression, test }),
+            notes: Vec::new(),
+        }
+    }
+
+    pub fn unrouted(expression: ParsedExpr, device: Device) -> Self {
+        Self {
+            reason: Box::new(ErrorReason::Unrouted { expression, device }),
+            notes: Vec::new(),
+        }
+    }
+
+    pub fn device_not_open(expression: ParsedExpr, device: Device) -> Self {
+        Self {
+            reason: Box::new(ErrorReason::DeviceNotOpen { expression, device }),
+            notes: vec![ErrorNote::Help(match device {
+                Device::USB => "Open the USB printer with USBOPEN before using it",
+                Device::TCU | Device::Printer => "Open the TCU with TCUOPEN before using it",
+                Device::Reference => "The reference device is opened by the frontend",
+            })],
+        }
+    }
+
+    pub fn device_already_open(expression: ParsedExpr, device: Device) -> Self {
+        Self {
+            reason: Box::new(ErrorReason::DeviceAlreadyOpen { expression, device }),
+            notes: vec![ErrorNote::Help(match device {
+                Device::USB => "Close the USB printer with USBCLOSE before opening it again",
+                Device::TCU | Device::Printer => {
+                    "Close the TCU with TCUCLOSE before opening it again"
+                }
+                Device::Reference => "The reference device is opened by the frontend",
+            })],
+        }
+    }
+
+    pub fn relay_already_open(expression: ParsedExpr, relay: u32) -> Self {
+        Self {
+            reason: Box::new(ErrorReason::RelayAlreadyOpen { expression, relay }),
+            notes: vec![ErrorNote::Help(
+                "Close the relay with TCUCLOSE before opening it again",
+            )],
+        }
+    }
+
+    pub fn unsupported(expression: ParsedExpr, transport: Transport) -> Self {
+        Self {
+            reason: Box::new(ErrorReason::Unsupported {
+                kind: expression.expression_kind(),
+                expression,
+                transport,
+            }),
+            notes: Vec::new(),
+        }
+    }
+
+    pub fn from_asset_error(expression: ParsedExpr, path: PathBuf, error: std::io::Error) -> Self {
+        Self {
+            reason: Box::new(ErrorReason::AssetError {
+                expression,
+                path,
+                error,
+            }),
+            notes: Vec::new(),
+        }
+    }
+
+    pub fn asset_too_large(expression: ParsedExpr, path: PathBuf, size: u64, limit: u64) -> Self {
+        Self {
+            reason: Box::new(ErrorReason::AssetTooLarge {
+                expression,
+                path,
+                size,
+                limit,
+            }),
+            notes: Vec::new(),
+        }
+    }
+
+    pub fn unconfirmed(expression: ParsedExpr) -> Self {
+        Self {
+            reason: Box::new(ErrorReason::Unconfirmed { expression }),
+            notes: Vec::new(),
+        }
+    }
+
+    pub fn late_confirmation(expression: ParsedExpr) -> Self {
+        Self {
+            reason: Box::new(ErrorReason::LateConfirmation { expression }),
+            notes: Vec::new(),
+        }
+    }
+
+    pub fn unknown_measurement(expression: ParsedExpr, name: impl Into<String>) -> Self {
+        Self {
+            reason: Box::new(ErrorReason::UnknownMeasurement {
+                expression,
+                name: name.into(),
+            }),
+            notes: Vec::new(),
+        }
+    }
+
+    pub fn input_not_entered(expression: ParsedExpr, name: impl Into<String>) -> Self {
+        Self {
+            reason: Box::new(ErrorReason::InputNotEntered {
+                expression,
+                name: name.into(),
+            }),
+            notes: Vec::new(),
+        }
+    }
+
+    pub fn zero_denominator(expression: ParsedExpr, name: impl Into<String>) -> Self {
+        Self {
+            reason: Box::new(ErrorReason::ZeroDenominator {
+                expression,
+                name: name.into(),
+            }),
+            notes: Vec::new(),
+        }
+    }
+
+    pub fn timer_not_started(expression: ParsedExpr, name: impl Into<String>) -> Self {
+        Self {
+            reason: Box::new(ErrorReason::TimerNotStarted {
+                expression,
+                name: name.into(),
+            }),
+            notes: Vec::new(),
+        }
+    }
+
+    pub fn latency_not_collected(expression: ParsedExpr, name: impl Into<String>) -> Self {
+        Self {
+            reason: Box::new(ErrorReason::LatencyNotCollected {
+                expression,
+                name: name.into(),
+            }),
+            notes: Vec::new(),
+        }
+    }
+
+    pub fn echo_mismatch(expression: ParsedExpr, expected: Vec<u8>, received: Vec<u8>) -> Self {
+        Self {
+            reason: Box::new(ErrorReason::EchoMismatch {
+                expression,
+                expected,
+                received,
+            }),
+            notes: Vec::new(),
+        }
+    }
+
+    pub fn invalid_measurement(
+        expression: ParsedExpr,
+        format: &'static str,
+        response: Vec<u8>,
+        error: MeasurementError,
+    ) -> Self {
+        Self {
+            reason: Box::new(ErrorReason::InvalidMeasurement {
+                expression,
+                format,
+                response,
+                error,
+            }),
+            notes: Vec::new(),
+        }
+    }
+
+    pub fn timeout(expression: ParsedExpr, elapsed: Duration) -> Self {
+        Self {
+            reason: Box::new(ErrorReason::Timeout {
+                expression,
+                elapsed,
+            }),
+            notes: Vec::new(),
+        }
+    }
+
+    pub fn nested_board(expression: ParsedExpr, outer: impl Into<String>) -> Self {
+        Self {
+            reason: Box::new(ErrorReason::NestedBoard {
+                expression,
+                outer: outer.into(),
+            }),
+            notes: Vec::new(),
+        }
+    }
+
+    pub fn break_outside_block(expression: ParsedExpr) -> Self {
+        Self {
+            reason: Box::new(ErrorReason::BreakOutsideBlock { expression }),
+            notes: Vec::new(),
+        }
+    }
+
+    pub fn undefined_label(expression: ParsedExpr, label: &str) -> Self {
+        Self {
+            reason: Box::new(ErrorReason::UndefinedLabel {
+                expression,
+                label: label.to_owned(),
+            }),
+            notes: Vec::new(),
+        }
+    }
+
+    pub fn jump_limit_exceeded(expression: ParsedExpr, limit: u32) -> Self {
+        Self {
+            reason: Box::new(ErrorReason::JumpLimitExceeded { expression, limit }),
+            notes: Vec::new(),
+        }
+    }
+
+    /// # Arguments
+    /// * `expression` - Command printing the text.
+    /// * `argument` - Argument containing the character.
+    /// * `character` - Character that can't be represented.
+    /// * `encoding` - Encoding the text was being converted to.
+    ///
+    pub fn unencodable(
+        expression: ParsedExpr,
+        argument: ParsedExpr,
+        character: char,
+        encoding: Encoding,
+    ) -> Self {
+        Self {
+            reason: Box::new(ErrorReason::Unencodable {
+                expression,
+                argument,
+                character,
+                encoding,
+            }),
+            notes: Vec::new(),
+        }
+    }
+
+    /// # Arguments
+    /// * `expression` - Command the arithmetic belongs to.
+    /// * `operation` - The failing operation.
+    /// * `lhs` - Value of the operation's left hand side.
+    /// * `rhs` - Value of the operation's right hand side.
+    ///
+    pub fn arithmetic(expression: ParsedExpr, operation: ParsedExpr, lhs: u32, rhs: u32) -> Self {
+        Self {
+            reason: Box::new(ErrorReason::ArithmeticError {
+                expression,
+                operation,
+                lhs,
+                rhs,
+            }),
+            notes: Vec::new(),
+        }
+    }
+
+    pub fn setting_out_of_range(expression: ParsedExpr, setting: ParsedExpr, value: i64) -> Self {
+        Self {
+            reason: Box::new(ErrorReason::SettingOutOfRange {
+                expression,
+                setting,
+                value,
+            }),
+            notes: Vec::new(),
+        }
+    }
+
+    pub fn from_output_error(path: PathBuf, error: std::io::Error) -> Self {
+        Self {
+            reason: Box::new(ErrorReason::OutputError { path, error }),
             notes: Vec::new(),
         }
     }
@@ -67,10 +544,7 @@ impl Error {
 
 impl From<syntax::Error> for Error {
     fn from(error: syntax::Error) -> Self {
-        Self {
-            reason: ErrorReason::SyntaxError(error.reason().to_owned()),
-            notes: error.notes().to_owned(),
-        }
+        Self::from_syntax_error(error.reason().to_owned(), error.notes().to_owned())
     }
 }
 
@@ -110,6 +584,68 @@ impl ErrorReason {
             ErrorReason::SyntaxError(reason) => format!("Syntax error - {}", reason.message()),
             ErrorReason::TestFailure { test, .. } => format!("Test failed - {}", test.message),
             ErrorReason::IOError { error, .. } => format!("IO error - {}", error),
+            ErrorReason::Unrouted { device, .. } => format!("No {device} assigned"),
+            ErrorReason::DeviceNotOpen { device, .. } => format!("The {device} isn't open"),
+            ErrorReason::DeviceAlreadyOpen { device, .. } => {
+                format!("The {device} is already open")
+            }
+            ErrorReason::RelayAlreadyOpen { relay, .. } => {
+                format!("TCU relay {relay} is already open")
+            }
+            ErrorReason::Unsupported {
+                kind, transport, ..
+            } => format!(
+                "{} is unsupported by the {transport} transport",
+                kind.name()
+            ),
+            ErrorReason::AssetError { path, error, .. } => {
+                format!("Failed to read '{}' - {error}", path.display())
+            }
+            ErrorReason::AssetTooLarge { path, .. } => {
+                format!("'{}' is too large to send", path.display())
+            }
+            ErrorReason::Unconfirmed { .. } => "Safety check not confirmed".to_owned(),
+            ErrorReason::LateConfirmation { .. } => {
+                "Safety checks must precede all other commands".to_owned()
+            }
+            ErrorReason::UnknownMeasurement { name, .. } => {
+                format!("No measurement stored as '{name}'")
+            }
+            ErrorReason::InputNotEntered { name, .. } => {
+                format!("No text entered for '{name}'")
+            }
+            ErrorReason::ZeroDenominator { name, .. } => {
+                format!("Ratio is undefined as '{name}' measured 0")
+            }
+            ErrorReason::TimerNotStarted { name, .. } => format!("Timer '{name}' isn't running"),
+            ErrorReason::LatencyNotCollected { name, .. } => {
+                format!("No latencies were collected under '{name}'")
+            }
+            ErrorReason::EchoMismatch { .. } => "Incorrect echo of command".to_owned(),
+            ErrorReason::InvalidMeasurement { error, .. } => {
+                format!("Invalid measurement - {error}")
+            }
+            ErrorReason::Timeout { .. } => "Timed out waiting for a response".to_owned(),
+            ErrorReason::NestedBoard { outer, .. } => {
+                format!("BOARD within board '{outer}'. Boards can't be nested")
+            }
+            ErrorReason::BreakOutsideBlock { .. } => "BREAK outside of a block".to_owned(),
+            ErrorReason::UndefinedLabel { label, .. } => format!("Undefined label '{label}'"),
+            ErrorReason::JumpLimitExceeded { limit, .. } => {
+                format!("Exceeded the limit of {limit} jumps")
+            }
+            ErrorReason::Unencodable {
+                character,
+                encoding,
+                ..
+            } => format!("'{character}' can't be printed using the {encoding} encoding"),
+            ErrorReason::ArithmeticError { .. } => "Setting can't be computed".to_owned(),
+            ErrorReason::SettingOutOfRange { value, .. } => {
+                format!("Computed setting of {value} is out of range")
+            }
+            ErrorReason::OutputError { path, error } => {
+                format!("Failed to create '{}' - {error}", path.display())
+            }
         }
     }
 
@@ -118,41 +654,87 @@ impl ErrorReason {
             ErrorReason::SyntaxError(reason) => reason.labels(),
 
             ErrorReason::TestFailure { expression, test } => {
-                let range_expr = match expression.expression() {
-                    Expr::TCUTest { min, max, .. } => Some((min, max)),
-                    Expr::PrinterTest { min, max, .. } => Some((min, max)),
-                    Expr::USBPrinterTest { min, max, .. } => Some((min, max)),
+                let expected_expr = match expression.expression() {
+                    Expr::TCUTest { expected, .. } => Some(expected),
+                    Expr::PrinterTest { expected, .. } => Some(expected),
+                    Expr::USBPrinterTest { expected, .. } => Some(expected),
+                    Expr::StopTimer { expected, .. } => Some(expected),
+                    Expr::LatencyTest { expected, .. } => Some(expected),
+                    Expr::RatioTest { expected, .. } => Some(expected),
                     _ => None,
                 };
 
+                let expected = match &test.expected {
+                    Expected::Range(range) => range,
+                    Expected::Comparison(..)
+                    | Expected::Bounds(..)
+                    | Expected::OneOf(..)
+                    | Expected::Baseline { .. } => {
+                        let span = expected_expr
+                            .map(|expected| expected.span())
+                            .unwrap_or(expression.span());
+
+                        return vec![Label::new(span.clone()).with_message(format!(
+                            "Expected {} but measured {}",
+                            test.expected, test.measurement
+                        ))];
+                    }
+                    Expected::Bitfield(..) => {
+                        let span = expected_expr
+                            .map(|expected| expected.span())
+                            .unwrap_or(expression.span());
+
+                        return vec![Label::new(span.clone()).with_message(format!(
+                            "Expected {} but measured {:#X}",
+                            test.expected, test.measurement
+                        ))];
+                    }
+                    Expected::Stable { samples, spread } => {
+                        let span = expected_expr
+                            .and_then(|expected| match expected.expression() {
+                                Expr::Stability { spread, .. } => Some(spread.span()),
+                                _ => None,
+                            })
+                            .unwrap_or(expression.span());
+
+                        return vec![Label::new(span.clone()).with_message(format!(
+                            "Expected a spread of at most {spread} over {samples} samples but measured a spread of {}",
+                            test.measurement
+                        ))];
+                    }
+                };
+
+                let range_expr = expected_expr.and_then(|expected| match expected.expression() {
+                    Expr::Range { min, max } => Some((min.span(), max.span())),
+                    // Both bounds are derived from the tolerance as a whole.
+                    Expr::Tolerance { .. } => Some((expected.span(), expected.span())),
+                    _ => None,
+                });
+
                 // Create a label highlighting the failing command.
                 let mut labels = Vec::new();
 
                 // Create a label highlighting the bound that the measured value violated.
-                if test.measurement > *test.expected.end() {
-                    let span = range_expr
-                        .map(|(_, max)| max.span())
-                        .unwrap_or(expression.span());
+                if test.measurement > i64::from(*expected.end()) {
+                    let span = range_expr.map(|(_, max)| max).unwrap_or(expression.span());
 
                     labels.push(
                         Label::new(span.clone())
                             .with_message(format!(
                                 "Expected maximum value of {} but measured {}",
-                                test.expected.end(),
+                                expected.end(),
                                 test.measurement
                             ))
                             .with_order(1),
                     );
                 }
 
-                if test.measurement < *test.expected.start() {
-                    let span = range_expr
-                        .map(|(min, _)| min.span())
-                        .unwrap_or(expression.span());
+                if test.measurement < i64::from(*expected.start()) {
+                    let span = range_expr.map(|(min, _)| min).unwrap_or(expression.span());
 
                     labels.push(Label::new(span.clone()).with_message(format!(
                         "Expected minimum value of {} but measured {}",
-                        test.expected.start(),
+                        expected.start(),
                         test.measurement
                     )));
                 }
@@ -164,6 +746,173 @@ impl ErrorReason {
                 vec![Label::new(expression.span().clone())
                     .with_message("When executing this command")]
             }
+
+            ErrorReason::Unrouted { expression, device } => {
+                vec![Label::new(expression.span().clone())
+                    .with_message(format!("This command requires the {device}"))]
+            }
+
+            ErrorReason::DeviceNotOpen { expression, device } => {
+                vec![Label::new(expression.span().clone())
+                    .with_message(format!("Uses the {device} before it's opened"))]
+            }
+
+            ErrorReason::DeviceAlreadyOpen { expression, device } => {
+                vec![Label::new(expression.span().clone())
+                    .with_message(format!("Opens the {device} again before it's closed"))]
+            }
+
+            ErrorReason::RelayAlreadyOpen { expression, relay } => {
+                vec![Label::new(expression.span().clone())
+                    .with_message(format!("Opens relay {relay} again before it's closed"))]
+            }
+
+            ErrorReason::Unsupported {
+                expression,
+                transport,
+                ..
+            } => {
+                vec![Label::new(expression.span().clone())
+                    .with_message(format!("Can't be used with the {transport} transport"))]
+            }
+
+            ErrorReason::AssetError { expression, .. } => {
+                vec![Label::new(expression.span().clone()).with_message("Referenced here")]
+            }
+
+            ErrorReason::AssetTooLarge {
+                expression,
+                size,
+                limit,
+                ..
+            } => {
+                vec![Label::new(expression.span().clone()).with_message(format!(
+                    "File is {size} bytes but at most {limit} can be sent"
+                ))]
+            }
+
+            ErrorReason::Unconfirmed { expression } => {
+                vec![Label::new(expression.span().clone()).with_message("Declined by the operator")]
+            }
+
+            ErrorReason::LateConfirmation { expression } => {
+                vec![Label::new(expression.span().clone())
+                    .with_message("Move this before the first command")]
+            }
+
+            ErrorReason::UnknownMeasurement { expression, .. } => {
+                vec![Label::new(expression.span().clone())
+                    .with_message("Used before a TCUMEASURE with the same name")]
+            }
+
+            ErrorReason::InputNotEntered { expression, .. } => {
+                vec![Label::new(expression.span().clone())
+                    .with_message("Uses text the operator didn't enter")]
+            }
+
+            ErrorReason::ZeroDenominator { expression, .. } => {
+                vec![Label::new(expression.span().clone()).with_message("Division by zero")]
+            }
+
+            ErrorReason::TimerNotStarted { expression, .. } => {
+                vec![Label::new(expression.span().clone())
+                    .with_message("Stopped before a STARTTIMER with the same name")]
+            }
+
+            ErrorReason::LatencyNotCollected { expression, .. } => {
+                vec![Label::new(expression.span().clone()).with_message(
+                    "No responses were completed since a STARTLATENCY with the same name",
+                )]
+            }
+
+            ErrorReason::EchoMismatch {
+                expression,
+                expected,
+                received,
+            } => {
+                vec![Label::new(expression.span().clone()).with_message(format!(
+                    "Expected echo \"{}\" but received \"{}\"",
+                    expected.escape_ascii(),
+                    received.escape_ascii()
+                ))]
+            }
+
+            ErrorReason::InvalidMeasurement {
+                expression,
+                format,
+                response,
+                ..
+            } => {
+                vec![Label::new(expression.span().clone()).with_message(format!(
+                    "Expected a {format} measurement but received \"{}\"",
+                    response.escape_ascii()
+                ))]
+            }
+
+            ErrorReason::Timeout {
+                expression,
+                elapsed,
+            } => {
+                vec![Label::new(expression.span().clone())
+                    .with_message(format!("Nothing received for {}ms", elapsed.as_millis()))]
+            }
+
+            ErrorReason::NestedBoard { expression, .. } => {
+                vec![Label::new(expression.span().clone())
+                    .with_message("Move this after the enclosing ENDBOARD")]
+            }
+
+            ErrorReason::BreakOutsideBlock { expression } => {
+                vec![Label::new(expression.span().clone())
+                    .with_message("Not within a block. Nothing to exit")]
+            }
+
+            ErrorReason::UndefinedLabel { expression, .. } => {
+                vec![Label::new(expression.span().clone())
+                    .with_message("No LABEL of this name in this block or any enclosing it")]
+            }
+
+            ErrorReason::JumpLimitExceeded { expression, .. } => {
+                vec![Label::new(expression.span().clone()).with_message("Most likely loops forever")]
+            }
+
+            ErrorReason::Unencodable {
+                argument,
+                character,
+                ..
+            } => {
+                vec![Label::new(argument.span().clone()).with_message(format!(
+                    "Contains '{character}' (U+{:04X})",
+                    u32::from(*character)
+                ))]
+            }
+
+            ErrorReason::ArithmeticError {
+                operation,
+                lhs,
+                rhs,
+                ..
+            } => {
+                let Expr::Arithmetic { operator, .. } = operation.expression() else {
+                    return Vec::new();
+                };
+
+                let problem = match operator {
+                    Operator::Divide if *rhs == 0 => "is a division by zero",
+                    Operator::Subtract => "is negative",
+                    _ => "overflows",
+                };
+
+                vec![Label::new(operation.span().clone())
+                    .with_message(format!("{lhs} {operator} {rhs} {problem}"))]
+            }
+
+            ErrorReason::SettingOutOfRange { setting, value, .. } => {
+                vec![Label::new(setting.span().clone())
+                    .with_message(format!("Must be between 0 and 255 but computed {value}"))]
+            }
+
+            ErrorReason::OutputError { .. } => Vec::new(),
         }
     }
 }
@@ -177,6 +926,77 @@ impl Error {
         &self.reason
     }
 
+    pub fn kind(&self) -> ErrorKind {
+        match self.reason.as_ref() {
+            ErrorReason::SyntaxError(_) => ErrorKind::Syntax,
+            ErrorReason::TestFailure { .. } => ErrorKind::TestFailure,
+            ErrorReason::IOError { error, .. } if error.kind() == std::io::ErrorKind::TimedOut => {
+                ErrorKind::Timeout
+            }
+            ErrorReason::IOError { .. } => ErrorKind::Comms,
+            ErrorReason::Unrouted { .. } => ErrorKind::Configuration,
+            ErrorReason::DeviceNotOpen { .. } => ErrorKind::Script,
+            ErrorReason::DeviceAlreadyOpen { .. } => ErrorKind::Script,
+            ErrorReason::RelayAlreadyOpen { .. } => ErrorKind::Script,
+            ErrorReason::Unsupported { .. } => ErrorKind::Configuration,
+            ErrorReason::AssetError { .. } => ErrorKind::Asset,
+            ErrorReason::AssetTooLarge { .. } => ErrorKind::Asset,
+            ErrorReason::Unconfirmed { .. } => ErrorKind::Operator,
+            ErrorReason::LateConfirmation { .. } => ErrorKind::Script,
+            ErrorReason::UnknownMeasurement { .. } => ErrorKind::Script,
+            ErrorReason::InputNotEntered { .. } => ErrorKind::Operator,
+            ErrorReason::ZeroDenominator { .. } => ErrorKind::Measurement,
+            ErrorReason::TimerNotStarted { .. } => ErrorKind::Script,
+            ErrorReason::LatencyNotCollected { .. } => ErrorKind::Script,
+            ErrorReason::EchoMismatch { .. } => ErrorKind::Comms,
+            ErrorReason::InvalidMeasurement { .. } => ErrorKind::Comms,
+            ErrorReason::Timeout { .. } => ErrorKind::Timeout,
+            ErrorReason::NestedBoard { .. } => ErrorKind::Script,
+            ErrorReason::BreakOutsideBlock { .. } => ErrorKind::Script,
+            ErrorReason::UndefinedLabel { .. } => ErrorKind::Script,
+            ErrorReason::JumpLimitExceeded { .. } => ErrorKind::Script,
+            ErrorReason::Unencodable { .. } => ErrorKind::Script,
+            ErrorReason::ArithmeticError { .. } => ErrorKind::Measurement,
+            ErrorReason::SettingOutOfRange { .. } => ErrorKind::Measurement,
+            ErrorReason::OutputError { .. } => ErrorKind::Output,
+        }
+    }
+
+    /// Return the area of the script the error occured in.
+    ///
+    pub fn span(&self) -> Option<Range<usize>> {
+        match self.reason.as_ref() {
+            ErrorReason::SyntaxError(reason) => reason.span().cloned(),
+            ErrorReason::TestFailure { expression, .. } => Some(expression.span().clone()),
+            ErrorReason::IOError { expression, .. } => Some(expression.span().clone()),
+            ErrorReason::Unrouted { expression, .. } => Some(expression.span().clone()),
+            ErrorReason::DeviceNotOpen { expression, .. } => Some(expression.span().clone()),
+            ErrorReason::DeviceAlreadyOpen { expression, .. } => Some(expression.span().clone()),
+            ErrorReason::RelayAlreadyOpen { expression, .. } => Some(expression.span().clone()),
+            ErrorReason::Unsupported { expression, .. } => Some(expression.span().clone()),
+            ErrorReason::AssetError { expression, .. } => Some(expression.span().clone()),
+            ErrorReason::AssetTooLarge { expression, .. } => Some(expression.span().clone()),
+            ErrorReason::Unconfirmed { expression } => Some(expression.span().clone()),
+            ErrorReason::LateConfirmation { expression } => Some(expression.span().clone()),
+            ErrorReason::UnknownMeasurement { expression, .. } => Some(expression.span().clone()),
+            ErrorReason::InputNotEntered { expression, .. } => Some(expression.span().clone()),
+            ErrorReason::ZeroDenominator { expression, .. } => Some(expression.span().clone()),
+            ErrorReason::TimerNotStarted { expression, .. } => Some(expression.span().clone()),
+            ErrorReason::LatencyNotCollected { expression, .. } => Some(expression.span().clone()),
+            ErrorReason::EchoMismatch { expression, .. } => Some(expression.span().clone()),
+            ErrorReason::InvalidMeasurement { expression, .. } => Some(expression.span().clone()),
+            ErrorReason::Timeout { expression, .. } => Some(expression.span().clone()),
+            ErrorReason::NestedBoard { expression, .. } => Some(expression.span().clone()),
+            ErrorReason::BreakOutsideBlock { expression } => Some(expression.span().clone()),
+            ErrorReason::UndefinedLabel { expression, .. } => Some(expression.span().clone()),
+            ErrorReason::JumpLimitExceeded { expression, .. } => Some(expression.span().clone()),
+            ErrorReason::Unencodable { expression, .. } => Some(expression.span().clone()),
+            ErrorReason::ArithmeticError { expression, .. } => Some(expression.span().clone()),
+            ErrorReason::SettingOutOfRange { expression, .. } => Some(expression.span().clone()),
+            ErrorReason::OutputError { .. } => None,
+        }
+    }
+
     pub fn notes(&self) -> &[ErrorNote] {
         &self.notes
     }
@@ -194,15 +1014,93 @@ impl std::fmt::Display for Error {
 
 ////////////////////////////////////////////////////////////////
 
+impl ErrorKind {
+    const ALL: [ErrorKind; 10] = [
+        ErrorKind::Syntax,
+        ErrorKind::Script,
+        ErrorKind::TestFailure,
+        ErrorKind::Measurement,
+        ErrorKind::Timeout,
+        ErrorKind::Comms,
+        ErrorKind::Configuration,
+        ErrorKind::Asset,
+        ErrorKind::Operator,
+        ErrorKind::Output,
+    ];
+}
+
+impl std::fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ErrorKind::Syntax => write!(f, "syntax"),
+            ErrorKind::Script => write!(f, "script"),
+            ErrorKind::TestFailure => write!(f, "test-failure"),
+            ErrorKind::Measurement => write!(f, "measurement"),
+            ErrorKind::Timeout => write!(f, "timeout"),
+            ErrorKind::Comms => write!(f, "comms"),
+            ErrorKind::Configuration => write!(f, "configuration"),
+            ErrorKind::Asset => write!(f, "asset"),
+            ErrorKind::Operator => write!(f, "operator"),
+            ErrorKind::Output => write!(f, "output"),
+        }
+    }
+}
+
+impl std::str::FromStr for ErrorKind {
+    type Err = String;
+
+    /// Parse an error kind from it's name as displayed. Case insensitive.
+    ///
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        ErrorKind::ALL
+            .into_iter()
+            .find(|kind| kind.to_string().eq_ignore_ascii_case(name))
+            .ok_or_else(|| {
+                let names: Vec<String> = ErrorKind::ALL.iter().map(ToString::to_string).collect();
+                format!(
+                    "Unknown error kind '{name}'. Expected one of {}",
+                    names.join(", ")
+                )
+            })
+    }
+}
+
+////////////////////////////////////////////////////////////////
+
 impl std::error::Error for Error {
     fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
-        match &self.reason {
+        match self.reason.as_ref() {
             ErrorReason::SyntaxError(_) => None,
             ErrorReason::TestFailure { .. } => None,
             ErrorReason::IOError {
                 expression: _,
                 error,
             } => Some(error),
+            ErrorReason::Unrouted { .. } => None,
+            ErrorReason::DeviceNotOpen { .. } => None,
+            ErrorReason::DeviceAlreadyOpen { .. } => None,
+            ErrorReason::RelayAlreadyOpen { .. } => None,
+            ErrorReason::Unsupported { .. } => None,
+            ErrorReason::AssetError { error, .. } => Some(error),
+            ErrorReason::AssetTooLarge { .. } => None,
+            ErrorReason::Unconfirmed { .. } => None,
+            ErrorReason::LateConfirmation { .. } => None,
+            ErrorReason::UnknownMeasurement { .. } => None,
+            ErrorReason::InputNotEntered { .. } => None,
+            ErrorReason::ZeroDenominator { .. } => None,
+            ErrorReason::TimerNotStarted { .. } => None,
+            ErrorReason::LatencyNotCollected { .. } => None,
+            ErrorReason::EchoMismatch { .. } => None,
+            ErrorReason::InvalidMeasurement { error, .. } => Some(error),
+            ErrorReason::Timeout { .. } => None,
+            ErrorReason::NestedBoard { .. } => None,
+            ErrorReason::BreakOutsideBlock { .. } => None,
+            ErrorReason::UndefinedLabel { .. } => None,
+            ErrorReason::JumpLimitExceeded { .. } => None,
+            ErrorReason::Unencodable { .. } => None,
+            ErrorReason::ArithmeticError { .. } => None,
+            ErrorReason::SettingOutOfRange { .. } => None,
+            ErrorReason::OutputError { error, .. } => Some(error),
         }
     }
 }
@@ -0,0 +1,191 @@
+use std::{
+    ops::Range,
+    sync::{Arc, Mutex},
+};
+
+////////////////////////////////////////////////////////////////
+// types
+////////////////////////////////////////////////////////////////
+
+/// Retains every raw exchange with a device during a run for later analysis. Cloning a capture
+/// returns a handle to the same exchanges.
+///
+#[derive(Clone, Debug, Default)]
+pub struct Capture {
+    exchanges: Arc<Mutex<Vec<Exchange>>>,
+    limit: Option<usize>,
+}
+
+////////////////////////////////////////////////////////////////
+
+/// Everything transmitted and received by a single transaction, including any retries.
+///
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Exchange {
+    span: Range<usize>,
+    sent: Vec<u8>,
+    received: Vec<u8>,
+    truncated: bool,
+}
+
+////////////////////////////////////////////////////////////////
+// construction / conversion
+////////////////////////////////////////////////////////////////
+
+impl Capture {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Limit the number of bytes retained from the responses of each transaction. Anything
+    /// received beyond the limit is discarded.
+    ///
+    #[must_use]
+    pub fn with_limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+}
+
+////////////////////////////////////////////////////////////////
+// methods
+////////////////////////////////////////////////////////////////
+
+impl Capture {
+    /// Return the number of transactions captured.
+    ///
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lock().is_empty()
+    }
+
+    /// Return the exchange of the transaction at the given index. Transactions are indexed in the
+    /// order they were first transmitted.
+    ///
+    pub fn get(&self, index: usize) -> Option<Exchange> {
+        self.lock().get(index).cloned()
+    }
+
+    /// Return every captured exchange.
+    ///
+    pub fn exchanges(&self) -> Vec<Exchange> {
+        self.lock().clone()
+    }
+
+    /// Start capturing a new transaction.
+    ///
+    /// # Returns
+    /// The index of the transaction's exchange.
+    ///
+    pub(super) fn open(&self, span: Range<usize>, sent: &[u8]) -> usize {
+        let mut exchanges = self.lock();
+        exchanges.push(Exchange {
+            span,
+            sent: sent.to_owned(),
+            received: Vec::new(),
+            truncated: false,
+        });
+
+        exchanges.len() - 1
+    }
+
+    /// Add bytes transmitted by a transaction. e.g. When it's retried.
+    ///
+    pub(super) fn send(&self, index: usize, bytes: &[u8]) {
+        if let Some(exchange) = self.lock().get_mut(index) {
+            exchange.sent.extend_from_slice(bytes);
+        }
+    }
+
+    /// Add bytes received by a transaction, up to the limit.
+    ///
+    pub(super) fn receive(&self, index: usize, bytes: &[u8]) {
+        let limit = self.limit.unwrap_or(usize::MAX);
+
+        if let Some(exchange) = self.lock().get_mut(index) {
+            let remaining = limit.saturating_sub(exchange.received.len());
+            let count = remaining.min(bytes.len());
+
+            exchange.received.extend_from_slice(&bytes[..count]);
+            exchange.truncated |= count < bytes.len();
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Vec<Exchange>> {
+        // A poisoned lock only means another thread panicked mid-capture. What was captured is
+        // still worth keeping.
+        self.exchanges
+            .lock()
+            .unwrap_or_else(|error| error.into_inner())
+    }
+}
+
+////////////////////////////////////////////////////////////////
+// field access
+////////////////////////////////////////////////////////////////
+
+impl Exchange {
+    /// Return the span of the command in the script that created the transaction.
+    ///
+    pub fn span(&self) -> &Range<usize> {
+        &self.span
+    }
+
+    pub fn sent(&self) -> &[u8] {
+        &self.sent
+    }
+
+    pub fn received(&self) -> &[u8] {
+        &self.received
+    }
+
+    /// Return true if any of the response was discarded due to the capture's limit.
+    ///
+    pub fn truncated(&self) -> bool {
+        self.truncated
+    }
+}
+
+////////////////////////////////////////////////////////////////
+// comparison
+////////////////////////////////////////////////////////////////
+
+impl PartialEq for Capture {
+    fn eq(&self, other: &Self) -> bool {
+        // Captures are handles so compare by identity.
+        Arc::ptr_eq(&self.exchanges, &other.exchanges)
+    }
+}
+
+impl Eq for Capture {}
+
+////////////////////////////////////////////////////////////////
+// tests
+////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    ////////////////////////////////////////////////////////////////
+
+    #[test]
+    fn test_capture_limit() {
+        let capture = Capture::new().with_limit(4);
+        let handle = capture.clone();
+
+        let index = handle.open(0..5, b"M01\r");
+        handle.receive(index, b"M01\r");
+        handle.receive(index, b"0001\r");
+
+        let exchange = capture.get(index).unwrap();
+        assert_eq!(exchange.sent(), b"M01\r");
+        assert_eq!(exchange.received(), b"M01\r");
+        assert!(exchange.truncated());
+    }
+}
+
+////////////////////////////////////////////////////////////////
@@ -0,0 +1,299 @@
+use std::{
+    io::{Read, Write},
+    ops::Range,
+    sync::Arc,
+};
+
+use crate::{error::Error, syntax::ParsedExpr};
+
+use super::{
+    capture::Capture,
+    fingerprint::Fingerprint,
+    measurement::{Expected, FailedTest, MeasurementFormat, UnitSuffix},
+    observer::TransactionObserver,
+    results::{test_channel, Results, TestOutcome},
+    transaction::{Device, Echo, Transaction, TransactionStatus},
+};
+
+////////////////////////////////////////////////////////////////
+// types
+////////////////////////////////////////////////////////////////
+
+/// A test comparing a measurement taken by the device under test against the same measurement
+/// taken by a reference device. Passes if the two agree within a tolerance.
+///
+#[derive(Clone, Debug, PartialEq)]
+pub struct CrossCheck {
+    expression: ParsedExpr,
+    dut: Box<Transaction>,
+    reference: Box<Transaction>,
+    tolerance: u32,
+    retries: u32,
+    failure_message: String,
+    record: bool,
+
+    /// Where the outcome of the check is reported.
+    results: Option<Results>,
+
+    dut_measurement: Option<i64>,
+    reference_measurement: Option<i64>,
+}
+
+////////////////////////////////////////////////////////////////
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum CrossCheckStatus {
+    Success,
+    Ongoing(CrossCheck),
+
+    /// The check was skipped because it was run in record mode. Contains the span of the command
+    /// and the difference between the measurements.
+    Recorded {
+        span: Range<usize>,
+        measurement: i64,
+    },
+}
+
+////////////////////////////////////////////////////////////////
+// construction / conversion
+////////////////////////////////////////////////////////////////
+
+impl CrossCheck {
+    /// # Arguments
+    /// * `expression` - Command that created the check.
+    /// * `dut` - Bytes sent to the TCU to measure the device under test.
+    /// * `reference` - Bytes sent to the reference device to measure it.
+    /// * `tolerance` - Maximum difference between the measurements for the check to pass.
+    /// * `retries` - Number of times to re-take both measurements before failing.
+    /// * `failure_message` - Message reported on failure.
+    ///
+    pub fn new(
+        expression: ParsedExpr,
+        dut: Vec<u8>,
+        reference: Vec<u8>,
+        tolerance: u32,
+        retries: u32,
+        failure_message: String,
+    ) -> Self {
+        Self {
+            dut: Box::new(Transaction::measuring(expression.clone(), dut, Device::TCU)),
+            reference: Box::new(Transaction::measuring(
+                expression.clone(),
+                reference,
+                Device::Reference,
+            )),
+            expression,
+            tolerance,
+            retries,
+            failure_message,
+            record: false,
+            results: None,
+            dut_measurement: None,
+            reference_measurement: None,
+        }
+    }
+
+    /// Set whether the check should record the difference between the measurements instead of
+    /// testing it.
+    ///
+    #[must_use]
+    pub fn recording(mut self, record: bool) -> Self {
+        self.record = record;
+        self
+    }
+
+    /// Set how the TCU's echo is separated from the rest of it's response.
+    ///
+    #[must_use]
+    pub fn echo_format(mut self, echo: Echo) -> Self {
+        *self.dut = self.dut.echo_format(echo);
+        self
+    }
+
+    /// Set the format the TCU's measurement is parsed in.
+    ///
+    #[must_use]
+    pub fn measurement_format(mut self, format: MeasurementFormat) -> Self {
+        *self.dut = self.dut.measurement_format(format);
+        self
+    }
+
+    /// Set the unit that follows the TCU's measurement.
+    ///
+    #[must_use]
+    pub fn measurement_unit(mut self, unit: UnitSuffix) -> Self {
+        *self.dut = self.dut.measurement_unit(unit);
+        self
+    }
+
+    /// Send the trigger byte to the TCU once it's echoed the command to prompt it to take it's
+    /// measurement.
+    ///
+    #[must_use]
+    pub fn measurement_trigger(mut self, trigger: u8) -> Self {
+        *self.dut = self.dut.measurement_trigger(trigger);
+        self
+    }
+
+    /// Set the number of times each device's command is re-transmitted after a comms error.
+    ///
+    #[must_use]
+    pub fn comms_retries(mut self, retries: u32) -> Self {
+        *self.dut = self.dut.comms_retries(retries);
+        *self.reference = self.reference.comms_retries(retries);
+        self
+    }
+
+    /// Report the outcome of the check to the results.
+    ///
+    #[must_use]
+    pub fn reporting(mut self, results: Results) -> Self {
+        self.results = Some(results);
+        self
+    }
+
+    /// Retain the raw bytes exchanged with both devices in the capture.
+    ///
+    #[must_use]
+    pub fn capturing(mut self, capture: Capture) -> Self {
+        *self.dut = self.dut.capturing(capture.clone());
+        *self.reference = self.reference.capturing(capture);
+        self
+    }
+
+    /// Notify the observer of every exchange with both devices.
+    ///
+    #[must_use]
+    pub fn observing(mut self, observer: Arc<dyn TransactionObserver>) -> Self {
+        *self.dut = self.dut.observing(observer.clone());
+        *self.reference = self.reference.observing(observer);
+        self
+    }
+}
+
+////////////////////////////////////////////////////////////////
+// methods
+////////////////////////////////////////////////////////////////
+
+impl CrossCheck {
+    /// Return the span of the command in the script that created the check.
+    ///
+    pub fn span(&self) -> &Range<usize> {
+        self.expression.span()
+    }
+
+    /// Return the transactions taking the device under test's and the reference's measurements.
+    ///
+    pub(crate) fn transactions(&self) -> [&Transaction; 2] {
+        [&self.dut, &self.reference]
+    }
+
+    /// Add the bytes sent to both devices and the check's tolerance to the fingerprint.
+    ///
+    pub(crate) fn fingerprint(&self, fingerprint: &mut Fingerprint) {
+        self.dut.fingerprint(fingerprint);
+        self.reference.fingerprint(fingerprint);
+        fingerprint.write_u32(self.tolerance);
+        fingerprint.write_u32(self.retries);
+        fingerprint.write_str(&self.failure_message);
+    }
+
+    /// Progress the check. Each measurement is taken in turn, the device under test's first.
+    ///
+    /// # Arguments
+    /// * `tcu` - Port of the TCU which measures the device under test.
+    /// * `reference` - Port of the reference device.
+    ///
+    pub fn process<T: Read + Write, U: Read + Write>(
+        mut self,
+        tcu: &mut T,
+        reference: &mut U,
+    ) -> Result<CrossCheckStatus, Error> {
+        let Some(dut_measurement) = self.dut_measurement else {
+            match self.dut.clone().process(tcu)? {
+                TransactionStatus::Ongoing(transaction) => *self.dut = transaction,
+                TransactionStatus::Recorded { measurement, .. } => {
+                    self.dut_measurement = Some(measurement)
+                }
+                TransactionStatus::Success { .. } => unreachable!("Measuring transactions record"),
+            }
+
+            return Ok(CrossCheckStatus::Ongoing(self));
+        };
+
+        let Some(reference_measurement) = self.reference_measurement else {
+            match self.reference.clone().process(reference)? {
+                TransactionStatus::Ongoing(transaction) => *self.reference = transaction,
+                TransactionStatus::Recorded { measurement, .. } => {
+                    self.reference_measurement = Some(measurement)
+                }
+                TransactionStatus::Success { .. } => unreachable!("Measuring transactions record"),
+            }
+
+            return Ok(CrossCheckStatus::Ongoing(self));
+        };
+
+        let difference = dut_measurement.abs_diff(reference_measurement);
+        let difference = i64::try_from(difference).unwrap_or(i64::MAX);
+
+        if self.record {
+            return Ok(CrossCheckStatus::Recorded {
+                span: self.expression.span().clone(),
+                measurement: difference,
+            });
+        }
+
+        if difference <= i64::from(self.tolerance) {
+            self.report(difference, true);
+            return Ok(CrossCheckStatus::Success);
+        }
+
+        if self.retries > 0 {
+            self.retries -= 1;
+            *self.dut = self.dut.clone().restarted();
+            *self.reference = self.reference.clone().restarted();
+            self.dut_measurement = None;
+            self.reference_measurement = None;
+            return Ok(CrossCheckStatus::Ongoing(self));
+        }
+
+        self.report(difference, false);
+
+        // Limits are unsigned, so clamped for a reference measurement near or below zero.
+        let limit = |limit: i64| u32::try_from(limit.max(0)).unwrap_or(u32::MAX);
+        let minimum = limit(reference_measurement.saturating_sub(self.tolerance.into()));
+        let maximum = limit(reference_measurement.saturating_add(self.tolerance.into()));
+
+        Err(Error::from_failed_test(
+            self.expression,
+            FailedTest {
+                measurement: dut_measurement,
+                expected: Expected::Range(minimum..=maximum),
+                message: self.failure_message,
+                attempt: 1,
+                attempts: 1,
+            },
+        ))
+    }
+
+    /// Report the final outcome of the check to any results.
+    ///
+    fn report(&self, difference: i64, passed: bool) {
+        let (Some(results), Some(channel)) = (&self.results, test_channel(&self.expression)) else {
+            return;
+        };
+
+        results.report(TestOutcome {
+            span: self.expression.span().clone(),
+            device: Device::TCU,
+            channel,
+            measured: difference,
+            passed,
+            message: self.failure_message.clone(),
+            elapsed: self.dut.elapsed(),
+            board: None,
+        });
+    }
+}
+
+////////////////////////////////////////////////////////////////
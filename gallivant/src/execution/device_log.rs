@@ -0,0 +1,267 @@
+use std::{
+    io::{self, Write},
+    ops::Range,
+    sync::{Arc, Mutex},
+};
+
+use chrono::NaiveDateTime;
+
+use super::transaction::Device;
+
+////////////////////////////////////////////////////////////////
+// types
+////////////////////////////////////////////////////////////////
+
+/// Record of every time a device was opened or closed during a run. Cloning a log returns a handle
+/// to the same events.
+///
+#[derive(Clone, Debug, Default)]
+pub struct DeviceLog {
+    events: Arc<Mutex<Vec<DeviceEvent>>>,
+}
+
+////////////////////////////////////////////////////////////////
+
+/// A single open or close of a device.
+///
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DeviceEvent {
+    span: Range<usize>,
+    device: Device,
+    action: DeviceAction,
+    time: NaiveDateTime,
+    outcome: Outcome,
+}
+
+////////////////////////////////////////////////////////////////
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum DeviceAction {
+    Open,
+    Close,
+}
+
+////////////////////////////////////////////////////////////////
+
+/// Result of an open or close request.
+///
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Outcome {
+    /// The frontend hasn't finished handling the request.
+    Pending,
+    Success,
+
+    /// The request failed, with the reason given.
+    Failure(String),
+}
+
+////////////////////////////////////////////////////////////////
+// construction / conversion
+////////////////////////////////////////////////////////////////
+
+impl DeviceLog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+////////////////////////////////////////////////////////////////
+// methods
+////////////////////////////////////////////////////////////////
+
+impl DeviceLog {
+    /// Return the number of events logged.
+    ///
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lock().is_empty()
+    }
+
+    /// Return every logged event in the order they were requested.
+    ///
+    pub fn events(&self) -> Vec<DeviceEvent> {
+        self.lock().clone()
+    }
+
+    /// Export the log as CSV. Each row contains the time the event was requested, the device, the
+    /// action, the command's source text and the outcome.
+    ///
+    /// # Arguments
+    /// * `script` - Source of the script the log was taken from.
+    /// * `writer` - Destination of the CSV.
+    ///
+    pub fn write_csv<W: Write>(&self, script: &str, mut writer: W) -> io::Result<()> {
+        writeln!(writer, "time,device,action,command,outcome")?;
+
+        for event in self.lock().iter() {
+            let command = script.get(event.span.clone()).unwrap_or_default();
+            let command = command.trim().replace('"', "\"\"");
+            let outcome = event.outcome.to_string().replace('"', "\"\"");
+
+            writeln!(
+                writer,
+                "{},{},{},\"{command}\",\"{outcome}\"",
+                event.time.format("%Y-%m-%dT%H:%M:%S%.3f"),
+                event.device,
+                event.action,
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Log a new event, pending it's outcome.
+    ///
+    pub(crate) fn log(
+        &self,
+        span: Range<usize>,
+        device: Device,
+        action: DeviceAction,
+        time: NaiveDateTime,
+    ) {
+        self.lock().push(DeviceEvent {
+            span,
+            device,
+            action,
+            time,
+            outcome: Outcome::Pending,
+        });
+    }
+
+    /// Set the outcome of any pending events requested by the command at the given span.
+    ///
+    pub(crate) fn resolve(&self, span: &Range<usize>, outcome: Outcome) {
+        self.lock()
+            .iter_mut()
+            .filter(|event| event.outcome == Outcome::Pending && &event.span == span)
+            .for_each(|event| event.outcome = outcome.clone());
+    }
+
+    /// Set the outcome of every pending event.
+    ///
+    pub(crate) fn resolve_all(&self, outcome: Outcome) {
+        self.lock()
+            .iter_mut()
+            .filter(|event| event.outcome == Outcome::Pending)
+            .for_each(|event| event.outcome = outcome.clone());
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Vec<DeviceEvent>> {
+        // A poisoned lock only means another thread panicked mid-run. What was logged is still
+        // needed for the audit trail.
+        self.events
+            .lock()
+            .unwrap_or_else(|error| error.into_inner())
+    }
+}
+
+////////////////////////////////////////////////////////////////
+// field access
+////////////////////////////////////////////////////////////////
+
+impl DeviceEvent {
+    /// Return the span of the command in the script that requested the event.
+    ///
+    pub fn span(&self) -> &Range<usize> {
+        &self.span
+    }
+
+    pub fn device(&self) -> Device {
+        self.device
+    }
+
+    pub fn action(&self) -> DeviceAction {
+        self.action
+    }
+
+    /// Return the time the event was requested.
+    ///
+    pub fn time(&self) -> NaiveDateTime {
+        self.time
+    }
+
+    pub fn outcome(&self) -> &Outcome {
+        &self.outcome
+    }
+}
+
+////////////////////////////////////////////////////////////////
+// comparison
+////////////////////////////////////////////////////////////////
+
+impl PartialEq for DeviceLog {
+    fn eq(&self, other: &Self) -> bool {
+        // Logs are handles so compare by identity.
+        Arc::ptr_eq(&self.events, &other.events)
+    }
+}
+
+impl Eq for DeviceLog {}
+
+////////////////////////////////////////////////////////////////
+// ...
+////////////////////////////////////////////////////////////////
+
+impl std::fmt::Display for DeviceAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DeviceAction::Open => write!(f, "open"),
+            DeviceAction::Close => write!(f, "close"),
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////
+
+impl std::fmt::Display for Outcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Outcome::Pending => write!(f, "pending"),
+            Outcome::Success => write!(f, "success"),
+            Outcome::Failure(reason) => write!(f, "failed - {reason}"),
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////
+// tests
+////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::*;
+
+    ////////////////////////////////////////////////////////////////
+
+    #[test]
+    fn test_write_csv() {
+        let script = "USBOPEN\nTCUOPEN 1";
+        let time = NaiveDate::from_ymd_opt(2023, 6, 1)
+            .unwrap()
+            .and_hms_milli_opt(12, 30, 5, 250)
+            .unwrap();
+
+        let log = DeviceLog::new();
+        log.log(0..7, Device::Printer, DeviceAction::Open, time);
+        log.log(8..17, Device::TCU, DeviceAction::Open, time);
+        log.resolve(&(0..7), Outcome::Success);
+        log.resolve(&(8..17), Outcome::Failure("No \"ack\"".to_owned()));
+
+        let mut csv = Vec::new();
+        log.write_csv(script, &mut csv).unwrap();
+
+        assert_eq!(
+            String::from_utf8(csv).unwrap(),
+            "time,device,action,command,outcome\n\
+             2023-06-01T12:30:05.250,printer,open,\"USBOPEN\",\"success\"\n\
+             2023-06-01T12:30:05.250,TCU,open,\"TCUOPEN 1\",\"failed - No \"\"ack\"\"\"\n"
+        );
+    }
+}
+
+////////////////////////////////////////////////////////////////
@@ -0,0 +1,212 @@
+////////////////////////////////////////////////////////////////
+// types
+////////////////////////////////////////////////////////////////
+
+/// Encoding that text printed by the script is converted to before being sent to the printer.
+///
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Encoding {
+    /// Text is sent as written in the script. i.e. As UTF-8.
+    #[default]
+    Passthrough,
+
+    /// ISO 8859-1.
+    Latin1,
+
+    /// Windows-1252. Latin-1 with printable characters in place of the C1 control codes.
+    Windows1252,
+
+    /// Code page 437. The original IBM PC character set.
+    CP437,
+
+    /// Code page 850. Multilingual Latin-1.
+    CP850,
+}
+
+////////////////////////////////////////////////////////////////
+// constants
+////////////////////////////////////////////////////////////////
+
+/// Characters 0x80 to 0x9F of Windows-1252. Unassigned codes map to the matching C1 control code.
+///
+const WINDOWS1252: [char; 32] = [
+    '€', '\u{0081}', '‚', 'ƒ', '„', '…', '†', '‡', 'ˆ', '‰', 'Š', '‹', 'Œ', '\u{008D}', 'Ž',
+    '\u{008F}', '\u{0090}', '‘', '’', '“', '”', '•', '–', '—', '˜', '™', 'š', '›', 'œ', '\u{009D}',
+    'ž', 'Ÿ',
+];
+
+////////////////////////////////////////////////////////////////
+
+/// Characters 0x80 to 0xFF of code page 437.
+///
+const CP437: [char; 128] = [
+    'Ç', 'ü', 'é', 'â', 'ä', 'à', 'å', 'ç', 'ê', 'ë', 'è', 'ï', 'î', 'ì', 'Ä', 'Å', //
+    'É', 'æ', 'Æ', 'ô', 'ö', 'ò', 'û', 'ù', 'ÿ', 'Ö', 'Ü', '¢', '£', '¥', '₧', 'ƒ', //
+    'á', 'í', 'ó', 'ú', 'ñ', 'Ñ', 'ª', 'º', '¿', '⌐', '¬', '½', '¼', '¡', '«', '»', //
+    '░', '▒', '▓', '│', '┤', '╡', '╢', '╖', '╕', '╣', '║', '╗', '╝', '╜', '╛', '┐', //
+    '└', '┴', '┬', '├', '─', '┼', '╞', '╟', '╚', '╔', '╩', '╦', '╠', '═', '╬', '╧', //
+    '╨', '╤', '╥', '╙', '╘', '╒', '╓', '╫', '╪', '┘', '┌', '█', '▄', '▌', '▐', '▀', //
+    'α', 'ß', 'Γ', 'π', 'Σ', 'σ', 'µ', 'τ', 'Φ', 'Θ', 'Ω', 'δ', '∞', 'φ', 'ε', '∩', //
+    '≡', '±', '≥', '≤', '⌠', '⌡', '÷', '≈', '°', '∙', '·', '√', 'ⁿ', '²', '■', '\u{00A0}',
+];
+
+////////////////////////////////////////////////////////////////
+
+/// Characters 0x80 to 0xFF of code page 850.
+///
+const CP850: [char; 128] = [
+    'Ç', 'ü', 'é', 'â', 'ä', 'à', 'å', 'ç', 'ê', 'ë', 'è', 'ï', 'î', 'ì', 'Ä', 'Å', //
+    'É', 'æ', 'Æ', 'ô', 'ö', 'ò', 'û', 'ù', 'ÿ', 'Ö', 'Ü', 'ø', '£', 'Ø', '×', 'ƒ', //
+    'á', 'í', 'ó', 'ú', 'ñ', 'Ñ', 'ª', 'º', '¿', '®', '¬', '½', '¼', '¡', '«', '»', //
+    '░', '▒', '▓', '│', '┤', 'Á', 'Â', 'À', '©', '╣', '║', '╗', '╝', '¢', '¥', '┐', //
+    '└', '┴', '┬', '├', '─', '┼', 'ã', 'Ã', '╚', '╔', '╩', '╦', '╠', '═', '╬', '¤', //
+    'ð', 'Ð', 'Ê', 'Ë', 'È', 'ı', 'Í', 'Î', 'Ï', '┘', '┌', '█', '▄', '¦', 'Ì', '▀', //
+    'Ó', 'ß', 'Ô', 'Ò', 'õ', 'Õ', 'µ', 'þ', 'Þ', 'Ú', 'Û', 'Ù', 'ý', 'Ý', '¯', '´', //
+    '\u{00AD}', '±', '‗', '¾', '¶', '§', '÷', '¸', '°', '¨', '·', '¹', '³', '²', '■', '\u{00A0}',
+];
+
+////////////////////////////////////////////////////////////////
+// methods
+////////////////////////////////////////////////////////////////
+
+impl Encoding {
+    /// Convert text to the encoding. ASCII is unchanged by every encoding.
+    ///
+    /// # Errors
+    /// The first character in the text that the encoding can't represent.
+    ///
+    pub fn encode(&self, text: &str) -> Result<Vec<u8>, char> {
+        if *self == Encoding::Passthrough {
+            return Ok(text.as_bytes().to_vec());
+        }
+
+        text.chars()
+            .map(|character| self.encode_char(character).ok_or(character))
+            .collect()
+    }
+
+    fn encode_char(&self, character: char) -> Option<u8> {
+        if character.is_ascii() {
+            return Some(character as u8);
+        }
+
+        let upper = |table: &[char]| {
+            let position = table.iter().position(|c| *c == character)?;
+            u8::try_from(0x80 + position).ok()
+        };
+
+        match self {
+            Encoding::Passthrough => None,
+            Encoding::Latin1 => u8::try_from(u32::from(character)).ok(),
+            Encoding::Windows1252 => {
+                upper(&WINDOWS1252).or_else(|| match u8::try_from(u32::from(character)) {
+                    Ok(byte) if byte >= 0xA0 => Some(byte),
+                    _ => None,
+                })
+            }
+            Encoding::CP437 => upper(&CP437),
+            Encoding::CP850 => upper(&CP850),
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////
+// ...
+////////////////////////////////////////////////////////////////
+
+impl std::fmt::Display for Encoding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Encoding::Passthrough => write!(f, "passthrough"),
+            Encoding::Latin1 => write!(f, "latin1"),
+            Encoding::Windows1252 => write!(f, "cp1252"),
+            Encoding::CP437 => write!(f, "cp437"),
+            Encoding::CP850 => write!(f, "cp850"),
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////
+
+impl std::str::FromStr for Encoding {
+    type Err = String;
+
+    /// Parse an encoding from it's name as displayed. Case insensitive.
+    ///
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        [
+            Encoding::Passthrough,
+            Encoding::Latin1,
+            Encoding::Windows1252,
+            Encoding::CP437,
+            Encoding::CP850,
+        ]
+        .into_iter()
+        .find(|encoding| encoding.to_string().eq_ignore_ascii_case(name))
+        .ok_or_else(|| {
+            format!(
+                "Unknown encoding '{name}'. Expected passthrough, latin1, cp1252, cp437 or cp850"
+            )
+        })
+    }
+}
+
+////////////////////////////////////////////////////////////////
+// tests
+////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    ////////////////////////////////////////////////////////////////
+
+    #[test]
+    fn test_encode() {
+        let text = "25°C café";
+
+        assert_eq!(
+            Encoding::Passthrough.encode(text),
+            Ok(text.as_bytes().to_vec())
+        );
+        assert_eq!(
+            Encoding::Latin1.encode(text),
+            Ok(b"25\xB0C caf\xE9".to_vec())
+        );
+        assert_eq!(
+            Encoding::Windows1252.encode(text),
+            Ok(b"25\xB0C caf\xE9".to_vec())
+        );
+        assert_eq!(
+            Encoding::CP437.encode(text),
+            Ok(b"25\xF8C caf\x82".to_vec())
+        );
+        assert_eq!(
+            Encoding::CP850.encode(text),
+            Ok(b"25\xF8C caf\x82".to_vec())
+        );
+
+        assert_eq!(Encoding::Windows1252.encode("5€"), Ok(b"5\x80".to_vec()));
+    }
+
+    ////////////////////////////////////////////////////////////////
+
+    #[test]
+    fn test_unrepresentable() {
+        assert_eq!(Encoding::Latin1.encode("5€"), Err('€'));
+        assert_eq!(Encoding::Windows1252.encode("\u{0080}"), Err('\u{0080}'));
+        assert_eq!(Encoding::CP437.encode("Ø"), Err('Ø'));
+        assert_eq!(Encoding::CP850.encode("Ø"), Ok(vec![0x9D]));
+    }
+
+    ////////////////////////////////////////////////////////////////
+
+    #[test]
+    fn test_from_str() {
+        assert_eq!("CP850".parse(), Ok(Encoding::CP850));
+        assert_eq!("passthrough".parse(), Ok(Encoding::Passthrough));
+        assert!("utf-16".parse::<Encoding>().is_err());
+    }
+}
+
+////////////////////////////////////////////////////////////////
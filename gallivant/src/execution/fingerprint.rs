@@ -0,0 +1,101 @@
+////////////////////////////////////////////////////////////////
+// types
+////////////////////////////////////////////////////////////////
+
+/// 64 bit FNV-1a hash used to fingerprint a script's behaviour. Used instead of the hashers in
+/// std as their output isn't guaranteed to be the same between releases or platforms.
+///
+/// Every value is written in a fixed byte order and variable length values are prefixed with
+/// their length so that adjacent values can't run into each other.
+///
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Fingerprint(u64);
+
+////////////////////////////////////////////////////////////////
+// construction / conversion
+////////////////////////////////////////////////////////////////
+
+impl Fingerprint {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+
+    pub(crate) fn new() -> Self {
+        Self(Self::OFFSET_BASIS)
+    }
+}
+
+////////////////////////////////////////////////////////////////
+// methods
+////////////////////////////////////////////////////////////////
+
+impl Fingerprint {
+    pub(crate) fn write_u32(&mut self, value: u32) {
+        self.write(&value.to_le_bytes());
+    }
+
+    pub(crate) fn write_u64(&mut self, value: u64) {
+        self.write(&value.to_le_bytes());
+    }
+
+    pub(crate) fn write_bytes(&mut self, bytes: &[u8]) {
+        self.write_u64(bytes.len() as u64);
+        self.write(bytes);
+    }
+
+    pub(crate) fn write_str(&mut self, string: &str) {
+        self.write_bytes(string.as_bytes());
+    }
+
+    pub(crate) fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= u64::from(*byte);
+            self.0 = self.0.wrapping_mul(Self::PRIME);
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////
+// tests
+////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    ////////////////////////////////////////////////////////////////
+
+    #[test]
+    fn test_fnv1a() {
+        // Published FNV-1a test vectors.
+        let hash = |bytes: &[u8]| {
+            let mut fingerprint = Fingerprint::new();
+            fingerprint.write(bytes);
+            fingerprint.finish()
+        };
+
+        assert_eq!(hash(b""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(hash(b"a"), 0xaf63_dc4c_8601_ec8c);
+        assert_eq!(hash(b"foobar"), 0x8594_4171_f739_67e8);
+    }
+
+    ////////////////////////////////////////////////////////////////
+
+    #[test]
+    fn test_length_prefixed() {
+        let hash = |strings: &[&str]| {
+            let mut fingerprint = Fingerprint::new();
+            for string in strings {
+                fingerprint.write_str(string);
+            }
+            fingerprint.finish()
+        };
+
+        assert_ne!(hash(&["ab", "c"]), hash(&["a", "bc"]));
+    }
+}
+
+////////////////////////////////////////////////////////////////
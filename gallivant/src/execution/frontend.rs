@@ -1,11 +1,21 @@
-use std::time::Duration;
+use std::{ops::Range, time::Duration};
 
-use super::transaction::Transaction;
+use super::{
+    cross_check::CrossCheck,
+    fingerprint::Fingerprint,
+    transaction::{Device, Transaction},
+};
 
 ////////////////////////////////////////////////////////////////
 // types
 ////////////////////////////////////////////////////////////////
 
+/// Baud rates a port can be reconfigured to by SETBAUD.
+///
+pub const BAUD_RATES: [u32; 8] = [1200, 2400, 4800, 9600, 19200, 38400, 57600, 115200];
+
+////////////////////////////////////////////////////////////////
+
 /// Requests for actions a frontend needs to perform during script execution.
 ///
 #[derive(Clone, Debug, PartialEq)]
@@ -14,15 +24,104 @@ pub enum FrontendRequest {
     Wait(Duration),
 
     GuiPrint(String),
-    GuiDialogue { kind: Dialog, message: String },
+    GuiDialogue {
+        kind: Dialog,
+        message: String,
+    },
+
+    /// Sound an alert to draw the operator's attention.
+    Beep,
 
     TCUTransact(Transaction),
     TCUFlush,
 
-    // Requests for direct communication with the printer i.e. not via the TCU.
+    // Requests for direct communication with the printer i.e. not via the TCU. Transactions are
+    // with Device::USB.
     PrinterOpen,
     PrinterClose,
     PrinterTransact(Transaction),
+
+    /// Reopen the port the printer is directly connected to at a new baud rate, one of
+    /// [`BAUD_RATES`]. e.g. After a command switching the printer's baud rate. SETBAUD only applies
+    /// to this port so the device is always [`Device::USB`].
+    Reconfigure {
+        baud: u32,
+    },
+
+    /// Download firmware from an HTTP or HTTPS URL and program it into the device. The download
+    /// is left to the frontend so the crate remains independent of any IO.
+    FetchAndProgram {
+        url: String,
+        device: Device,
+    },
+
+    /// Measure the device under test via the TCU and compare against the reference device.
+    CrossCheck(CrossCheck),
+
+    /// A timer was stopped and any test of the elapsed time passed.
+    Timed {
+        span: Range<usize>,
+        name: String,
+        elapsed: Duration,
+
+        /// The elapsed time wasn't tested as the script is being run in record mode.
+        recorded: bool,
+    },
+
+    /// Latencies stopped being collected and the test of their percentile passed.
+    Latency {
+        span: Range<usize>,
+        name: String,
+
+        /// The percentile of the latencies collected.
+        latency: Duration,
+
+        /// Number of latencies collected.
+        samples: usize,
+
+        /// The percentile wasn't tested as the script is being run in record mode.
+        recorded: bool,
+    },
+
+    /// The ratio of two stored measurements, in thousandths, passed it's test.
+    Ratio {
+        span: Range<usize>,
+        ratio: i64,
+
+        /// The ratio wasn't tested as the script is being run in record mode.
+        recorded: bool,
+    },
+
+    /// A statement wasn't run as one of it's annotations prevented it.
+    Skipped {
+        span: Range<usize>,
+        reason: String,
+    },
+
+    /// Bytes a transaction is about to transmit, in hex, while protocol mode is toggled on by
+    /// PROTOCOL. Reported before the request making the transaction.
+    Protocol {
+        device: Device,
+        dump: String,
+    },
+
+    /// The step of the test now running changed. Reported before the step's first request.
+    Step(String),
+
+    /// Number of the script's top-level statements completed so far, out of the total. Only
+    /// reported if requested by [`Interpreter::with_progress`].
+    ///
+    /// [`Interpreter::with_progress`]: crate::Interpreter::with_progress
+    Progress {
+        current: usize,
+        total: usize,
+    },
+
+    /// The run is paused so nothing was done. Reported in place of every request until the run is
+    /// resumed by [`Interpreter::resume`].
+    ///
+    /// [`Interpreter::resume`]: crate::Interpreter::resume
+    Paused,
 }
 
 ////////////////////////////////////////////////////////////////
@@ -34,7 +133,143 @@ pub enum Dialog {
     Notification,
 
     /// Dialog that should display a message and allow the user to either continue or stop the test.
-    ManualInput,
+    /// If it has a timeout, the frontend should answer it automatically once the timeout expires.
+    ManualInput {
+        timeout: Option<DialogTimeout>,
+    },
+
+    /// Dialog that should display a message and allow the operator to enter text. The frontend
+    /// must report the text entered with [`Interpreter::enter_input`].
+    ///
+    /// [`Interpreter::enter_input`]: crate::Interpreter::enter_input
+    Input,
+
+    /// Safety check the operator must confirm before the script can continue. The frontend must
+    /// report the operator's answer with [`Interpreter::confirm`].
+    ///
+    /// [`Interpreter::confirm`]: crate::Interpreter::confirm
+    Confirmation,
+}
+
+////////////////////////////////////////////////////////////////
+
+/// Time after which a dialog the operator hasn't answered is answered automatically, and how.
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct DialogTimeout {
+    pub duration: Duration,
+    pub action: TimeoutAction,
+}
+
+////////////////////////////////////////////////////////////////
+
+/// How a dialog is answered once it's timeout expires.
+///
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum TimeoutAction {
+    /// Continue the test as if the operator had.
+    #[default]
+    Continue,
+
+    /// Stop the test as if the operator had.
+    Fail,
+}
+
+////////////////////////////////////////////////////////////////
+// methods
+////////////////////////////////////////////////////////////////
+
+impl FrontendRequest {
+    /// Return the devices the frontend needs to communicate with to fulfil the request.
+    ///
+    pub fn devices(&self) -> &'static [Device] {
+        match self {
+            FrontendRequest::TCUTransact(_) | FrontendRequest::TCUFlush => &[Device::TCU],
+            FrontendRequest::PrinterOpen
+            | FrontendRequest::PrinterClose
+            | FrontendRequest::PrinterTransact(_)
+            | FrontendRequest::Reconfigure { .. }
+            | FrontendRequest::FetchAndProgram {
+                device: Device::USB,
+                ..
+            } => &[Device::USB],
+            FrontendRequest::CrossCheck(_) => &[Device::TCU, Device::Reference],
+            _ => &[],
+        }
+    }
+
+    /// Add the request's significant content to the fingerprint. Spans and the values of
+    /// measurements taken while running aren't significant.
+    ///
+    pub(crate) fn fingerprint(&self, fingerprint: &mut Fingerprint) {
+        match self {
+            FrontendRequest::None => (),
+            FrontendRequest::Wait(duration) => {
+                fingerprint.write_str("wait");
+                fingerprint.write_u64(duration.as_millis() as u64);
+            }
+            FrontendRequest::GuiPrint(message) => {
+                fingerprint.write_str("print");
+                fingerprint.write_str(message);
+            }
+            FrontendRequest::GuiDialogue { kind, message } => {
+                fingerprint.write_str("dialog");
+                match kind {
+                    Dialog::ManualInput { timeout } => {
+                        fingerprint.write_str("ManualInput");
+                        if let Some(timeout) = timeout {
+                            fingerprint.write_u64(timeout.duration.as_millis() as u64);
+                            fingerprint.write_str(&format!("{:?}", timeout.action));
+                        }
+                    }
+                    kind => fingerprint.write_str(&format!("{kind:?}")),
+                }
+                fingerprint.write_str(message);
+            }
+            FrontendRequest::Beep => fingerprint.write_str("beep"),
+            FrontendRequest::TCUTransact(transaction) => {
+                fingerprint.write_str("tcu transact");
+                transaction.fingerprint(fingerprint);
+            }
+            FrontendRequest::TCUFlush => fingerprint.write_str("tcu flush"),
+            FrontendRequest::PrinterOpen => fingerprint.write_str("printer open"),
+            FrontendRequest::PrinterClose => fingerprint.write_str("printer close"),
+            FrontendRequest::PrinterTransact(transaction) => {
+                fingerprint.write_str("printer transact");
+                transaction.fingerprint(fingerprint);
+            }
+            FrontendRequest::Reconfigure { baud } => {
+                fingerprint.write_str("reconfigure");
+                fingerprint.write_u64(u64::from(*baud));
+            }
+            FrontendRequest::FetchAndProgram { url, device } => {
+                fingerprint.write_str("program");
+                fingerprint.write_str(&device.to_string());
+                fingerprint.write_str(url);
+            }
+            FrontendRequest::CrossCheck(check) => {
+                fingerprint.write_str("cross check");
+                check.fingerprint(fingerprint);
+            }
+            FrontendRequest::Timed { name, .. } => {
+                fingerprint.write_str("timed");
+                fingerprint.write_str(name);
+            }
+            FrontendRequest::Latency { name, .. } => {
+                fingerprint.write_str("latency");
+                fingerprint.write_str(name);
+            }
+            FrontendRequest::Ratio { .. } => fingerprint.write_str("ratio"),
+            FrontendRequest::Skipped { reason, .. } => {
+                fingerprint.write_str("skipped");
+                fingerprint.write_str(reason);
+            }
+            FrontendRequest::Protocol { .. }
+            | FrontendRequest::Step(_)
+            | FrontendRequest::Progress { .. }
+            | FrontendRequest::Paused => (),
+        }
+    }
 }
 
 ////////////////////////////////////////////////////////////////
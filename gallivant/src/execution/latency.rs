@@ -0,0 +1,160 @@
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+////////////////////////////////////////////////////////////////
+// types
+////////////////////////////////////////////////////////////////
+
+/// Response latencies collected by name during a run. Each collector receives the latency of
+/// every response completed while it's running. Cloning a log returns a handle to the same
+/// collectors, allowing transactions to record into it once the frontend has completed them.
+///
+#[derive(Clone, Debug, Default)]
+pub(crate) struct LatencyLog {
+    collectors: Arc<Mutex<BTreeMap<String, Vec<Duration>>>>,
+}
+
+////////////////////////////////////////////////////////////////
+// methods
+////////////////////////////////////////////////////////////////
+
+impl LatencyLog {
+    /// Start collecting latencies under the name, discarding any previously collected.
+    ///
+    pub(crate) fn start(&self, name: &str) {
+        self.lock().insert(name.to_owned(), Vec::new());
+    }
+
+    /// Stop collecting latencies under the name.
+    ///
+    /// # Returns
+    /// The latencies collected. None if the collector wasn't running.
+    ///
+    pub(crate) fn stop(&self, name: &str) -> Option<Vec<Duration>> {
+        self.lock().remove(name)
+    }
+
+    /// Record the latency of a response to every running collector.
+    ///
+    pub(crate) fn record(&self, latency: Duration) {
+        for latencies in self.lock().values_mut() {
+            latencies.push(latency);
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, BTreeMap<String, Vec<Duration>>> {
+        self.collectors
+            .lock()
+            .unwrap_or_else(|error| error.into_inner())
+    }
+}
+
+////////////////////////////////////////////////////////////////
+
+/// Return the percentile of the latencies using the nearest-rank method. i.e. The smallest latency
+/// that at least the given percentage of latencies are less than or equal to.
+///
+/// No interpolation is performed, so the percentile is always one of the latencies. With fewer
+/// samples than needed to distinguish the percentile it's the largest latency. e.g. The 95th
+/// percentile of less than 20 latencies.
+///
+/// # Arguments
+/// * `latencies` - Latencies collected.
+/// * `percentile` - Percentile between 1 and 100.
+///
+/// # Returns
+/// None if there are no latencies.
+///
+pub(crate) fn percentile(latencies: &[Duration], percentile: u32) -> Option<Duration> {
+    debug_assert!((1..=100).contains(&percentile));
+
+    let mut latencies = latencies.to_vec();
+    latencies.sort();
+
+    // Rank is ceil(percentile / 100 * count), from 1.
+    let rank = (percentile as usize * latencies.len()).div_ceil(100);
+    latencies.get(rank.max(1) - 1).copied()
+}
+
+////////////////////////////////////////////////////////////////
+// comparison
+////////////////////////////////////////////////////////////////
+
+impl PartialEq for LatencyLog {
+    fn eq(&self, other: &Self) -> bool {
+        // Logs are handles so compare by identity.
+        Arc::ptr_eq(&self.collectors, &other.collectors)
+    }
+}
+
+impl Eq for LatencyLog {}
+
+////////////////////////////////////////////////////////////////
+// tests
+////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    ////////////////////////////////////////////////////////////////
+
+    fn millis(latencies: impl IntoIterator<Item = u64>) -> Vec<Duration> {
+        latencies.into_iter().map(Duration::from_millis).collect()
+    }
+
+    ////////////////////////////////////////////////////////////////
+
+    #[test]
+    fn test_percentile() {
+        let latencies = millis(1..=100);
+        assert_eq!(percentile(&latencies, 95), Some(Duration::from_millis(95)));
+        assert_eq!(
+            percentile(&latencies, 100),
+            Some(Duration::from_millis(100))
+        );
+        assert_eq!(percentile(&latencies, 1), Some(Duration::from_millis(1)));
+
+        // Order doesn't matter.
+        let latencies = millis([40, 10, 30, 20]);
+        assert_eq!(percentile(&latencies, 50), Some(Duration::from_millis(20)));
+        assert_eq!(percentile(&latencies, 51), Some(Duration::from_millis(30)));
+    }
+
+    ////////////////////////////////////////////////////////////////
+
+    #[test]
+    fn test_percentile_few_samples() {
+        // 95% of 19 samples is 18.05, so every sample must be within the bound.
+        let latencies = millis(1..=19);
+        assert_eq!(percentile(&latencies, 95), Some(Duration::from_millis(19)));
+
+        let latencies = millis(1..=20);
+        assert_eq!(percentile(&latencies, 95), Some(Duration::from_millis(19)));
+
+        assert_eq!(percentile(&millis([7]), 1), Some(Duration::from_millis(7)));
+        assert_eq!(percentile(&[], 95), None);
+    }
+
+    ////////////////////////////////////////////////////////////////
+
+    #[test]
+    fn test_collectors() {
+        let log = LatencyLog::default();
+        log.record(Duration::from_millis(1));
+
+        log.start("all");
+        log.record(Duration::from_millis(2));
+        log.start("last");
+        log.clone().record(Duration::from_millis(3));
+
+        assert_eq!(log.stop("last"), Some(millis([3])));
+        assert_eq!(log.stop("all"), Some(millis([2, 3])));
+        assert_eq!(log.stop("all"), None);
+    }
+}
+
+////////////////////////////////////////////////////////////////
@@ -1,13 +1,54 @@
-use std::ops::RangeInclusive;
+use std::{
+    collections::BTreeSet,
+    ops::{Bound, Range, RangeBounds, RangeFrom, RangeInclusive, RangeTo, RangeToInclusive},
+    sync::Arc,
+};
 
 ////////////////////////////////////////////////////////////////
 // types
 ////////////////////////////////////////////////////////////////
 
-/// A measurement returned from either the TCU or the printer's debug protocol.
+/// A measurement returned from either the TCU or the printer's debug protocol. Measurements are
+/// signed as some devices report negative readings, though test limits are never negative.
 ///
 #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
-pub struct Measurement(u32);
+pub struct Measurement(i64);
+
+////////////////////////////////////////////////////////////////
+
+/// Text formats a device may report it's measurements in.
+///
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum MeasurementFormat {
+    /// Hexadecimal, as reported by the TCU. May be prefixed by `0x` and preceded by a sign. e.g.
+    /// `1A2B`, `0xFF` or `-00C8`.
+    #[default]
+    HexU32,
+
+    /// Unsigned decimal. e.g. `255`.
+    DecU32,
+
+    /// Signed decimal. e.g. `-12`.
+    DecI32,
+}
+
+////////////////////////////////////////////////////////////////
+
+/// Unit a device may append to it's text measurements. e.g. `1200mV` or `55Hz`.
+///
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum UnitSuffix {
+    /// Measurements have no unit, so any suffix is invalid.
+    #[default]
+    Unitless,
+
+    /// Any unit is ignored. The unit is every trailing character that can't be a digit of the
+    /// measurement's format, so a hex measurement's unit can't end in a hex digit. e.g. `dB`.
+    Any,
+
+    /// Measurements must be followed by the given unit, which is ignored. e.g. `mV`.
+    Expected(String),
+}
 
 ////////////////////////////////////////////////////////////////
 
@@ -15,8 +56,13 @@ pub struct Measurement(u32);
 ///
 #[derive(Clone, Debug, PartialEq)]
 pub struct MeasurementTest {
-    pub expected: RangeInclusive<u32>,
+    pub expected: Expected,
     pub retries: u32,
+
+    /// Attempts at the test that have already failed, each using one of it's retries. Along with
+    /// the remaining retries this gives the total number of attempts the test allows.
+    pub attempt: u32,
+
     pub failure_message: String,
 }
 
@@ -26,17 +72,140 @@ pub struct MeasurementTest {
 ///
 #[derive(Clone, Debug, PartialEq)]
 pub struct FailedTest {
-    pub measurement: u32,
-    pub expected: RangeInclusive<u32>,
+    pub measurement: i64,
+    pub expected: Expected,
     pub message: String,
+
+    /// The attempt that failed, counting from 1, out of the total attempts allowed.
+    pub attempt: u32,
+    pub attempts: u32,
+}
+
+////////////////////////////////////////////////////////////////
+
+/// Values a measurement must take to pass a test.
+///
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Expected {
+    Range(RangeInclusive<u32>),
+
+    /// The measurement must compare to the value using the operator. e.g. `>= 3000`.
+    Comparison(Comparison, u32),
+
+    /// The measurement must lie between a lower and upper bound, either of which may be exclusive
+    /// or absent. e.g. Greater than 500 with no upper limit.
+    Bounds(Bound<u32>, Bound<u32>),
+
+    /// The measurement must be one of a set of discrete values. e.g. Allowed status codes.
+    OneOf(BTreeSet<u32>),
+
+    /// A number of measurements must be taken with the difference between the largest and
+    /// smallest being no more than the spread. The value tested is the observed spread.
+    Stable {
+        samples: u32,
+        spread: u32,
+    },
+
+    /// The measurement must be within the tolerance, in percent, of the baseline. The baseline is
+    /// the measurement taken the first time the test ran, which always passes.
+    Baseline {
+        tolerance: u32,
+        baseline: Option<i64>,
+    },
+
+    /// The measurement's bits under each mask must equal the value paired with it. e.g. Fault
+    /// flags packed into a status word.
+    Bitfield(Vec<(u32, u32)>),
 }
 
 ////////////////////////////////////////////////////////////////
 
+/// Operators for comparing a measurement against a single value.
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Comparison {
+    GreaterEqual,
+    LessEqual,
+    Greater,
+    Less,
+}
+
+////////////////////////////////////////////////////////////////
+
+/// Ways of reducing several measurements taken for a test to the one tested.
+///
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SampleReduction {
+    /// Test the average of the measurements.
+    #[default]
+    Average,
+
+    /// Test the smallest measurement.
+    Min,
+
+    /// Test the largest measurement.
+    Max,
+
+    /// Pass only if every measurement passes. The first to fail is tested.
+    All,
+
+    /// Pass if any measurement passes. The first to pass is tested.
+    Any,
+}
+
+////////////////////////////////////////////////////////////////
+
+/// Parser for measurements with framing or an encoding the built in formats can't handle. Given the
+/// measurement once it's complete, i.e. after any status line is removed and any lines it's spread
+/// over are joined, including the terminating carriage return.
+///
+/// Cloning the parser returns a handle to the same function.
+///
+#[derive(Clone)]
+#[allow(clippy::type_complexity)]
+pub struct MeasurementParser(Arc<dyn Fn(&[u8]) -> Result<Measurement, Error> + Send + Sync>);
+
+////////////////////////////////////////////////////////////////
+
+/// Reasons a measurement encoded as packed BCD is invalid.
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BcdError {
+    /// A nibble of the byte isn't a decimal digit.
+    InvalidNibble(u8),
+
+    /// The measurement wasn't the expected number of bytes.
+    Length { expected: usize, received: usize },
+
+    /// The measurement is too large to be represented.
+    Overflow,
+}
+
+////////////////////////////////////////////////////////////////
+
+/// A measurement wasn't followed by the unit it was expected to be in.
+///
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UnitError {
+    pub expected: String,
+}
+
+////////////////////////////////////////////////////////////////
+
+#[allow(clippy::enum_variant_names)]
 #[derive(Debug)]
 pub enum Error {
     TestFailed(FailedTest),
-    TestFailedRetryable(MeasurementTest),
+
+    /// The test failed but has retries left. The test to retry has the retry used.
+    TestFailedRetryable {
+        test: MeasurementTest,
+        measurement: Measurement,
+        attempt: u32,
+        attempts: u32,
+    },
 
     /// Parsing of a measurement failed.
     ParseError(Box<dyn std::error::Error>),
@@ -62,19 +231,280 @@ impl From<std::num::ParseIntError> for Error {
 
 ////////////////////////////////////////////////////////////////
 
-impl TryFrom<&[u8]> for Measurement {
-    type Error = Error;
+impl From<u32> for Measurement {
+    fn from(measurement: u32) -> Self {
+        Self(measurement.into())
+    }
+}
 
-    fn try_from(bytes: &[u8]) -> Result<Self, Self::Error> {
+impl From<i64> for Measurement {
+    fn from(measurement: i64) -> Self {
+        Self(measurement)
+    }
+}
+
+////////////////////////////////////////////////////////////////
+
+impl MeasurementParser {
+    pub fn new(
+        parse: impl Fn(&[u8]) -> Result<Measurement, Error> + Send + Sync + 'static,
+    ) -> Self {
+        Self(Arc::new(parse))
+    }
+}
+
+////////////////////////////////////////////////////////////////
+
+impl From<BcdError> for Error {
+    fn from(error: BcdError) -> Self {
+        Self::ParseError(Box::new(error))
+    }
+}
+
+////////////////////////////////////////////////////////////////
+
+impl From<UnitError> for Error {
+    fn from(error: UnitError) -> Self {
+        Self::ParseError(Box::new(error))
+    }
+}
+
+////////////////////////////////////////////////////////////////
+
+impl Measurement {
+    /// Parse a measurement from a device's response.
+    ///
+    /// # Arguments
+    /// * `bytes` - Response containing the measurement as hex, terminated by a carriage return.
+    ///   The measurement may be preceded by a sign. e.g. `+1A2B` or `-00C8`.
+    /// * `strict` - If false, whitespace surrounding the measurement is ignored. Some firmware
+    ///   versions pad their measurements with spaces.
+    ///
+    pub fn parse(bytes: &[u8], strict: bool) -> Result<Self, Error> {
+        Self::parse_formatted(bytes, MeasurementFormat::HexU32, strict)
+    }
+
+    /// Parse a measurement from a device's response in the given format.
+    ///
+    /// # Arguments
+    /// * `bytes` - Response containing the measurement, terminated by a carriage return.
+    /// * `format` - Format the measurement is in.
+    /// * `strict` - If false, whitespace surrounding the measurement is ignored.
+    ///
+    pub fn parse_formatted(
+        bytes: &[u8],
+        format: MeasurementFormat,
+        strict: bool,
+    ) -> Result<Self, Error> {
+        Self::parse_with_unit(bytes, format, &UnitSuffix::Unitless, strict)
+    }
+
+    /// Parse a measurement from a device's response in the given format, followed by a unit.
+    ///
+    /// # Arguments
+    /// * `bytes` - Response containing the measurement, terminated by a carriage return.
+    /// * `format` - Format the measurement is in.
+    /// * `unit` - Unit following the measurement. Removed before it's parsed.
+    /// * `strict` - If false, whitespace surrounding the measurement, or between it and it's unit,
+    ///   is ignored.
+    ///
+    pub fn parse_with_unit(
+        bytes: &[u8],
+        format: MeasurementFormat,
+        unit: &UnitSuffix,
+        strict: bool,
+    ) -> Result<Self, Error> {
         let measurement = std::str::from_utf8(bytes)?;
         let measurement = measurement
             .chars()
             .take_while(|&c| c != '\r')
             .collect::<String>();
 
-        let measurement = u32::from_str_radix(&measurement, 16)?;
+        let measurement = if strict {
+            measurement.as_str()
+        } else {
+            measurement.trim()
+        };
+
+        let measurement = match unit {
+            UnitSuffix::Unitless => measurement,
+            UnitSuffix::Any => measurement.trim_end_matches(|c| !format.is_digit(c)),
+            UnitSuffix::Expected(unit) => {
+                measurement.strip_suffix(unit.as_str()).ok_or(UnitError {
+                    expected: unit.clone(),
+                })?
+            }
+        };
+
+        let measurement = if strict {
+            measurement
+        } else {
+            measurement.trim_end()
+        };
+
+        let measurement = match format {
+            MeasurementFormat::HexU32 => {
+                // Any 0x prefix follows the sign.
+                let sign = usize::from(measurement.starts_with(['+', '-']));
+                let (sign, digits) = measurement.split_at(sign);
+                let measurement = match digits.strip_prefix("0x").or(digits.strip_prefix("0X")) {
+                    Some(digits) if !digits.starts_with(['+', '-']) => format!("{sign}{digits}"),
+                    _ => measurement.to_owned(),
+                };
+
+                // Accepts a leading sign.
+                i64::from_str_radix(&measurement, 16)?
+            }
+            MeasurementFormat::DecU32 => measurement.parse::<u32>()?.into(),
+            MeasurementFormat::DecI32 => measurement.parse::<i32>()?.into(),
+        };
+
         Ok(Measurement(measurement))
     }
+
+    /// Parse a measurement from a device's response, where the measurement is encoded as packed
+    /// BCD. i.e. Two decimal digits per byte, most significant first. e.g. `[0x12, 0x34]` is 1234.
+    ///
+    /// # Arguments
+    /// * `bytes` - Response containing the measurement, terminated by a carriage return. As a
+    ///   carriage return isn't valid BCD, it can't occur within the measurement.
+    /// * `length` - Number of bytes the measurement is encoded in.
+    ///
+    pub fn parse_bcd(bytes: &[u8], length: usize) -> Result<Self, Error> {
+        let measurement: Vec<u8> = bytes.iter().copied().take_while(|&b| b != b'\r').collect();
+
+        if measurement.len() != length {
+            return Err(BcdError::Length {
+                expected: length,
+                received: measurement.len(),
+            }
+            .into());
+        }
+
+        let measurement = measurement.into_iter().try_fold(0u32, |value, byte| {
+            let (high, low) = (u32::from(byte >> 4), u32::from(byte & 0x0F));
+            if high > 9 || low > 9 {
+                return Err(BcdError::InvalidNibble(byte));
+            }
+
+            value
+                .checked_mul(100)
+                .and_then(|value| value.checked_add(high * 10 + low))
+                .ok_or(BcdError::Overflow)
+        })?;
+
+        Ok(Measurement(measurement.into()))
+    }
+}
+
+////////////////////////////////////////////////////////////////
+
+impl Measurement {
+    /// Return the spread of a set of measurements. i.e. The difference between the largest and
+    /// smallest.
+    ///
+    pub(crate) fn spread(samples: &[Measurement]) -> Self {
+        let max = samples.iter().max().map_or(0, Measurement::value);
+        let min = samples.iter().min().map_or(0, Measurement::value);
+        Measurement(max - min)
+    }
+
+    /// Return the average of a set of measurements, rounded towards zero.
+    ///
+    fn average(samples: &[Measurement]) -> Self {
+        let sum: i128 = samples.iter().map(|sample| i128::from(sample.0)).sum();
+        let count = samples.len().max(1) as i128;
+        Measurement((sum / count) as i64)
+    }
+}
+
+////////////////////////////////////////////////////////////////
+
+impl MeasurementFormat {
+    /// Return the name of the format as used in error messages.
+    ///
+    pub fn name(&self) -> &'static str {
+        match self {
+            MeasurementFormat::HexU32 => "hex",
+            MeasurementFormat::DecU32 => "decimal",
+            MeasurementFormat::DecI32 => "signed decimal",
+        }
+    }
+
+    /// Return true if the character is a digit of measurements in the format.
+    ///
+    fn is_digit(&self, character: char) -> bool {
+        match self {
+            MeasurementFormat::HexU32 => character.is_ascii_hexdigit(),
+            MeasurementFormat::DecU32 | MeasurementFormat::DecI32 => character.is_ascii_digit(),
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////
+
+impl TryFrom<&[u8]> for Measurement {
+    type Error = Error;
+
+    fn try_from(bytes: &[u8]) -> Result<Self, Self::Error> {
+        Self::parse(bytes, false)
+    }
+}
+
+////////////////////////////////////////////////////////////////
+
+impl Expected {
+    /// Return the range of measurements within a tolerance, in percent, of a nominal value. e.g.
+    /// 1000 ±5% is 950..=1050. The tolerance is rounded down to a whole measurement and the range
+    /// is limited to the values a limit can take.
+    ///
+    pub fn tolerance(nominal: u32, percent: u32) -> Self {
+        let deviation = u64::from(nominal) * u64::from(percent) / 100;
+        let deviation = u32::try_from(deviation).unwrap_or(u32::MAX);
+        Self::Range(nominal.saturating_sub(deviation)..=nominal.saturating_add(deviation))
+    }
+
+    fn from_bounds(range: &impl RangeBounds<u32>) -> Self {
+        Self::Bounds(range.start_bound().cloned(), range.end_bound().cloned())
+    }
+}
+
+////////////////////////////////////////////////////////////////
+
+impl From<RangeInclusive<u32>> for Expected {
+    fn from(range: RangeInclusive<u32>) -> Self {
+        Self::Range(range)
+    }
+}
+
+impl From<Range<u32>> for Expected {
+    fn from(range: Range<u32>) -> Self {
+        Self::from_bounds(&range)
+    }
+}
+
+impl From<RangeFrom<u32>> for Expected {
+    fn from(range: RangeFrom<u32>) -> Self {
+        Self::from_bounds(&range)
+    }
+}
+
+impl From<RangeTo<u32>> for Expected {
+    fn from(range: RangeTo<u32>) -> Self {
+        Self::from_bounds(&range)
+    }
+}
+
+impl From<RangeToInclusive<u32>> for Expected {
+    fn from(range: RangeToInclusive<u32>) -> Self {
+        Self::from_bounds(&range)
+    }
+}
+
+impl From<(Bound<u32>, Bound<u32>)> for Expected {
+    fn from(bounds: (Bound<u32>, Bound<u32>)) -> Self {
+        Self::from_bounds(&bounds)
+    }
 }
 
 ////////////////////////////////////////////////////////////////
@@ -82,19 +512,85 @@ impl TryFrom<&[u8]> for Measurement {
 impl FailedTest {
     fn from_test_and_measurement(test: MeasurementTest, measurement: Measurement) -> Self {
         let Measurement(measurement) = measurement;
-        Self {
-            measurement,
-            expected: test.expected,
-            message: test.failure_message,
+        let attempt = test.attempt();
+        let attempts = test.attempts();
+
+        // Only the first bitfield that failed is reported, along with the bits measured under it.
+        let failed_field = match &test.expected {
+            Expected::Bitfield(fields) => fields
+                .iter()
+                .copied()
+                .find(|&field| !Expected::Bitfield(vec![field]).contains(measurement)),
+            _ => None,
+        };
+
+        match failed_field {
+            Some((mask, value)) => Self {
+                measurement: measurement & i64::from(mask),
+                expected: Expected::Bitfield(vec![(mask, value)]),
+                message: test.failure_message,
+                attempt,
+                attempts,
+            },
+            None => Self {
+                measurement,
+                expected: test.expected,
+                message: test.failure_message,
+                attempt,
+                attempts,
+            },
         }
     }
 }
 
+////////////////////////////////////////////////////////////////
+// field access
+////////////////////////////////////////////////////////////////
+
+impl Measurement {
+    pub fn value(&self) -> i64 {
+        self.0
+    }
+}
+
+////////////////////////////////////////////////////////////////
+
+impl MeasurementTest {
+    /// Return the attempt, counting from 1, the next measurement tested will be.
+    ///
+    pub fn attempt(&self) -> u32 {
+        self.attempt.saturating_add(1)
+    }
+
+    /// Return the total number of attempts the test allows, including any already used.
+    ///
+    pub fn attempts(&self) -> u32 {
+        self.attempt.saturating_add(self.retries).saturating_add(1)
+    }
+}
+
 ////////////////////////////////////////////////////////////////
 // methods
 ////////////////////////////////////////////////////////////////
 
+impl MeasurementParser {
+    /// Parse a measurement from a device's response.
+    ///
+    pub fn parse(&self, bytes: &[u8]) -> Result<Measurement, Error> {
+        (self.0)(bytes)
+    }
+}
+
+////////////////////////////////////////////////////////////////
+
 impl MeasurementTest {
+    /// Return true if the measurement passes the test. Unlike [`MeasurementTest::test`], retries
+    /// aren't considered. Intended for checking a test's limits without hardware.
+    ///
+    pub fn passes(&self, measurement: impl Into<i64>) -> bool {
+        self.expected.contains(measurement)
+    }
+
     /// Test a measurement.
     ///
     /// # Arguments
@@ -105,12 +601,21 @@ impl MeasurementTest {
     /// Result where the Ok value indicates the test was successfull.
     ///
     pub fn test(mut self, Measurement(measurement): Measurement) -> Result<(), Error> {
-        let test_success = self.expected.contains(&measurement);
+        let test_success = self.expected.contains(measurement);
 
         if !test_success {
             return if self.retries > 0 {
+                let attempt = self.attempt();
+                let attempts = self.attempts();
+
                 self.retries -= 1;
-                Err(Error::TestFailedRetryable(self))
+                self.attempt += 1;
+                Err(Error::TestFailedRetryable {
+                    test: self,
+                    measurement: Measurement(measurement),
+                    attempt,
+                    attempts,
+                })
             } else {
                 Err(Error::TestFailed(FailedTest::from_test_and_measurement(
                     self,
@@ -123,6 +628,86 @@ impl MeasurementTest {
     }
 }
 
+////////////////////////////////////////////////////////////////
+
+impl Expected {
+    /// Return true if the measurement passes.
+    ///
+    pub fn contains(&self, measurement: impl Into<i64>) -> bool {
+        let measurement = measurement.into();
+
+        match self {
+            Expected::Range(range) => {
+                (i64::from(*range.start())..=i64::from(*range.end())).contains(&measurement)
+            }
+            Expected::Comparison(operator, value) => operator.compare(measurement, *value),
+            Expected::Bounds(lower, upper) => {
+                (lower.map(i64::from), upper.map(i64::from)).contains(&measurement)
+            }
+            Expected::OneOf(values) => {
+                u32::try_from(measurement).is_ok_and(|measurement| values.contains(&measurement))
+            }
+            Expected::Stable { spread, .. } => measurement <= i64::from(*spread),
+            Expected::Baseline {
+                tolerance,
+                baseline: Some(baseline),
+            } => {
+                u128::from(measurement.abs_diff(*baseline)) * 100
+                    <= u128::from(baseline.unsigned_abs()) * u128::from(*tolerance)
+            }
+            Expected::Baseline { baseline: None, .. } => true,
+            Expected::Bitfield(fields) => fields
+                .iter()
+                .all(|(mask, value)| measurement & i64::from(*mask) == i64::from(*value)),
+        }
+    }
+
+    /// Return false if no measurement can pass.
+    ///
+    pub fn is_satisfiable(&self) -> bool {
+        match self {
+            Expected::Range(range) => !range.is_empty(),
+            Expected::Comparison(Comparison::Greater, value) => *value < u32::MAX,
+            Expected::Comparison(Comparison::Less, value) => *value > 0,
+            Expected::Comparison(..) => true,
+            Expected::Bounds(lower, upper) => {
+                let lowest = match lower {
+                    Bound::Included(value) => i64::from(*value),
+                    Bound::Excluded(value) => i64::from(*value) + 1,
+                    Bound::Unbounded => 0,
+                };
+                let highest = match upper {
+                    Bound::Included(value) => i64::from(*value),
+                    Bound::Excluded(value) => i64::from(*value) - 1,
+                    Bound::Unbounded => i64::from(u32::MAX),
+                };
+                lowest <= highest
+            }
+            Expected::OneOf(values) => !values.is_empty(),
+            Expected::Stable { samples, .. } => *samples > 0,
+            Expected::Baseline { .. } => true,
+            Expected::Bitfield(fields) => fields.iter().all(|(mask, value)| value & !mask == 0),
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////
+
+impl Comparison {
+    /// Compare a measurement with a value.
+    ///
+    pub fn compare(&self, measurement: impl Into<i64>, value: u32) -> bool {
+        let (measurement, value) = (measurement.into(), i64::from(value));
+
+        match self {
+            Comparison::GreaterEqual => measurement >= value,
+            Comparison::LessEqual => measurement <= value,
+            Comparison::Greater => measurement > value,
+            Comparison::Less => measurement < value,
+        }
+    }
+}
+
 ////////////////////////////////////////////////////////////////
 // ...
 ////////////////////////////////////////////////////////////////
@@ -138,17 +723,217 @@ impl std::fmt::Display for Measurement {
 impl std::fmt::Display for Error {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Error::TestFailed(test) => write!(
+            Error::TestFailed(test) => {
+                if test.attempts > 1 {
+                    write!(f, "Attempt {}/{}: ", test.attempt, test.attempts)?;
+                }
+
+                match &test.expected {
+                    Expected::Range(range) => write!(
+                        f,
+                        "Test failed, expected between {} and {} but measured {}",
+                        range.start(),
+                        range.end(),
+                        test.measurement
+                    ),
+                    Expected::Stable { samples, spread } => write!(
+                        f,
+                        "Test failed, measured a spread of {} over {samples} samples, expected at most {spread}",
+                        test.measurement
+                    ),
+                    Expected::Comparison(..)
+                    | Expected::Bounds(..)
+                    | Expected::OneOf(..)
+                    | Expected::Baseline { .. } => write!(
+                        f,
+                        "Test failed, measured {}, expected {}",
+                        test.measurement, test.expected
+                    ),
+                    Expected::Bitfield(..) => write!(
+                        f,
+                        "Test failed, measured {:#X}, expected {}",
+                        test.measurement, test.expected
+                    ),
+                }
+            }
+            Error::TestFailedRetryable {
+                test,
+                measurement,
+                attempt,
+                attempts,
+            } => match &test.expected {
+                Expected::Bitfield(..) => write!(
+                    f,
+                    "Attempt {attempt}/{attempts}: measured {:#X}, expected {}",
+                    measurement.0, test.expected
+                ),
+                _ => write!(
+                    f,
+                    "Attempt {attempt}/{attempts}: measured {measurement}, expected {}",
+                    test.expected
+                ),
+            },
+            Error::ParseError(error) => write!(f, "{error}"),
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////
+
+impl std::fmt::Debug for MeasurementParser {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("MeasurementParser")
+    }
+}
+
+////////////////////////////////////////////////////////////////
+
+impl PartialEq for MeasurementParser {
+    fn eq(&self, other: &Self) -> bool {
+        // Functions can't be compared so compare by identity.
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+////////////////////////////////////////////////////////////////
+
+impl std::fmt::Display for BcdError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BcdError::InvalidNibble(byte) => {
+                write!(f, "Invalid BCD byte {byte:#04X}, each nibble must be 0-9")
+            }
+            BcdError::Length { expected, received } => write!(
                 f,
-                "Test failed, expected between {} and {} but measured {}",
-                test.expected.start(),
-                test.expected.end(),
-                test.measurement
+                "Expected a BCD measurement of {expected} bytes but received {received}"
             ),
-            Error::TestFailedRetryable(test) => {
-                write!(f, "Test failed, retries remaining: {}", test.retries)
+            BcdError::Overflow => write!(f, "BCD measurement is too large"),
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////
+
+impl std::error::Error for BcdError {}
+
+////////////////////////////////////////////////////////////////
+
+impl std::fmt::Display for UnitError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let UnitError { expected } = self;
+        write!(f, "Measurement isn't followed by it's unit, {expected}")
+    }
+}
+
+////////////////////////////////////////////////////////////////
+
+impl std::error::Error for UnitError {}
+
+////////////////////////////////////////////////////////////////
+
+impl std::fmt::Display for Expected {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Expected::Range(range) => write!(f, "{}..={}", range.start(), range.end()),
+            Expected::Comparison(operator, value) => write!(f, "{operator} {value}"),
+            Expected::Bounds(lower, upper) => {
+                let lower = match lower {
+                    Bound::Included(value) => Some(format!(">= {value}")),
+                    Bound::Excluded(value) => Some(format!("> {value}")),
+                    Bound::Unbounded => None,
+                };
+                let upper = match upper {
+                    Bound::Included(value) => Some(format!("<= {value}")),
+                    Bound::Excluded(value) => Some(format!("< {value}")),
+                    Bound::Unbounded => None,
+                };
+
+                match (lower, upper) {
+                    (Some(lower), Some(upper)) => write!(f, "{lower} and {upper}"),
+                    (Some(bound), None) | (None, Some(bound)) => write!(f, "{bound}"),
+                    (None, None) => write!(f, "any value"),
+                }
             }
-            Error::ParseError(error) => write!(f, "{error}"),
+            Expected::OneOf(values) => {
+                let values = values
+                    .iter()
+                    .map(u32::to_string)
+                    .collect::<Vec<String>>()
+                    .join(", ");
+                write!(f, "one of {values}")
+            }
+            Expected::Stable { samples, spread } => {
+                write!(f, "a spread of at most {spread} over {samples} samples")
+            }
+            Expected::Baseline {
+                tolerance,
+                baseline: Some(baseline),
+            } => write!(f, "within {tolerance}% of the baseline {baseline}"),
+            Expected::Baseline {
+                tolerance,
+                baseline: None,
+            } => write!(f, "a baseline to test within {tolerance}% of"),
+            Expected::Bitfield(fields) => {
+                let fields = fields
+                    .iter()
+                    .map(|(mask, value)| format!("bits {mask:#X} equal to {value:#X}"))
+                    .collect::<Vec<String>>()
+                    .join(" and ");
+                write!(f, "{fields}")
+            }
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////
+
+impl SampleReduction {
+    /// Reduce the measurements taken for a test to the one tested. If the reduction is a decision
+    /// over every measurement, the one deciding it is returned, otherwise the first.
+    ///
+    pub(crate) fn reduce(&self, samples: &[Measurement], test: &MeasurementTest) -> Measurement {
+        let first = samples.first().copied().unwrap_or(Measurement(0));
+        match self {
+            SampleReduction::Average => Measurement::average(samples),
+            SampleReduction::Min => samples.iter().min().copied().unwrap_or(first),
+            SampleReduction::Max => samples.iter().max().copied().unwrap_or(first),
+            SampleReduction::All => samples
+                .iter()
+                .find(|sample| !test.passes(sample.value()))
+                .copied()
+                .unwrap_or(first),
+            SampleReduction::Any => samples
+                .iter()
+                .find(|sample| test.passes(sample.value()))
+                .copied()
+                .unwrap_or(first),
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////
+
+impl std::fmt::Display for SampleReduction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SampleReduction::Average => write!(f, "AVERAGE"),
+            SampleReduction::Min => write!(f, "MIN"),
+            SampleReduction::Max => write!(f, "MAX"),
+            SampleReduction::All => write!(f, "ALL"),
+            SampleReduction::Any => write!(f, "ANY"),
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////
+
+impl std::fmt::Display for Comparison {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Comparison::GreaterEqual => write!(f, ">="),
+            Comparison::LessEqual => write!(f, "<="),
+            Comparison::Greater => write!(f, ">"),
+            Comparison::Less => write!(f, "<"),
         }
     }
 }
@@ -159,14 +944,14 @@ impl std::error::Error for Error {
     fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
         match self {
             Error::TestFailed(_) => None,
-            Error::TestFailedRetryable(_) => None,
+            Error::TestFailedRetryable { .. } => None,
             Error::ParseError(error) => Some(error.as_ref()),
         }
     }
 }
 
 ////////////////////////////////////////////////////////////////
-/// tests
+// tests
 ////////////////////////////////////////////////////////////////
 
 #[cfg(test)]
@@ -184,11 +969,196 @@ mod tests {
 
     ////////////////////////////////////////////////////////////////
 
+    #[test]
+    fn test_measurement_space_padded() {
+        let measurement = Measurement::try_from(&b"  1A2B\r"[..]).unwrap();
+        assert_eq!(measurement.0, 0x1A2B);
+
+        let measurement = Measurement::try_from(&b"1A2B \r"[..]).unwrap();
+        assert_eq!(measurement.0, 0x1A2B);
+
+        assert!(Measurement::parse(b"  1A2B\r", true).is_err());
+    }
+
+    ////////////////////////////////////////////////////////////////
+
+    #[test]
+    fn test_measurement_zero_padded() {
+        let measurement = Measurement::try_from(&b"001A2B\r"[..]).unwrap();
+        assert_eq!(measurement.0, 0x1A2B);
+
+        let measurement = Measurement::parse(b"00000000001A2B\r", true).unwrap();
+        assert_eq!(measurement.0, 0x1A2B);
+    }
+
+    ////////////////////////////////////////////////////////////////
+
+    #[test]
+    fn test_measurement_signed() {
+        let measurement = Measurement::try_from(&b"+1234\r"[..]).unwrap();
+        assert_eq!(measurement.value(), 0x1234);
+
+        let measurement = Measurement::try_from(&b"-0050\r"[..]).unwrap();
+        assert_eq!(measurement.value(), -0x50);
+
+        let measurement = Measurement::try_from(&b" -00C8 \r"[..]).unwrap();
+        assert_eq!(measurement.value(), -0xC8);
+
+        let measurement = Measurement::parse(b"1234\r", true).unwrap();
+        assert_eq!(measurement.value(), 0x1234);
+
+        assert!(Measurement::try_from(&b"- 0050\r"[..]).is_err());
+        assert!(Measurement::try_from(&b"+-0050\r"[..]).is_err());
+        assert!(Measurement::try_from(&b"-\r"[..]).is_err());
+    }
+
+    ////////////////////////////////////////////////////////////////
+
+    #[test]
+    fn test_measurement_formats() {
+        let parse = |bytes: &[u8], format| Measurement::parse_formatted(bytes, format, false);
+
+        assert_eq!(
+            parse(b"-12\r", MeasurementFormat::DecI32).unwrap().value(),
+            -12
+        );
+        assert_eq!(
+            parse(b"0xFF\r", MeasurementFormat::HexU32).unwrap().value(),
+            0xFF
+        );
+        assert_eq!(
+            parse(b"255\r", MeasurementFormat::DecU32).unwrap().value(),
+            255
+        );
+
+        // The same responses in other formats.
+        assert_eq!(
+            parse(b"-12\r", MeasurementFormat::HexU32).unwrap().value(),
+            -0x12
+        );
+        assert_eq!(
+            parse(b"255\r", MeasurementFormat::HexU32).unwrap().value(),
+            0x255
+        );
+        assert!(parse(b"-12\r", MeasurementFormat::DecU32).is_err());
+        assert!(parse(b"0xFF\r", MeasurementFormat::DecU32).is_err());
+        assert!(parse(b"0xFF\r", MeasurementFormat::DecI32).is_err());
+
+        // Decimal measurements must fit their type.
+        assert_eq!(
+            parse(b"4294967295\r", MeasurementFormat::DecU32)
+                .unwrap()
+                .value(),
+            u32::MAX.into()
+        );
+        assert!(parse(b"4294967296\r", MeasurementFormat::DecU32).is_err());
+        assert!(parse(b"2147483648\r", MeasurementFormat::DecI32).is_err());
+
+        // The prefix follows any sign.
+        assert_eq!(
+            parse(b"-0x10\r", MeasurementFormat::HexU32)
+                .unwrap()
+                .value(),
+            -0x10
+        );
+        assert!(parse(b"0x-10\r", MeasurementFormat::HexU32).is_err());
+    }
+
+    ////////////////////////////////////////////////////////////////
+
+    #[test]
+    fn test_measurement_units() {
+        use MeasurementFormat::{DecI32, DecU32, HexU32};
+
+        let parse = |bytes: &[u8], format, unit: &UnitSuffix| {
+            Measurement::parse_with_unit(bytes, format, unit, false).map(|m| m.value())
+        };
+        let millivolts = UnitSuffix::Expected("mV".to_owned());
+
+        // Units are invalid unless they're stripped.
+        assert!(parse(b"1200mV\r", DecU32, &UnitSuffix::Unitless).is_err());
+        assert!(parse(b"55Hz\r", DecU32, &UnitSuffix::Unitless).is_err());
+
+        assert_eq!(parse(b"1200mV\r", DecU32, &UnitSuffix::Any).unwrap(), 1200);
+        assert_eq!(parse(b"55Hz\r", DecU32, &UnitSuffix::Any).unwrap(), 55);
+        assert_eq!(parse(b"-12 mV\r", DecI32, &millivolts).unwrap(), -12);
+        assert_eq!(
+            parse(b"1A2Bmv\r", HexU32, &UnitSuffix::Any).unwrap(),
+            0x1A2B
+        );
+
+        // Stripping is optional for any unit but not an expected one.
+        assert_eq!(parse(b"1200\r", DecU32, &UnitSuffix::Any).unwrap(), 1200);
+        assert!(parse(b"1200\r", DecU32, &millivolts).is_err());
+        assert!(parse(b"55Hz\r", DecU32, &millivolts).is_err());
+        assert!(parse(b"mV\r", DecU32, &UnitSuffix::Any).is_err());
+    }
+
+    ////////////////////////////////////////////////////////////////
+
+    #[test]
+    fn test_negative_measurement() {
+        // Limits are unsigned, so a negative measurement is below all of them.
+        assert!(!Expected::Range(0..=20).contains(-1));
+        assert!(Expected::Comparison(Comparison::Less, 100).contains(-5));
+        assert!(!Expected::Comparison(Comparison::GreaterEqual, 0).contains(-5));
+        assert!(!Expected::OneOf(BTreeSet::from([0, u32::MAX])).contains(-1));
+
+        let expected = Expected::Baseline {
+            tolerance: 10,
+            baseline: Some(-1000),
+        };
+        assert!(expected.contains(-1100));
+        assert!(!expected.contains(-1101));
+        assert!(!expected.contains(1000));
+    }
+
+    ////////////////////////////////////////////////////////////////
+
+    #[test]
+    fn test_measurement_bcd() {
+        let measurement = Measurement::parse_bcd(&[0x12, 0x34, b'\r'], 2).unwrap();
+        assert_eq!(measurement.0, 1234);
+
+        let measurement = Measurement::parse_bcd(&[0x00, 0x07, 0x99, b'\r'], 3).unwrap();
+        assert_eq!(measurement.0, 799);
+
+        let measurement = Measurement::parse_bcd(&[0x42, 0x94, 0x96, 0x72, 0x95], 5).unwrap();
+        assert_eq!(measurement.0, 4294967295);
+        assert!(Measurement::parse_bcd(&[0x42, 0x94, 0x96, 0x72, 0x96], 5).is_err());
+    }
+
+    ////////////////////////////////////////////////////////////////
+
+    #[test]
+    fn test_measurement_bcd_invalid() {
+        let error = Measurement::parse_bcd(&[0x12, 0x3A, b'\r'], 2).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Invalid BCD byte 0x3A, each nibble must be 0-9"
+        );
+
+        let error = Measurement::parse_bcd(&[0xF2, 0x34, b'\r'], 2).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Invalid BCD byte 0xF2, each nibble must be 0-9"
+        );
+
+        let error = Measurement::parse_bcd(&[0x12, b'\r'], 2).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Expected a BCD measurement of 2 bytes but received 1"
+        );
+    }
+
+    ////////////////////////////////////////////////////////////////
+
     #[test]
     fn test_success() {
         let test = MeasurementTest {
-            expected: 0..=20,
+            expected: (0..=20).into(),
             retries: 0,
+            attempt: 0,
             failure_message: "test failed".to_owned(),
         };
 
@@ -201,15 +1171,16 @@ mod tests {
     #[test]
     fn test_failure_retry() {
         let test = MeasurementTest {
-            expected: 0..=20,
+            expected: (0..=20).into(),
             retries: 1,
+            attempt: 0,
             failure_message: "test failed".to_owned(),
         };
 
         let measurement = Measurement::try_from(&b"00F0\r"[..]).unwrap();
         let result = test.test(measurement);
 
-        if let Err(Error::TestFailedRetryable(test)) = result {
+        if let Err(Error::TestFailedRetryable { test, .. }) = result {
             let measurement = Measurement::try_from(&b"0010\r"[..]).unwrap();
             assert!(matches!(test.test(measurement), Ok(())))
         } else {
@@ -222,14 +1193,331 @@ mod tests {
     #[test]
     fn test_failure_no_retry() {
         let test = MeasurementTest {
-            expected: 0..=20,
+            expected: (0..=20).into(),
             retries: 0,
+            attempt: 0,
             failure_message: "test failed".to_owned(),
         };
 
         let measurement = Measurement::try_from(&b"00F0\r"[..]).unwrap();
         assert!(matches!(test.test(measurement), Err(Error::TestFailed(_))));
     }
+
+    ////////////////////////////////////////////////////////////////
+
+    #[test]
+    fn test_failure_attempts() {
+        let mut test = MeasurementTest {
+            expected: (900..=1100).into(),
+            retries: 4,
+            attempt: 0,
+            failure_message: "test failed".to_owned(),
+        };
+
+        for (attempt, value) in (1..=4).zip([1200, 1300, 800, 1200]) {
+            let result = test.test(Measurement(value));
+            let message = result.as_ref().map_err(Error::to_string).unwrap_err();
+            let Err(Error::TestFailedRetryable {
+                test: retry,
+                measurement,
+                attempt: failed_attempt,
+                attempts,
+            }) = result
+            else {
+                panic!("Expected test to fail but be retryable. Got: {result:?}");
+            };
+
+            assert_eq!(measurement, Measurement(value));
+            assert_eq!((failed_attempt, attempts), (attempt, 5));
+            assert_eq!((retry.attempt(), retry.attempts()), (attempt + 1, 5));
+            assert_eq!(
+                message,
+                format!("Attempt {attempt}/5: measured {value}, expected 900..=1100")
+            );
+
+            test = retry;
+        }
+
+        let Err(Error::TestFailed(failed)) = test.test(Measurement(1200)) else {
+            panic!("Expected test to fail without retries");
+        };
+        assert_eq!(failed.measurement, 1200);
+        assert_eq!((failed.attempt, failed.attempts), (5, 5));
+        assert_eq!(
+            Error::TestFailed(failed).to_string(),
+            "Attempt 5/5: Test failed, expected between 900 and 1100 but measured 1200"
+        );
+    }
+
+    ////////////////////////////////////////////////////////////////
+
+    #[test]
+    fn test_bounds() {
+        let test = |expected: Expected| MeasurementTest {
+            expected,
+            retries: 0,
+            attempt: 0,
+            failure_message: "test failed".to_owned(),
+        };
+
+        // One sided.
+        let above = test((Bound::Excluded(500), Bound::Unbounded).into());
+        assert!(!above.passes(500));
+        assert!(above.passes(501));
+        assert!(above.passes(u32::MAX));
+        assert_eq!(above.expected.to_string(), "> 500");
+
+        let below = test((..100).into());
+        assert!(below.passes(99));
+        assert!(below.passes(-5));
+        assert!(!below.passes(100));
+        assert_eq!(below.expected.to_string(), "< 100");
+
+        let at_least = test((500..).into());
+        assert!(at_least.passes(500));
+        assert!(!at_least.passes(499));
+
+        let at_most = test((..=100).into());
+        assert!(at_most.passes(100));
+        assert!(!at_most.passes(101));
+
+        // Exclusive endpoints.
+        let between = test((Bound::Excluded(10), Bound::Excluded(20)).into());
+        assert!(!between.passes(10));
+        assert!(between.passes(11));
+        assert!(between.passes(19));
+        assert!(!between.passes(20));
+        assert_eq!(between.expected.to_string(), "> 10 and < 20");
+
+        let half_open = test((10..20).into());
+        assert!(half_open.passes(10));
+        assert!(!half_open.passes(20));
+        assert!(half_open.test(Measurement(20)).is_err());
+
+        // Inclusive ranges are unchanged.
+        assert_eq!(Expected::from(10..=20), Expected::Range(10..=20));
+
+        assert!(Expected::from((Bound::Excluded(10), Bound::Excluded(12))).is_satisfiable());
+        assert!(!Expected::from((Bound::Excluded(10), Bound::Excluded(11))).is_satisfiable());
+        assert!(!Expected::from(..0).is_satisfiable());
+        assert!(!Expected::from((Bound::Excluded(u32::MAX), Bound::Unbounded)).is_satisfiable());
+        assert!(Expected::from((Bound::Unbounded, Bound::Unbounded)).is_satisfiable());
+    }
+
+    ////////////////////////////////////////////////////////////////
+
+    #[test]
+    fn test_comparison_boundaries() {
+        let expected = |operator, value| Expected::Comparison(operator, value);
+
+        assert!(expected(Comparison::GreaterEqual, 3000).contains(3000));
+        assert!(!expected(Comparison::GreaterEqual, 3000).contains(2999));
+
+        assert!(expected(Comparison::LessEqual, 100).contains(100));
+        assert!(!expected(Comparison::LessEqual, 100).contains(101));
+
+        assert!(expected(Comparison::Greater, 3000).contains(3001));
+        assert!(!expected(Comparison::Greater, 3000).contains(3000));
+
+        assert!(expected(Comparison::Less, 100).contains(99));
+        assert!(!expected(Comparison::Less, 100).contains(100));
+
+        assert!(!expected(Comparison::Less, 0).is_satisfiable());
+        assert!(!expected(Comparison::Greater, u32::MAX).is_satisfiable());
+    }
+
+    ////////////////////////////////////////////////////////////////
+
+    #[test]
+    fn test_comparison_failure_message() {
+        let test = MeasurementTest {
+            expected: Expected::Comparison(Comparison::GreaterEqual, 3000),
+            retries: 0,
+            attempt: 0,
+            failure_message: "test failed".to_owned(),
+        };
+
+        let measurement = Measurement::try_from(&b"0BB7\r"[..]).unwrap();
+        let error = test.test(measurement).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Test failed, measured 2999, expected >= 3000"
+        );
+    }
+
+    ////////////////////////////////////////////////////////////////
+
+    #[test]
+    fn test_one_of_membership() {
+        let expected = Expected::OneOf(BTreeSet::from([1, 3, 7]));
+
+        assert!(expected.contains(1));
+        assert!(expected.contains(3));
+        assert!(expected.contains(7));
+
+        assert!(!expected.contains(0));
+        assert!(!expected.contains(2));
+        assert!(!expected.contains(8));
+
+        assert!(!Expected::OneOf(BTreeSet::new()).is_satisfiable());
+    }
+
+    ////////////////////////////////////////////////////////////////
+
+    #[test]
+    fn test_one_of_failure_message() {
+        let test = MeasurementTest {
+            expected: Expected::OneOf(BTreeSet::from([7, 1, 3])),
+            retries: 0,
+            attempt: 0,
+            failure_message: "test failed".to_owned(),
+        };
+
+        let measurement = Measurement::try_from(&b"0002\r"[..]).unwrap();
+        let error = test.test(measurement).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Test failed, measured 2, expected one of 1, 3, 7"
+        );
+    }
+
+    ////////////////////////////////////////////////////////////////
+
+    #[test]
+    fn test_stable_spread() {
+        let samples = [Measurement(105), Measurement(98), Measurement(110)];
+        assert_eq!(Measurement::spread(&samples), Measurement(12));
+        assert_eq!(Measurement::spread(&[Measurement(7)]), Measurement(0));
+
+        let expected = Expected::Stable {
+            samples: 3,
+            spread: 12,
+        };
+        assert!(expected.contains(12));
+        assert!(!expected.contains(13));
+
+        assert!(!Expected::Stable {
+            samples: 0,
+            spread: 12
+        }
+        .is_satisfiable());
+    }
+
+    ////////////////////////////////////////////////////////////////
+
+    #[test]
+    fn test_stable_failure_message() {
+        let test = MeasurementTest {
+            expected: Expected::Stable {
+                samples: 4,
+                spread: 10,
+            },
+            retries: 0,
+            attempt: 0,
+            failure_message: "test failed".to_owned(),
+        };
+
+        let error = test.test(Measurement(25)).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Test failed, measured a spread of 25 over 4 samples, expected at most 10"
+        );
+    }
+
+    ////////////////////////////////////////////////////////////////
+
+    #[test]
+    fn test_tolerance() {
+        let expected = Expected::tolerance(1000, 5);
+        assert_eq!(expected, Expected::Range(950..=1050));
+
+        let test = MeasurementTest {
+            expected,
+            retries: 0,
+            attempt: 0,
+            failure_message: "test failed".to_owned(),
+        };
+        assert!(test.passes(950));
+        assert!(test.passes(1050));
+        assert!(!test.passes(949));
+        assert!(!test.passes(1051));
+
+        // Rounded down and limited.
+        assert_eq!(Expected::tolerance(999, 5), Expected::Range(950..=1048));
+        assert_eq!(Expected::tolerance(10, 200), Expected::Range(0..=30));
+        assert_eq!(
+            Expected::tolerance(u32::MAX, 1),
+            Expected::Range(u32::MAX - u32::MAX / 100..=u32::MAX)
+        );
+    }
+
+    ////////////////////////////////////////////////////////////////
+
+    #[test]
+    fn test_bitfield() {
+        // Bit 3 set and bits 5-6 equal to 2.
+        let expected = Expected::Bitfield(vec![(0x08, 0x08), (0x60, 0x40)]);
+        assert!(expected.contains(0x48));
+        assert!(expected.contains(0xCF));
+        assert!(!expected.contains(0x40));
+        assert!(!expected.contains(0x68));
+
+        assert!(expected.is_satisfiable());
+        assert!(!Expected::Bitfield(vec![(0x60, 0x80)]).is_satisfiable());
+
+        let test = MeasurementTest {
+            expected,
+            retries: 0,
+            attempt: 0,
+            failure_message: "test failed".to_owned(),
+        };
+        let Err(Error::TestFailed(failed)) = test.test(Measurement(0x28)) else {
+            panic!("Expected the test to fail");
+        };
+
+        // Only the failing field is reported, with the bits measured under it's mask.
+        assert_eq!(failed.measurement, 0x20);
+        assert_eq!(failed.expected, Expected::Bitfield(vec![(0x60, 0x40)]));
+        assert_eq!(
+            Error::TestFailed(failed).to_string(),
+            "Test failed, measured 0x20, expected bits 0x60 equal to 0x40"
+        );
+    }
+
+    ////////////////////////////////////////////////////////////////
+
+    #[test]
+    fn test_baseline_tolerance() {
+        let expected = Expected::Baseline {
+            tolerance: 2,
+            baseline: Some(1000),
+        };
+        assert!(expected.contains(980));
+        assert!(expected.contains(1020));
+        assert!(!expected.contains(979));
+        assert!(!expected.contains(1021));
+
+        // Without a baseline the measurement is taken as the baseline.
+        let expected = Expected::Baseline {
+            tolerance: 0,
+            baseline: None,
+        };
+        assert!(expected.contains(u32::MAX));
+
+        let test = MeasurementTest {
+            expected: Expected::Baseline {
+                tolerance: 5,
+                baseline: Some(u32::MAX.into()),
+            },
+            retries: 0,
+            attempt: 0,
+            failure_message: "test failed".to_owned(),
+        };
+        assert_eq!(
+            test.test(Measurement(0)).unwrap_err().to_string(),
+            "Test failed, measured 0, expected within 5% of the baseline 4294967295"
+        );
+    }
 }
 
 ////////////////////////////////////////////////////////////////
@@ -1,13 +1,47 @@
+mod capture;
+mod cross_check;
+mod device_log;
+mod encoding;
+mod fingerprint;
 mod frontend;
+mod latency;
 mod measurement;
+mod observer;
+mod recording;
+mod results;
+mod routing;
+mod session;
+mod simulation;
+mod store;
 mod transaction;
+mod transport;
 
 ////////////////////////////////////////////////////////////////
 // exports
 ////////////////////////////////////////////////////////////////
 
-pub use frontend::{Dialog, FrontendRequest};
-pub use measurement::{FailedTest, Measurement, MeasurementTest};
-pub use transaction::{Device, Transaction, TransactionStatus};
+pub use capture::{Capture, Exchange};
+pub use cross_check::{CrossCheck, CrossCheckStatus};
+pub use device_log::{DeviceAction, DeviceEvent, DeviceLog, Outcome};
+pub use encoding::Encoding;
+pub use frontend::{Dialog, DialogTimeout, FrontendRequest, TimeoutAction, BAUD_RATES};
+pub use measurement::{
+    BcdError, Comparison, Error as MeasurementError, Expected, FailedTest, Measurement,
+    MeasurementFormat, MeasurementParser, MeasurementTest, SampleReduction, UnitError, UnitSuffix,
+};
+pub use observer::TransactionObserver;
+pub use recording::{RecordedTest, Recording};
+pub use results::{Results, TestOutcome};
+pub use routing::{Routing, RoutingBuilder};
+pub use session::Session;
+pub use transaction::{
+    Checksum, Device, Echo, LineEnding, Transaction, TransactionPhase, TransactionStatus,
+};
+pub use transport::Transport;
+
+pub(crate) use fingerprint::Fingerprint;
+pub(crate) use latency::{percentile, LatencyLog};
+pub(crate) use observer::SharedObserver;
+pub(crate) use store::MeasurementStore;
 
 ////////////////////////////////////////////////////////////////
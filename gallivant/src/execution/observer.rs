@@ -0,0 +1,82 @@
+use std::sync::Arc;
+
+use super::transaction::Transaction;
+
+////////////////////////////////////////////////////////////////
+// types
+////////////////////////////////////////////////////////////////
+
+/// Notified of every exchange between a transaction and it's device as it happens. e.g. To write a
+/// structured log of a run without parsing the frontend's output. The transaction gives the device
+/// and the span of the command that created it.
+///
+/// Both methods do nothing by default, so an observer need only implement those it's interested
+/// in.
+///
+pub trait TransactionObserver: Send + Sync {
+    /// Called once bytes have been transmitted. i.e. The command, including any re-transmission
+    /// of it, or a measurement trigger.
+    ///
+    fn on_tx(&self, _transaction: &Transaction, _bytes: &[u8]) {}
+
+    /// Called once bytes have been received. Each call is given the bytes of a single read, so a
+    /// response may be spread over several calls.
+    ///
+    fn on_rx(&self, _transaction: &Transaction, _bytes: &[u8]) {}
+}
+
+////////////////////////////////////////////////////////////////
+
+/// Handle to an observer shared by every transaction it's attached to.
+///
+#[derive(Clone)]
+pub(crate) struct SharedObserver(Arc<dyn TransactionObserver>);
+
+////////////////////////////////////////////////////////////////
+// construction / conversion
+////////////////////////////////////////////////////////////////
+
+impl From<Arc<dyn TransactionObserver>> for SharedObserver {
+    fn from(observer: Arc<dyn TransactionObserver>) -> Self {
+        Self(observer)
+    }
+}
+
+////////////////////////////////////////////////////////////////
+// methods
+////////////////////////////////////////////////////////////////
+
+impl SharedObserver {
+    pub(crate) fn handle(&self) -> Arc<dyn TransactionObserver> {
+        self.0.clone()
+    }
+
+    pub(crate) fn on_tx(&self, transaction: &Transaction, bytes: &[u8]) {
+        self.0.on_tx(transaction, bytes);
+    }
+
+    pub(crate) fn on_rx(&self, transaction: &Transaction, bytes: &[u8]) {
+        self.0.on_rx(transaction, bytes);
+    }
+}
+
+////////////////////////////////////////////////////////////////
+
+impl std::fmt::Debug for SharedObserver {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("SharedObserver")
+    }
+}
+
+////////////////////////////////////////////////////////////////
+
+impl PartialEq for SharedObserver {
+    fn eq(&self, other: &Self) -> bool {
+        // Observers are handles so compare by identity.
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for SharedObserver {}
+
+////////////////////////////////////////////////////////////////
@@ -0,0 +1,170 @@
+use std::{
+    io::{self, Write},
+    ops::{Range, RangeInclusive},
+};
+
+////////////////////////////////////////////////////////////////
+// types
+////////////////////////////////////////////////////////////////
+
+/// Measurements collected from test commands while a script is run in record mode.
+///
+/// A single recording can be fed measurements from several runs of the same script (e.g. one run
+/// per golden board) so that the spread of each test's measurements can be used to derive its
+/// expected range.
+///
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Recording {
+    tests: Vec<RecordedTest>,
+}
+
+////////////////////////////////////////////////////////////////
+
+/// Every measurement recorded for a single test command.
+///
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RecordedTest {
+    span: Range<usize>,
+    samples: Vec<i64>,
+}
+
+////////////////////////////////////////////////////////////////
+// construction / conversion
+////////////////////////////////////////////////////////////////
+
+impl Recording {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+////////////////////////////////////////////////////////////////
+// methods
+////////////////////////////////////////////////////////////////
+
+impl Recording {
+    /// Add a measurement taken by the test command at the given span of the script.
+    ///
+    pub fn add(&mut self, span: Range<usize>, measurement: impl Into<i64>) {
+        let measurement = measurement.into();
+        match self.tests.iter_mut().find(|test| test.span == span) {
+            Some(test) => test.samples.push(measurement),
+            None => self.tests.push(RecordedTest {
+                span,
+                samples: vec![measurement],
+            }),
+        }
+    }
+
+    /// Export the recording as CSV. Each row contains the test command's source text, the minimum
+    /// and maximum recorded measurements followed by every recorded measurement.
+    ///
+    /// # Arguments
+    /// * `script` - Source of the script the recording was taken from.
+    /// * `writer` - Destination of the CSV.
+    ///
+    pub fn write_csv<W: Write>(&self, script: &str, mut writer: W) -> io::Result<()> {
+        writeln!(writer, "command,min,max,samples")?;
+
+        for test in self.tests.iter() {
+            let command = script.get(test.span.clone()).unwrap_or_default();
+            let command = command.trim().replace('"', "\"\"");
+
+            let (min, max) = test
+                .range()
+                .map(|range| (range.start().to_string(), range.end().to_string()))
+                .unwrap_or_default();
+
+            let samples = test
+                .samples
+                .iter()
+                .map(i64::to_string)
+                .collect::<Vec<String>>()
+                .join(",");
+
+            writeln!(writer, "\"{command}\",{min},{max},{samples}")?;
+        }
+
+        Ok(())
+    }
+}
+
+////////////////////////////////////////////////////////////////
+
+impl RecordedTest {
+    /// Return the range covering every recorded measurement.
+    ///
+    pub fn range(&self) -> Option<RangeInclusive<i64>> {
+        let min = self.samples.iter().min()?;
+        let max = self.samples.iter().max()?;
+        Some(*min..=*max)
+    }
+}
+
+////////////////////////////////////////////////////////////////
+// field access
+////////////////////////////////////////////////////////////////
+
+impl Recording {
+    pub fn tests(&self) -> &[RecordedTest] {
+        &self.tests
+    }
+}
+
+////////////////////////////////////////////////////////////////
+
+impl RecordedTest {
+    pub fn span(&self) -> &Range<usize> {
+        &self.span
+    }
+
+    pub fn samples(&self) -> &[i64] {
+        &self.samples
+    }
+}
+
+////////////////////////////////////////////////////////////////
+// tests
+////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    ////////////////////////////////////////////////////////////////
+
+    #[test]
+    fn test_samples_grouped_by_span() {
+        let mut recording = Recording::new();
+        recording.add(0..10, 5);
+        recording.add(11..20, 100);
+        recording.add(0..10, 7);
+        recording.add(0..10, 3);
+
+        assert_eq!(recording.tests().len(), 2);
+        assert_eq!(recording.tests()[0].samples(), [5, 7, 3]);
+        assert_eq!(recording.tests()[0].range(), Some(3..=7));
+        assert_eq!(recording.tests()[1].range(), Some(100..=100));
+    }
+
+    ////////////////////////////////////////////////////////////////
+
+    #[test]
+    fn test_write_csv() {
+        let script = r#"TCUTEST 3, 0, 0, 0, "FAIL""#;
+
+        let mut recording = Recording::new();
+        recording.add(0..script.len(), 12);
+        recording.add(0..script.len(), 10);
+
+        let mut csv = Vec::new();
+        recording.write_csv(script, &mut csv).unwrap();
+
+        assert_eq!(
+            String::from_utf8(csv).unwrap(),
+            "command,min,max,samples\n\"TCUTEST 3, 0, 0, 0, \"\"FAIL\"\"\",10,12,12,10\n"
+        );
+    }
+}
+
+////////////////////////////////////////////////////////////////
@@ -0,0 +1,240 @@
+use std::{
+    ops::Range,
+    sync::{
+        mpsc::{self, Receiver, Sender},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use crate::syntax::{Expr, ParsedExpr};
+
+use super::transaction::Device;
+
+////////////////////////////////////////////////////////////////
+// types
+////////////////////////////////////////////////////////////////
+
+/// Collects the outcome of every test performed by a device during a run. Each outcome is reported
+/// the moment it's determined, to the collection and to every subscriber. Cloning the results
+/// returns a handle to the same outcomes.
+///
+#[derive(Clone, Debug, Default)]
+pub struct Results {
+    inner: Arc<Mutex<Inner>>,
+
+    /// Board that outcomes reported through this handle are tagged with.
+    board: Option<String>,
+}
+
+////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Default)]
+struct Inner {
+    outcomes: Vec<TestOutcome>,
+    subscribers: Vec<Sender<TestOutcome>>,
+}
+
+////////////////////////////////////////////////////////////////
+
+/// Final outcome of a test command, after any retries of the test itself. A test re-run by a
+/// RETRY block reports the outcome of each run.
+///
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TestOutcome {
+    /// Span of the test command in the script.
+    pub span: Range<usize>,
+    pub device: Device,
+    pub channel: u32,
+
+    /// The measurement tested. For a REFTEST, the difference between the two measurements.
+    pub measured: i64,
+    pub passed: bool,
+
+    /// The test's failure message.
+    pub message: String,
+
+    /// Time from the test's first transmission until it's outcome was determined.
+    pub elapsed: Duration,
+
+    /// Board the test was performed on, if within a BOARD block.
+    pub board: Option<String>,
+}
+
+////////////////////////////////////////////////////////////////
+// construction / conversion
+////////////////////////////////////////////////////////////////
+
+impl Results {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Return a handle to the same outcomes that tags every outcome reported through it with the
+    /// board.
+    ///
+    pub(crate) fn on_board(&self, board: Option<&str>) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            board: board.map(str::to_owned),
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////
+// methods
+////////////////////////////////////////////////////////////////
+
+impl Results {
+    /// Return every outcome reported so far, in the order they were determined.
+    ///
+    pub fn outcomes(&self) -> Vec<TestOutcome> {
+        self.lock().outcomes.clone()
+    }
+
+    /// Return every outcome reported so far grouped by the board they were performed on. Boards
+    /// are in the order their first outcome was determined. Outcomes of tests outside of any BOARD
+    /// block are grouped under None.
+    ///
+    pub fn by_board(&self) -> Vec<(Option<String>, Vec<TestOutcome>)> {
+        let mut boards: Vec<(Option<String>, Vec<TestOutcome>)> = Vec::new();
+        for outcome in self.outcomes() {
+            match boards.iter_mut().find(|(board, _)| *board == outcome.board) {
+                Some((_, outcomes)) => outcomes.push(outcome),
+                None => boards.push((outcome.board.clone(), vec![outcome])),
+            }
+        }
+
+        boards
+    }
+
+    /// Subscribe to outcomes as they're determined. Only outcomes reported after subscribing are
+    /// received. Dropping the receiver unsubscribes.
+    ///
+    pub fn subscribe(&self) -> Receiver<TestOutcome> {
+        let (sender, receiver) = mpsc::channel();
+        self.lock().subscribers.push(sender);
+        receiver
+    }
+
+    pub(super) fn report(&self, mut outcome: TestOutcome) {
+        outcome.board.clone_from(&self.board);
+
+        let mut inner = self.lock();
+        inner
+            .subscribers
+            .retain(|subscriber| subscriber.send(outcome.clone()).is_ok());
+        inner.outcomes.push(outcome);
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Inner> {
+        self.inner.lock().unwrap_or_else(|error| error.into_inner())
+    }
+}
+
+////////////////////////////////////////////////////////////////
+
+/// Return the channel tested by a test command. None if the expression isn't a test command that
+/// a device performs.
+///
+pub(super) fn test_channel(expr: &ParsedExpr) -> Option<u32> {
+    let channel = match expr.expression() {
+        Expr::TCUTest { channel, .. }
+        | Expr::PrinterTest { channel, .. }
+        | Expr::USBPrinterTest { channel, .. }
+        | Expr::ReferenceTest { channel, .. } => channel,
+        _ => return None,
+    };
+
+    match channel.expression() {
+        Expr::UInt(channel) => Some(*channel),
+        _ => None,
+    }
+}
+
+////////////////////////////////////////////////////////////////
+// comparison
+////////////////////////////////////////////////////////////////
+
+impl PartialEq for Results {
+    fn eq(&self, other: &Self) -> bool {
+        // Results are handles so compare by identity.
+        Arc::ptr_eq(&self.inner, &other.inner)
+    }
+}
+
+impl Eq for Results {}
+
+////////////////////////////////////////////////////////////////
+// tests
+////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    ////////////////////////////////////////////////////////////////
+
+    #[test]
+    fn test_subscribers() {
+        let outcome = |measured| TestOutcome {
+            span: 0..10,
+            device: Device::TCU,
+            channel: 3,
+            measured,
+            passed: true,
+            message: String::new(),
+            elapsed: Duration::ZERO,
+            board: None,
+        };
+
+        let results = Results::new();
+        results.report(outcome(1));
+
+        let subscriber = results.subscribe();
+        let dropped = results.subscribe();
+        drop(dropped);
+
+        results.clone().report(outcome(2));
+
+        assert_eq!(subscriber.try_iter().collect::<Vec<_>>(), [outcome(2)]);
+        assert_eq!(results.outcomes(), [outcome(1), outcome(2)]);
+        assert_eq!(results.lock().subscribers.len(), 1);
+    }
+
+    ////////////////////////////////////////////////////////////////
+
+    #[test]
+    fn test_by_board() {
+        let outcome = |measured, board: Option<&str>| TestOutcome {
+            span: 0..10,
+            device: Device::TCU,
+            channel: 3,
+            measured,
+            passed: true,
+            message: String::new(),
+            elapsed: Duration::ZERO,
+            board: board.map(str::to_owned),
+        };
+
+        let results = Results::new();
+        results.report(outcome(1, None));
+        results.on_board(Some("A1")).report(outcome(2, None));
+        results.on_board(Some("A2")).report(outcome(3, None));
+        results.on_board(Some("A1")).report(outcome(4, None));
+
+        assert_eq!(
+            results.by_board(),
+            [
+                (None, vec![outcome(1, None)]),
+                (
+                    Some("A1".to_owned()),
+                    vec![outcome(2, Some("A1")), outcome(4, Some("A1"))]
+                ),
+                (Some("A2".to_owned()), vec![outcome(3, Some("A2"))]),
+            ]
+        );
+    }
+}
+
+////////////////////////////////////////////////////////////////
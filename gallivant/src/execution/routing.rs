@@ -0,0 +1,205 @@
+use std::collections::BTreeMap;
+
+use super::transaction::Device;
+
+////////////////////////////////////////////////////////////////
+// types
+////////////////////////////////////////////////////////////////
+
+/// Assignment of each device to the frontend's identifier for it. e.g. The path of the serial port
+/// the device is connected to.
+///
+/// A device may also have fallback identifiers, tried in order if it fails to open on the one it's
+/// assigned. e.g. A spare TCU in a redundant test cell.
+///
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Routing {
+    routes: BTreeMap<Device, String>,
+    fallbacks: BTreeMap<Device, Vec<String>>,
+}
+
+////////////////////////////////////////////////////////////////
+
+/// Builder for a [`Routing`], populated by the frontend at the start of a run.
+///
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RoutingBuilder {
+    routes: BTreeMap<Device, String>,
+    fallbacks: BTreeMap<Device, Vec<String>>,
+}
+
+////////////////////////////////////////////////////////////////
+// construction / conversion
+////////////////////////////////////////////////////////////////
+
+impl Routing {
+    pub fn builder() -> RoutingBuilder {
+        RoutingBuilder::default()
+    }
+}
+
+////////////////////////////////////////////////////////////////
+
+impl RoutingBuilder {
+    /// Assign an identifier to a device, replacing any previously assigned.
+    ///
+    #[must_use]
+    pub fn route(mut self, device: Device, identifier: impl Into<String>) -> Self {
+        self.routes.insert(device, identifier.into());
+        self
+    }
+
+    /// Add an identifier to try if the device fails to open on it's assigned identifier and any
+    /// earlier fallbacks. Fallbacks are only tried for a device that's been assigned an identifier.
+    ///
+    #[must_use]
+    pub fn fallback(mut self, device: Device, identifier: impl Into<String>) -> Self {
+        self.fallbacks
+            .entry(device)
+            .or_default()
+            .push(identifier.into());
+        self
+    }
+
+    pub fn build(self) -> Routing {
+        Routing {
+            routes: self.routes,
+            fallbacks: self.fallbacks,
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////
+// methods
+////////////////////////////////////////////////////////////////
+
+impl Routing {
+    /// Return the identifier assigned to a device.
+    ///
+    pub fn get(&self, device: Device) -> Option<&str> {
+        self.routes.get(&device).map(String::as_str)
+    }
+
+    pub fn contains(&self, device: Device) -> bool {
+        self.routes.contains_key(&device)
+    }
+
+    /// Return the identifier assigned to a device followed by any fallbacks, in the order they
+    /// should be tried. Empty if the device has no assignment.
+    ///
+    pub fn identifiers(&self, device: Device) -> impl Iterator<Item = &str> {
+        let fallbacks = self
+            .routes
+            .contains_key(&device)
+            .then(|| self.fallbacks.get(&device))
+            .flatten()
+            .into_iter()
+            .flatten();
+
+        self.get(device)
+            .into_iter()
+            .chain(fallbacks.map(String::as_str))
+    }
+
+    /// Open a device by trying each of it's identifiers in turn until one opens.
+    ///
+    /// # Arguments
+    /// * `device` - Device to open.
+    /// * `open` - Opens the device given an identifier.
+    ///
+    /// # Returns
+    /// The identifier the device was opened with along with the opened device.
+    ///
+    /// # Errors
+    /// Every identifier tried along with the reason it failed to open. Empty if the device has no
+    /// assignment.
+    ///
+    pub fn open_with<T, E>(
+        &self,
+        device: Device,
+        mut open: impl FnMut(&str) -> Result<T, E>,
+    ) -> Result<(&str, T), Vec<(&str, E)>> {
+        let mut failures = Vec::new();
+        for identifier in self.identifiers(device) {
+            match open(identifier) {
+                Ok(opened) => return Ok((identifier, opened)),
+                Err(error) => failures.push((identifier, error)),
+            }
+        }
+
+        Err(failures)
+    }
+}
+
+////////////////////////////////////////////////////////////////
+// tests
+////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    ////////////////////////////////////////////////////////////////
+
+    #[test]
+    fn test_routing() {
+        let routing = Routing::builder()
+            .route(Device::TCU, "COM1")
+            .route(Device::Printer, "COM2")
+            .route(Device::TCU, "/dev/ttyUSB0")
+            .build();
+
+        assert_eq!(routing.get(Device::TCU), Some("/dev/ttyUSB0"));
+        assert_eq!(routing.get(Device::Printer), Some("COM2"));
+        assert!(Routing::default().get(Device::TCU).is_none());
+    }
+
+    ////////////////////////////////////////////////////////////////
+
+    #[test]
+    fn test_fallback() {
+        let routing = Routing::builder()
+            .fallback(Device::TCU, "COM3")
+            .route(Device::TCU, "COM1")
+            .fallback(Device::TCU, "COM4")
+            .fallback(Device::Printer, "COM5")
+            .build();
+
+        let identifiers: Vec<&str> = routing.identifiers(Device::TCU).collect();
+        assert_eq!(identifiers, ["COM1", "COM3", "COM4"]);
+
+        // A fallback alone doesn't route a device.
+        assert!(!routing.contains(Device::Printer));
+        assert_eq!(routing.identifiers(Device::Printer).count(), 0);
+    }
+
+    ////////////////////////////////////////////////////////////////
+
+    #[test]
+    fn test_open_with_fallback() {
+        let routing = Routing::builder()
+            .route(Device::TCU, "COM1")
+            .fallback(Device::TCU, "COM2")
+            .fallback(Device::TCU, "COM3")
+            .build();
+
+        let opened = routing.open_with(Device::TCU, |port| match port {
+            "COM1" => Err("busy"),
+            port => Ok(port.to_owned()),
+        });
+        assert_eq!(opened, Ok(("COM2", "COM2".to_owned())));
+
+        let failed = routing.open_with(Device::TCU, |_| Err::<(), _>("busy"));
+        assert_eq!(
+            failed,
+            Err(vec![("COM1", "busy"), ("COM2", "busy"), ("COM3", "busy")])
+        );
+
+        // Without fallbacks only the assigned identifier is tried.
+        let routing = Routing::builder().route(Device::TCU, "COM1").build();
+        let failed = routing.open_with(Device::TCU, |_| Err::<(), _>("busy"));
+        assert_eq!(failed, Err(vec![("COM1", "busy")]));
+    }
+}
+
+////////////////////////////////////////////////////////////////
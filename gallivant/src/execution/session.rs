@@ -0,0 +1,84 @@
+use std::{
+    collections::BTreeSet,
+    sync::{Arc, Mutex},
+};
+
+use super::{frontend::FrontendRequest, transaction::Device};
+
+////////////////////////////////////////////////////////////////
+// types
+////////////////////////////////////////////////////////////////
+
+/// Devices held open across a session of sequential runs. e.g. One run per board. Once a run has
+/// opened a device it's left open for the following runs, which skip opening it, until the session
+/// ends. Cloning a session returns a handle to the same devices.
+///
+#[derive(Clone, Debug, Default)]
+pub struct Session {
+    open: Arc<Mutex<BTreeSet<Device>>>,
+}
+
+////////////////////////////////////////////////////////////////
+// construction / conversion
+////////////////////////////////////////////////////////////////
+
+impl Session {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+////////////////////////////////////////////////////////////////
+// methods
+////////////////////////////////////////////////////////////////
+
+impl Session {
+    /// Return true if the device is being held open by the session.
+    ///
+    pub fn is_open(&self, device: Device) -> bool {
+        self.lock().contains(&device)
+    }
+
+    /// End the session. The session is left empty so may be reused for another.
+    ///
+    /// # Returns
+    /// Requests for the frontend to close each device held open.
+    ///
+    pub fn close_all(&self) -> Vec<FrontendRequest> {
+        std::mem::take(&mut *self.lock())
+            .into_iter()
+            .filter_map(|device| match device {
+                Device::USB => Some(FrontendRequest::PrinterClose),
+                Device::TCU | Device::Printer | Device::Reference => None,
+            })
+            .collect()
+    }
+
+    /// Hold the device open for the rest of the session.
+    ///
+    /// # Returns
+    /// False if it was already held open, in which case it doesn't need opening again.
+    ///
+    pub(crate) fn open(&self, device: Device) -> bool {
+        self.lock().insert(device)
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, BTreeSet<Device>> {
+        self.open.lock().unwrap_or_else(|error| error.into_inner())
+    }
+}
+
+////////////////////////////////////////////////////////////////
+// comparison
+////////////////////////////////////////////////////////////////
+
+impl PartialEq for Session {
+    fn eq(&self, other: &Self) -> bool {
+        // Sessions are handles so compare by identity.
+        Arc::ptr_eq(&self.open, &other.open)
+    }
+}
+
+impl Eq for Session {}
+
+////////////////////////////////////////////////////////////////
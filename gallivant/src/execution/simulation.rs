@@ -0,0 +1,104 @@
+use std::{
+    collections::VecDeque,
+    io::{self, Read, Write},
+};
+
+////////////////////////////////////////////////////////////////
+// types
+////////////////////////////////////////////////////////////////
+
+/// Stand-in for a device's port that responds to each command with a canned measurement.
+///
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub(super) struct SimulatedPort {
+    echo: bool,
+    measurements: VecDeque<u32>,
+    rxdata: VecDeque<u8>,
+
+    /// Whether the device waits for a trigger before responding with a measurement, and whether a
+    /// command is awaiting one.
+    triggered: bool,
+    awaiting_trigger: bool,
+}
+
+////////////////////////////////////////////////////////////////
+// construction / conversion
+////////////////////////////////////////////////////////////////
+
+impl SimulatedPort {
+    /// # Arguments
+    /// * `echo` - Whether the device echoes each command before responding.
+    /// * `measurements` - Measurement to respond to each command with, in order.
+    ///
+    pub(super) fn new(echo: bool, measurements: impl IntoIterator<Item = u32>) -> Self {
+        Self {
+            echo,
+            measurements: measurements.into_iter().collect(),
+            rxdata: VecDeque::new(),
+            triggered: false,
+            awaiting_trigger: false,
+        }
+    }
+
+    /// Set whether the device only responds with a measurement once it's sent a trigger following
+    /// the command. The trigger isn't echoed.
+    ///
+    pub(super) fn triggered(mut self, triggered: bool) -> Self {
+        self.triggered = triggered;
+        self
+    }
+}
+
+////////////////////////////////////////////////////////////////
+// methods
+////////////////////////////////////////////////////////////////
+
+impl SimulatedPort {
+    /// Return true if there's a response waiting to be read.
+    ///
+    pub(super) fn has_response(&self) -> bool {
+        !self.rxdata.is_empty()
+    }
+}
+
+////////////////////////////////////////////////////////////////
+
+impl Read for SimulatedPort {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let count = buf.len().min(self.rxdata.len());
+        for (byte, rxbyte) in buf.iter_mut().zip(self.rxdata.drain(..count)) {
+            *byte = rxbyte;
+        }
+
+        Ok(count)
+    }
+}
+
+////////////////////////////////////////////////////////////////
+
+impl Write for SimulatedPort {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        // The trigger isn't echoed.
+        let trigger = std::mem::take(&mut self.awaiting_trigger);
+        if self.echo && !trigger {
+            self.rxdata.extend(buf);
+        }
+
+        if self.triggered && !trigger {
+            self.awaiting_trigger = true;
+            return Ok(buf.len());
+        }
+
+        if let Some(measurement) = self.measurements.pop_front() {
+            self.rxdata.extend(format!("{measurement:04X}\r").bytes());
+        }
+
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+////////////////////////////////////////////////////////////////
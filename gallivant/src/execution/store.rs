@@ -0,0 +1,54 @@
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Mutex},
+};
+
+////////////////////////////////////////////////////////////////
+// types
+////////////////////////////////////////////////////////////////
+
+/// Measurements stored by name during a run so later commands can refer to them. Cloning a store
+/// returns a handle to the same measurements, allowing transactions to store into it once the
+/// frontend has completed them.
+///
+#[derive(Clone, Debug, Default)]
+pub(crate) struct MeasurementStore {
+    values: Arc<Mutex<BTreeMap<String, i64>>>,
+}
+
+////////////////////////////////////////////////////////////////
+// methods
+////////////////////////////////////////////////////////////////
+
+impl MeasurementStore {
+    /// Store a measurement, replacing any previously stored under the same name.
+    ///
+    pub(crate) fn insert(&self, name: &str, value: i64) {
+        self.lock().insert(name.to_owned(), value);
+    }
+
+    pub(crate) fn get(&self, name: &str) -> Option<i64> {
+        self.lock().get(name).copied()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, BTreeMap<String, i64>> {
+        self.values
+            .lock()
+            .unwrap_or_else(|error| error.into_inner())
+    }
+}
+
+////////////////////////////////////////////////////////////////
+// comparison
+////////////////////////////////////////////////////////////////
+
+impl PartialEq for MeasurementStore {
+    fn eq(&self, other: &Self) -> bool {
+        // Stores are handles so compare by identity.
+        Arc::ptr_eq(&self.values, &other.values)
+    }
+}
+
+impl Eq for MeasurementStore {}
+
+////////////////////////////////////////////////////////////////
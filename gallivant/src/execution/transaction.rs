@@ -1,8 +1,32 @@
-use std::io::{self, Read, Write};
+use std::{
+    io::{Read, Write},
+    ops::Range,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
-use crate::{error::Error, syntax::ParsedExpr};
+use crate::{
+    error::{Error, ErrorNote},
+    syntax::ParsedExpr,
+};
 
-use super::measurement::{self, Measurement, MeasurementTest};
+use super::{
+    capture::Capture,
+    fingerprint::Fingerprint,
+    latency::LatencyLog,
+    measurement::{
+        self, Expected, Measurement, MeasurementFormat, MeasurementParser, MeasurementTest,
+        SampleReduction, UnitSuffix,
+    },
+    observer::{SharedObserver, TransactionObserver},
+    results::{test_channel, Results, TestOutcome},
+    simulation::SimulatedPort,
+    store::MeasurementStore,
+};
+
+/// Default maximum number of bytes taken from the port by each read.
+///
+const DEFAULT_READ_SIZE: usize = 256;
 
 ////////////////////////////////////////////////////////////////
 // types
@@ -18,14 +42,183 @@ pub struct Transaction {
     device: Device,
     response: Vec<u8>,
     test: Option<MeasurementTest>,
+    record: bool,
+
+    /// Don't tolerate whitespace surrounding measurements.
+    strict: bool,
+
+    /// Format of text measurements and the unit that follows them.
+    format: MeasurementFormat,
+    unit: UnitSuffix,
+
+    /// Number of bytes of packed BCD measurements are encoded in, if not ascii hex.
+    bcd: Option<usize>,
+
+    /// Minimum number of bytes following the echo before the measurement can be complete.
+    min_measurement: usize,
+
+    /// Number of lines the measurement is spread over.
+    measurement_lines: usize,
+
+    /// Parser used in place of the built in formats, if set.
+    parser: Option<MeasurementParser>,
+
+    echo: Echo,
+
+    /// Terminator of each line of the response.
+    line_ending: LineEnding,
+
+    /// Whether the device echoes the command before any response.
+    echoed: bool,
+
+    /// Byte sent once the echo has been received to prompt the device to take it's measurement.
+    /// For devices that won't sample until polled.
+    trigger: Option<u8>,
+    triggered: bool,
+
+    /// Measurements taken so far by a test of the measurement's stability.
+    samples: Vec<Measurement>,
+
+    /// Number of measurements taken for each one tested, how they're reduced to it and those taken
+    /// so far towards it.
+    sampling: u32,
+    reduction: SampleReduction,
+    readings: Vec<Measurement>,
+
+    /// If set, any response is ignored and the transaction completes once the drain period has
+    /// elapsed after transmission.
+    ignore_response: Option<Duration>,
+    txtime: Option<Instant>,
+    retrying: bool,
+
+    /// Maximum number of bytes taken from the port by each read.
+    read_size: usize,
+
+    /// Longest the device may go without sending anything before the transaction fails, and the
+    /// time anything was last received.
+    response_timeout: Option<Duration>,
+    rxtime: Option<Instant>,
+
+    /// Number of times the command is re-transmitted after a comms error before the transaction
+    /// fails, and the number of times it has been.
+    comms_retries: u32,
+    comms_retried: u32,
+
+    /// Whether the test has been retried after a failing measurement.
+    test_retried: bool,
+
+    /// Time of the first transmission, including any retries.
+    started: Option<Instant>,
+
+    /// Capture of the raw exchange and the index of this transaction within it.
+    capture: Option<(Capture, Option<usize>)>,
+
+    /// Store the measurement is saved to under the given name.
+    store: Option<(MeasurementStore, String)>,
+
+    /// Where the latency of each response is recorded.
+    latencies: Option<LatencyLog>,
+
+    /// Where the outcome of the transaction's test is reported.
+    results: Option<Results>,
+
+    /// Notified of every exchange with the device.
+    observer: Option<SharedObserver>,
 }
 
 ////////////////////////////////////////////////////////////////
 
+#[allow(clippy::large_enum_variant)] // Ongoing is by far the most common status.
 #[derive(Clone, Debug, PartialEq)]
 pub enum TransactionStatus {
-    Success,
+    /// The transaction completed. Contains the measurement tested, if it's command was a test that
+    /// passed. e.g. To log the value a channel measured.
+    Success {
+        measurement: Option<Measurement>,
+    },
     Ongoing(Transaction),
+
+    /// The transaction's test was skipped because it was run in record mode. Contains the span of
+    /// the test command and the measurement taken.
+    Recorded {
+        span: Range<usize>,
+        measurement: i64,
+    },
+}
+
+////////////////////////////////////////////////////////////////
+
+/// Phase of an ongoing transaction.
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum TransactionPhase {
+    /// The command is yet to be transmitted.
+    Writing,
+
+    /// Waiting for the device to echo the command.
+    AwaitingEcho,
+
+    /// Waiting for the device to return a measurement.
+    AwaitingMeasurement,
+
+    /// The echo has been received and the measurement trigger is yet to be transmitted.
+    Triggering,
+
+    /// A measurement failed it's test and the command is yet to be re-transmitted.
+    Retrying,
+
+    /// Waiting for a period to elapse before the transaction completes.
+    Waiting,
+}
+
+////////////////////////////////////////////////////////////////
+
+/// How the TCU's echo of a command is separated from the rest of it's response.
+///
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Echo {
+    /// The echo is terminated by a line ending.
+    #[default]
+    Delimited,
+
+    /// The echo is exactly this many bytes and may not be terminated. For firmware that sends the
+    /// echo and measurement without a carriage return between them.
+    FixedLength(usize),
+}
+
+////////////////////////////////////////////////////////////////
+
+/// Terminator of each line of a device's response, including the echo.
+///
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LineEnding {
+    /// Carriage return.
+    #[default]
+    Cr,
+
+    /// Carriage return followed by a line feed.
+    CrLf,
+
+    /// Line feed.
+    Lf,
+}
+
+////////////////////////////////////////////////////////////////
+
+/// Checksum appended to a command, for devices whose protocol requires one. See
+/// [`Transaction::checksum`].
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Checksum {
+    /// XOR of every byte. A single byte.
+    Xor8,
+
+    /// Sum of every byte, discarding any overflow. A single byte.
+    Sum8,
+
+    /// CRC-16/CCITT-FALSE. i.e. Polynomial 0x1021 with an initial value of 0xFFFF. Two bytes, most
+    /// significant first.
+    Crc16,
 }
 
 ////////////////////////////////////////////////////////////////
@@ -36,7 +229,19 @@ pub enum TransactionStatus {
 #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
 pub enum Device {
     TCU,
+
+    /// Printer tested through the TCU. i.e. The device whose channels PRINTERTEST measures, as
+    /// checked against a profile. Only transactions created by [`Transaction::with_printer`] are
+    /// with it, as the USB commands use [`Device::USB`].
     Printer,
+
+    /// External meter used to cross-check measurements taken by the device under test. Commands
+    /// aren't echoed.
+    Reference,
+
+    /// Printer connected directly over USB rather than via the TCU. i.e. Commanded by the USB
+    /// prefixed commands. Commands aren't echoed.
+    USB,
 }
 
 ////////////////////////////////////////////////////////////////
@@ -49,123 +254,1112 @@ impl Transaction {
         txbytes: Vec<u8>,
         test: Option<MeasurementTest>,
     ) -> Self {
-        Self {
-            expression,
-            txbytes,
-            txcomplete: false,
-            device: Device::TCU,
-            response: Vec::new(),
-            test,
-        }
+        Self::new(expression, txbytes, Device::TCU, test)
     }
 
     pub fn with_printer(
         expression: ParsedExpr,
         txbytes: Vec<u8>,
         test: Option<MeasurementTest>,
+    ) -> Self {
+        Self::new(expression, txbytes, Device::Printer, test)
+    }
+
+    /// Create a transaction with the printer over USB. i.e. Not via the TCU. Like the printer, the
+    /// command isn't echoed.
+    ///
+    pub fn with_usb(
+        expression: ParsedExpr,
+        txbytes: Vec<u8>,
+        test: Option<MeasurementTest>,
+    ) -> Self {
+        Self::new(expression, txbytes, Device::USB, test)
+    }
+
+    /// Create a transaction with the device using the default configuration. Only the TCU echoes
+    /// commands.
+    ///
+    fn new(
+        expression: ParsedExpr,
+        txbytes: Vec<u8>,
+        device: Device,
+        test: Option<MeasurementTest>,
     ) -> Self {
         Self {
             expression,
             txbytes,
             txcomplete: false,
-            device: Device::Printer,
+            device,
+            echoed: device == Device::TCU,
             response: Vec::new(),
             test,
+            record: false,
+            strict: false,
+            format: MeasurementFormat::HexU32,
+            unit: UnitSuffix::Unitless,
+            bcd: None,
+            min_measurement: 0,
+            measurement_lines: 1,
+            parser: None,
+            echo: Echo::Delimited,
+            line_ending: LineEnding::Cr,
+            trigger: None,
+            triggered: false,
+            samples: Vec::new(),
+            sampling: 1,
+            reduction: SampleReduction::Average,
+            readings: Vec::new(),
+            ignore_response: None,
+            txtime: None,
+            retrying: false,
+            read_size: DEFAULT_READ_SIZE,
+            response_timeout: None,
+            rxtime: None,
+            comms_retries: 0,
+            comms_retried: 0,
+            test_retried: false,
+            started: None,
+            capture: None,
+            store: None,
+            latencies: None,
+            results: None,
+            observer: None,
         }
     }
 }
 
+////////////////////////////////////////////////////////////////
+
+impl Transaction {
+    /// Create a transaction that only takes a measurement. The measurement is returned by
+    /// [`TransactionStatus::Recorded`] once taken.
+    ///
+    pub(super) fn measuring(expression: ParsedExpr, txbytes: Vec<u8>, device: Device) -> Self {
+        let test = MeasurementTest {
+            expected: Expected::Range(0..=u32::MAX),
+            retries: 0,
+            attempt: 0,
+            failure_message: String::new(),
+        };
+
+        Self {
+            record: true,
+            ..Self::new(expression, txbytes, device, Some(test))
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////
+
+impl Transaction {
+    /// Set whether the transaction should record it's measurement instead of testing it.
+    ///
+    #[must_use]
+    pub fn recording(mut self, record: bool) -> Self {
+        self.record = record;
+        self
+    }
+
+    /// Set whether measurements must be parsed strictly. By default any whitespace surrounding a
+    /// measurement is ignored.
+    ///
+    #[must_use]
+    pub fn strict_measurement(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// Set the format text measurements are parsed in, for devices that don't report them as hex.
+    /// Ignored by BCD measurements and any measurement parser.
+    ///
+    #[must_use]
+    pub fn measurement_format(mut self, format: MeasurementFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Set the unit that follows text measurements, for devices that append one. e.g. `1200mV`.
+    /// By default measurements have no unit. Ignored by BCD measurements and any measurement
+    /// parser.
+    ///
+    #[must_use]
+    pub fn measurement_unit(mut self, unit: UnitSuffix) -> Self {
+        self.unit = unit;
+        self
+    }
+
+    /// Set measurements to be parsed as packed BCD of the given number of bytes, for devices that
+    /// don't report them as ascii hex. e.g. `[0x12, 0x34]` is 1234.
+    ///
+    #[must_use]
+    pub fn bcd_measurement(mut self, bytes: usize) -> Self {
+        self.bcd = Some(bytes);
+        self
+    }
+
+    /// Set the maximum number of bytes taken from the port by each read, for devices whose
+    /// responses exceed the default of 256 bytes. A response longer than this is still received,
+    /// but over several reads.
+    ///
+    #[must_use]
+    pub fn read_buffer_size(mut self, bytes: usize) -> Self {
+        self.read_size = bytes.max(1);
+        self
+    }
+
+    /// Set the longest the device may go without sending anything, once the command has been
+    /// transmitted, before the transaction fails with a timeout. Counts as a comms error so the
+    /// command is re-transmitted if the transaction has comms retries left. While set, a port's own
+    /// read timeouts are treated as nothing having been received.
+    ///
+    /// [`Transaction::process`] doesn't block, so the timeout is only checked when it's called.
+    ///
+    #[must_use]
+    pub fn response_timeout(mut self, timeout: Duration) -> Self {
+        self.response_timeout = Some(timeout);
+        self
+    }
+
+    /// Set the minimum number of bytes, following any echo and including the terminating carriage
+    /// return, before the measurement can be complete. For firmware that precedes the measurement
+    /// with a status line. A carriage return within the minimum doesn't terminate the measurement
+    /// but separates the status from it, and only the last line is parsed. e.g. With a minimum of
+    /// 7, `S\r0BB8\r` is a measurement of 0x0BB8. By default the first carriage return terminates
+    /// the measurement.
+    ///
+    #[must_use]
+    pub fn min_measurement_length(mut self, bytes: usize) -> Self {
+        self.min_measurement = bytes;
+        self
+    }
+
+    /// Set the number of carriage return terminated lines the measurement is spread over. e.g. A
+    /// line for the high byte followed by a line for the low byte. The lines are joined in the
+    /// order received, the first being the most significant, with the carriage returns between
+    /// them removed. Nothing else is removed, so `12\r34\r` is a measurement of 0x1234. Any
+    /// status line preceding the measurement, see [`Transaction::min_measurement_length`], isn't
+    /// joined. By default the measurement is a single line.
+    ///
+    #[must_use]
+    pub fn measurement_lines(mut self, lines: usize) -> Self {
+        self.measurement_lines = lines.max(1);
+        self
+    }
+
+    /// Set a parser for measurements, used in place of the built in ascii hex and BCD formats. For
+    /// devices with framing those formats can't handle. The parser is given the measurement once
+    /// any status line has been removed and any lines it's spread over have been joined, see
+    /// [`Transaction::min_measurement_length`] and [`Transaction::measurement_lines`].
+    ///
+    #[must_use]
+    pub fn measurement_parser(mut self, parser: MeasurementParser) -> Self {
+        self.parser = Some(parser);
+        self
+    }
+
+    /// Set the number of times the command is re-transmitted after a comms error, i.e. an IO error
+    /// such as a timeout or an incorrect echo, before the transaction fails. These are separate
+    /// from the test's retries, which only apply to failing measurements. By default a comms
+    /// error fails the transaction.
+    ///
+    #[must_use]
+    pub fn comms_retries(mut self, retries: u32) -> Self {
+        self.comms_retries = retries;
+        self
+    }
+
+    /// Set whether the device echoes the command before any response, for devices that don't fit
+    /// the default of the TCU echoing and any other device not. The echo is separated from the
+    /// rest of the response as set by [`Transaction::echo_format`].
+    ///
+    #[must_use]
+    pub fn expecting_echo(mut self, echoed: bool) -> Self {
+        self.echoed = echoed;
+        self
+    }
+
+    /// Set how the echo is separated from the rest of the response.
+    ///
+    #[must_use]
+    pub fn echo_format(mut self, echo: Echo) -> Self {
+        self.echo = echo;
+        self
+    }
+
+    /// Set the terminator of each line of the response, for firmware that doesn't end lines with
+    /// a carriage return. Measurements are passed to their parser with their lines joined and a
+    /// single carriage return in place of the terminator, whatever the line ending.
+    ///
+    #[must_use]
+    pub fn line_ending(mut self, ending: LineEnding) -> Self {
+        self.line_ending = ending;
+        self
+    }
+
+    /// Take this many measurements in place of a single one, re-transmitting the command for each,
+    /// and reduce them to the one tested. e.g. Averaging a noisy analog channel or testing the
+    /// worst case. A count of 0 is taken as 1.
+    ///
+    #[must_use]
+    pub fn sampling(mut self, reduction: SampleReduction, samples: u32) -> Self {
+        self.reduction = reduction;
+        self.sampling = samples.max(1);
+        self
+    }
+
+    /// Append a checksum of the command to it before it's transmitted, for devices whose protocol
+    /// requires one. The checksum is of every byte before the command's terminating carriage
+    /// return and is inserted before it. e.g. `P01\r` with [`Checksum::Xor8`] is sent as
+    /// `P01\x51\r`.
+    ///
+    #[must_use]
+    pub fn checksum(mut self, checksum: Checksum) -> Self {
+        let end = match self.txbytes.last() {
+            Some(b'\r') => self.txbytes.len() - 1,
+            _ => self.txbytes.len(),
+        };
+
+        let bytes = checksum.calculate(&self.txbytes[..end]);
+        self.txbytes.splice(end..end, bytes);
+        self
+    }
+
+    /// Send the trigger byte once the echo has been received to prompt the device to take it's
+    /// measurement, rather than expecting the measurement to follow the echo. Only test commands
+    /// are triggered. Each retry of the test re-transmits the command and triggers it again.
+    ///
+    #[must_use]
+    pub fn measurement_trigger(mut self, trigger: u8) -> Self {
+        self.trigger = Some(trigger);
+        self
+    }
+
+    /// Save the measurement to the store under the given name once taken.
+    ///
+    #[must_use]
+    pub(crate) fn storing(mut self, store: MeasurementStore, name: impl Into<String>) -> Self {
+        self.store = Some((store, name.into()));
+        self
+    }
+
+    /// Test the measurement against the baseline in the store, if the test is against a baseline.
+    /// Baselines are stored by the position of the command in the script. If the command hasn't
+    /// taken a baseline yet, this measurement is saved as it's baseline instead.
+    ///
+    #[must_use]
+    pub(crate) fn with_baselines(mut self, baselines: &MeasurementStore) -> Self {
+        let Some(MeasurementTest {
+            expected: Expected::Baseline { baseline, .. },
+            ..
+        }) = &mut self.test
+        else {
+            return self;
+        };
+
+        let key = self.expression.span().start.to_string();
+        match baselines.get(&key) {
+            Some(value) => *baseline = Some(value),
+            None => self.store = Some((baselines.clone(), key)),
+        }
+
+        self
+    }
+
+    /// Record the time between transmitting the command and completing each response to the log.
+    ///
+    #[must_use]
+    pub(crate) fn logging_latency(mut self, latencies: LatencyLog) -> Self {
+        self.latencies = Some(latencies);
+        self
+    }
+
+    /// Retain the raw bytes transmitted and received by the transaction in the capture.
+    ///
+    #[must_use]
+    pub fn capturing(mut self, capture: Capture) -> Self {
+        self.capture = Some((capture, None));
+        self
+    }
+
+    /// Report the outcome of the transaction's test to the results. Only test commands report.
+    ///
+    #[must_use]
+    pub fn reporting(mut self, results: Results) -> Self {
+        self.results = Some(results);
+        self
+    }
+
+    /// Notify the observer of every exchange with the device. The observer is shared, so a handle
+    /// kept by the frontend sees the exchanges of every transaction it's attached to.
+    ///
+    #[must_use]
+    pub fn observing(mut self, observer: Arc<dyn TransactionObserver>) -> Self {
+        self.observer = Some(observer.into());
+        self
+    }
+
+    /// Ignore any response to the transaction. Anything received within the drain period after
+    /// transmission is read and discarded. A drain period of zero completes the transaction as soon
+    /// as it's transmitted.
+    ///
+    #[must_use]
+    pub fn ignoring_response(mut self, drain: Duration) -> Self {
+        self.ignore_response = Some(drain);
+        self
+    }
+}
+
 ////////////////////////////////////////////////////////////////
 // methods
 ////////////////////////////////////////////////////////////////
 
+impl LineEnding {
+    /// Return the bytes terminating each line.
+    ///
+    pub fn terminator(&self) -> &'static [u8] {
+        match self {
+            LineEnding::Cr => b"\r",
+            LineEnding::CrLf => b"\r\n",
+            LineEnding::Lf => b"\n",
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////
+
+impl Checksum {
+    /// Return the checksum of the bytes, in the order it's transmitted.
+    ///
+    pub fn calculate(&self, bytes: &[u8]) -> Vec<u8> {
+        match self {
+            Checksum::Xor8 => vec![bytes.iter().fold(0, |checksum, byte| checksum ^ byte)],
+            Checksum::Sum8 => vec![bytes
+                .iter()
+                .fold(0u8, |checksum, byte| checksum.wrapping_add(*byte))],
+            Checksum::Crc16 => {
+                let crc = bytes.iter().fold(0xFFFFu16, |crc, byte| {
+                    (0..8).fold(crc ^ (u16::from(*byte) << 8), |crc, _| {
+                        if crc & 0x8000 != 0 {
+                            (crc << 1) ^ 0x1021
+                        } else {
+                            crc << 1
+                        }
+                    })
+                });
+                crc.to_be_bytes().to_vec()
+            }
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////
+
+impl std::fmt::Display for Device {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Device::TCU => write!(f, "TCU"),
+            Device::Printer => write!(f, "printer"),
+            Device::Reference => write!(f, "reference device"),
+            Device::USB => write!(f, "USB printer"),
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////
+
 impl Transaction {
     pub fn bytes(&self) -> &[u8] {
         &self.txbytes
     }
 
-    pub fn process<T: Read + Write>(mut self, port: &mut T) -> Result<TransactionStatus, Error> {
-        let into_io_error = |error| Error::from_io_error(self.expression.clone(), error);
+    /// Return the bytes transmitted in hex, each separated by a space. e.g. `4D 30 33 0D`.
+    ///
+    pub fn hex_dump(&self) -> String {
+        self.txbytes
+            .iter()
+            .map(|byte| format!("{byte:02X}"))
+            .collect::<Vec<String>>()
+            .join(" ")
+    }
 
+    /// Return the bytes received in response to the latest transmission so far, including any
+    /// echo. e.g. To show a device's output while a measurement is still being received.
+    ///
+    /// # Example
+    /// ```
+    /// use std::{collections::VecDeque, io};
+    /// use gallivant::{FrontendRequest, Interpreter, TransactionStatus};
+    ///
+    /// /// Port that loops back everything written followed by the start of a measurement.
+    /// struct Port(VecDeque<u8>);
+    ///
+    /// impl io::Read for Port {
+    ///     fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+    ///         self.0.read(buffer)
+    ///     }
+    /// }
+    ///
+    /// impl io::Write for Port {
+    ///     fn write(&mut self, buffer: &[u8]) -> io::Result<usize> {
+    ///         self.0.extend(buffer);
+    ///         self.0.extend(b"00");
+    ///         Ok(buffer.len())
+    ///     }
+    ///
+    ///     fn flush(&mut self) -> io::Result<()> {
+    ///         Ok(())
+    ///     }
+    /// }
+    ///
+    /// let script = r#"TCUTEST 3, 0, 16, 0, "Out of range""#;
+    /// let Some(Ok(FrontendRequest::TCUTransact(transaction))) =
+    ///     Interpreter::try_from_str(script).unwrap().next()
+    /// else {
+    ///     unreachable!()
+    /// };
+    ///
+    /// // Transmit then receive.
+    /// let mut port = Port(VecDeque::new());
+    /// let TransactionStatus::Ongoing(transaction) = transaction.process(&mut port).unwrap() else {
+    ///     unreachable!()
+    /// };
+    /// let TransactionStatus::Ongoing(transaction) = transaction.process(&mut port).unwrap() else {
+    ///     unreachable!()
+    /// };
+    ///
+    /// assert_eq!(transaction.response(), b"M03\r00");
+    /// ```
+    ///
+    pub fn response(&self) -> &[u8] {
+        &self.response
+    }
+
+    /// Return the span of the command in the script that created the transaction.
+    ///
+    pub fn span(&self) -> &Range<usize> {
+        self.expression.span()
+    }
+
+    /// Return the device the transaction is with.
+    ///
+    pub fn device(&self) -> Device {
+        self.device
+    }
+
+    /// Return the test performed on the transaction's measurement, if any.
+    ///
+    pub fn test(&self) -> Option<&MeasurementTest> {
+        self.test.as_ref()
+    }
+
+    /// Return the longest the device may go without sending anything, if limited.
+    ///
+    pub fn timeout(&self) -> Option<Duration> {
+        self.response_timeout
+    }
+
+    /// Add the transaction's device, transmitted bytes, test and drain period to the fingerprint.
+    ///
+    pub(crate) fn fingerprint(&self, fingerprint: &mut Fingerprint) {
+        fingerprint.write_str(&self.device.to_string());
+        fingerprint.write_bytes(&self.txbytes);
+
+        match &self.test {
+            Some(test) => {
+                fingerprint.write_str("test");
+                fingerprint.write_str(&test.expected.to_string());
+                fingerprint.write_u32(test.retries);
+                fingerprint.write_str(&test.failure_message);
+
+                if self.sampling > 1 {
+                    fingerprint.write_str(&self.reduction.to_string());
+                    fingerprint.write_u32(self.sampling);
+                }
+            }
+            None => fingerprint.write_str("untested"),
+        }
+
+        match self.ignore_response {
+            Some(drain) => {
+                fingerprint.write_str("drain");
+                fingerprint.write_u64(drain.as_millis() as u64);
+            }
+            None => fingerprint.write_str("response"),
+        }
+    }
+
+    /// Write the transaction as a JSON object, as it would be transmitted. The object contains the
+    /// device, the kind of command that created it, the bytes transmitted in hex, the test if any
+    /// and the span of the command in the script.
+    ///
+    pub(crate) fn write_json<W: Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        let bytes: String = self
+            .txbytes
+            .iter()
+            .map(|byte| format!("{byte:02X}"))
+            .collect();
+        let span = self.expression.span();
+
+        write!(
+            writer,
+            "{{\"device\": {}, \"command\": {}, \"bytes\": \"{bytes}\", \"test\": ",
+            json_string(&self.device.to_string()),
+            json_string(&format!("{:?}", self.expression.expression_kind())),
+        )?;
+
+        match &self.test {
+            Some(test) => write!(
+                writer,
+                "{{\"expected\": {}, \"retries\": {}, \"message\": {}}}",
+                json_string(&test.expected.to_string()),
+                test.retries,
+                json_string(&test.failure_message),
+            )?,
+            None => write!(writer, "null")?,
+        }
+
+        write!(
+            writer,
+            ", \"span\": {{\"start\": {}, \"end\": {}}}}}",
+            span.start, span.end
+        )
+    }
+
+    /// Return the phase the transaction is currently in.
+    ///
+    pub fn phase(&self) -> TransactionPhase {
+        if !self.txcomplete {
+            return if self.retrying {
+                TransactionPhase::Retrying
+            } else {
+                TransactionPhase::Writing
+            };
+        }
+
+        if self.ignore_response.is_some() {
+            return TransactionPhase::Waiting;
+        }
+
+        if self.awaiting_trigger() {
+            return TransactionPhase::Triggering;
+        }
+
+        if self.echo_length().is_none() {
+            TransactionPhase::AwaitingEcho
+        } else {
+            TransactionPhase::AwaitingMeasurement
+        }
+    }
+
+    pub fn process<T: Read + Write>(mut self, port: &mut T) -> Result<TransactionStatus, Error> {
         // Send bytes if needed.
         if !self.txcomplete {
-            port.write_all(&self.txbytes).map_err(into_io_error)?;
+            if let Err(error) = port.write_all(&self.txbytes) {
+                let error = Error::from_io_error(self.expression.clone(), error);
+                return self.retry_comms(error);
+            }
+
+            if let Some(observer) = &self.observer {
+                observer.on_tx(&self, &self.txbytes);
+            }
+
+            if let Some((capture, index)) = &mut self.capture {
+                match index {
+                    Some(index) => capture.send(*index, &self.txbytes),
+                    None => {
+                        *index = Some(capture.open(self.expression.span().clone(), &self.txbytes))
+                    }
+                }
+            }
+
             self.txcomplete = true;
+            self.txtime = Some(Instant::now());
+            self.rxtime = None;
+            self.started.get_or_insert(Instant::now());
+            self.retrying = false;
 
-            return if self.device == Device::Printer && self.test.is_none() {
-                Ok(TransactionStatus::Success)
+            if self.ignore_response.is_some_and(|drain| drain.is_zero()) {
+                return Ok(TransactionStatus::Success { measurement: None });
+            }
+
+            return if self.ignore_response.is_some() {
+                Ok(TransactionStatus::Ongoing(self))
+            } else if !self.echoed && self.test.is_none() {
+                Ok(TransactionStatus::Success { measurement: None })
             } else {
                 Ok(TransactionStatus::Ongoing(self))
             };
         }
 
+        // Prompt the device to take it's measurement once it's echoed the command.
+        if self.awaiting_trigger() {
+            let trigger = [self.trigger.unwrap_or_default()];
+            if let Err(error) = port.write_all(&trigger) {
+                let error = Error::from_io_error(self.expression.clone(), error);
+                return self.retry_comms(error);
+            }
+
+            if let Some(observer) = &self.observer {
+                observer.on_tx(&self, &trigger);
+            }
+
+            if let Some((capture, Some(index))) = &self.capture {
+                capture.send(*index, &trigger);
+            }
+
+            self.triggered = true;
+            self.rxtime = Some(Instant::now());
+            return Ok(TransactionStatus::Ongoing(self));
+        }
+
         let response = {
-            let mut buffer = [0; 256];
-            let count = port.read(&mut buffer).map_err(into_io_error)?;
+            let mut buffer = vec![0; self.read_size];
+            let count = match port.read(&mut buffer) {
+                Ok(count) => count,
+                Err(error)
+                    if error.kind() == std::io::ErrorKind::TimedOut
+                        && self.response_timeout.is_some() =>
+                {
+                    0
+                }
+                Err(error) => {
+                    let error = Error::from_io_error(self.expression.clone(), error);
+                    return self.retry_comms(error);
+                }
+            };
             buffer[0..count].to_owned()
         };
 
+        if let Some(observer) = self.observer.as_ref().filter(|_| !response.is_empty()) {
+            observer.on_rx(&self, &response);
+        }
+
+        if let Some((capture, Some(index))) = &self.capture {
+            capture.receive(*index, &response);
+        }
+
+        if let Some(drain) = self.ignore_response {
+            let elapsed = self.txtime.map(|time| time.elapsed()).unwrap_or_default();
+            return if elapsed >= drain {
+                Ok(TransactionStatus::Success { measurement: None })
+            } else {
+                Ok(TransactionStatus::Ongoing(self))
+            };
+        }
+
+        if response.is_empty() && self.response_timeout.is_some() {
+            return self.check_timeout();
+        }
+
+        self.rxtime = Some(Instant::now());
         self.response.extend_from_slice(&response);
         self.evaluate_response()
     }
 
-    fn evaluate_response(mut self) -> Result<TransactionStatus, Error> {
-        // Find the number of expected \r characters.
-        let echo_expected = self.device == Device::TCU;
-        let expected_endings = if self.test.is_some() && echo_expected {
-            2
-        } else if self.test.is_some() || echo_expected {
-            1
-        } else {
-            0
+    /// Complete the transaction offline, as if the device responded to each transmission with the
+    /// next of the given measurements. Intended for testing a script's tests without hardware.
+    ///
+    /// # Arguments
+    /// * `measurements` - Measurements the device returns. Any retries of the test, or further
+    ///   samples taken by a stability test, consume further measurements.
+    ///
+    /// # Returns
+    /// The status once the transaction completes. If the measurements run out first, the ongoing
+    /// transaction is returned.
+    ///
+    /// # Example
+    /// ```
+    /// use gallivant::{FrontendRequest, Interpreter, TransactionStatus};
+    ///
+    /// let script = r#"TCUTEST 3, >= 3000, 0, "Too low""#;
+    /// let Some(Ok(FrontendRequest::TCUTransact(transaction))) =
+    ///     Interpreter::try_from_str(script).unwrap().next()
+    /// else {
+    ///     unreachable!()
+    /// };
+    ///
+    /// assert!(transaction.clone().simulate([2999]).is_err());
+    /// assert!(matches!(transaction.simulate([3000]), Ok(TransactionStatus::Success { .. })));
+    /// ```
+    ///
+    pub fn simulate(
+        self,
+        measurements: impl IntoIterator<Item = u32>,
+    ) -> Result<TransactionStatus, Error> {
+        let mut port = SimulatedPort::new(self.echoed, measurements)
+            .triggered(self.trigger.is_some() && self.test.is_some());
+
+        let mut transaction = self;
+        loop {
+            transaction = match transaction.process(&mut port)? {
+                TransactionStatus::Ongoing(transaction) => transaction,
+                status => return Ok(status),
+            };
+
+            let phase = transaction.phase();
+            let writing = matches!(
+                phase,
+                TransactionPhase::Writing
+                    | TransactionPhase::Triggering
+                    | TransactionPhase::Retrying
+            );
+            if !writing && !port.has_response() {
+                return Ok(TransactionStatus::Ongoing(transaction));
+            }
+        }
+    }
+
+    /// Return the transaction as it was before anything was transmitted.
+    ///
+    pub(super) fn restarted(mut self) -> Self {
+        self.txcomplete = false;
+        self.response.clear();
+        self.txtime = None;
+        self.rxtime = None;
+        self.retrying = false;
+        self.triggered = false;
+        self.samples.clear();
+        self.readings.clear();
+        self.comms_retried = 0;
+        self.test_retried = false;
+        self
+    }
+
+    /// Re-transmit the command after a comms error if the transaction has comms retries left.
+    ///
+    /// # Errors
+    /// The comms error if there are no retries left.
+    ///
+    fn retry_comms(mut self, error: Error) -> Result<TransactionStatus, Error> {
+        if self.comms_retried >= self.comms_retries {
+            return Err(match self.comms_retried {
+                0 => error,
+                _ => error.with_note(ErrorNote::Note(
+                    "Failed after using all of the transaction's comms retries",
+                )),
+            });
+        }
+
+        self.comms_retried += 1;
+        self.txcomplete = false;
+        self.retrying = true;
+        self.triggered = false;
+        self.response.clear();
+        Ok(TransactionStatus::Ongoing(self))
+    }
+
+    /// Fail with a timeout, or re-transmit if there are comms retries left, if the device has gone
+    /// longer than the response timeout without sending anything.
+    ///
+    fn check_timeout(self) -> Result<TransactionStatus, Error> {
+        let elapsed = self
+            .rxtime
+            .or(self.txtime)
+            .map(|time| time.elapsed())
+            .unwrap_or_default();
+
+        match self.response_timeout {
+            Some(timeout) if elapsed >= timeout => {
+                let error = Error::timeout(self.expression.clone(), elapsed);
+                self.retry_comms(error)
+            }
+            _ => Ok(TransactionStatus::Ongoing(self)),
+        }
+    }
+
+    /// Return true if the echo has been received and the device is yet to be prompted to take it's
+    /// measurement.
+    ///
+    fn awaiting_trigger(&self) -> bool {
+        self.txcomplete
+            && self.trigger.is_some()
+            && !self.triggered
+            && self.test.is_some()
+            && self.echo_length().is_some()
+    }
+
+    /// Return the length of the echo at the start of the response. None if the echo hasn't been
+    /// fully received yet.
+    ///
+    fn echo_length(&self) -> Option<usize> {
+        if !self.echoed {
+            return Some(0);
+        }
+
+        match self.echo {
+            Echo::Delimited => line_ends(&self.response, self.line_ending)
+                .first()
+                .map(|end| end.end),
+            Echo::FixedLength(length) => (self.response.len() >= length).then_some(length),
+        }
+    }
+
+    /// Return the echo expected of the command. A delimited echo ends with the response's line
+    /// ending in place of the command's carriage return.
+    ///
+    /// # Arguments
+    /// * `echo_length` - Length of the echo received.
+    ///
+    fn expected_echo(&self, echo_length: usize) -> Vec<u8> {
+        match self.echo {
+            _ if !self.echoed => Vec::new(),
+            Echo::Delimited => {
+                let command = self.txbytes.strip_suffix(b"\r").unwrap_or(&self.txbytes);
+                [command, self.line_ending.terminator()].concat()
+            }
+            Echo::FixedLength(_) => self.txbytes[..echo_length.min(self.txbytes.len())].to_owned(),
+        }
+    }
+
+    /// Report the final outcome of the transaction's test to any results.
+    ///
+    pub(super) fn report(&self, measured: i64, passed: bool, message: String) {
+        let (Some(results), Some(channel)) = (&self.results, test_channel(&self.expression)) else {
+            return;
+        };
+
+        results.report(TestOutcome {
+            span: self.expression.span().clone(),
+            device: self.device,
+            channel,
+            measured,
+            passed,
+            message,
+            elapsed: self.elapsed(),
+            board: None,
+        });
+    }
+
+    /// Return the time since the first transmission.
+    ///
+    pub(super) fn elapsed(&self) -> Duration {
+        self.started
+            .map(|started| started.elapsed())
+            .unwrap_or_default()
+    }
+
+    /// Return the measurement from the response following the echo, joining it's lines if it's
+    /// spread over several. None until it's complete.
+    ///
+    fn assemble_measurement(&self, response: &[u8]) -> Option<Vec<u8>> {
+        // The measurement ends at the first line ending that both completes it's lines and
+        // satisfies the minimum length. Any earlier ones separate a status line from it.
+        let terminators = line_ends(response, self.line_ending);
+        let last = terminators.iter().enumerate().position(|(index, end)| {
+            index + 1 >= self.measurement_lines && end.end >= self.min_measurement
+        })?;
+
+        // Join the measurement's lines, dropping their line endings.
+        let first = last + 1 - self.measurement_lines;
+        let mut start = match first.checked_sub(1) {
+            Some(separator) => terminators[separator].end,
+            None => 0,
         };
 
+        let mut measurement = Vec::new();
+        for end in &terminators[first..=last] {
+            measurement.extend_from_slice(&response[start..end.start]);
+            start = end.end;
+        }
+        measurement.push(b'\r');
+        Some(measurement)
+    }
+
+    fn evaluate_response(mut self) -> Result<TransactionStatus, Error> {
         // No response expected.
-        if expected_endings == 0 {
-            return Ok(TransactionStatus::Success);
+        if self.test.is_none() && !self.echoed {
+            return Ok(TransactionStatus::Success { measurement: None });
         }
 
-        let parts: Vec<&[u8]> = self.response.split_inclusive(|&b| b == b'\r').collect();
+        let Some(echo_length) = self.echo_length() else {
+            return Ok(TransactionStatus::Ongoing(self));
+        };
 
-        // Incomplete response.
-        if parts.len() < expected_endings {
+        if self.awaiting_trigger() {
             return Ok(TransactionStatus::Ongoing(self));
         }
 
-        let (echo, measurement) = if echo_expected {
-            (parts.get(0), parts.get(1))
-        } else {
-            (None, parts.get(0))
-        };
+        let (echo, remainder) = self.response.split_at(echo_length);
+        let expected_echo = self.expected_echo(echo_length);
+        let echo_valid = echo == expected_echo.as_slice();
+
+        let measurement = self.assemble_measurement(remainder);
+
+        // Incomplete response.
+        if self.test.is_some() && measurement.is_none() {
+            return Ok(TransactionStatus::Ongoing(self));
+        }
 
         // Validate the echo.
-        if echo.is_some_and(|echo| *echo != self.txbytes) {
-            todo!("Command echo incorrect");
+        if !echo_valid {
+            let received = echo.to_owned();
+            let error = Error::echo_mismatch(self.expression.clone(), expected_echo, received);
+            return self.retry_comms(error);
+        }
+
+        if let (Some(latencies), Some(txtime)) = (&self.latencies, self.txtime) {
+            latencies.record(txtime.elapsed());
         }
 
         // Test the measurement.
-        if let Some(test) = self.test {
-            let measurement = *measurement.unwrap(); // Already checked that the measurement exists.
-            let measurement = Measurement::try_from(measurement)
-                .unwrap_or_else(|_| todo!("Handle measurement parsing failure"));
+        if let Some(test) = self.test.take() {
+            let response = measurement.unwrap(); // Already checked that the measurement exists.
+            let (measurement, format) = match (&self.parser, self.bcd) {
+                (Some(parser), _) => (parser.parse(&response), "custom"),
+                (None, Some(length)) => (Measurement::parse_bcd(&response, length), "packed BCD"),
+                (None, None) => (
+                    Measurement::parse_with_unit(&response, self.format, &self.unit, self.strict),
+                    self.format.name(),
+                ),
+            };
+
+            // Most likely garbled in transit, so re-transmit if possible.
+            let measurement = match measurement {
+                Ok(measurement) => measurement,
+                Err(error) => {
+                    let expression = self.expression.clone();
+                    let error = Error::invalid_measurement(expression, format, response, error);
+                    self.test = Some(test);
+                    return self.retry_comms(error);
+                }
+            };
+
+            // Keep re-transmitting until enough readings have been taken, then reduce them.
+            let measurement = if self.sampling > 1 {
+                self.readings.push(measurement);
+                if self.readings.len() < self.sampling as usize {
+                    self.test = Some(test);
+                    self.txcomplete = false;
+                    self.triggered = false;
+                    self.response.clear();
+                    return Ok(TransactionStatus::Ongoing(self));
+                }
+
+                let reduced = self.reduction.reduce(&self.readings, &test);
+                self.readings.clear();
+                reduced
+            } else {
+                measurement
+            };
+
+            // Keep re-transmitting until enough samples have been taken, then test their spread.
+            let measurement = match test.expected {
+                Expected::Stable { samples, .. } => {
+                    self.samples.push(measurement);
+                    if self.samples.len() < samples as usize {
+                        self.test = Some(test);
+                        self.txcomplete = false;
+                        self.triggered = false;
+                        self.response.clear();
+                        return Ok(TransactionStatus::Ongoing(self));
+                    }
+
+                    let spread = Measurement::spread(&self.samples);
+                    self.samples.clear();
+                    spread
+                }
+                _ => measurement,
+            };
+
+            if let Some((store, name)) = &self.store {
+                store.insert(name, measurement.value());
+            }
+
+            if self.record {
+                return Ok(TransactionStatus::Recorded {
+                    span: self.expression.span().clone(),
+                    measurement: measurement.value(),
+                });
+            }
 
+            let message = test.failure_message.clone();
             match test.test(measurement) {
-                Ok(_) => (),
-                Err(measurement::Error::TestFailedRetryable(test)) => {
+                Ok(_) => {
+                    self.report(measurement.value(), true, message);
+                    return Ok(TransactionStatus::Success {
+                        measurement: Some(measurement),
+                    });
+                }
+                Err(measurement::Error::TestFailedRetryable { test, .. }) => {
                     self.test = Some(test);
+                    self.test_retried = true;
                     self.txcomplete = false;
+                    self.retrying = true;
+                    self.triggered = false;
+                    self.response.clear();
                     return Ok(TransactionStatus::Ongoing(self));
                 }
                 Err(measurement::Error::TestFailed(test)) => {
-                    return Err(Error::from_failed_test(self.expression, test))
+                    self.report(test.measurement, false, message);
+
+                    let mut error = Error::from_failed_test(self.expression, test);
+                    if self.test_retried {
+                        error = error.with_note(ErrorNote::Note(
+                            "Failed after using all of the test's retries",
+                        ));
+                    }
+                    return Err(error);
+                }
+                // Not returned by a test, which only takes already parsed measurements.
+                Err(error @ measurement::Error::ParseError(_)) => {
+                    return Err(Error::invalid_measurement(
+                        self.expression,
+                        format,
+                        response,
+                        error,
+                    ));
                 }
-                _ => todo!(),
             }
         }
 
         // Success.
-        Ok(TransactionStatus::Success)
+        Ok(TransactionStatus::Success { measurement: None })
+    }
+}
+
+////////////////////////////////////////////////////////////////
+// helpers
+////////////////////////////////////////////////////////////////
+
+/// Return the position of every line ending in the bytes, in order.
+///
+fn line_ends(bytes: &[u8], ending: LineEnding) -> Vec<Range<usize>> {
+    let terminator = ending.terminator();
+
+    let mut ends = Vec::new();
+    let mut start = 0;
+    while let Some(offset) = bytes[start..]
+        .windows(terminator.len())
+        .position(|window| window == terminator)
+    {
+        start += offset + terminator.len();
+        ends.push(start - terminator.len()..start);
+    }
+
+    ends
+}
+
+////////////////////////////////////////////////////////////////
+
+/// Quote and escape the text as a JSON string.
+///
+fn json_string(text: &str) -> String {
+    let mut string = String::from('"');
+    for character in text.chars() {
+        match character {
+            '"' => string.push_str("\\\""),
+            '\\' => string.push_str("\\\\"),
+            '\n' => string.push_str("\\n"),
+            '\r' => string.push_str("\\r"),
+            '\t' => string.push_str("\\t"),
+            character if character.is_control() => {
+                string.push_str(&format!("\\u{:04x}", character as u32))
+            }
+            character => string.push(character),
+        }
     }
+    string.push('"');
+    string
 }
 
 ////////////////////////////////////////////////////////////////
@@ -0,0 +1,47 @@
+use super::transaction::Device;
+
+////////////////////////////////////////////////////////////////
+// types
+////////////////////////////////////////////////////////////////
+
+/// How the frontend is connected to the printer.
+///
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Transport {
+    /// Via the TCU's serial port.
+    Serial,
+
+    /// Directly via USB. i.e. Using the USB prefixed commands.
+    USB,
+}
+
+////////////////////////////////////////////////////////////////
+// methods
+////////////////////////////////////////////////////////////////
+
+impl Transport {
+    /// Return true if the device can be communicated with using this transport.
+    ///
+    pub fn supports(&self, device: Device) -> bool {
+        match self {
+            Transport::Serial => matches!(device, Device::TCU | Device::Reference),
+            Transport::USB => matches!(device, Device::USB | Device::Reference),
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////
+// ...
+////////////////////////////////////////////////////////////////
+
+impl std::fmt::Display for Transport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Transport::Serial => write!(f, "serial"),
+            Transport::USB => write!(f, "USB"),
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////
@@ -0,0 +1,362 @@
+use std::fmt::Write;
+
+use crate::syntax::{Expr, ParsedExpr};
+
+////////////////////////////////////////////////////////////////
+// rendering
+////////////////////////////////////////////////////////////////
+
+/// Render the statements of a script as a graphviz DOT flowchart.
+///
+/// Each statement is a node labeled by it's kind and any annotations. Solid edges show the order
+/// statements run in. Blocks are drawn as a cluster containing a header node and their body. A
+/// RETRY block also has a dashed edge from the end of it's body back to it's header, as a failing
+/// test restarts the block. An IF block's header has an edge to the start of each section,
+/// labeled with whether it's run when the condition holds. Script comments and ONABORT blocks,
+/// which aren't part of a normal run, aren't drawn.
+///
+pub(crate) fn dot(ast: &[ParsedExpr]) -> String {
+    let mut graph = Graph::default();
+    let flow = graph.sequence(ast, 1);
+
+    graph.line(1, "end [label=\"End\", shape=oval];");
+    match &flow.entry {
+        Some(entry) => {
+            graph.join(&["start".to_owned()], entry, "");
+            graph.join(&flow.exits, "end", "");
+        }
+        None => graph.join(&["start".to_owned()], "end", ""),
+    }
+
+    let mut dot = String::from("digraph script {\n");
+    dot.push_str("    node [shape=box];\n");
+    dot.push_str("    start [label=\"Start\", shape=oval];\n");
+    dot.push_str(&graph.nodes);
+    dot.push_str(&graph.edges);
+    dot.push_str("}\n");
+    dot
+}
+
+////////////////////////////////////////////////////////////////
+// helpers
+////////////////////////////////////////////////////////////////
+
+#[derive(Default)]
+struct Graph {
+    nodes: String,
+    edges: String,
+    count: usize,
+}
+
+////////////////////////////////////////////////////////////////
+
+/// How control enters and leaves a sequence of statements.
+///
+#[derive(Default)]
+struct Flow {
+    /// First node of the sequence. None if it's empty.
+    entry: Option<String>,
+
+    /// Nodes that continue on to whatever follows the sequence.
+    exits: Vec<String>,
+
+    /// BREAK nodes, which leave the innermost block enclosing the sequence.
+    breaks: Vec<String>,
+}
+
+////////////////////////////////////////////////////////////////
+
+impl Graph {
+    fn sequence(&mut self, ast: &[ParsedExpr], depth: usize) -> Flow {
+        let mut flow = Flow::default();
+        let mut exits: Option<Vec<String>> = None;
+
+        for expr in ast {
+            if let Expr::ScriptComment(_) | Expr::AbortBlock { .. } = expr.expression() {
+                continue;
+            }
+
+            let statement = self.statement(expr, depth);
+            let Some(entry) = statement.entry else {
+                continue;
+            };
+
+            match &exits {
+                Some(exits) => self.join(exits, &entry, ""),
+                None => flow.entry = Some(entry),
+            }
+
+            exits = Some(statement.exits);
+            flow.breaks.extend(statement.breaks);
+        }
+
+        flow.exits = exits.unwrap_or_default();
+        flow
+    }
+
+    fn statement(&mut self, expr: &ParsedExpr, depth: usize) -> Flow {
+        let (body, repeats) = match expr.expression() {
+            Expr::RetryBlock { body, .. } => (body, Some("retry")),
+            Expr::RepeatBlock { body, .. } => (body, Some("repeat")),
+            Expr::BoardBlock { body, .. } => (body, None),
+            Expr::If {
+                then_body,
+                else_body,
+                ..
+            } => return self.branch(expr, then_body, else_body, depth),
+            Expr::Break => {
+                let node = self.node(expr, depth);
+                return Flow {
+                    entry: Some(node.clone()),
+                    exits: Vec::new(),
+                    breaks: vec![node],
+                };
+            }
+            _ => {
+                let node = self.node(expr, depth);
+                return Flow {
+                    entry: Some(node.clone()),
+                    exits: vec![node],
+                    breaks: Vec::new(),
+                };
+            }
+        };
+
+        let cluster = self.count;
+        self.line(depth, &format!("subgraph cluster_{cluster} {{"));
+        self.line(depth + 1, "style=rounded;");
+
+        let header = self.node(expr, depth + 1);
+        let inner = self.sequence(body, depth + 1);
+        self.line(depth, "}");
+
+        let mut exits = match &inner.entry {
+            Some(entry) => {
+                self.join(std::slice::from_ref(&header), entry, "");
+                if let Some(label) = repeats {
+                    let attributes = format!("style=dashed, label=\"{label}\"");
+                    self.join(&inner.exits, &header, &attributes);
+                }
+                inner.exits
+            }
+            None => vec![header.clone()],
+        };
+        exits.extend(inner.breaks);
+
+        Flow {
+            entry: Some(header),
+            exits,
+            breaks: Vec::new(),
+        }
+    }
+
+    /// Add a cluster for an IF block. Control continues from the end of either section, or from
+    /// the header if a section is empty.
+    ///
+    fn branch(
+        &mut self,
+        expr: &ParsedExpr,
+        then_body: &[ParsedExpr],
+        else_body: &[ParsedExpr],
+        depth: usize,
+    ) -> Flow {
+        let cluster = self.count;
+        self.line(depth, &format!("subgraph cluster_{cluster} {{"));
+        self.line(depth + 1, "style=rounded;");
+
+        let header = self.node(expr, depth + 1);
+        let mut flow = Flow {
+            entry: Some(header.clone()),
+            ..Flow::default()
+        };
+        for (body, label) in [(then_body, "then"), (else_body, "else")] {
+            let inner = self.sequence(body, depth + 1);
+            match &inner.entry {
+                Some(entry) => {
+                    let attributes = format!("label=\"{label}\"");
+                    self.join(std::slice::from_ref(&header), entry, &attributes);
+                    flow.exits.extend(inner.exits);
+                }
+                None if !flow.exits.contains(&header) => flow.exits.push(header.clone()),
+                None => (),
+            }
+            flow.breaks.extend(inner.breaks);
+        }
+        self.line(depth, "}");
+
+        flow
+    }
+
+    /// Add a node for the expression and return it's ID.
+    ///
+    fn node(&mut self, expr: &ParsedExpr, depth: usize) -> String {
+        let id = format!("n{}", self.count);
+        self.count += 1;
+
+        let mut label = match expr.expression() {
+            Expr::RetryBlock {
+                attempts: count, ..
+            }
+            | Expr::RepeatBlock { count, .. } => match count.expression() {
+                Expr::UInt(count) => format!("{} {count}", expr.expression_kind().name()),
+                _ => expr.expression_kind().name().to_owned(),
+            },
+            Expr::BoardBlock { id, .. } => match id.expression() {
+                Expr::String(id) => format!("{} {id}", expr.expression_kind().name()),
+                _ => expr.expression_kind().name().to_owned(),
+            },
+            Expr::If { condition, .. } => {
+                format!("{} {condition}", expr.expression_kind().name())
+            }
+            _ => expr.expression_kind().name().to_owned(),
+        };
+        for annotation in expr.annotations() {
+            label.push('\n');
+            label.push_str(&annotation.to_string());
+        }
+
+        self.line(depth, &format!("{id} [label=\"{}\"];", escape(&label)));
+        id
+    }
+
+    fn join(&mut self, from: &[String], to: &str, attributes: &str) {
+        for from in from {
+            let _ = if attributes.is_empty() {
+                writeln!(self.edges, "    {from} -> {to};")
+            } else {
+                writeln!(self.edges, "    {from} -> {to} [{attributes}];")
+            };
+        }
+    }
+
+    fn line(&mut self, depth: usize, line: &str) {
+        let _ = writeln!(self.nodes, "{}{line}", "    ".repeat(depth));
+    }
+}
+
+////////////////////////////////////////////////////////////////
+
+/// Escape text for use within a quoted DOT string.
+///
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+////////////////////////////////////////////////////////////////
+// tests
+////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::syntax::parse_from_str;
+
+    ////////////////////////////////////////////////////////////////
+
+    #[test]
+    fn test_sequence() {
+        let script = "; Setup\nTCUOPEN 1\n@step \"Close\"\nTCUCLOSE 1\n";
+        let ast = parse_from_str(script).unwrap();
+
+        assert_eq!(
+            dot(&ast),
+            r#"digraph script {
+    node [shape=box];
+    start [label="Start", shape=oval];
+    n0 [label="Command: 'TCUOPEN'"];
+    n1 [label="Command: 'TCUCLOSE'\n@step \"Close\""];
+    end [label="End", shape=oval];
+    n0 -> n1;
+    start -> n0;
+    n1 -> end;
+}
+"#
+        );
+    }
+
+    ////////////////////////////////////////////////////////////////
+
+    #[test]
+    fn test_blocks() {
+        let script = r#"
+BOARD "A"
+    RETRY 3
+        TCUTEST 1, 0, 10, 0, "a"
+        BREAK
+    ENDRETRY
+ENDBOARD
+"#;
+        let ast = parse_from_str(script).unwrap();
+
+        assert_eq!(
+            dot(&ast),
+            r#"digraph script {
+    node [shape=box];
+    start [label="Start", shape=oval];
+    subgraph cluster_0 {
+        style=rounded;
+        n0 [label="Command: 'BOARD' A"];
+        subgraph cluster_1 {
+            style=rounded;
+            n1 [label="Command: 'RETRY' 3"];
+            n2 [label="Command: 'TCUTEST'"];
+            n3 [label="Command: 'BREAK'"];
+        }
+    }
+    end [label="End", shape=oval];
+    n2 -> n3;
+    n1 -> n2;
+    n0 -> n1;
+    start -> n0;
+    n3 -> end;
+}
+"#
+        );
+    }
+
+    ////////////////////////////////////////////////////////////////
+
+    #[test]
+    fn test_if_block() {
+        let script = r#"
+TCUTEST 1, 0, 10, 0, "a"
+IF LASTRESULT == FAIL
+    TCUCLOSE 1
+ENDIF
+"#;
+        let ast = parse_from_str(script).unwrap();
+
+        assert_eq!(
+            dot(&ast),
+            r#"digraph script {
+    node [shape=box];
+    start [label="Start", shape=oval];
+    n0 [label="Command: 'TCUTEST'"];
+    subgraph cluster_1 {
+        style=rounded;
+        n1 [label="Command: 'IF' LASTRESULT == FAIL"];
+        n2 [label="Command: 'TCUCLOSE'"];
+    }
+    end [label="End", shape=oval];
+    n1 -> n2 [label="then"];
+    n0 -> n1;
+    start -> n0;
+    n2 -> end;
+    n1 -> end;
+}
+"#
+        );
+    }
+
+    ////////////////////////////////////////////////////////////////
+
+    #[test]
+    fn test_empty() {
+        let ast = parse_from_str("; Nothing").unwrap();
+        assert!(dot(&ast).contains("    start -> end;\n"));
+    }
+}
+
+////////////////////////////////////////////////////////////////
@@ -1,9 +1,41 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs::File,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use chrono::NaiveDateTime;
+
 use super::{
-    error::Error,
-    execution::FrontendRequest,
-    syntax::{evaluate, parse_from_str, EvalState, ParsedExpr},
+    analysis,
+    clock::Clock,
+    diagnostic::{Diagnostic, Diagnostics, Severity},
+    error::{Error, ErrorReason},
+    execution::{
+        Capture, Checksum, Device, DeviceAction, DeviceLog, Dialog, Echo, Encoding, Fingerprint,
+        FrontendRequest, MeasurementFormat, Outcome, Results, Routing, Session, TimeoutAction,
+        Transaction, TransactionObserver, Transport, UnitSuffix,
+    },
+    graph,
+    parse_error::ParseError,
+    profile::Profile,
+    run_retry::RunRetry,
+    shuffle::Shuffle,
+    syntax::{
+        self, evaluate, parse_all, parse_from_str_permissive, parse_with_includes, preprocess,
+        Annotation, EvalState, Expr, ExprKind, ParsedExpr,
+    },
 };
 
+////////////////////////////////////////////////////////////////
+
+/// Maximum number of GOTO jumps made when evaluating a script without executing it. Whatever would
+/// end a loop may only be known once the script is executed, so it's assumed to never end.
+///
+const DRY_RUN_JUMPS: u32 = 1000;
+
 ////////////////////////////////////////////////////////////////
 // types
 ////////////////////////////////////////////////////////////////
@@ -13,8 +45,105 @@ use super::{
 #[derive(Default, Clone, Debug, PartialEq)]
 pub struct Interpreter {
     ast: Vec<ParsedExpr>,
-    index: usize,
+    frames: Vec<Frame>,
     state: EvalState,
+
+    /// Statements skipped due to parse errors. None unless the script was parsed permissively.
+    skipped: Option<Vec<Diagnostic>>,
+
+    /// CONFIRM command awaiting the operator's confirmation.
+    confirmation: Option<ParsedExpr>,
+
+    /// Step of the test last reported to the frontend, if the current statement is part of one.
+    step: Option<String>,
+
+    /// Request held back until the next call while the bytes it transmits are reported.
+    pending: Option<FrontendRequest>,
+
+    /// Whether the last request returned was a test, which passed if the frontend takes another
+    /// request rather than recovering from it's failure.
+    testing: bool,
+
+    /// Severity of traceability IDs used by more than one statement when analyzing the script.
+    duplicate_ids: Severity,
+
+    /// Device each kind of command depends on being open, checked when analyzing the script.
+    state_dependent: BTreeMap<ExprKind, Device>,
+
+    /// Policy for retrying the whole run and the number of times it's been retried so far.
+    run_retry: RunRetry,
+    retried: u32,
+
+    /// Policy for randomizing the order of independent tests, if they should be.
+    shuffle: Option<Shuffle>,
+
+    /// Number of top-level statements reported as completed. None unless progress is reported.
+    progress: Option<usize>,
+
+    /// Maximum number of GOTO jumps the run may make, if limited, and the number made so far.
+    max_jumps: Option<u32>,
+    jumps: u32,
+
+    /// Whether the run is paused, in which case nothing is evaluated until it's resumed.
+    paused: bool,
+
+    /// Whether the run has been cancelled, in which case it's finished until restarted.
+    cancelled: bool,
+}
+
+////////////////////////////////////////////////////////////////
+
+/// Result of evaluating an entire script without executing it.
+///
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Evaluation {
+    pub requests: Vec<FrontendRequest>,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+////////////////////////////////////////////////////////////////
+
+/// A sequence of expressions currently being executed. i.e. The script itself or the body of a
+/// block.
+///
+#[derive(Clone, Debug, PartialEq)]
+struct Frame {
+    body: Vec<ParsedExpr>,
+    index: usize,
+    kind: FrameKind,
+
+    /// Step the frame's statements are part of, unless annotated with their own.
+    step: Option<String>,
+}
+
+////////////////////////////////////////////////////////////////
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum FrameKind {
+    Script,
+
+    /// Body of a RETRY block that should be re-run on a test failure while it has attempts left.
+    Retry {
+        retries: u32,
+    },
+
+    /// Body of a REPEAT block that's run again from the start once finished while it has
+    /// repetitions left.
+    Repeat {
+        repetitions: u32,
+    },
+
+    /// Body of a BOARD block. Tests within it are performed on the board.
+    Board {
+        id: String,
+    },
+
+    /// Body of whichever section of an IF block it's condition selected.
+    Conditional,
+
+    /// Bodies of the script's ONABORT blocks, run in place of the rest of the script once it's
+    /// been aborted.
+    Abort,
 }
 
 ////////////////////////////////////////////////////////////////
@@ -22,14 +151,484 @@ pub struct Interpreter {
 ////////////////////////////////////////////////////////////////
 
 impl Interpreter {
+    /// Create an interpreter for the script. Parse errors are returned as [`Error`]s for
+    /// frontends that handle them alongside runtime errors. See [`Interpreter::try_parse`] to
+    /// handle them separately.
+    ///
+    /// # Errors
+    /// If the script can't be parsed.
+    ///
     pub fn try_from_str(script: &str) -> Result<Self, Vec<Error>> {
-        Ok(Self {
-            ast: parse_from_str(script)
-                .map_err(|error| error.into_iter().map(Error::from).collect::<Vec<Error>>())?,
-            index: 0,
+        Self::try_parse(script)
+            .map_err(|errors| errors.into_iter().map(Error::from).collect::<Vec<Error>>())
+    }
+
+    /// Create an interpreter for the script.
+    ///
+    /// # Errors
+    /// Every error found while parsing the script, along with it's location.
+    ///
+    pub fn try_parse(script: &str) -> Result<Self, Vec<ParseError>> {
+        Self::try_parse_with_symbols(script, std::iter::empty::<String>())
+    }
+
+    /// Create an interpreter for the script with the given symbols defined. Sections of the script
+    /// between `IFDEF <symbol>` and `ENDIF` are only included if their symbol is defined. Excluded
+    /// sections aren't parsed. See [`Interpreter::try_parse_with_symbols`] to handle parse errors
+    /// separately.
+    ///
+    /// # Errors
+    /// If the script can't be parsed.
+    ///
+    pub fn try_from_str_with_symbols(
+        script: &str,
+        symbols: impl IntoIterator<Item = impl Into<String>>,
+    ) -> Result<Self, Vec<Error>> {
+        Self::try_parse_with_symbols(script, symbols)
+            .map_err(|errors| errors.into_iter().map(Error::from).collect::<Vec<Error>>())
+    }
+
+    /// Create an interpreter for the script with the given symbols defined. See
+    /// [`Interpreter::try_from_str_with_symbols`].
+    ///
+    /// # Errors
+    /// Every error found while parsing the script, along with it's location.
+    ///
+    pub fn try_parse_with_symbols(
+        script: &str,
+        symbols: impl IntoIterator<Item = impl Into<String>>,
+    ) -> Result<Self, Vec<ParseError>> {
+        Self::try_parse_with_includes(script, symbols, |_| None)
+    }
+
+    /// Create an interpreter for the script with the given symbols defined, loading any scripts
+    /// it INCLUDEs with `load`. `load` is given the path exactly as it's written in the INCLUDE
+    /// and returns None if the script can't be loaded. Included scripts have the same symbols
+    /// defined. See [`Interpreter::try_parse_with_includes`] to handle parse errors separately.
+    ///
+    /// # Errors
+    /// If the script, or any script it includes, can't be parsed or an included script can't be
+    /// loaded.
+    ///
+    pub fn try_from_str_with_includes(
+        script: &str,
+        symbols: impl IntoIterator<Item = impl Into<String>>,
+        load: impl FnMut(&str) -> Option<String>,
+    ) -> Result<Self, Vec<Error>> {
+        Self::try_parse_with_includes(script, symbols, load)
+            .map_err(|errors| errors.into_iter().map(Error::from).collect::<Vec<Error>>())
+    }
+
+    /// Create an interpreter for the script with the given symbols defined, loading any scripts
+    /// it INCLUDEs with `load`. See [`Interpreter::try_from_str_with_includes`].
+    ///
+    /// # Errors
+    /// Every error found while parsing the script, along with it's location. Errors within an
+    /// included script are located at the INCLUDE.
+    ///
+    pub fn try_parse_with_includes(
+        script: &str,
+        symbols: impl IntoIterator<Item = impl Into<String>>,
+        mut load: impl FnMut(&str) -> Option<String>,
+    ) -> Result<Self, Vec<ParseError>> {
+        let to_parse_errors = |errors: Vec<syntax::Error>| {
+            errors
+                .into_iter()
+                .map(|error| ParseError::new(error, script))
+                .collect::<Vec<ParseError>>()
+        };
+
+        let symbols = symbols.into_iter().map(Into::into).collect();
+        let preprocessed = preprocess(script, &symbols).map_err(to_parse_errors)?;
+
+        // An included script that can't be preprocessed is left as is, failing to parse at the
+        // offending IFDEF or ENDIF instead.
+        let load = |path: &str| {
+            let included = load(path)?;
+            Some(preprocess(&included, &symbols).unwrap_or(included))
+        };
+        let ast = parse_with_includes(&preprocessed, load).map_err(to_parse_errors)?;
+
+        Ok(Self::from_ast(ast))
+    }
+
+    /// Create an interpreter for a script that's already been parsed. e.g. One cached from an
+    /// earlier [`Interpreter::ast`].
+    ///
+    pub fn from_ast(ast: Vec<ParsedExpr>) -> Self {
+        Self {
+            frames: vec![Frame::new(ast.clone(), FrameKind::Script, None)],
+            ast,
             state: EvalState::new(),
+            skipped: None,
+            confirmation: None,
+            step: None,
+            pending: None,
+            testing: false,
+            duplicate_ids: Severity::Warning,
+            state_dependent: BTreeMap::new(),
+            run_retry: RunRetry::default(),
+            retried: 0,
+            shuffle: None,
+            progress: None,
+            max_jumps: None,
+            jumps: 0,
+            paused: false,
+            cancelled: false,
+        }
+    }
+
+    /// Create an interpreter that skips any lines of the script that can't be parsed, running
+    /// everything else. Intended for experimenting with scripts only. A permissive run can't be
+    /// trusted to have fully tested a device.
+    ///
+    /// Each skipped line is reported as a warning by [`Interpreter::skipped_statements`] and by
+    /// [`Interpreter::evaluate`].
+    ///
+    /// No symbols are defined, so any IFDEF sections are excluded.
+    ///
+    /// # Errors
+    /// If an error can't be recovered from by skipping lines. e.g. An unclosed block.
+    ///
+    pub fn try_from_str_permissive(script: &str) -> Result<Self, Vec<Error>> {
+        let to_errors = |errors: Vec<syntax::Error>| {
+            errors.into_iter().map(Error::from).collect::<Vec<Error>>()
+        };
+
+        let preprocessed = preprocess(script, &BTreeSet::new()).map_err(to_errors)?;
+        let (ast, errors) = parse_from_str_permissive(&preprocessed).map_err(to_errors)?;
+
+        let skipped = errors
+            .into_iter()
+            .map(|error| {
+                let error = Error::from(error);
+                Diagnostic::warning(
+                    error.span().unwrap_or_default(),
+                    format!(
+                        "Skipped unparseable statement - {}",
+                        error.reason().message()
+                    ),
+                )
+            })
+            .collect();
+
+        Ok(Self {
+            skipped: Some(skipped),
+            ..Self::from_ast(ast)
         })
     }
+
+    /// Return every error found while parsing the script, in script order, rather than only
+    /// those found before parsing stopped. Lines containing an error are skipped, as by
+    /// [`Interpreter::try_from_str_permissive`], so every statement is attempted. Intended for
+    /// editors reporting every problem with a script at once.
+    ///
+    /// No symbols are defined, so any IFDEF sections are excluded.
+    ///
+    pub fn parse_errors(script: &str) -> Vec<ParseError> {
+        let errors = match preprocess(script, &BTreeSet::new()) {
+            Ok(preprocessed) => parse_all(&preprocessed).1,
+            Err(errors) => errors,
+        };
+
+        errors
+            .into_iter()
+            .map(|error| ParseError::new(error, script))
+            .collect()
+    }
+
+    /// Check the script for every error that running it would cause, without performing any of
+    /// it's requests. e.g. Before it's run on hardware. If the script can't be parsed, every parse
+    /// error is returned as by [`Interpreter::parse_errors`]. Otherwise the script is evaluated as
+    /// by [`Interpreter::evaluate`] and every error is returned, including commands that use a
+    /// device before it's opened or open one that's already open. Devices left open aren't errors
+    /// but are reported by [`Interpreter::analyze`].
+    ///
+    /// No symbols are defined, so any IFDEF sections are excluded.
+    ///
+    pub fn lint(script: &str) -> Vec<Error> {
+        let Ok(interpreter) = Self::try_parse(script) else {
+            return Self::parse_errors(script)
+                .into_iter()
+                .map(Error::from)
+                .collect();
+        };
+
+        let mut interpreter = interpreter.dry_run();
+        let mut errors = Vec::new();
+        while let Some(result) = interpreter.next() {
+            // Safety checks and inputs can't be answered without an operator.
+            interpreter.confirm(true);
+            interpreter.enter_input("");
+
+            if let Err(error) = result {
+                errors.push(error);
+            }
+        }
+
+        errors
+    }
+
+    /// Set whether test commands should record their measurements rather than test them. Recorded
+    /// measurements are returned by transactions as [`TransactionStatus::Recorded`] and can be
+    /// collected in a [`Recording`].
+    ///
+    /// [`TransactionStatus::Recorded`]: crate::TransactionStatus::Recorded
+    /// [`Recording`]: crate::Recording
+    ///
+    #[must_use]
+    pub fn with_record_mode(mut self, record: bool) -> Self {
+        self.state.record = record;
+        self
+    }
+
+    /// Set the clock used as the source of the current time. e.g. By SETTIME or when checking
+    /// an `@window` annotation.
+    ///
+    #[must_use]
+    pub fn with_clock(mut self, clock: Clock) -> Self {
+        self.state.clock = clock;
+        self
+    }
+
+    /// Set the devices the frontend has assigned. Once set, evaluating a command that requires a
+    /// device without an assignment results in an error.
+    ///
+    #[must_use]
+    pub fn with_routing(mut self, routing: Routing) -> Self {
+        self.state.routing = Some(routing);
+        self
+    }
+
+    /// Set how the frontend is connected to the printer. Once set, evaluating a command that
+    /// requires the other transport results in an error.
+    ///
+    #[must_use]
+    pub fn with_transport(mut self, transport: Transport) -> Self {
+        self.state.transport = Some(transport);
+        self
+    }
+
+    /// Set how the TCU's echo is separated from the rest of it's responses. By default the echo is
+    /// expected to be terminated by a carriage return.
+    ///
+    #[must_use]
+    pub fn with_echo_format(mut self, echo: Echo) -> Self {
+        self.state.echo = echo;
+        self
+    }
+
+    /// Set the format the TCU reports it's measurements in. By default they're hex.
+    ///
+    #[must_use]
+    pub fn with_measurement_format(mut self, format: MeasurementFormat) -> Self {
+        self.state.format = format;
+        self
+    }
+
+    /// Set the unit the TCU appends to it's measurements, which is stripped before they're
+    /// parsed. e.g. `1200mV`. By default they have no unit.
+    ///
+    #[must_use]
+    pub fn with_measurement_unit(mut self, unit: UnitSuffix) -> Self {
+        self.state.unit = unit;
+        self
+    }
+
+    /// Set a byte to send to the TCU once it's echoed a test command, prompting it to take the
+    /// measurement. For devices that won't sample until polled. By default the measurement is
+    /// expected to follow the echo without prompting.
+    ///
+    #[must_use]
+    pub fn with_measurement_trigger(mut self, trigger: u8) -> Self {
+        self.state.trigger = Some(trigger);
+        self
+    }
+
+    /// Set the severity of traceability IDs, `@id`, used by more than one statement when analyzing
+    /// the script. By default they're warnings.
+    ///
+    #[must_use]
+    pub fn with_duplicate_id_severity(mut self, severity: Severity) -> Self {
+        self.duplicate_ids = severity;
+        self
+    }
+
+    /// Consider a kind of command to depend on the state of a device, e.g. a dialog instructing the
+    /// operator once the device is ready. Analyzing the script then warns of any such command
+    /// that runs before the device is opened. By default no commands are considered to depend on
+    /// a device, as whether a dialog does depends on it's message.
+    ///
+    #[must_use]
+    pub fn with_state_dependent(mut self, kind: ExprKind, device: Device) -> Self {
+        self.state_dependent.insert(kind, device);
+        self
+    }
+
+    /// Set the number of times each transaction is re-transmitted after a comms error, i.e. a
+    /// timeout or an incorrect echo, before it fails. Separate from a test's retries, which only
+    /// apply to failing measurements. By default a comms error fails the transaction.
+    ///
+    #[must_use]
+    pub fn with_comms_retries(mut self, retries: u32) -> Self {
+        self.state.comms_retries = retries;
+        self
+    }
+
+    /// Set the encoding that text printed by PRINT commands is converted to. By default text is
+    /// sent as written, i.e. as UTF-8.
+    ///
+    #[must_use]
+    pub fn with_encoding(mut self, encoding: Encoding) -> Self {
+        self.state.encoding = encoding;
+        self
+    }
+
+    /// Set a checksum to append to each command sent directly to the printer over USB, for printers
+    /// whose protocol requires one. By default commands are sent without a checksum.
+    ///
+    #[must_use]
+    pub fn with_printer_checksum(mut self, checksum: Checksum) -> Self {
+        self.state.checksum = Some(checksum);
+        self
+    }
+
+    /// Set how a WAITDIALOG with a timeout is answered once it expires. By default the run
+    /// continues.
+    ///
+    #[must_use]
+    pub fn with_dialog_timeout_action(mut self, action: TimeoutAction) -> Self {
+        self.state.timeout_action = action;
+        self
+    }
+
+    /// Set the path of the script so that any files it references, such as PRINTIMAGE's, can be
+    /// found relative to it. Otherwise they're relative to the current directory.
+    ///
+    #[must_use]
+    pub fn with_script_path(mut self, path: impl AsRef<Path>) -> Self {
+        self.state.assets = path.as_ref().parent().map(Path::to_owned);
+        self
+    }
+
+    /// Set the directory that artifacts of the run, such as recordings, are written to. The
+    /// directory is created if it doesn't already exist.
+    ///
+    /// # Errors
+    /// If the directory can't be created.
+    ///
+    pub fn with_output_directory(mut self, directory: impl Into<PathBuf>) -> Result<Self, Error> {
+        let directory = directory.into();
+        std::fs::create_dir_all(&directory)
+            .map_err(|error| Error::from_output_error(directory.clone(), error))?;
+
+        self.state.output = Some(directory);
+        Ok(self)
+    }
+
+    /// Retain the raw bytes exchanged by every transaction in the capture. The capture is a handle
+    /// so a clone kept by the frontend can be inspected after the run.
+    ///
+    #[must_use]
+    pub fn with_capture(mut self, capture: Capture) -> Self {
+        self.state.capture = Some(capture);
+        self
+    }
+
+    /// Report the outcome of every test performed by a device to the results as soon as it's
+    /// determined. Frontends can subscribe to the results to be notified of each outcome, or
+    /// collect them all after the run.
+    ///
+    #[must_use]
+    pub fn with_results(mut self, results: Results) -> Self {
+        self.state.results = Some(results);
+        self
+    }
+
+    /// Notify the observer of every byte transmitted and received by each transaction. e.g. To
+    /// write a structured log of every exchange with the printer and TCU. The observer is shared
+    /// by every transaction so a handle kept by the frontend sees the whole run.
+    ///
+    #[must_use]
+    pub fn with_transaction_observer(mut self, observer: Arc<dyn TransactionObserver>) -> Self {
+        self.state.observer = Some(observer.into());
+        self
+    }
+
+    /// Log every device opened or closed by the script, along with the outcome, to the log. The
+    /// log is a handle so a clone kept by the frontend can be inspected after the run.
+    ///
+    /// An event is considered successful once the next request is taken from the interpreter,
+    /// unless the frontend passes an error to [`Interpreter::recover`] first.
+    ///
+    #[must_use]
+    pub fn with_device_log(mut self, log: DeviceLog) -> Self {
+        self.state.device_log = Some(log);
+        self
+    }
+
+    /// Run the script as one of a session's sequential runs. Devices the script opens are held open
+    /// by the session, so later runs skip reopening them, and closing them is left to
+    /// [`Session::close_all`] once the session ends. The session is a handle so the same one should
+    /// be given to the interpreter of each run.
+    ///
+    #[must_use]
+    pub fn with_session(mut self, session: Session) -> Self {
+        self.state.session = Some(session);
+        self
+    }
+
+    /// Set the policy for retrying the whole run when it ends with a transient error. See
+    /// [`Interpreter::retry_run`].
+    ///
+    #[must_use]
+    pub fn with_run_retry(mut self, run_retry: RunRetry) -> Self {
+        self.run_retry = run_retry;
+        self
+    }
+
+    /// Report the progress of the run through the script's top-level statements with a
+    /// [`FrontendRequest::Progress`] after each is completed. e.g. To drive a progress bar. A
+    /// block counts as a single statement, completed once it's finished running. By default
+    /// progress isn't reported.
+    ///
+    #[must_use]
+    pub fn with_progress(mut self, progress: bool) -> Self {
+        self.progress = progress.then_some(0);
+        self
+    }
+
+    /// Randomize the order that tests annotated as `@independent` are run in using the policy.
+    /// Every restart of the run, including retries, uses the same order. See [`Shuffle`].
+    ///
+    #[must_use]
+    pub fn with_shuffle(mut self, shuffle: Shuffle) -> Self {
+        self.shuffle = Some(shuffle);
+        self.restart();
+        self
+    }
+
+    /// Limit the number of GOTO jumps the run may make, guarding against a script that loops
+    /// forever. Once the limit's reached, the next GOTO is an error. By default it's unlimited.
+    ///
+    #[must_use]
+    pub fn with_max_jumps(mut self, max_jumps: u32) -> Self {
+        self.max_jumps = Some(max_jumps);
+        self
+    }
+}
+
+////////////////////////////////////////////////////////////////
+
+impl Frame {
+    fn new(body: Vec<ParsedExpr>, kind: FrameKind, step: Option<String>) -> Self {
+        Self {
+            body,
+            index: 0,
+            kind,
+            step,
+        }
+    }
 }
 
 ////////////////////////////////////////////////////////////////
@@ -40,11 +639,236 @@ impl Iterator for Interpreter {
     type Item = Result<FrontendRequest, Error>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if let Some(expr) = self.ast.get(self.index) {
-            self.index += 1;
-            Some(evaluate(expr, &mut self.state))
-        } else {
-            None
+        // The frontend only takes another request once the previous one was handled.
+        if let Some(log) = &self.state.device_log {
+            log.resolve_all(Outcome::Success);
+        }
+
+        if std::mem::take(&mut self.testing) {
+            self.state.last_result = Some(true);
+        }
+
+        if self.cancelled {
+            return None;
+        }
+
+        if self.paused {
+            return Some(Ok(FrontendRequest::Paused));
+        }
+
+        if let Some(request) = self.pending.take() {
+            self.testing = Self::is_test(&request);
+            return Some(Ok(request));
+        }
+
+        // Any text for the last input dialog must be entered before the next request.
+        self.state.awaiting_input = None;
+
+        // Nothing else may run until the operator has confirmed the safety check.
+        if let Some(expr) = self.confirmation.take() {
+            self.frames.truncate(1);
+            self.frames[0].index = self.frames[0].body.len();
+            return Some(Err(Error::unconfirmed(expr)));
+        }
+
+        loop {
+            let frame = self.frames.last_mut()?;
+
+            // Report each top-level statement completed since the last report.
+            if let Some(reported) = &mut self.progress {
+                if matches!(frame.kind, FrameKind::Script) && frame.index > *reported {
+                    *reported = frame.index;
+                    return Some(Ok(FrontendRequest::Progress {
+                        current: frame.index,
+                        total: frame.body.len(),
+                    }));
+                }
+            }
+
+            let Some(expr) = frame.body.get(frame.index).cloned() else {
+                if let FrameKind::Repeat { repetitions } = &mut frame.kind {
+                    if *repetitions > 0 {
+                        *repetitions -= 1;
+                        frame.index = 0;
+                        continue;
+                    }
+                }
+
+                // Keep the script's frame so the interpreter remains finished.
+                if self.frames.len() == 1 {
+                    return None;
+                }
+
+                self.frames.pop();
+                continue;
+            };
+
+            frame.index += 1;
+
+            if !matches!(
+                expr.expression(),
+                Expr::Confirm(_) | Expr::ScriptComment(_) | Expr::Define { .. }
+            ) {
+                self.state.started = true;
+            }
+
+            if let Some(request) = self.skipped(&expr) {
+                return Some(Ok(request));
+            }
+
+            // Exit the innermost block, abandoning any of it's remaining attempts. IF blocks within
+            // it are exited along with it.
+            if let Expr::Break = expr.expression() {
+                let block = self
+                    .frames
+                    .iter()
+                    .rposition(|frame| frame.kind != FrameKind::Conditional);
+                match block {
+                    Some(0) | None => return Some(Err(Error::break_outside_block(expr))),
+                    Some(block) => self.frames.truncate(block),
+                }
+                continue;
+            }
+
+            if let Expr::Goto(label) = expr.expression() {
+                let Expr::String(label) = label.expression() else {
+                    panic!("Invalid GOTO arg {label:?}");
+                };
+
+                match self.jump(&expr, label) {
+                    Ok(()) => continue,
+                    Err(error) => return Some(Err(error)),
+                }
+            }
+
+            // Only marks a position to jump to.
+            if let Expr::Label(_) = expr.expression() {
+                continue;
+            }
+
+            let frame = self.frames.last_mut()?;
+            let step = Self::step(&expr).or_else(|| frame.step.clone());
+            let is_block = matches!(
+                expr.expression(),
+                Expr::RetryBlock { .. }
+                    | Expr::RepeatBlock { .. }
+                    | Expr::BoardBlock { .. }
+                    | Expr::AbortBlock { .. }
+                    | Expr::If { .. }
+            );
+
+            // Report a change of step before running the statement, which is run by the next call.
+            if !is_block && step != self.step {
+                self.step.clone_from(&step);
+                if let Some(description) = &step {
+                    frame.index -= 1;
+                    return Some(Ok(FrontendRequest::Step(description.to_owned())));
+                }
+            }
+
+            match expr.expression() {
+                Expr::RetryBlock { attempts, body } => {
+                    let Expr::UInt(attempts) = attempts.expression() else {
+                        panic!("Invalid RETRY arg {attempts:?}");
+                    };
+
+                    if *attempts < 2 {
+                        self.state.diagnostics.push(Diagnostic::warning(
+                            expr.span().clone(),
+                            format!("RETRY block with {attempts} attempts is never retried"),
+                        ));
+                    }
+
+                    let retries = attempts.saturating_sub(1);
+                    self.push_frame(body.clone(), FrameKind::Retry { retries }, step);
+                }
+
+                Expr::RepeatBlock { count, body } => {
+                    let Expr::UInt(count) = count.expression() else {
+                        panic!("Invalid REPEAT arg {count:?}");
+                    };
+
+                    if *count == 0 {
+                        self.state.diagnostics.push(Diagnostic::warning(
+                            expr.span().clone(),
+                            "REPEAT block with a count of 0 is never run",
+                        ));
+                    }
+
+                    if let Some(repetitions) = count.checked_sub(1) {
+                        self.push_frame(body.clone(), FrameKind::Repeat { repetitions }, step);
+                    }
+                }
+
+                Expr::BoardBlock { id, body } => {
+                    let Expr::String(id) = id.expression() else {
+                        panic!("Invalid BOARD arg {id:?}");
+                    };
+
+                    if let Some(board) = self.board() {
+                        return Some(Err(Error::nested_board(expr.clone(), board)));
+                    }
+
+                    let kind = FrameKind::Board { id: id.to_owned() };
+                    self.push_frame(body.clone(), kind, step);
+                }
+
+                // Only run once aborted.
+                Expr::AbortBlock { .. } => (),
+
+                Expr::If {
+                    condition,
+                    then_body,
+                    else_body,
+                } => {
+                    let Expr::LastResult { passed } = condition.expression() else {
+                        panic!("Invalid IF condition {condition:?}");
+                    };
+
+                    let body = match self.state.last_result == Some(*passed) {
+                        true => then_body,
+                        false => else_body,
+                    };
+                    self.push_frame(body.clone(), FrameKind::Conditional, step);
+                }
+
+                _ => {
+                    let request = evaluate(&expr, &mut self.state)
+                        .and_then(|request| self.route(request, &expr))
+                        .and_then(|request| self.track_open(request, &expr));
+
+                    if request.is_ok() {
+                        self.log_device_event(&expr);
+                    }
+
+                    if let Ok(FrontendRequest::GuiDialogue {
+                        kind: Dialog::Confirmation,
+                        ..
+                    }) = &request
+                    {
+                        self.confirmation = Some(expr);
+                    }
+
+                    // Report the bytes a transaction transmits before the transaction itself.
+                    if let Ok(
+                        FrontendRequest::TCUTransact(transaction)
+                        | FrontendRequest::PrinterTransact(transaction),
+                    ) = &request
+                    {
+                        if self.state.protocol {
+                            let dump = FrontendRequest::Protocol {
+                                device: transaction.device(),
+                                dump: transaction.hex_dump(),
+                            };
+                            self.pending = request.ok();
+                            return Some(Ok(dump));
+                        }
+                    }
+
+                    self.testing = request.as_ref().is_ok_and(Self::is_test);
+                    return Some(request);
+                }
+            }
         }
     }
 }
@@ -56,8 +880,805 @@ impl Iterator for Interpreter {
 impl Interpreter {
     /// Restart the interpreter from the beginning of the script.
     pub fn restart(&mut self) {
-        self.index = 0;
-        self.state = EvalState::new();
+        if let Some(shuffle) = &mut self.shuffle {
+            shuffle.reset();
+        }
+
+        self.frames.clear();
+        self.push_frame(self.ast.clone(), FrameKind::Script, None);
+        self.state.restart();
+        self.confirmation = None;
+        self.step = None;
+        self.pending = None;
+        self.testing = false;
+        self.progress = self.progress.map(|_| 0);
+        self.jumps = 0;
+        self.cancelled = false;
+    }
+
+    /// Restart the run from the beginning if it ended with an error that the run retry policy
+    /// considers transient and it has retries left.
+    ///
+    /// # Returns
+    /// Requests closing every device the failed attempt left open. The frontend should handle
+    /// them before taking the first request of the next attempt, which reopens the devices as the
+    /// script does. None if the run shouldn't be retried, in which case the interpreter is
+    /// unchanged.
+    ///
+    pub fn retry_run(&mut self, error: &Error) -> Option<Vec<FrontendRequest>> {
+        if !self.run_retry.should_retry(error, self.retried) {
+            return None;
+        }
+
+        let cleanup = self.close_all();
+        self.retried += 1;
+        self.restart();
+
+        Some(cleanup)
+    }
+
+    /// Return the number of times the run has been retried by [`Interpreter::retry_run`].
+    ///
+    pub fn run_retries(&self) -> u32 {
+        self.retried
+    }
+
+    /// Abandon the rest of the script and run it's ONABORT blocks in their place. e.g. When the run
+    /// ends with an error or the operator stops it. Following calls to [`Interpreter::next`]
+    /// return the requests of the blocks, in script order, then None.
+    ///
+    /// The frontend should report any error returned while running the blocks then continue with
+    /// the next request, so that one failing cleanup command doesn't prevent the rest running.
+    /// Any error that caused the abort should still be reported once they're complete. Devices
+    /// left open afterwards can be closed with [`Interpreter::close_all`].
+    ///
+    /// Does nothing if already aborted, so an error within the blocks doesn't restart them.
+    ///
+    pub fn abort(&mut self) {
+        if self.is_aborted() {
+            return;
+        }
+
+        let cleanup = self
+            .ast
+            .iter()
+            .filter_map(|expr| match expr.expression() {
+                Expr::AbortBlock { body } => Some(body.clone()),
+                _ => None,
+            })
+            .flatten()
+            .collect();
+
+        self.frames.clear();
+        self.push_frame(cleanup, FrameKind::Abort, None);
+        self.confirmation = None;
+        self.step = None;
+    }
+
+    /// Return true if the script has been aborted by [`Interpreter::abort`].
+    ///
+    pub fn is_aborted(&self) -> bool {
+        self.frames
+            .first()
+            .is_some_and(|frame| frame.kind == FrameKind::Abort)
+    }
+
+    /// Close every device the script has opened but not yet closed. e.g. When a run ends early.
+    /// Devices held open by a session, see [`Interpreter::with_session`], are left open.
+    ///
+    /// # Returns
+    /// Requests for the frontend to close each device.
+    ///
+    pub fn close_all(&mut self) -> Vec<FrontendRequest> {
+        let session = self.state.session.clone();
+        std::mem::take(&mut self.state.open)
+            .into_iter()
+            .filter(|device| {
+                !session
+                    .as_ref()
+                    .is_some_and(|session| session.is_open(*device))
+            })
+            .filter_map(|device| match device {
+                Device::USB => Some(FrontendRequest::PrinterClose),
+                Device::TCU | Device::Printer | Device::Reference => None,
+            })
+            .collect()
+    }
+
+    /// Cancel the run, closing everything it left open. e.g. When the operator stops it and it's
+    /// ONABORT blocks shouldn't be run, otherwise see [`Interpreter::abort`]. Following calls to
+    /// [`Interpreter::next`] return None until the interpreter is restarted.
+    ///
+    /// # Returns
+    /// Requests closing each TCU relay the script opened but didn't close, followed by the requests
+    /// of [`Interpreter::close_all`].
+    ///
+    pub fn cancel(&mut self) -> Vec<FrontendRequest> {
+        let mut requests = Vec::new();
+        for (relay, span) in std::mem::take(&mut self.state.relays) {
+            let relay = ParsedExpr::from_kind_and_span(Expr::UInt(relay), span.clone());
+            let close = ParsedExpr::from_kind_and_span(Expr::TCUClose(Box::new(relay)), span);
+
+            // A relay the run's configuration doesn't allow closing couldn't have been opened.
+            if let Ok(request) =
+                evaluate(&close, &mut self.state).and_then(|request| self.route(request, &close))
+            {
+                requests.push(request);
+            }
+        }
+
+        requests.extend(self.close_all());
+        self.cancelled = true;
+        self.paused = false;
+        self.confirmation = None;
+        self.step = None;
+
+        requests
+    }
+
+    /// Return true if the run has been cancelled by [`Interpreter::cancel`].
+    ///
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled
+    }
+
+    /// Report the operator's answer to the safety check of the last [`Dialog::Confirmation`]
+    /// request. Unless confirmed, the next request is an error and the script ends.
+    ///
+    pub fn confirm(&mut self, confirmed: bool) {
+        if confirmed {
+            self.confirmation = None;
+        }
+    }
+
+    /// Report the text the operator entered into the last [`Dialog::Input`] request. Later
+    /// commands referring to the dialog's target use it. Must be called before the next request,
+    /// otherwise the text is ignored and those commands fail.
+    ///
+    pub fn enter_input(&mut self, text: impl Into<String>) {
+        if let Some(target) = self.state.awaiting_input.take() {
+            self.state.inputs.insert(target, text.into());
+        }
+    }
+
+    /// Pause the run. e.g. So the operator can investigate a failure. While paused, every request
+    /// is [`FrontendRequest::Paused`] and nothing is evaluated, so no devices are communicated
+    /// with. The frontend should stop taking requests until it resumes the run, as the interpreter
+    /// never finishes while paused.
+    ///
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+
+    /// Resume a paused run from the statement it was paused before.
+    ///
+    pub fn resume(&mut self) {
+        self.paused = false;
+    }
+
+    /// Return true if the run is paused.
+    ///
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Set the clock used as the source of the current time.
+    ///
+    pub fn set_clock(&mut self, clock: Clock) {
+        self.state.clock = clock;
+    }
+
+    /// Return the parsed script. With the `serde` feature it can be serialized, e.g. to cache it,
+    /// then run again with [`Interpreter::from_ast`].
+    ///
+    pub fn ast(&self) -> &[ParsedExpr] {
+        &self.ast
+    }
+
+    /// Return the devices the frontend has assigned, if set.
+    ///
+    pub fn routing(&self) -> Option<&Routing> {
+        self.state.routing.as_ref()
+    }
+
+    /// Return the directory artifacts of the run are written to, if set.
+    ///
+    pub fn output_directory(&self) -> Option<&Path> {
+        self.state.output.as_deref()
+    }
+
+    /// Create a file for an artifact of the run. Relative paths are within the output directory,
+    /// if set.
+    ///
+    /// # Errors
+    /// If the file can't be created.
+    ///
+    pub fn create_artifact(&self, path: impl AsRef<Path>) -> Result<File, Error> {
+        let path = match &self.state.output {
+            Some(directory) => directory.join(path),
+            None => path.as_ref().to_owned(),
+        };
+
+        File::create(&path).map_err(|error| Error::from_output_error(path, error))
+    }
+
+    /// Return true if the script was parsed permissively, potentially skipping some of it.
+    ///
+    pub fn is_permissive(&self) -> bool {
+        self.skipped.is_some()
+    }
+
+    /// Return a warning for every statement skipped due to a parse error.
+    ///
+    pub fn skipped_statements(&self) -> &[Diagnostic] {
+        self.skipped.as_deref().unwrap_or_default()
+    }
+
+    /// Take any diagnostics found while evaluating the script so far.
+    ///
+    pub fn take_diagnostics(&mut self) -> Vec<Diagnostic> {
+        std::mem::take(&mut self.state.diagnostics)
+    }
+
+    /// Evaluate the whole script from the beginning without executing any of it's requests.
+    /// Evaluation continues past any errors, which are returned as diagnostics alongside any
+    /// non-fatal issues found.
+    ///
+    /// As nothing is executed, no tests fail and so every block is evaluated exactly once.
+    ///
+    pub fn evaluate(&self) -> Evaluation {
+        let mut interpreter = self.dry_run();
+        let mut evaluation = Evaluation {
+            diagnostics: self.skipped_statements().to_vec(),
+            ..Evaluation::default()
+        };
+        while let Some(result) = interpreter.next() {
+            // Safety checks and inputs can't be answered without an operator.
+            interpreter.confirm(true);
+            interpreter.enter_input("");
+
+            match result {
+                Ok(request) => evaluation.requests.push(request),
+                Err(error) => evaluation.diagnostics.push(Diagnostic::from(&error)),
+            }
+
+            evaluation
+                .diagnostics
+                .extend(interpreter.take_diagnostics());
+        }
+
+        evaluation
+    }
+
+    /// Run every check of the script that doesn't require executing it and return everything
+    /// found. Includes any statements skipped by a permissive parse, errors and warnings from
+    /// evaluating the script (see [`Interpreter::evaluate`]) and warnings for:
+    /// * Devices, channels and timers that are opened without being closed or vice versa.
+    /// * Statements that can't be reached as a preceding test can never pass.
+    /// * Commands that run before a device they depend on is opened. See
+    ///   [`Interpreter::with_state_dependent`].
+    ///
+    /// Traceability IDs used by more than one statement are reported for every occurrence, with the
+    /// severity set by [`Interpreter::with_duplicate_id_severity`].
+    ///
+    pub fn analyze(&self) -> Diagnostics {
+        let evaluation = self.evaluate();
+        let unreachable = if self.state.record {
+            Vec::new()
+        } else {
+            analysis::unreachable(&self.ast, &evaluation.requests)
+        };
+
+        evaluation
+            .diagnostics
+            .into_iter()
+            .chain(analysis::balance(&self.ast))
+            .chain(unreachable)
+            .chain(analysis::duplicate_ids(&self.ast, self.duplicate_ids))
+            .chain(analysis::opened_before(&self.ast, &self.state_dependent))
+            .collect()
+    }
+
+    /// Compute a fingerprint of the script's behaviour. The fingerprint changes when a command,
+    /// test or the bytes sent to a device change but not when only comments or whitespace do.
+    ///
+    /// The script is evaluated as if run at midnight on the 1st of January 1970, without record
+    /// mode, and the following from each evaluated request are hashed in order:
+    /// * Transactions - The device, transmitted bytes, drain period if the response is ignored
+    ///   and the expected values, retries and failure message of any test.
+    /// * Cross checks - Both transactions, the tolerance, retries and failure message.
+    /// * Waits - The duration in milliseconds.
+    /// * Prints and dialogs - The message and kind of dialog.
+    /// * Flushes and printer opens / closes - That they occur.
+    /// * Stopped timers and ratios - The timer's name and that the ratio occurs.
+    /// * Skipped statements - The annotation that skipped them. The statement itself isn't hashed.
+    /// * Errors - The error's message and, for failed tests, the expected values.
+    ///
+    /// Spans, script comments (`;`), steps and measurements aren't hashed. Nor is the interpreter's
+    /// configuration except where it causes evaluation to fail. e.g. A missing route. As a
+    /// RATIOTEST or a setting computed from a measurement can't be evaluated without measurements,
+    /// the RATIOTEST's expected values and the setting's arithmetic aren't hashed. Independent
+    /// tests are hashed in script order regardless of any [`Shuffle`].
+    ///
+    /// The hash is 64 bit FNV-1a so is the same between releases and platforms.
+    ///
+    pub fn fingerprint(&self) -> u64 {
+        let mut interpreter = self.clone();
+        interpreter.shuffle = None;
+
+        let mut interpreter = interpreter.dry_run();
+        interpreter.state.record = false;
+        interpreter.state.clock = Clock::Fixed(NaiveDateTime::default());
+
+        let mut fingerprint = Fingerprint::new();
+        while let Some(result) = interpreter.next() {
+            interpreter.confirm(true);
+            interpreter.enter_input("");
+
+            match result {
+                Ok(request) => request.fingerprint(&mut fingerprint),
+                Err(error) => {
+                    fingerprint.write_str("error");
+                    fingerprint.write_str(&error.reason().message());
+
+                    if let ErrorReason::TestFailure { test, .. } = error.reason() {
+                        fingerprint.write_str(&test.expected.to_string());
+                    }
+                }
+            }
+        }
+
+        fingerprint.finish()
+    }
+
+    /// Write the transactions the script would perform as a JSON array, without performing any of
+    /// them. The script is evaluated as by [`Interpreter::evaluate`], so blocks are expanded and
+    /// settings computed, and every transaction, including both of a cross check's, is written in
+    /// the order it would be performed. Statements that fail to evaluate are left out.
+    ///
+    /// Each transaction is an object containing the device, the kind of command that created it,
+    /// the bytes transmitted in hex, the test if any and the span of the command in the script.
+    /// e.g.
+    /// ```json
+    /// {"device": "TCU", "command": "TCUTest", "bytes": "4D30330D", "test": {"expected": "0..=16",
+    /// "retries": 1, "message": "FAIL"}, "span": {"start": 0, "end": 27}}
+    /// ```
+    ///
+    pub fn write_plan_json<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        let evaluation = self.evaluate();
+        let transactions = evaluation
+            .requests
+            .iter()
+            .flat_map(|request| match request {
+                FrontendRequest::TCUTransact(transaction)
+                | FrontendRequest::PrinterTransact(transaction) => vec![transaction],
+                FrontendRequest::CrossCheck(check) => check.transactions().to_vec(),
+                _ => Vec::new(),
+            });
+
+        write!(writer, "[")?;
+        for (index, transaction) in transactions.enumerate() {
+            write!(writer, "{}\n  ", if index == 0 { "" } else { "," })?;
+            transaction.write_json(&mut writer)?;
+        }
+        writeln!(writer, "\n]")
+    }
+
+    /// Render the script as a flowchart in graphviz's DOT language. Intended for generating
+    /// diagrams of a script rather than checking it. Nothing is evaluated so the flowchart shows
+    /// the script as written.
+    ///
+    pub fn to_dot(&self) -> String {
+        graph::dot(&self.ast)
+    }
+
+    /// Check the whole script against the capabilities of a site's devices without evaluating it.
+    ///
+    /// # Returns
+    /// An error diagnostic for every incompatibility found, in script order.
+    ///
+    pub fn validate(&self, profile: &Profile) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+        for expr in self.ast.iter() {
+            profile.check(expr, &mut diagnostics);
+        }
+
+        diagnostics
+    }
+
+    /// Attempt to recover from an error that occured while executing the script.
+    ///
+    /// Test failures within a RETRY block are recovered from by restarting the innermost block
+    /// that has attempts remaining. Any retries given to the failing test command itself will
+    /// already have been used up by the time it's failure reaches the block. Otherwise a test
+    /// failure is recovered from if it's immediately followed by an IF block with statements to run
+    /// on a failure.
+    ///
+    /// # Arguments
+    /// * `error` - Error returned when executing a request.
+    ///
+    /// # Returns
+    /// Ok if the interpreter recovered and execution can continue. Otherwise the error is returned.
+    ///
+    pub fn recover(&mut self, error: Error) -> Result<(), Error> {
+        if let (Some(log), Some(span)) = (&self.state.device_log, error.span()) {
+            log.resolve(&span, Outcome::Failure(error.reason().message()));
+        }
+
+        if !matches!(error.reason(), ErrorReason::TestFailure { .. }) {
+            return Err(error);
+        }
+
+        self.testing = false;
+        self.state.last_result = Some(false);
+
+        let retry_frame = self.frames.iter().rposition(|frame| match frame.kind {
+            FrameKind::Retry { retries } => retries > 0,
+            FrameKind::Script
+            | FrameKind::Repeat { .. }
+            | FrameKind::Board { .. }
+            | FrameKind::Conditional
+            | FrameKind::Abort => false,
+        });
+
+        let Some(position) = retry_frame else {
+            let handled = self.frames.last().is_some_and(|frame| {
+                frame
+                    .body
+                    .iter()
+                    .skip(frame.index)
+                    .find(|expr| !matches!(expr.expression(), Expr::ScriptComment(_)))
+                    .is_some_and(Self::handles_failure)
+            });
+
+            return match handled {
+                true => Ok(()),
+                false => Err(error),
+            };
+        };
+
+        self.frames.truncate(position + 1);
+        let frame = &mut self.frames[position];
+        if let FrameKind::Retry { retries } = &mut frame.kind {
+            *retries -= 1;
+        }
+        frame.index = 0;
+
+        Ok(())
+    }
+
+    /// Return a copy of the interpreter, restarted, that won't have any effect outside of itself
+    /// when run.
+    ///
+    fn dry_run(&self) -> Self {
+        let mut interpreter = self.clone();
+        interpreter.restart();
+
+        // Nothing is opened or closed when evaluating.
+        interpreter.state.device_log = None;
+        interpreter.state.session = None;
+
+        let max_jumps = interpreter
+            .max_jumps
+            .map_or(DRY_RUN_JUMPS, |max| max.min(DRY_RUN_JUMPS));
+        interpreter.max_jumps = Some(max_jumps);
+
+        interpreter
+    }
+
+    /// Check that the devices required by a request are supported by the active transport and
+    /// have been assigned.
+    ///
+    fn route(&self, request: FrontendRequest, expr: &ParsedExpr) -> Result<FrontendRequest, Error> {
+        if let Some(transport) = self.state.transport {
+            if !request
+                .devices()
+                .iter()
+                .all(|device| transport.supports(*device))
+            {
+                return Err(Error::unsupported(expr.clone(), transport));
+            }
+        }
+
+        let request = self.configure(request);
+
+        let Some(routing) = &self.state.routing else {
+            return Ok(request);
+        };
+
+        let unrouted = request
+            .devices()
+            .iter()
+            .find(|device| !routing.contains(**device));
+        match unrouted {
+            Some(device) => Err(Error::unrouted(expr.clone(), *device)),
+            None => Ok(request),
+        }
+    }
+
+    /// Apply the interpreter's configuration to any transactions made by the request.
+    ///
+    fn configure(&self, request: FrontendRequest) -> FrontendRequest {
+        let echo = self.state.echo;
+        let format = self.state.format;
+        let comms_retries = self.state.comms_retries;
+        let trigger = |transaction: Transaction| match self.state.trigger {
+            Some(trigger) => transaction.measurement_trigger(trigger),
+            None => transaction,
+        };
+        let capture = |transaction: Transaction| match &self.state.capture {
+            Some(capture) => transaction.capturing(capture.clone()),
+            None => transaction,
+        };
+        let observe = |transaction: Transaction| match &self.state.observer {
+            Some(observer) => transaction.observing(observer.handle()),
+            None => transaction,
+        };
+        let latency =
+            |transaction: Transaction| transaction.logging_latency(self.state.latencies.clone());
+        let checksum = |transaction: Transaction| match self.state.checksum {
+            Some(checksum) => transaction.checksum(checksum),
+            None => transaction,
+        };
+        let results = self
+            .state
+            .results
+            .as_ref()
+            .map(|results| results.on_board(self.board()));
+        let report = |transaction: Transaction| match &results {
+            Some(results) => transaction.reporting(results.clone()),
+            None => transaction,
+        };
+
+        match request {
+            FrontendRequest::TCUTransact(transaction) => {
+                FrontendRequest::TCUTransact(observe(report(capture(trigger(latency(
+                    transaction
+                        .echo_format(echo)
+                        .measurement_format(format)
+                        .measurement_unit(self.state.unit.clone())
+                        .comms_retries(comms_retries),
+                ))))))
+            }
+            FrontendRequest::PrinterTransact(transaction) => {
+                FrontendRequest::PrinterTransact(observe(report(capture(latency(checksum(
+                    transaction.comms_retries(comms_retries),
+                ))))))
+            }
+            FrontendRequest::CrossCheck(check) => {
+                let check = check
+                    .echo_format(echo)
+                    .measurement_format(format)
+                    .measurement_unit(self.state.unit.clone())
+                    .comms_retries(comms_retries);
+                let check = match self.state.trigger {
+                    Some(trigger) => check.measurement_trigger(trigger),
+                    None => check,
+                };
+                let check = match &self.state.capture {
+                    Some(capture) => check.capturing(capture.clone()),
+                    None => check,
+                };
+                let check = match &self.state.observer {
+                    Some(observer) => check.observing(observer.handle()),
+                    None => check,
+                };
+                FrontendRequest::CrossCheck(match &results {
+                    Some(results) => check.reporting(results.clone()),
+                    None => check,
+                })
+            }
+            request => request,
+        }
+    }
+
+    /// Track the devices opened and closed by the script. The printer must be opened by the script
+    /// before it's written to. The TCU and reference device are opened by the frontend before the
+    /// run so are always open.
+    ///
+    /// Opening a device or TCU relay the script has already opened, without closing it in between,
+    /// is an error. Within a session, opening a device the session already holds open and closing
+    /// any device are skipped.
+    ///
+    fn track_open(
+        &mut self,
+        request: FrontendRequest,
+        expr: &ParsedExpr,
+    ) -> Result<FrontendRequest, Error> {
+        match request {
+            FrontendRequest::PrinterOpen => {
+                if !self.state.open.insert(Device::USB) {
+                    return Err(Error::device_already_open(expr.clone(), Device::USB));
+                }
+
+                if let Some(session) = &self.state.session {
+                    if !session.open(Device::USB) {
+                        return Ok(FrontendRequest::None);
+                    }
+                }
+            }
+            FrontendRequest::PrinterClose => {
+                if !self.state.open.remove(&Device::USB) {
+                    return Err(Error::device_not_open(expr.clone(), Device::USB));
+                }
+
+                if self.state.session.is_some() {
+                    return Ok(FrontendRequest::None);
+                }
+            }
+            FrontendRequest::TCUTransact(_) => match expr.expression() {
+                Expr::TCUOpen(relay) => {
+                    if let Expr::UInt(relay) = relay.expression() {
+                        if self.state.relays.contains_key(relay) {
+                            return Err(Error::relay_already_open(expr.clone(), *relay));
+                        }
+
+                        self.state.relays.insert(*relay, expr.span().clone());
+                    }
+                }
+                Expr::TCUClose(relay) => {
+                    if let Expr::UInt(relay) = relay.expression() {
+                        self.state.relays.remove(relay);
+                    }
+                }
+                _ => (),
+            },
+            _ => {
+                let closed = request
+                    .devices()
+                    .iter()
+                    .find(|device| **device == Device::USB && !self.state.open.contains(device));
+
+                if let Some(device) = closed {
+                    return Err(Error::device_not_open(expr.clone(), *device));
+                }
+            }
+        }
+
+        Ok(request)
+    }
+
+    /// Log the expression to the device log if it opens or closes a device.
+    ///
+    fn log_device_event(&self, expr: &ParsedExpr) {
+        let Some(log) = &self.state.device_log else {
+            return;
+        };
+
+        let (device, action) = match expr.expression() {
+            Expr::TCUOpen(_) => (Device::TCU, DeviceAction::Open),
+            Expr::TCUClose(_) => (Device::TCU, DeviceAction::Close),
+            Expr::USBOpen => (Device::USB, DeviceAction::Open),
+            Expr::USBClose => (Device::USB, DeviceAction::Close),
+            _ => return,
+        };
+
+        log.log(expr.span().clone(), device, action, self.state.clock.now());
+    }
+
+    /// Begin executing a sequence of expressions, after shuffling any independent tests within it.
+    ///
+    fn push_frame(&mut self, mut body: Vec<ParsedExpr>, kind: FrameKind, step: Option<String>) {
+        if let Some(shuffle) = &mut self.shuffle {
+            shuffle.shuffle(&mut body);
+        }
+
+        self.frames.push(Frame::new(body, kind, step));
+    }
+
+    /// Continue from the statement following the label in the innermost block containing it,
+    /// exiting any blocks within that one.
+    ///
+    fn jump(&mut self, expr: &ParsedExpr, label: &str) -> Result<(), Error> {
+        if let Some(limit) = self.max_jumps.filter(|&limit| self.jumps >= limit) {
+            return Err(Error::jump_limit_exceeded(expr.clone(), limit));
+        }
+
+        let is_label = |statement: &ParsedExpr| match statement.expression() {
+            Expr::Label(name) => name == label,
+            _ => false,
+        };
+        let target = self
+            .frames
+            .iter()
+            .enumerate()
+            .rev()
+            .find_map(|(depth, frame)| {
+                let index = frame.body.iter().position(is_label)?;
+                Some((depth, index))
+            });
+
+        let Some((depth, index)) = target else {
+            return Err(Error::undefined_label(expr.clone(), label));
+        };
+
+        self.frames.truncate(depth + 1);
+        self.frames[depth].index = index + 1;
+        self.jumps += 1;
+        Ok(())
+    }
+
+    /// Return the identifier of the board currently being tested, if within a BOARD block.
+    ///
+    fn board(&self) -> Option<&str> {
+        self.frames
+            .iter()
+            .rev()
+            .find_map(|frame| match &frame.kind {
+                FrameKind::Board { id } => Some(id.as_str()),
+                FrameKind::Script
+                | FrameKind::Retry { .. }
+                | FrameKind::Repeat { .. }
+                | FrameKind::Conditional
+                | FrameKind::Abort => None,
+            })
+    }
+
+    /// Return whether the expression is an IF block with a non-empty section run when the last
+    /// test failed.
+    ///
+    fn handles_failure(expr: &ParsedExpr) -> bool {
+        let Expr::If {
+            condition,
+            then_body,
+            else_body,
+        } = expr.expression()
+        else {
+            return false;
+        };
+
+        match condition.expression() {
+            Expr::LastResult { passed: false } => !then_body.is_empty(),
+            Expr::LastResult { passed: true } => !else_body.is_empty(),
+            _ => false,
+        }
+    }
+
+    /// Return whether the request tests the device. i.e. It can fail with a test failure.
+    ///
+    fn is_test(request: &FrontendRequest) -> bool {
+        match request {
+            FrontendRequest::TCUTransact(transaction)
+            | FrontendRequest::PrinterTransact(transaction) => transaction.test().is_some(),
+            FrontendRequest::CrossCheck(_) => true,
+            _ => false,
+        }
+    }
+
+    /// Return the step of the test the expression is annotated as being part of, if any.
+    ///
+    fn step(expr: &ParsedExpr) -> Option<String> {
+        expr.annotations()
+            .iter()
+            .find_map(|annotation| match annotation {
+                Annotation::Step(description) => Some(description.to_owned()),
+                Annotation::Window { .. } | Annotation::Id(_) | Annotation::Independent => None,
+            })
+    }
+
+    /// Check an expression's annotations to see if it should be skipped rather than run.
+    ///
+    /// # Returns
+    /// A request reporting the skipped expression if it should be skipped. Otherwise None.
+    ///
+    fn skipped(&self, expr: &ParsedExpr) -> Option<FrontendRequest> {
+        let now = self.state.clock.now().time();
+
+        expr.annotations()
+            .iter()
+            .find_map(|annotation| match annotation {
+                Annotation::Window { start, end } => {
+                    if Annotation::window_contains(*start, *end, now) {
+                        return None;
+                    }
+
+                    Some(FrontendRequest::Skipped {
+                        span: expr.span().clone(),
+                        reason: format!("Outside of {annotation}"),
+                    })
+                }
+                Annotation::Step(_) | Annotation::Id(_) | Annotation::Independent => None,
+            })
     }
 }
 
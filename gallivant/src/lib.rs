@@ -1,6 +1,15 @@
+mod analysis;
+mod clock;
+mod diagnostic;
 mod error;
 mod execution;
+mod graph;
 mod interpreter;
+mod line_index;
+mod parse_error;
+mod profile;
+mod run_retry;
+mod shuffle;
 mod syntax;
 
 ////////////////////////////////////////////////////////////////
@@ -8,9 +17,24 @@ mod syntax;
 ////////////////////////////////////////////////////////////////
 
 pub use crate::{
-    error::Error,
-    execution::{Device, Dialog, FrontendRequest, Transaction, TransactionStatus},
-    interpreter::Interpreter,
+    clock::Clock,
+    diagnostic::{Diagnostic, Diagnostics, Severity},
+    error::{Error, ErrorKind, ErrorNote, ErrorReason},
+    execution::{
+        BcdError, Capture, Checksum, Comparison, CrossCheck, CrossCheckStatus, Device,
+        DeviceAction, DeviceEvent, DeviceLog, Dialog, DialogTimeout, Echo, Encoding, Exchange,
+        Expected, FrontendRequest, LineEnding, Measurement, MeasurementError, MeasurementFormat,
+        MeasurementParser, Outcome, RecordedTest, Recording, Results, Routing, RoutingBuilder,
+        SampleReduction, Session, TestOutcome, TimeoutAction, Transaction, TransactionObserver,
+        TransactionPhase, TransactionStatus, Transport, UnitError, UnitSuffix, BAUD_RATES,
+    },
+    interpreter::{Evaluation, Interpreter},
+    line_index::LineIndex,
+    parse_error::{ParseError, SyntaxErrorReason},
+    profile::{Profile, ProfileBuilder},
+    run_retry::RunRetry,
+    shuffle::Shuffle,
+    syntax::{Annotation, Expr, ExprKind, Operator, ParsedExpr},
 };
 
 ////////////////////////////////////////////////////////////////
@@ -0,0 +1,65 @@
+////////////////////////////////////////////////////////////////
+// types
+////////////////////////////////////////////////////////////////
+
+/// Lookup from offsets into a script to the line and column they fall on. Built once from the
+/// script so many offsets can be located without rescanning it.
+///
+/// Offsets are counted in characters rather than bytes, matching the spans reported by errors and
+/// diagnostics, so multi-byte UTF-8 characters count as a single column.
+///
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LineIndex {
+    /// Character offset of the start of each line.
+    line_starts: Vec<usize>,
+}
+
+////////////////////////////////////////////////////////////////
+// construction / conversion
+////////////////////////////////////////////////////////////////
+
+impl LineIndex {
+    pub fn new(script: &str) -> Self {
+        let line_starts = std::iter::once(0)
+            .chain(
+                script
+                    .chars()
+                    .enumerate()
+                    .filter(|(_, c)| *c == '\n')
+                    .map(|(index, _)| index + 1),
+            )
+            .collect();
+
+        Self { line_starts }
+    }
+}
+
+////////////////////////////////////////////////////////////////
+// methods
+////////////////////////////////////////////////////////////////
+
+impl LineIndex {
+    /// Return the 1-based line and column of a character offset into the script. Offsets past the
+    /// end of the script are located on the last line.
+    ///
+    pub fn locate(&self, offset: usize) -> (usize, usize) {
+        let line = self.line_starts.partition_point(|start| *start <= offset);
+        let column = offset - self.line_starts[line - 1] + 1;
+        (line, column)
+    }
+
+    /// Return the character offset the given 1-based line starts at. None if the script doesn't
+    /// have that many lines.
+    ///
+    pub fn line_start(&self, line: usize) -> Option<usize> {
+        self.line_starts.get(line.checked_sub(1)?).copied()
+    }
+
+    /// Return the number of lines in the script.
+    ///
+    pub fn line_count(&self) -> usize {
+        self.line_starts.len()
+    }
+}
+
+////////////////////////////////////////////////////////////////
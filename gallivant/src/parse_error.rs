@@ -0,0 +1,141 @@
+use std::ops::Range;
+
+use ariadne::Report;
+
+use crate::{
+    error::{Error, ErrorNote},
+    line_index::LineIndex,
+    syntax,
+};
+
+pub use crate::syntax::ErrorReason as SyntaxErrorReason;
+
+////////////////////////////////////////////////////////////////
+// types
+////////////////////////////////////////////////////////////////
+
+/// An error found while parsing a script, before any of it's run. Unlike [`Error`], parse errors
+/// have no device or IO context, only a location in the script.
+///
+/// Converts into an [`Error`] for frontends that handle parse and runtime errors together.
+///
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseError {
+    reason: SyntaxErrorReason,
+    notes: Vec<ErrorNote>,
+
+    /// 1-based line and column of the start of the error.
+    location: Option<(usize, usize)>,
+
+    /// Whether the error is on a line that can be skipped by a permissive parse.
+    recoverable: bool,
+}
+
+////////////////////////////////////////////////////////////////
+// construction / conversion
+////////////////////////////////////////////////////////////////
+
+impl ParseError {
+    pub(crate) fn new(error: syntax::Error, script: &str) -> Self {
+        let (location, recoverable) = match error.reason().span() {
+            Some(span) => {
+                let (line, column) = LineIndex::new(script).locate(span.start);
+                let line_start = span.start + 1 - column;
+
+                let text: String = script
+                    .chars()
+                    .skip(line_start)
+                    .take_while(|c| *c != '\n')
+                    .collect();
+                let recoverable = !text.trim().is_empty() && !text.starts_with(';');
+
+                (Some((line, column)), recoverable)
+            }
+            None => (None, false),
+        };
+
+        Self {
+            reason: error.reason().to_owned(),
+            notes: error.notes().to_owned(),
+            location,
+            recoverable,
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////
+
+impl From<ParseError> for Error {
+    fn from(error: ParseError) -> Self {
+        Error::from_syntax_error(error.reason, error.notes)
+    }
+}
+
+////////////////////////////////////////////////////////////////
+// field access
+////////////////////////////////////////////////////////////////
+
+impl ParseError {
+    pub fn reason(&self) -> &SyntaxErrorReason {
+        &self.reason
+    }
+
+    pub fn notes(&self) -> &[ErrorNote] {
+        &self.notes
+    }
+
+    /// Return the area of the script the error occured in. None if the error is at the end of the
+    /// script. e.g. An unclosed block.
+    ///
+    pub fn span(&self) -> Option<Range<usize>> {
+        self.reason.span().cloned()
+    }
+
+    /// Return the 1-based line the error starts on.
+    ///
+    pub fn line(&self) -> Option<usize> {
+        self.location.map(|(line, _)| line)
+    }
+
+    /// Return the 1-based column, in characters, the error starts at.
+    ///
+    pub fn column(&self) -> Option<usize> {
+        self.location.map(|(_, column)| column)
+    }
+
+    /// Return whether the error can be recovered from by skipping the line it's on. See
+    /// [`Interpreter::try_from_str_permissive`].
+    ///
+    /// [`Interpreter::try_from_str_permissive`]: crate::Interpreter::try_from_str_permissive
+    ///
+    pub fn is_recoverable(&self) -> bool {
+        self.recoverable
+    }
+}
+
+////////////////////////////////////////////////////////////////
+// ...
+////////////////////////////////////////////////////////////////
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.location {
+            Some((line, column)) => write!(f, "{line}:{column}: {}", self.reason.message()),
+            None => write!(f, "{}", self.reason.message()),
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////
+
+impl std::error::Error for ParseError {}
+
+////////////////////////////////////////////////////////////////
+
+impl From<&ParseError> for Report<'_> {
+    fn from(error: &ParseError) -> Self {
+        Report::from(Error::from(error.clone()))
+    }
+}
+
+////////////////////////////////////////////////////////////////
@@ -0,0 +1,213 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    ops::RangeInclusive,
+};
+
+use crate::{
+    diagnostic::{Diagnostic, Severity},
+    execution::Device,
+    syntax::{Expr, ExprKind, ParsedExpr},
+};
+
+////////////////////////////////////////////////////////////////
+// types
+////////////////////////////////////////////////////////////////
+
+/// Capabilities of the firmware on a particular site's devices. Scripts can be validated against a
+/// profile before being shipped to the site.
+///
+/// Anything not restricted by the profile is assumed to be supported.
+///
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Profile {
+    commands: Option<BTreeSet<ExprKind>>,
+    options: Option<BTreeMap<u32, RangeInclusive<u32>>>,
+    channels: BTreeMap<Device, u32>,
+}
+
+////////////////////////////////////////////////////////////////
+
+/// Builder for a [`Profile`].
+///
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ProfileBuilder {
+    commands: Option<BTreeSet<ExprKind>>,
+    options: Option<BTreeMap<u32, RangeInclusive<u32>>>,
+    channels: BTreeMap<Device, u32>,
+}
+
+////////////////////////////////////////////////////////////////
+// construction / conversion
+////////////////////////////////////////////////////////////////
+
+impl Profile {
+    pub fn builder() -> ProfileBuilder {
+        ProfileBuilder::default()
+    }
+}
+
+////////////////////////////////////////////////////////////////
+
+impl ProfileBuilder {
+    /// Add commands to those supported. Once any are added, every other command is unsupported.
+    ///
+    #[must_use]
+    pub fn commands(mut self, commands: impl IntoIterator<Item = ExprKind>) -> Self {
+        self.commands
+            .get_or_insert_with(BTreeSet::new)
+            .extend(commands);
+        self
+    }
+
+    /// Add an option to those supported, along with the settings it accepts. Once any are added,
+    /// every other option is unsupported.
+    ///
+    #[must_use]
+    pub fn option(mut self, option: u32, settings: RangeInclusive<u32>) -> Self {
+        self.options
+            .get_or_insert_with(BTreeMap::new)
+            .insert(option, settings);
+        self
+    }
+
+    /// Set the number of measurement channels a device has. Channels are numbered from 0.
+    ///
+    #[must_use]
+    pub fn channels(mut self, device: Device, count: u32) -> Self {
+        self.channels.insert(device, count);
+        self
+    }
+
+    pub fn build(self) -> Profile {
+        Profile {
+            commands: self.commands,
+            options: self.options,
+            channels: self.channels,
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////
+// methods
+////////////////////////////////////////////////////////////////
+
+impl Profile {
+    /// Return true if the profile supports the command.
+    ///
+    pub fn supports(&self, command: ExprKind) -> bool {
+        self.commands
+            .as_ref()
+            .is_none_or(|commands| commands.contains(&command))
+    }
+
+    /// Check an expression, and any it contains, against the profile.
+    ///
+    /// # Arguments
+    /// * `expr` - Expression to check.
+    /// * `diagnostics` - Destination of any incompatibilities found.
+    ///
+    pub(crate) fn check(&self, expr: &ParsedExpr, diagnostics: &mut Vec<Diagnostic>) {
+        let mut incompatible = |expr: &ParsedExpr, message: String| {
+            diagnostics.push(Diagnostic::new(
+                Severity::Error,
+                expr.span().clone(),
+                message,
+            ));
+        };
+
+        let kind = expr.expression_kind();
+        if kind != ExprKind::ScriptComment && !self.supports(kind) {
+            incompatible(expr, format!("{} isn't supported", kind.name()));
+        }
+
+        match expr.expression() {
+            Expr::SetOption { option, setting } | Expr::USBSetOption { option, setting } => {
+                let Expr::UInt(option_value) = option.expression() else {
+                    return;
+                };
+
+                // Computed settings can only be checked once they're computed.
+                let setting_value = match setting.expression() {
+                    Expr::UInt(setting_value) => Some(setting_value),
+                    _ => None,
+                };
+
+                match self
+                    .options
+                    .as_ref()
+                    .map(|options| options.get(option_value))
+                {
+                    Some(None) => {
+                        incompatible(option, format!("Option {option_value} isn't supported"))
+                    }
+                    Some(Some(settings))
+                        if setting_value.is_some_and(|value| !settings.contains(value)) =>
+                    {
+                        incompatible(
+                            setting,
+                            format!(
+                                "Option {option_value} must be set between {} and {}",
+                                settings.start(),
+                                settings.end()
+                            ),
+                        )
+                    }
+                    _ => (),
+                }
+            }
+
+            Expr::TCUTest { channel, .. } => self.check_channel(channel, Device::TCU, diagnostics),
+            Expr::PrinterTest { channel, .. } | Expr::USBPrinterTest { channel, .. } => {
+                self.check_channel(channel, Device::Printer, diagnostics)
+            }
+            Expr::ReferenceTest {
+                channel, reference, ..
+            } => {
+                self.check_channel(channel, Device::Printer, diagnostics);
+                self.check_channel(reference, Device::Reference, diagnostics);
+            }
+
+            Expr::NoResponse { command, .. } => self.check(command, diagnostics),
+            Expr::RetryBlock { body, .. }
+            | Expr::RepeatBlock { body, .. }
+            | Expr::BoardBlock { body, .. }
+            | Expr::AbortBlock { body } => {
+                body.iter().for_each(|expr| self.check(expr, diagnostics))
+            }
+            Expr::If {
+                then_body,
+                else_body,
+                ..
+            } => then_body
+                .iter()
+                .chain(else_body)
+                .for_each(|expr| self.check(expr, diagnostics)),
+
+            _ => (),
+        }
+    }
+
+    /// Check a test's channel is within the number of channels the device has.
+    ///
+    fn check_channel(
+        &self,
+        channel: &ParsedExpr,
+        device: Device,
+        diagnostics: &mut Vec<Diagnostic>,
+    ) {
+        let (Expr::UInt(value), Some(count)) = (channel.expression(), self.channels.get(&device))
+        else {
+            return;
+        };
+
+        if value >= count {
+            diagnostics.push(Diagnostic::new(
+                Severity::Error,
+                channel.span().clone(),
+                format!("Channel {value} doesn't exist, the {device} has {count} channels"),
+            ));
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////
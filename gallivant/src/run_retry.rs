@@ -0,0 +1,79 @@
+use std::collections::BTreeSet;
+
+use crate::error::{Error, ErrorKind};
+
+////////////////////////////////////////////////////////////////
+// types
+////////////////////////////////////////////////////////////////
+
+/// Policy for retrying an entire run that ended with an error. Only errors of a kind considered
+/// transient are retried, so that e.g. flaky comms don't reject a board but a genuine test failure
+/// still does.
+///
+/// By default a run isn't retried and timeouts and comms errors are considered transient.
+///
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RunRetry {
+    retries: u32,
+    transient: BTreeSet<ErrorKind>,
+}
+
+////////////////////////////////////////////////////////////////
+// construction / conversion
+////////////////////////////////////////////////////////////////
+
+impl RunRetry {
+    /// Create a policy that retries a run up to the given number of times.
+    ///
+    pub fn new(retries: u32) -> Self {
+        Self {
+            retries,
+            transient: BTreeSet::from([ErrorKind::Timeout, ErrorKind::Comms]),
+        }
+    }
+
+    /// Set the kinds of error considered transient, replacing the defaults.
+    ///
+    #[must_use]
+    pub fn with_transient(mut self, kinds: impl IntoIterator<Item = ErrorKind>) -> Self {
+        self.transient = kinds.into_iter().collect();
+        self
+    }
+}
+
+////////////////////////////////////////////////////////////////
+
+impl Default for RunRetry {
+    fn default() -> Self {
+        Self::new(0)
+    }
+}
+
+////////////////////////////////////////////////////////////////
+// field access
+////////////////////////////////////////////////////////////////
+
+impl RunRetry {
+    pub fn retries(&self) -> u32 {
+        self.retries
+    }
+
+    pub fn transient(&self) -> &BTreeSet<ErrorKind> {
+        &self.transient
+    }
+}
+
+////////////////////////////////////////////////////////////////
+// methods
+////////////////////////////////////////////////////////////////
+
+impl RunRetry {
+    /// Return whether a run that ended with the error should be retried, given the number of
+    /// times it's already been retried.
+    ///
+    pub fn should_retry(&self, error: &Error, retried: u32) -> bool {
+        retried < self.retries && self.transient.contains(&error.kind())
+    }
+}
+
+////////////////////////////////////////////////////////////////
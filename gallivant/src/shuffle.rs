@@ -0,0 +1,175 @@
+use crate::syntax::{Annotation, ParsedExpr};
+
+////////////////////////////////////////////////////////////////
+// types
+////////////////////////////////////////////////////////////////
+
+/// Policy for randomizing the order that tests annotated as `@independent` are run in, to catch
+/// tests that only pass because of the state a previous test left the device in.
+///
+/// Only consecutive independent statements within the same script or block are reordered amongst
+/// themselves. Every other statement keeps it's position, so setup commands still run before the
+/// tests following them.
+///
+/// The order is determined entirely by the seed, so a run can be reproduced by reusing it's seed.
+///
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Shuffle {
+    seed: u64,
+
+    /// State of the random number generator.
+    state: u64,
+}
+
+////////////////////////////////////////////////////////////////
+// construction / conversion
+////////////////////////////////////////////////////////////////
+
+impl Shuffle {
+    /// Create a policy that shuffles using the given seed.
+    ///
+    pub fn new(seed: u64) -> Self {
+        Self { seed, state: seed }
+    }
+}
+
+////////////////////////////////////////////////////////////////
+// field access
+////////////////////////////////////////////////////////////////
+
+impl Shuffle {
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+}
+
+////////////////////////////////////////////////////////////////
+// methods
+////////////////////////////////////////////////////////////////
+
+impl Shuffle {
+    /// Return to the start of the seed's sequence, so that the next run is shuffled the same as
+    /// the first.
+    ///
+    pub(crate) fn reset(&mut self) {
+        self.state = self.seed;
+    }
+
+    /// Shuffle each run of consecutive independent statements in the body.
+    ///
+    pub(crate) fn shuffle(&mut self, body: &mut [ParsedExpr]) {
+        let mut start = 0;
+        while start < body.len() {
+            let length = body[start..]
+                .iter()
+                .take_while(|expr| is_independent(expr))
+                .count();
+
+            // Fisher-Yates.
+            let run = &mut body[start..start + length];
+            for i in (1..run.len()).rev() {
+                let j = self.below(i as u64 + 1) as usize;
+                run.swap(i, j);
+            }
+
+            start += length.max(1);
+        }
+    }
+
+    /// Return the next number in the sequence. SplitMix64.
+    ///
+    fn next(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Return the next number in the sequence, reduced to below the bound.
+    ///
+    fn below(&mut self, bound: u64) -> u64 {
+        self.next() % bound
+    }
+}
+
+////////////////////////////////////////////////////////////////
+// helpers
+////////////////////////////////////////////////////////////////
+
+fn is_independent(expr: &ParsedExpr) -> bool {
+    expr.annotations()
+        .iter()
+        .any(|annotation| matches!(annotation, Annotation::Independent))
+}
+
+////////////////////////////////////////////////////////////////
+// tests
+////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::syntax::parse_from_str;
+
+    ////////////////////////////////////////////////////////////////
+
+    #[test]
+    fn test_shuffle() {
+        let script = r#"
+TCUOPEN 1
+@independent
+TCUTEST 1, 0, 10, 0, "a"
+@independent
+TCUTEST 2, 0, 10, 0, "b"
+@independent
+TCUTEST 3, 0, 10, 0, "c"
+TCUCLOSE 1
+@independent
+TCUTEST 4, 0, 10, 0, "d"
+"#;
+        let ast = parse_from_str(script).unwrap();
+        let orders: Vec<Vec<ParsedExpr>> = (0..16)
+            .map(|seed| {
+                let mut body = ast.clone();
+                Shuffle::new(seed).shuffle(&mut body);
+                body
+            })
+            .collect();
+
+        // Dependent statements and lone independent statements never move.
+        for body in &orders {
+            assert_eq!(body[0], ast[0]);
+            assert_eq!(body[4..], ast[4..]);
+
+            let mut run = body[1..4].to_vec();
+            run.sort_by_key(|expr| expr.span().start);
+            assert_eq!(run, ast[1..4]);
+        }
+
+        assert!(orders.iter().any(|body| body[1..4] != ast[1..4]));
+    }
+
+    ////////////////////////////////////////////////////////////////
+
+    #[test]
+    fn test_reproducible() {
+        let script = (0..8)
+            .map(|channel| format!("@independent\nTCUTEST {channel}, 0, 10, 0, \"a\"\n"))
+            .collect::<String>();
+        let ast = parse_from_str(&script).unwrap();
+
+        let mut shuffle = Shuffle::new(42);
+        let mut first = ast.clone();
+        shuffle.shuffle(&mut first);
+
+        shuffle.reset();
+        let mut second = ast.clone();
+        shuffle.shuffle(&mut second);
+
+        assert_eq!(first, second);
+        assert_eq!(shuffle.seed(), 42);
+    }
+}
+
+////////////////////////////////////////////////////////////////
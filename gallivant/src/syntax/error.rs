@@ -1,6 +1,7 @@
 use ariadne::{Label, Report, ReportKind};
 
 use super::expression::ExprKind;
+use crate::line_index::LineIndex;
 
 pub use crate::error::ErrorNote;
 
@@ -37,6 +38,50 @@ pub enum ErrorReason {
         value: u32,
         limits: (u32, u32),
     },
+
+    /// An argument's value isn't in the required format.
+    ArgFormat {
+        span: Span,
+        expected: &'static str,
+    },
+
+    /// An IFDEF without a matching ENDIF.
+    UnclosedIfdef {
+        span: Span,
+    },
+
+    /// An ENDIF without a matching IFDEF.
+    UnmatchedEndif {
+        span: Span,
+    },
+
+    /// A reference to a name that no earlier SET defines.
+    UndefinedReference {
+        span: Span,
+        name: String,
+    },
+
+    /// An INCLUDE of a script that couldn't be loaded.
+    IncludeNotFound {
+        span: Span,
+        path: String,
+    },
+
+    /// An INCLUDE of a script that's already being included. i.e. A script that includes itself,
+    /// directly or through other scripts.
+    IncludeCycle {
+        span: Span,
+        path: String,
+    },
+
+    /// An INCLUDE of a script containing an error. Location is the 1-based line and column of the
+    /// error within the included script.
+    IncludeFailed {
+        span: Span,
+        path: String,
+        location: Option<(usize, usize)>,
+        reason: Box<ErrorReason>,
+    },
 }
 
 ////////////////////////////////////////////////////////////////
@@ -108,6 +153,87 @@ impl Error {
             notes: Vec::new(),
         }
     }
+
+    /// Create a new error resulting from an argument's value not being in the required format.
+    ///
+    /// # Arguments
+    /// * `span` - Area in the input that the error occured.
+    /// * `expected` - Description of the required format.
+    ///
+    pub fn argument_format(span: Span, expected: &'static str) -> Self {
+        Self {
+            reason: ErrorReason::ArgFormat { span, expected },
+            notes: Vec::new(),
+        }
+    }
+
+    pub fn unclosed_ifdef(span: Span) -> Self {
+        Self {
+            reason: ErrorReason::UnclosedIfdef { span },
+            notes: vec![ErrorNote::Help("Close the section with ENDIF")],
+        }
+    }
+
+    pub fn unmatched_endif(span: Span) -> Self {
+        Self {
+            reason: ErrorReason::UnmatchedEndif { span },
+            notes: Vec::new(),
+        }
+    }
+
+    pub fn undefined_reference(span: Span, name: &str) -> Self {
+        Self {
+            reason: ErrorReason::UndefinedReference {
+                span,
+                name: name.to_owned(),
+            },
+            notes: vec![ErrorNote::Help(
+                "Define the value with SET <name> <value> before it's used",
+            )],
+        }
+    }
+
+    pub fn include_not_found(span: Span, path: &str) -> Self {
+        Self {
+            reason: ErrorReason::IncludeNotFound {
+                span,
+                path: path.to_owned(),
+            },
+            notes: Vec::new(),
+        }
+    }
+
+    pub fn include_cycle(span: Span, path: &str) -> Self {
+        Self {
+            reason: ErrorReason::IncludeCycle {
+                span,
+                path: path.to_owned(),
+            },
+            notes: vec![ErrorNote::Help(
+                "Scripts can't include themselves, directly or through other scripts",
+            )],
+        }
+    }
+
+    /// Error for an error within an included script. Located by the INCLUDE's span, with the
+    /// line and column of the error within the included script.
+    ///
+    pub fn include_failed(span: Span, path: &str, error: Error, script: &str) -> Self {
+        let location = error
+            .reason
+            .span()
+            .map(|error_span| LineIndex::new(script).locate(error_span.start));
+
+        Self {
+            reason: ErrorReason::IncludeFailed {
+                span,
+                path: path.to_owned(),
+                location,
+                reason: Box::new(error.reason),
+            },
+            notes: error.notes,
+        }
+    }
 }
 
 ////////////////////////////////////////////////////////////////
@@ -142,6 +268,23 @@ impl std::fmt::Display for Error {
 ////////////////////////////////////////////////////////////////
 
 impl ErrorReason {
+    pub fn span(&self) -> Option<&Span> {
+        match self {
+            ErrorReason::Unexpected { span, .. } => Some(span),
+            ErrorReason::Unclosed => None,
+            ErrorReason::UnrecognisedCommand { span } => Some(span),
+            ErrorReason::ArgType { span, .. } => Some(span),
+            ErrorReason::ArgValue { span, .. } => Some(span),
+            ErrorReason::ArgFormat { span, .. } => Some(span),
+            ErrorReason::UnclosedIfdef { span } => Some(span),
+            ErrorReason::UnmatchedEndif { span } => Some(span),
+            ErrorReason::UndefinedReference { span, .. } => Some(span),
+            ErrorReason::IncludeNotFound { span, .. } => Some(span),
+            ErrorReason::IncludeCycle { span, .. } => Some(span),
+            ErrorReason::IncludeFailed { span, .. } => Some(span),
+        }
+    }
+
     pub fn message(&self) -> &'static str {
         match self {
             ErrorReason::Unexpected { .. } => "Unexpected token",
@@ -149,6 +292,13 @@ impl ErrorReason {
             ErrorReason::UnrecognisedCommand { .. } => "Unrecognised command found",
             ErrorReason::ArgType { .. } => "Invalid argument type",
             ErrorReason::ArgValue { .. } => "Argument value exceeds limits",
+            ErrorReason::ArgFormat { .. } => "Invalid argument format",
+            ErrorReason::UnclosedIfdef { .. } => "IFDEF without a matching ENDIF",
+            ErrorReason::UnmatchedEndif { .. } => "ENDIF without a matching IFDEF",
+            ErrorReason::UndefinedReference { .. } => "Reference to an undefined value",
+            ErrorReason::IncludeNotFound { .. } => "Included script couldn't be loaded",
+            ErrorReason::IncludeCycle { .. } => "Circular INCLUDE",
+            ErrorReason::IncludeFailed { .. } => "Error in included script",
         }
     }
 
@@ -240,6 +390,58 @@ impl ErrorReason {
                         .with_priority(9),
                 ]
             }
+
+            ErrorReason::ArgFormat { span, expected } => {
+                vec![Label::new(span.clone())
+                    .with_message(format!("Expected {expected}"))
+                    .with_priority(10)]
+            }
+
+            ErrorReason::UnclosedIfdef { span } => {
+                vec![Label::new(span.clone())
+                    .with_message("Section is never closed")
+                    .with_priority(10)]
+            }
+
+            ErrorReason::UnmatchedEndif { span } => {
+                vec![Label::new(span.clone())
+                    .with_message("No section to close")
+                    .with_priority(10)]
+            }
+
+            ErrorReason::UndefinedReference { span, name } => {
+                vec![Label::new(span.clone())
+                    .with_message(format!("'{name}' isn't defined by an earlier SET"))
+                    .with_priority(10)]
+            }
+
+            ErrorReason::IncludeNotFound { span, path } => {
+                vec![Label::new(span.clone())
+                    .with_message(format!("'{path}' couldn't be loaded"))
+                    .with_priority(10)]
+            }
+
+            ErrorReason::IncludeCycle { span, path } => {
+                vec![Label::new(span.clone())
+                    .with_message(format!("'{path}' is already being included"))
+                    .with_priority(10)]
+            }
+
+            ErrorReason::IncludeFailed {
+                span,
+                path,
+                location,
+                reason,
+            } => {
+                let message = match location {
+                    Some((line, column)) => format!("{path}:{line}:{column}: {}", reason.message()),
+                    None => format!("{path}: {}", reason.message()),
+                };
+
+                vec![Label::new(span.clone())
+                    .with_message(message)
+                    .with_priority(10)]
+            }
         }
     }
 }
@@ -247,7 +449,7 @@ impl ErrorReason {
 ////////////////////////////////////////////////////////////////
 
 impl Error {
-    pub fn to_report(&self) -> Report {
+    pub fn to_report(&self) -> Report<'_> {
         let mut report = Report::build(ReportKind::Error, (), 0)
             .with_message(self.reason.message())
             .with_labels(self.reason.labels());
@@ -1,14 +1,18 @@
-use std::time::Duration;
+use std::{collections::BTreeMap, io::Read, time::Duration};
 
-use chrono::{Datelike, Local, Timelike};
+use chrono::{Datelike, Timelike};
 
 use crate::{
+    diagnostic::Diagnostic,
     error::Error,
-    execution::{Dialog, FrontendRequest, MeasurementTest, Transaction},
+    execution::{
+        self, CrossCheck, Device, Dialog, DialogTimeout, Expected, FailedTest, FrontendRequest,
+        MeasurementTest, SampleReduction, Transaction,
+    },
 };
 
 use super::{
-    expression::{Expr, ParsedExpr},
+    expression::{parse, Expr, ParsedExpr},
     state::EvalState,
 };
 
@@ -23,13 +27,237 @@ fn tcu_format_byte(byte: u8) -> Vec<u8> {
 
 ////////////////////////////////////////////////////////////////
 
+/// Largest file that can be sent by PRINTIMAGE. Protects against referencing the wrong file.
+///
+const MAX_IMAGE_SIZE: u64 = 256 * 1024;
+
+////////////////////////////////////////////////////////////////
+
+/// Scale of a RATIOTEST's ratio. i.e. The ratio is tested in thousandths.
+///
+const RATIO_SCALE: i128 = 1000;
+
+////////////////////////////////////////////////////////////////
+
+/// Return the values expected by a test command's range or comparison argument.
+///
+fn expected_values(expr: &ParsedExpr) -> Option<Expected> {
+    match expr.expression() {
+        Expr::Range { min, max } => match (min.expression(), max.expression()) {
+            (Expr::UInt(min), Expr::UInt(max)) => Some(Expected::Range(*min..=*max)),
+            _ => None,
+        },
+        Expr::Tolerance { nominal, percent } => {
+            match (nominal.expression(), percent.expression()) {
+                (Expr::UInt(nominal), Expr::UInt(percent)) => {
+                    Some(Expected::tolerance(*nominal, *percent))
+                }
+                _ => None,
+            }
+        }
+        Expr::Comparison { operator, value } => match value.expression() {
+            Expr::UInt(value) => Some(Expected::Comparison(*operator, *value)),
+            _ => None,
+        },
+        Expr::Set(values) => values
+            .iter()
+            .map(|value| match value.expression() {
+                Expr::UInt(value) => Some(*value),
+                _ => None,
+            })
+            .collect::<Option<_>>()
+            .map(Expected::OneOf),
+        Expr::Stability { samples, spread } => match (samples.expression(), spread.expression()) {
+            (Expr::UInt(samples), Expr::UInt(spread)) => Some(Expected::Stable {
+                samples: *samples,
+                spread: *spread,
+            }),
+            _ => None,
+        },
+        Expr::Baseline(tolerance) => match tolerance.expression() {
+            Expr::UInt(tolerance) => Some(Expected::Baseline {
+                tolerance: *tolerance,
+                baseline: None,
+            }),
+            _ => None,
+        },
+        Expr::Bitfield(fields) => fields
+            .iter()
+            .map(
+                |(mask, value)| match (mask.expression(), value.expression()) {
+                    (Expr::UInt(mask), Expr::UInt(value)) => Some((*mask, *value)),
+                    _ => None,
+                },
+            )
+            .collect::<Option<_>>()
+            .map(Expected::Bitfield),
+        _ => None,
+    }
+}
+
+////////////////////////////////////////////////////////////////
+
+/// Check the parameters of a test command for anything that likely isn't intended.
+///
+fn check_test(expr: &ParsedExpr, expected: &Expected, message: &str, state: &mut EvalState) {
+    if !expected.is_satisfiable() {
+        let reason = match expected {
+            Expected::Range(range) => format!(
+                "minimum {} is greater than maximum {}",
+                range.start(),
+                range.end()
+            ),
+            Expected::Comparison(..) | Expected::Bounds(..) | Expected::Baseline { .. } => {
+                format!("no measurement is {expected}")
+            }
+            Expected::OneOf(..) => "no values are allowed".to_owned(),
+            Expected::Stable { .. } => "no samples are taken".to_owned(),
+            Expected::Bitfield(..) => "a value has bits outside of it's mask".to_owned(),
+        };
+
+        state.diagnostics.push(Diagnostic::warning(
+            expr.span().clone(),
+            format!("Test can never pass, {reason}"),
+        ));
+    }
+
+    if message.trim().is_empty() {
+        state.diagnostics.push(Diagnostic::warning(
+            expr.span().clone(),
+            "Test has an empty failure message",
+        ));
+    }
+}
+
+////////////////////////////////////////////////////////////////
+
+/// Return how a test reduces it's measurements and the number it takes. A single measurement
+/// unless given. None if the number isn't a UInt.
+///
+fn sampling(
+    samples: &Option<(SampleReduction, Box<ParsedExpr>)>,
+) -> Option<(SampleReduction, u32)> {
+    match samples {
+        Some((reduction, samples)) => match samples.expression() {
+            Expr::UInt(samples) => Some((*reduction, *samples)),
+            _ => None,
+        },
+        None => Some((SampleReduction::Average, 1)),
+    }
+}
+
+////////////////////////////////////////////////////////////////
+
+/// Check the number of measurements a test takes is likely intended.
+///
+fn check_samples(expr: &ParsedExpr, samples: u32, state: &mut EvalState) {
+    if samples == 0 {
+        state.diagnostics.push(Diagnostic::warning(
+            expr.span().clone(),
+            "Test takes 0 measurements so a single measurement is tested",
+        ));
+    }
+}
+
+////////////////////////////////////////////////////////////////
+
+/// Return the value of an option's setting, performing any arithmetic.
+///
+/// Arithmetic is performed on unsigned 32 bit integers. Rather than wrapping or saturating, it
+/// fails if any operation overflows, has a negative result or divides by zero. Division truncates.
+/// A negative stored measurement can't be used.
+///
+/// # Arguments
+/// * `expr` - Command the setting belongs to.
+/// * `setting` - The setting.
+/// * `state` - State containing any stored measurements the setting refers to.
+///
+/// # Errors
+/// If an operation fails, a measurement hasn't been stored or the setting doesn't fit in a byte.
+///
+fn setting_value(expr: &ParsedExpr, setting: &ParsedExpr, state: &EvalState) -> Result<u8, Error> {
+    fn value(expr: &ParsedExpr, arg: &ParsedExpr, state: &EvalState) -> Result<u32, Error> {
+        match arg.expression() {
+            Expr::UInt(value) => Ok(*value),
+            Expr::Variable(name) => {
+                let value = state
+                    .measurements
+                    .get(name)
+                    .ok_or_else(|| Error::unknown_measurement(expr.clone(), name))?;
+                u32::try_from(value)
+                    .map_err(|_| Error::setting_out_of_range(expr.clone(), arg.clone(), value))
+            }
+            Expr::Arithmetic { operator, lhs, rhs } => {
+                let (lhs, rhs) = (value(expr, lhs, state)?, value(expr, rhs, state)?);
+                operator
+                    .apply(lhs, rhs)
+                    .ok_or_else(|| Error::arithmetic(expr.clone(), arg.clone(), lhs, rhs))
+            }
+            _ => panic!("Invalid arithmetic operand {arg:?}"),
+        }
+    }
+
+    let value = value(expr, setting, state)?;
+    u8::try_from(value)
+        .map_err(|_| Error::setting_out_of_range(expr.clone(), setting.clone(), value.into()))
+}
+
+////////////////////////////////////////////////////////////////
+
+/// Replace every operator input within the argument with the text the operator entered.
+///
+/// # Errors
+/// If the operator hasn't entered the text of an input.
+///
+fn resolve_inputs(
+    arg: &mut ParsedExpr,
+    expr: &ParsedExpr,
+    inputs: &BTreeMap<String, String>,
+) -> Result<(), Error> {
+    if let Expr::Input(name) = arg.expression() {
+        let text = inputs
+            .get(name)
+            .ok_or_else(|| Error::input_not_entered(expr.clone(), name))?;
+        *arg.expression_mut() = Expr::String(text.to_owned());
+        return Ok(());
+    }
+
+    arg.expression_mut()
+        .children_mut()
+        .into_iter()
+        .try_for_each(|child| resolve_inputs(child, expr, inputs))
+}
+
+////////////////////////////////////////////////////////////////
+
 pub fn evaluate(expr: &ParsedExpr, state: &mut EvalState) -> Result<FrontendRequest, Error> {
+    // Operator inputs aren't known until the script is run, unlike references.
+    let mut resolved = expr.clone();
+    resolve_inputs(&mut resolved, expr, &state.inputs)?;
+    let expr = &resolved;
+
     match expr.expression() {
         Expr::String(_) => panic!("Orphaned String"),
         Expr::UInt(_) => panic!("Orphaned UInt"),
+        Expr::Range { .. } => panic!("Orphaned Range"),
+        Expr::Tolerance { .. } => panic!("Orphaned Tolerance"),
+        Expr::Comparison { .. } => panic!("Orphaned Comparison"),
+        Expr::Set(..) => panic!("Orphaned Set"),
+        Expr::Stability { .. } => panic!("Orphaned Stability"),
+        Expr::Baseline(_) => panic!("Orphaned Baseline"),
+        Expr::Bitfield(_) => panic!("Orphaned Bitfield"),
+        Expr::LastResult { .. } => panic!("Orphaned LastResult"),
+        Expr::Variable(_) => panic!("Orphaned Variable"),
+        Expr::Reference { .. } => panic!("Unresolved Reference"),
+        Expr::Input(_) => panic!("Orphaned Input"),
+        Expr::Include(_) => panic!("Unresolved Include"),
+        Expr::Arithmetic { .. } => panic!("Orphaned Arithmetic"),
 
         Expr::ScriptComment(_) => Ok(FrontendRequest::None),
 
+        // Any references to the value are resolved when parsing.
+        Expr::Define { .. } => Ok(FrontendRequest::None),
+
         Expr::HPMode => {
             state.hpmode = !state.hpmode;
             Ok(FrontendRequest::None)
@@ -42,14 +270,14 @@ pub fn evaluate(expr: &ParsedExpr, state: &mut EvalState) -> Result<FrontendRequ
             panic!("Invalid COMMENT arg {:?}", arg);
         }
 
-        Expr::Wait(arg) => {
+        Expr::Wait(arg) | Expr::Delay(arg) => {
             if let Expr::UInt(milliseconds) = arg.expression() {
                 return Ok(FrontendRequest::Wait(Duration::from_millis(
                     (*milliseconds).into(),
                 )));
             }
 
-            panic!("Invalid WAIT arg {:?}", arg);
+            panic!("Invalid WAIT or DELAY arg {:?}", arg);
         }
 
         Expr::OpenDialog(arg) => {
@@ -62,24 +290,64 @@ pub fn evaluate(expr: &ParsedExpr, state: &mut EvalState) -> Result<FrontendRequ
             panic!("Invalid OPENDIALOG arg {:?}", arg);
         }
 
-        Expr::WaitDialog(arg) => {
+        Expr::WaitDialog { message, timeout } => {
+            let timeout = timeout.as_ref().map(|timeout| match timeout.expression() {
+                Expr::UInt(seconds) => DialogTimeout {
+                    duration: Duration::from_secs((*seconds).into()),
+                    action: state.timeout_action,
+                },
+                _ => panic!("Invalid WAITDIALOG timeout {timeout:?}"),
+            });
+
+            if let Expr::String(message) = message.expression() {
+                let kind = Dialog::ManualInput { timeout };
+                let message = message.to_owned();
+                return Ok(FrontendRequest::GuiDialogue { kind, message });
+            }
+
+            panic!("Invalid WAITDIALOG arg {:?}", message);
+        }
+
+        Expr::InputDialog { prompt, target } => {
+            if let Expr::String(message) = prompt.expression() {
+                state.awaiting_input = Some(target.to_owned());
+                let kind = Dialog::Input;
+                let message = message.to_owned();
+                return Ok(FrontendRequest::GuiDialogue { kind, message });
+            }
+
+            panic!("Invalid INPUTDIALOG arg {:?}", prompt);
+        }
+
+        Expr::Confirm(arg) => {
+            if state.started {
+                return Err(Error::late_confirmation(expr.clone()));
+            }
+
             if let Expr::String(message) = arg.expression() {
-                let kind = Dialog::ManualInput;
+                let kind = Dialog::Confirmation;
                 let message = message.to_owned();
                 return Ok(FrontendRequest::GuiDialogue { kind, message });
             }
 
-            panic!("Invalid WAITDIALOG arg {:?}", arg);
+            panic!("Invalid CONFIRM arg {:?}", arg);
         }
 
+        Expr::Beep => Ok(FrontendRequest::Beep),
         Expr::Flush => Ok(FrontendRequest::TCUFlush),
-        Expr::Protocol => Ok(FrontendRequest::None),
+        Expr::Protocol => {
+            state.protocol = !state.protocol;
+            Ok(FrontendRequest::None)
+        }
 
         Expr::Print(args) => {
             let mut arg_bytes = Vec::new();
             for arg in args {
                 if let Expr::String(str) = arg.expression() {
-                    arg_bytes.extend_from_slice(str.as_bytes());
+                    let bytes = state.encoding.encode(str).map_err(|character| {
+                        Error::unencodable(expr.clone(), arg.clone(), character, state.encoding)
+                    })?;
+                    arg_bytes.extend_from_slice(&bytes);
                 } else if let Expr::UInt(uint) = arg.expression() {
                     debug_assert!(*uint <= 255);
                     arg_bytes.push(*uint as u8);
@@ -132,7 +400,7 @@ pub fn evaluate(expr: &ParsedExpr, state: &mut EvalState) -> Result<FrontendRequ
         }
 
         Expr::SetTime => {
-            let datetime = Local::now();
+            let datetime = state.clock.now();
             let datetime = format!(
                 "{:02}:{:02}:{:02},{:02}/{:02}/{:02}",
                 datetime.hour(),
@@ -162,11 +430,9 @@ pub fn evaluate(expr: &ParsedExpr, state: &mut EvalState) -> Result<FrontendRequ
         }
 
         Expr::SetOption { option, setting } => {
-            if let (Expr::UInt(option), Expr::UInt(setting)) =
-                (option.expression(), setting.expression())
-            {
+            if let Expr::UInt(option) = option.expression() {
                 debug_assert!(*option <= 255);
-                debug_assert!(*setting <= 255);
+                let setting = setting_value(expr, setting, state)?;
 
                 let bytes = if state.hpmode {
                     format!("P061B00004F{:02X}{:02X}\r", option, setting).into_bytes()
@@ -212,40 +478,49 @@ pub fn evaluate(expr: &ParsedExpr, state: &mut EvalState) -> Result<FrontendRequ
 
         Expr::TCUTest {
             channel,
-            min,
-            max,
+            expected,
             retries,
             message,
+            samples,
         } => {
             let args = (
                 channel.expression(),
-                min.expression(),
-                max.expression(),
+                expected_values(expected),
                 retries.expression(),
                 message.expression(),
+                sampling(samples),
             );
             if let (
                 Expr::UInt(channel),
-                Expr::UInt(min),
-                Expr::UInt(max),
+                Some(expected),
                 Expr::UInt(retries),
                 Expr::String(message),
+                Some((reduction, samples)),
             ) = args
             {
                 debug_assert!(*channel <= 255);
 
-                return Ok(FrontendRequest::TCUTransact(Transaction::with_tcu(
-                    expr.clone(),
-                    format!("M{channel:02X}\r").into_bytes(),
-                    Some(MeasurementTest {
-                        expected: *min..=*max,
-                        retries: *retries,
-                        failure_message: message.to_owned(),
-                    }),
-                )));
+                check_test(expr, &expected, message, state);
+                check_samples(expr, samples, state);
+
+                return Ok(FrontendRequest::TCUTransact(
+                    Transaction::with_tcu(
+                        expr.clone(),
+                        format!("M{channel:02X}\r").into_bytes(),
+                        Some(MeasurementTest {
+                            expected,
+                            retries: *retries,
+                            attempt: 0,
+                            failure_message: message.to_owned(),
+                        }),
+                    )
+                    .recording(state.record)
+                    .with_baselines(&state.baselines)
+                    .sampling(reduction, samples),
+                ));
             }
 
-            panic!("Invalid TCUTEST args {channel:?}, {min:?}, {max:?}, {retries:?}, {message:?}")
+            panic!("Invalid TCUTEST args {channel:?}, {expected:?}, {retries:?}, {message:?}")
         }
 
         Expr::PrinterSet(arg) => {
@@ -270,49 +545,56 @@ pub fn evaluate(expr: &ParsedExpr, state: &mut EvalState) -> Result<FrontendRequ
 
         Expr::PrinterTest {
             channel,
-            min,
-            max,
+            expected,
             retries,
             message,
+            samples,
         } => {
             let args = (
                 channel.expression(),
-                min.expression(),
-                max.expression(),
+                expected_values(expected),
                 retries.expression(),
                 message.expression(),
+                sampling(samples),
             );
 
             if let (
                 Expr::UInt(channel),
-                Expr::UInt(min),
-                Expr::UInt(max),
+                Some(expected),
                 Expr::UInt(retries),
                 Expr::String(message),
+                Some((reduction, samples)),
             ) = args
             {
                 debug_assert!(*channel <= 255);
 
+                check_test(expr, &expected, message, state);
+                check_samples(expr, samples, state);
+
                 let bytes = if state.hpmode {
                     format!("W051B00004D{channel:02X}\r").into_bytes()
                 } else {
                     format!("W051B004D{channel:02X}\r").into_bytes()
                 };
 
-                return Ok(FrontendRequest::TCUTransact(Transaction::with_tcu(
-                    expr.clone(),
-                    bytes,
-                    Some(MeasurementTest {
-                        expected: *min..=*max,
-                        retries: *retries,
-                        failure_message: message.to_owned(),
-                    }),
-                )));
+                return Ok(FrontendRequest::TCUTransact(
+                    Transaction::with_tcu(
+                        expr.clone(),
+                        bytes,
+                        Some(MeasurementTest {
+                            expected,
+                            retries: *retries,
+                            attempt: 0,
+                            failure_message: message.to_owned(),
+                        }),
+                    )
+                    .recording(state.record)
+                    .with_baselines(&state.baselines)
+                    .sampling(reduction, samples),
+                ));
             }
 
-            panic!(
-                "Invalid PRINTERTEST args {channel:?}, {min:?}, {max:?}, {retries:?}, {message:?}"
-            )
+            panic!("Invalid PRINTERTEST args {channel:?}, {expected:?}, {retries:?}, {message:?}")
         }
 
         Expr::IssueTest(_) => Ok(FrontendRequest::None),
@@ -334,13 +616,27 @@ pub fn evaluate(expr: &ParsedExpr, state: &mut EvalState) -> Result<FrontendRequ
                 }
             }
 
-            Ok(FrontendRequest::PrinterTransact(Transaction::with_printer(
+            Ok(FrontendRequest::PrinterTransact(Transaction::with_usb(
                 expr.clone(),
                 bytes,
                 None,
             )))
         }
 
+        Expr::SendHex(arg) => {
+            if let Expr::String(text) = arg.expression() {
+                let bytes = parse::hex_bytes(text).expect("SENDHEX arg validated when parsed");
+
+                return Ok(FrontendRequest::PrinterTransact(Transaction::with_usb(
+                    expr.clone(),
+                    bytes,
+                    None,
+                )));
+            }
+
+            panic!("Invalid SENDHEX arg {arg:?}")
+        }
+
         Expr::USBSetTimeFormat(arg) => {
             if let Expr::UInt(uint) = arg.expression() {
                 let bytes = if state.hpmode {
@@ -349,7 +645,7 @@ pub fn evaluate(expr: &ParsedExpr, state: &mut EvalState) -> Result<FrontendRequ
                     vec![0x1B, b't', b'f', *uint as u8]
                 };
 
-                return Ok(FrontendRequest::PrinterTransact(Transaction::with_printer(
+                return Ok(FrontendRequest::PrinterTransact(Transaction::with_usb(
                     expr.clone(),
                     bytes,
                     None,
@@ -360,7 +656,7 @@ pub fn evaluate(expr: &ParsedExpr, state: &mut EvalState) -> Result<FrontendRequ
         }
 
         Expr::USBSetTime => {
-            let datetime = Local::now();
+            let datetime = state.clock.now();
             let datetime = format!(
                 "{:02}:{:02}:{:02},{:02}/{:02}/{:02}",
                 datetime.hour(),
@@ -379,7 +675,7 @@ pub fn evaluate(expr: &ParsedExpr, state: &mut EvalState) -> Result<FrontendRequ
 
             bytes.extend_from_slice(datetime.as_bytes());
 
-            Ok(FrontendRequest::PrinterTransact(Transaction::with_printer(
+            Ok(FrontendRequest::PrinterTransact(Transaction::with_usb(
                 expr.clone(),
                 bytes,
                 None,
@@ -387,19 +683,17 @@ pub fn evaluate(expr: &ParsedExpr, state: &mut EvalState) -> Result<FrontendRequ
         }
 
         Expr::USBSetOption { option, setting } => {
-            if let (Expr::UInt(option), Expr::UInt(setting)) =
-                (option.expression(), setting.expression())
-            {
+            if let Expr::UInt(option) = option.expression() {
                 debug_assert!(*option <= 255);
-                debug_assert!(*setting <= 255);
+                let setting = setting_value(expr, setting, state)?;
 
                 let bytes = if state.hpmode {
-                    vec![0x1B, 0x00, 0x00, b'O', *option as u8, *setting as u8]
+                    vec![0x1B, 0x00, 0x00, b'O', *option as u8, setting]
                 } else {
-                    vec![0x1B, 0x00, b'O', *option as u8, *setting as u8]
+                    vec![0x1B, 0x00, b'O', *option as u8, setting]
                 };
 
-                return Ok(FrontendRequest::PrinterTransact(Transaction::with_printer(
+                return Ok(FrontendRequest::PrinterTransact(Transaction::with_usb(
                     expr.clone(),
                     bytes,
                     None,
@@ -419,7 +713,7 @@ pub fn evaluate(expr: &ParsedExpr, state: &mut EvalState) -> Result<FrontendRequ
                     vec![0x1B, 0x00, b'S', *channel as u8]
                 };
 
-                return Ok(FrontendRequest::PrinterTransact(Transaction::with_printer(
+                return Ok(FrontendRequest::PrinterTransact(Transaction::with_usb(
                     expr.clone(),
                     bytes,
                     None,
@@ -429,52 +723,406 @@ pub fn evaluate(expr: &ParsedExpr, state: &mut EvalState) -> Result<FrontendRequ
             panic!("Invalid USBPRINTERSET arg {arg:?}")
         }
 
+        Expr::SetBaud(arg) => {
+            if let Expr::UInt(baud) = arg.expression() {
+                debug_assert!(execution::BAUD_RATES.contains(baud));
+                return Ok(FrontendRequest::Reconfigure { baud: *baud });
+            }
+
+            panic!("Invalid SETBAUD arg {arg:?}")
+        }
+
+        Expr::ProgramFirmware(arg) => {
+            if let Expr::String(url) = arg.expression() {
+                debug_assert!(parse::is_http_url(url));
+
+                return Ok(FrontendRequest::FetchAndProgram {
+                    url: url.to_owned(),
+                    device: Device::USB,
+                });
+            }
+
+            panic!("Invalid PROGRAMFIRMWARE arg {arg:?}")
+        }
+
         Expr::USBPrinterTest {
             channel,
-            min,
-            max,
+            expected,
             retries,
             message,
+            samples,
         } => {
             let args = (
                 channel.expression(),
-                min.expression(),
-                max.expression(),
+                expected_values(expected),
                 retries.expression(),
                 message.expression(),
+                sampling(samples),
             );
 
             if let (
                 Expr::UInt(channel),
-                Expr::UInt(min),
-                Expr::UInt(max),
+                Some(expected),
                 Expr::UInt(retries),
                 Expr::String(message),
+                Some((reduction, samples)),
             ) = args
             {
                 debug_assert!(*channel <= 255);
 
+                check_test(expr, &expected, message, state);
+                check_samples(expr, samples, state);
+
                 let bytes = if state.hpmode {
                     vec![0x1B, 0x00, 0x00, b'M', *channel as u8]
                 } else {
                     vec![0x1B, 0x00, b'M', *channel as u8]
                 };
 
-                return Ok(FrontendRequest::PrinterTransact(Transaction::with_printer(
+                return Ok(FrontendRequest::PrinterTransact(
+                    Transaction::with_usb(
+                        expr.clone(),
+                        bytes,
+                        Some(MeasurementTest {
+                            expected,
+                            retries: *retries,
+                            attempt: 0,
+                            failure_message: message.to_owned(),
+                        }),
+                    )
+                    .recording(state.record)
+                    .with_baselines(&state.baselines)
+                    .sampling(reduction, samples),
+                ));
+            }
+
+            panic!(
+                "Invalid USBPRINTERTEST args {channel:?}, {expected:?}, {retries:?}, {message:?}"
+            )
+        }
+
+        Expr::ReferenceTest {
+            channel,
+            reference,
+            tolerance,
+            retries,
+            message,
+        } => {
+            let args = (
+                channel.expression(),
+                reference.expression(),
+                tolerance.expression(),
+                retries.expression(),
+                message.expression(),
+            );
+
+            if let (
+                Expr::UInt(channel),
+                Expr::UInt(reference),
+                Expr::UInt(tolerance),
+                Expr::UInt(retries),
+                Expr::String(message),
+            ) = args
+            {
+                debug_assert!(*channel <= 255);
+                debug_assert!(*reference <= 255);
+
+                check_test(expr, &Expected::Range(0..=*tolerance), message, state);
+
+                // The device under test is measured in the same way as PRINTERTEST.
+                let dut = if state.hpmode {
+                    format!("W051B00004D{channel:02X}\r").into_bytes()
+                } else {
+                    format!("W051B004D{channel:02X}\r").into_bytes()
+                };
+
+                return Ok(FrontendRequest::CrossCheck(
+                    CrossCheck::new(
+                        expr.clone(),
+                        dut,
+                        format!("M{reference:02X}\r").into_bytes(),
+                        *tolerance,
+                        *retries,
+                        message.to_owned(),
+                    )
+                    .recording(state.record),
+                ));
+            }
+
+            panic!(
+                "Invalid REFTEST args {channel:?}, {reference:?}, {tolerance:?}, {retries:?}, {message:?}"
+            )
+        }
+
+        Expr::PrintImage(path) => {
+            if let Expr::String(path) = path.expression() {
+                let path = match &state.assets {
+                    Some(directory) => directory.join(path),
+                    None => path.into(),
+                };
+
+                let into_error = |error| Error::from_asset_error(expr.clone(), path.clone(), error);
+                let file = std::fs::File::open(&path).map_err(into_error)?;
+
+                let size = file.metadata().map_err(into_error)?.len();
+                if size > MAX_IMAGE_SIZE {
+                    return Err(Error::asset_too_large(
+                        expr.clone(),
+                        path,
+                        size,
+                        MAX_IMAGE_SIZE,
+                    ));
+                }
+
+                let mut bytes = Vec::new();
+                file.take(MAX_IMAGE_SIZE)
+                    .read_to_end(&mut bytes)
+                    .map_err(into_error)?;
+
+                return Ok(FrontendRequest::PrinterTransact(Transaction::with_usb(
                     expr.clone(),
                     bytes,
-                    Some(MeasurementTest {
-                        expected: *min..=*max,
-                        retries: *retries,
-                        failure_message: message.to_owned(),
-                    }),
+                    None,
                 )));
             }
 
+            panic!("Invalid PRINTIMAGE arg {path:?}")
+        }
+
+        Expr::TCUMeasure { name, channel } => {
+            if let (Expr::String(name), Expr::UInt(channel)) =
+                (name.expression(), channel.expression())
+            {
+                debug_assert!(*channel <= 255);
+
+                let test = MeasurementTest {
+                    expected: Expected::Range(0..=u32::MAX),
+                    retries: 0,
+                    attempt: 0,
+                    failure_message: String::new(),
+                };
+
+                return Ok(FrontendRequest::TCUTransact(
+                    Transaction::with_tcu(
+                        expr.clone(),
+                        format!("M{channel:02X}\r").into_bytes(),
+                        Some(test),
+                    )
+                    .storing(state.measurements.clone(), name),
+                ));
+            }
+
+            panic!("Invalid TCUMEASURE args {name:?}, {channel:?}")
+        }
+
+        Expr::RatioTest {
+            numerator,
+            denominator,
+            expected,
+            message,
+        } => {
+            let args = (
+                numerator.expression(),
+                denominator.expression(),
+                expected_values(expected),
+                message.expression(),
+            );
+
+            if let (
+                Expr::String(numerator),
+                Expr::String(denominator),
+                Some(expected),
+                Expr::String(message),
+            ) = args
+            {
+                check_test(expr, &expected, message, state);
+
+                let value = |name: &str| {
+                    state
+                        .measurements
+                        .get(name)
+                        .ok_or_else(|| Error::unknown_measurement(expr.clone(), name))
+                };
+                let (numerator_value, denominator_value) = (value(numerator)?, value(denominator)?);
+
+                if denominator_value == 0 {
+                    return Err(Error::zero_denominator(expr.clone(), denominator));
+                }
+
+                let ratio =
+                    i128::from(numerator_value) * RATIO_SCALE / i128::from(denominator_value);
+                let ratio =
+                    i64::try_from(ratio).unwrap_or(if ratio < 0 { i64::MIN } else { i64::MAX });
+
+                if !state.record && !expected.contains(ratio) {
+                    return Err(Error::from_failed_test(
+                        expr.clone(),
+                        FailedTest {
+                            measurement: ratio,
+                            expected,
+                            message: message.to_owned(),
+                            attempt: 1,
+                            attempts: 1,
+                        },
+                    ));
+                }
+
+                return Ok(FrontendRequest::Ratio {
+                    span: expr.span().clone(),
+                    ratio,
+                    recorded: state.record,
+                });
+            }
+
             panic!(
-                "Invalid USBPRINTERTEST args {channel:?}, {min:?}, {max:?}, {retries:?}, {message:?}"
+                "Invalid RATIOTEST args {numerator:?}, {denominator:?}, {expected:?}, {message:?}"
             )
         }
+
+        Expr::StartTimer(name) => {
+            if let Expr::String(name) = name.expression() {
+                state.timers.insert(name.to_owned(), state.clock.now());
+                return Ok(FrontendRequest::None);
+            }
+
+            panic!("Invalid STARTTIMER arg {name:?}")
+        }
+
+        Expr::StopTimer {
+            name,
+            expected,
+            message,
+        } => {
+            let args = (
+                name.expression(),
+                expected_values(expected),
+                message.expression(),
+            );
+
+            if let (Expr::String(name), Some(expected), Expr::String(message)) = args {
+                check_test(expr, &expected, message, state);
+
+                let Some(start) = state.timers.remove(name) else {
+                    return Err(Error::timer_not_started(expr.clone(), name));
+                };
+
+                // The clock may have gone backwards. e.g. When daylight saving ends.
+                let elapsed = (state.clock.now() - start).to_std().unwrap_or_default();
+                let measurement = u32::try_from(elapsed.as_millis()).unwrap_or(u32::MAX);
+
+                if !state.record && !expected.contains(measurement) {
+                    return Err(Error::from_failed_test(
+                        expr.clone(),
+                        FailedTest {
+                            measurement: measurement.into(),
+                            expected,
+                            message: message.to_owned(),
+                            attempt: 1,
+                            attempts: 1,
+                        },
+                    ));
+                }
+
+                return Ok(FrontendRequest::Timed {
+                    span: expr.span().clone(),
+                    name: name.to_owned(),
+                    elapsed,
+                    recorded: state.record,
+                });
+            }
+
+            panic!("Invalid STOPTIMER args {name:?}, {expected:?}, {message:?}")
+        }
+
+        Expr::StartLatency(name) => {
+            if let Expr::String(name) = name.expression() {
+                state.latencies.start(name);
+                return Ok(FrontendRequest::None);
+            }
+
+            panic!("Invalid STARTLATENCY arg {name:?}")
+        }
+
+        Expr::LatencyTest {
+            name,
+            percentile,
+            expected,
+            message,
+        } => {
+            let args = (
+                name.expression(),
+                percentile.expression(),
+                expected_values(expected),
+                message.expression(),
+            );
+
+            if let (
+                Expr::String(name),
+                Expr::UInt(percentile),
+                Some(expected),
+                Expr::String(message),
+            ) = args
+            {
+                check_test(expr, &expected, message, state);
+
+                let latencies = state.latencies.stop(name).unwrap_or_default();
+                let Some(latency) = execution::percentile(&latencies, *percentile) else {
+                    return Err(Error::latency_not_collected(expr.clone(), name));
+                };
+                let measurement = u32::try_from(latency.as_millis()).unwrap_or(u32::MAX);
+
+                if !state.record && !expected.contains(measurement) {
+                    return Err(Error::from_failed_test(
+                        expr.clone(),
+                        FailedTest {
+                            measurement: measurement.into(),
+                            expected,
+                            message: message.to_owned(),
+                            attempt: 1,
+                            attempts: 1,
+                        },
+                    ));
+                }
+
+                return Ok(FrontendRequest::Latency {
+                    span: expr.span().clone(),
+                    name: name.to_owned(),
+                    latency,
+                    samples: latencies.len(),
+                    recorded: state.record,
+                });
+            }
+
+            panic!("Invalid LATENCYTEST args {name:?}, {percentile:?}, {expected:?}, {message:?}")
+        }
+
+        Expr::NoResponse { drain, command } => {
+            if let Expr::UInt(drain) = drain.expression() {
+                let drain = Duration::from_millis((*drain).into());
+
+                return Ok(match evaluate(command, state)? {
+                    FrontendRequest::TCUTransact(transaction) => {
+                        FrontendRequest::TCUTransact(transaction.ignoring_response(drain))
+                    }
+                    FrontendRequest::PrinterTransact(transaction) => {
+                        FrontendRequest::PrinterTransact(transaction.ignoring_response(drain))
+                    }
+                    request => request,
+                });
+            }
+
+            panic!("Invalid NORESPONSE arg {drain:?}")
+        }
+
+        Expr::RetryBlock { .. } => unreachable!("RETRY blocks are executed by the interpreter"),
+        Expr::RepeatBlock { .. } => unreachable!("REPEAT blocks are executed by the interpreter"),
+        Expr::BoardBlock { .. } => unreachable!("BOARD blocks are executed by the interpreter"),
+        Expr::AbortBlock { .. } => unreachable!("ONABORT blocks are executed by the interpreter"),
+        Expr::If { .. } => unreachable!("IF blocks are executed by the interpreter"),
+        Expr::Break => unreachable!("BREAK is executed by the interpreter"),
+        Expr::Label(_) | Expr::Goto(_) => {
+            unreachable!("LABEL and GOTO are executed by the interpreter")
+        }
     }
 }
 
@@ -0,0 +1,133 @@
+use chrono::{NaiveTime, Timelike};
+use chumsky::prelude::*;
+
+use crate::syntax::error::Error;
+
+use super::{expression::Expr, kind::ExprKind, parse};
+
+////////////////////////////////////////////////////////////////
+// types
+////////////////////////////////////////////////////////////////
+
+/// Additional information attached to a statement by preceding it with an `@` prefixed annotation.
+///
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Annotation {
+    /// Only run the statement while the time of day is within the window. The window may wrap
+    /// around midnight.
+    Window { start: NaiveTime, end: NaiveTime },
+
+    /// Description of the step of the test the statement is part of, reported to the operator as
+    /// the statement begins. For a block, every statement within it is part of the step.
+    Step(String),
+
+    /// Traceability identifier linking the statement to a requirement. Should be unique within
+    /// the script.
+    Id(String),
+
+    /// The statement doesn't depend on the state left by any other, so may be run in any order
+    /// relative to consecutive independent statements. See [`Shuffle`].
+    ///
+    /// [`Shuffle`]: crate::Shuffle
+    ///
+    Independent,
+}
+
+////////////////////////////////////////////////////////////////
+// construction / conversion
+////////////////////////////////////////////////////////////////
+
+impl Annotation {
+    /// Return a parser for any annotation.
+    ///
+    pub fn parser() -> impl Parser<char, Annotation, Error = Error> + Clone {
+        let window = just("@window")
+            .then(parse::whitespace())
+            .ignore_then(time_of_day())
+            .then_ignore(just(',').padded_by(parse::whitespace()))
+            .then(time_of_day())
+            .map(|(start, end)| Annotation::Window { start, end });
+
+        let step = just("@step")
+            .then(parse::whitespace())
+            .ignore_then(ExprKind::String.parser())
+            .map(|arg| match arg.expression() {
+                Expr::String(description) => Annotation::Step(description.to_owned()),
+                _ => unreachable!("String parser returned {arg:?}"),
+            });
+
+        let id = just("@id")
+            .then(parse::whitespace())
+            .ignore_then(ExprKind::String.parser())
+            .map(|arg| match arg.expression() {
+                Expr::String(id) => Annotation::Id(id.to_owned()),
+                _ => unreachable!("String parser returned {arg:?}"),
+            });
+
+        let independent = just("@independent").to(Annotation::Independent);
+
+        choice((window, step, id, independent))
+            .padded_by(parse::whitespace())
+            .boxed()
+    }
+}
+
+////////////////////////////////////////////////////////////////
+// methods
+////////////////////////////////////////////////////////////////
+
+impl Annotation {
+    /// Return true if the window contains the given time.
+    ///
+    pub fn window_contains(start: NaiveTime, end: NaiveTime, time: NaiveTime) -> bool {
+        if start <= end {
+            start <= time && time < end
+        } else {
+            start <= time || time < end
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////
+// ...
+////////////////////////////////////////////////////////////////
+
+impl std::fmt::Display for Annotation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Annotation::Window { start, end } => write!(
+                f,
+                "@window \"{:02}:{:02}\", \"{:02}:{:02}\"",
+                start.hour(),
+                start.minute(),
+                end.hour(),
+                end.minute()
+            ),
+            Annotation::Step(description) => write!(f, "@step \"{description}\""),
+            Annotation::Id(id) => write!(f, "@id \"{id}\""),
+            Annotation::Independent => write!(f, "@independent"),
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////
+
+/// Parser for a string containing a time of day formatted as HH:MM.
+///
+fn time_of_day() -> impl Parser<char, NaiveTime, Error = Error> + Clone {
+    ExprKind::String.parser().try_map(|arg, span| {
+        if let Expr::String(string) = arg.expression() {
+            if let Ok(time) = NaiveTime::parse_from_str(string, "%H:%M") {
+                return Ok(time);
+            }
+        }
+
+        Err(Error::argument_format(
+            span,
+            "a time of day formatted as HH:MM",
+        ))
+    })
+}
+
+////////////////////////////////////////////////////////////////
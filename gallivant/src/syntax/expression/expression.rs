@@ -1,23 +1,125 @@
 use std::{borrow::Borrow, ops::Range};
 
-use super::kind::ExprKind;
+use crate::execution::{Comparison, SampleReduction};
+
+use super::{
+    annotation::Annotation,
+    kind::{ExprKind, Requirement},
+};
 
 ////////////////////////////////////////////////////////////////
 // types
 ////////////////////////////////////////////////////////////////
 
 #[derive(PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Expr {
     String(String),
     UInt(u32),
 
+    /// Range of values a measurement must be within. i.e. `<min>, <max>`.
+    Range {
+        min: Box<ParsedExpr>,
+        max: Box<ParsedExpr>,
+    },
+
+    /// Nominal value and the tolerance, in percent, a measurement must be within of it.
+    /// i.e. `<nominal> +- <percent>%`.
+    Tolerance {
+        nominal: Box<ParsedExpr>,
+        percent: Box<ParsedExpr>,
+    },
+
+    /// Value a measurement must compare to. e.g. `>= 3000`.
+    Comparison {
+        operator: Comparison,
+        value: Box<ParsedExpr>,
+    },
+
+    /// Set of values a measurement must be one of. i.e. `[<value>, ...]`.
+    Set(Vec<ParsedExpr>),
+
+    /// Number of measurements to take and the spread, i.e. the difference between the largest
+    /// and smallest, they must be within. i.e. `STABLE <samples>, <spread>`.
+    Stability {
+        samples: Box<ParsedExpr>,
+        spread: Box<ParsedExpr>,
+    },
+
+    /// Tolerance, in percent, that a measurement must be within of the measurement taken the
+    /// first time the command ran. i.e. `BASELINE <tolerance>`.
+    Baseline(Box<ParsedExpr>),
+
+    /// Masks paired with the value a measurement's bits under each must equal.
+    /// i.e. `BITS [<mask> = <value>, ...]`.
+    Bitfield(Vec<(ParsedExpr, ParsedExpr)>),
+
+    /// Measurement stored by an earlier TCUMEASURE, referred to by it's name. e.g. `trim`.
+    Variable(String),
+
+    /// Value defined by SET, referred to by it's name. e.g. `$channel`. Replaced by the value once
+    /// the script is parsed, at which point the value is checked against the requirements of the
+    /// argument it's used as.
+    Reference {
+        name: String,
+        requirements: Vec<Requirement>,
+    },
+
+    /// Text entered by the operator into an earlier INPUTDIALOG, referred to by the name it was
+    /// entered into. Replaced by the text when the command using it is run.
+    Input(String),
+
+    /// Arithmetic on unsigned integers and stored measurements, performed when the command using
+    /// it is run. e.g. `trim * 2 + 10`.
+    Arithmetic {
+        operator: Operator,
+        lhs: Box<ParsedExpr>,
+        rhs: Box<ParsedExpr>,
+    },
+
+    /// Text ignored when the script is run. Either the rest of a line following a `;` or a block
+    /// between `/*` and `*/`, which may span multiple lines.
     ScriptComment(String),
 
+    /// Give a value a name it can be referred to by in later arguments. i.e. `SET <name> <value>`.
+    Define {
+        name: String,
+        value: Box<ParsedExpr>,
+    },
+
+    /// Statements of another script, spliced in place of the INCLUDE once the script is parsed.
+    /// i.e. `INCLUDE "<path>"`.
+    Include(Box<ParsedExpr>),
+
     HPMode,
     Comment(Box<ParsedExpr>),
     Wait(Box<ParsedExpr>),
+
+    /// Pause for a number of milliseconds between device commands. e.g. Timing sensitive printer
+    /// setup.
+    Delay(Box<ParsedExpr>),
     OpenDialog(Box<ParsedExpr>),
-    WaitDialog(Box<ParsedExpr>),
+
+    /// Wait for the operator to acknowledge a message. If a timeout, in seconds, is given the
+    /// dialog is answered automatically once it expires. e.g. For unattended runs.
+    WaitDialog {
+        message: Box<ParsedExpr>,
+        timeout: Option<Box<ParsedExpr>>,
+    },
+
+    /// Ask the operator to enter text, which later arguments can refer to by the target's name.
+    /// i.e. `INPUTDIALOG <prompt>, $<target>`.
+    InputDialog {
+        prompt: Box<ParsedExpr>,
+        target: String,
+    },
+
+    /// A safety check the operator must confirm before anything else in the script is run.
+    Confirm(Box<ParsedExpr>),
+
+    /// Sound an alert to draw the operator's attention. e.g. To a failure or a dialog on a busy
+    /// bench.
+    Beep,
     Flush,
     Protocol,
     Print(Vec<ParsedExpr>),
@@ -34,18 +136,22 @@ pub enum Expr {
     TCUOpen(Box<ParsedExpr>),
     TCUTest {
         channel: Box<ParsedExpr>,
-        min: Box<ParsedExpr>,
-        max: Box<ParsedExpr>,
+        expected: Box<ParsedExpr>,
         retries: Box<ParsedExpr>,
         message: Box<ParsedExpr>,
+
+        /// How several measurements are reduced to the one tested and how many, if given.
+        samples: Option<(SampleReduction, Box<ParsedExpr>)>,
     },
     PrinterSet(Box<ParsedExpr>),
     PrinterTest {
         channel: Box<ParsedExpr>,
-        min: Box<ParsedExpr>,
-        max: Box<ParsedExpr>,
+        expected: Box<ParsedExpr>,
         retries: Box<ParsedExpr>,
         message: Box<ParsedExpr>,
+
+        /// How several measurements are reduced to the one tested and how many, if given.
+        samples: Option<(SampleReduction, Box<ParsedExpr>)>,
     },
     IssueTest(Box<ParsedExpr>), // Unused.
     TestResult {
@@ -57,6 +163,10 @@ pub enum Expr {
     USBOpen,
     USBClose,
     USBPrint(Vec<ParsedExpr>),
+
+    /// Raw bytes sent to the printer as written, in hex. e.g. `SENDHEX "1B 40 0D"`. For commands
+    /// not covered by any other.
+    SendHex(Box<ParsedExpr>),
     USBSetTimeFormat(Box<ParsedExpr>),
     USBSetTime,
     USBSetOption {
@@ -64,21 +174,154 @@ pub enum Expr {
         setting: Box<ParsedExpr>,
     },
     USBPrinterSet(Box<ParsedExpr>),
+
+    /// Send the raw contents of a file to the printer. Relative paths are relative to the script.
+    PrintImage(Box<ParsedExpr>),
+
+    /// Change the baud rate of the port the printer is connected to directly. e.g. After a
+    /// command switching the printer's baud rate.
+    SetBaud(Box<ParsedExpr>),
+
+    /// Program the printer with firmware downloaded from an HTTP or HTTPS URL. e.g. To test a
+    /// board with the firmware it ships with.
+    ProgramFirmware(Box<ParsedExpr>),
     USBPrinterTest {
         channel: Box<ParsedExpr>,
-        min: Box<ParsedExpr>,
-        max: Box<ParsedExpr>,
+        expected: Box<ParsedExpr>,
+        retries: Box<ParsedExpr>,
+        message: Box<ParsedExpr>,
+
+        /// How several measurements are reduced to the one tested and how many, if given.
+        samples: Option<(SampleReduction, Box<ParsedExpr>)>,
+    },
+
+    /// Test comparing a measurement of the device under test against the same measurement taken by
+    /// the reference device.
+    ReferenceTest {
+        channel: Box<ParsedExpr>,
+        reference: Box<ParsedExpr>,
+        tolerance: Box<ParsedExpr>,
         retries: Box<ParsedExpr>,
         message: Box<ParsedExpr>,
     },
+
+    /// Measure a TCU channel and store the measurement under the given name.
+    TCUMeasure {
+        name: Box<ParsedExpr>,
+        channel: Box<ParsedExpr>,
+    },
+
+    /// Test the ratio of two stored measurements. The ratio is in thousandths. e.g. `950, 1050`
+    /// allows the numerator to be within 5% of the denominator.
+    RatioTest {
+        numerator: Box<ParsedExpr>,
+        denominator: Box<ParsedExpr>,
+        expected: Box<ParsedExpr>,
+        message: Box<ParsedExpr>,
+    },
+
+    /// Start timing an operation under the given name.
+    StartTimer(Box<ParsedExpr>),
+
+    /// Stop a named timer and test the elapsed time in milliseconds.
+    StopTimer {
+        name: Box<ParsedExpr>,
+        expected: Box<ParsedExpr>,
+        message: Box<ParsedExpr>,
+    },
+
+    /// Start collecting the latency of every response under the given name.
+    StartLatency(Box<ParsedExpr>),
+
+    /// Stop collecting latencies under a name and test a percentile of them in milliseconds.
+    LatencyTest {
+        name: Box<ParsedExpr>,
+        percentile: Box<ParsedExpr>,
+        expected: Box<ParsedExpr>,
+        message: Box<ParsedExpr>,
+    },
+
+    /// Send a command without waiting for or validating any response. Any response received
+    /// within the drain period is discarded.
+    NoResponse {
+        drain: Box<ParsedExpr>,
+        command: Box<ParsedExpr>,
+    },
+
+    /// Block of commands that's re-run from the start if a test within it fails. Runs at most
+    /// `attempts` times before the failure is reported.
+    RetryBlock {
+        attempts: Box<ParsedExpr>,
+        body: Vec<ParsedExpr>,
+    },
+
+    /// Block of commands that's run `count` times in succession. e.g. Burn-in of a test.
+    RepeatBlock {
+        count: Box<ParsedExpr>,
+        body: Vec<ParsedExpr>,
+    },
+
+    /// Block of commands testing a single board of a panel. The outcome of every test within it
+    /// is tagged with the board's identifier. Boards can't be nested.
+    BoardBlock {
+        id: Box<ParsedExpr>,
+        body: Vec<ParsedExpr>,
+    },
+
+    /// Block of commands that's only run if the run is aborted. e.g. To make a fixture safe
+    /// after a failure. Skipped when reached in the script.
+    AbortBlock {
+        body: Vec<ParsedExpr>,
+    },
+
+    /// Block of commands that's only run if a condition holds when it's reached, with an optional
+    /// ELSE section run otherwise.
+    If {
+        condition: Box<ParsedExpr>,
+        then_body: Vec<ParsedExpr>,
+        else_body: Vec<ParsedExpr>,
+    },
+
+    /// Condition on the outcome of the last test run. i.e. `LASTRESULT == PASS` or
+    /// `LASTRESULT == FAIL`. Neither holds before any test has been run.
+    LastResult {
+        passed: bool,
+    },
+
+    /// Exit the innermost enclosing block, continuing with the statement after it.
+    Break,
+
+    /// Position a GOTO can continue from. i.e. `LABEL "<name>"`.
+    Label(String),
+
+    /// Continue from the statement following a LABEL, which must be in the same block or one
+    /// enclosing it. Any blocks within it are exited. i.e. `GOTO "<name>"`.
+    Goto(Box<ParsedExpr>),
+}
+
+////////////////////////////////////////////////////////////////
+
+/// Operators that can be used in arithmetic.
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Operator {
+    Add,
+    Subtract,
+    Multiply,
+
+    /// Integer division. The result is truncated.
+    Divide,
 }
 
 ////////////////////////////////////////////////////////////////
 
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ParsedExpr {
     expr: Expr,
     span: Range<usize>,
+    annotations: Vec<Annotation>,
 }
 
 ////////////////////////////////////////////////////////////////
@@ -87,7 +330,30 @@ pub struct ParsedExpr {
 
 impl ParsedExpr {
     pub fn from_kind_and_span(expr: Expr, span: Range<usize>) -> Self {
-        Self { expr, span }
+        Self {
+            expr,
+            span,
+            annotations: Vec::new(),
+        }
+    }
+
+    /// Attach annotations to the expression.
+    ///
+    #[must_use]
+    pub fn with_annotations(mut self, annotations: Vec<Annotation>) -> Self {
+        self.annotations = annotations;
+        self
+    }
+
+    /// Add a requirement the value of a reference must meet once resolved. Has no effect on any
+    /// other expression.
+    ///
+    #[must_use]
+    pub fn with_requirement(mut self, requirement: Requirement) -> Self {
+        if let Expr::Reference { requirements, .. } = &mut self.expr {
+            requirements.push(requirement);
+        }
+        self
     }
 
     /// Return a new Expr from the given ExprKind and with a default span. Primariliy intended for
@@ -97,6 +363,7 @@ impl ParsedExpr {
         Self {
             expr,
             span: Range::default(),
+            annotations: Vec::new(),
         }
     }
 
@@ -106,6 +373,7 @@ impl ParsedExpr {
         Self {
             expr: Expr::String(string.to_string()),
             span: Range::default(),
+            annotations: Vec::new(),
         }
     }
 
@@ -115,6 +383,7 @@ impl ParsedExpr {
         Self {
             expr: Expr::UInt(uint),
             span: Range::default(),
+            annotations: Vec::new(),
         }
     }
 }
@@ -126,12 +395,27 @@ impl<T: Borrow<Expr>> From<T> for ExprKind {
         match expr.borrow() {
             Expr::String(_) => ExprKind::String,
             Expr::UInt(_) => ExprKind::UInt,
+            Expr::Range { .. } => ExprKind::Range,
+            Expr::Tolerance { .. } => ExprKind::Tolerance,
+            Expr::Comparison { .. } => ExprKind::Comparison,
+            Expr::Set(..) => ExprKind::Set,
+            Expr::Stability { .. } => ExprKind::Stability,
+            Expr::Baseline(_) => ExprKind::Baseline,
+            Expr::Bitfield(_) => ExprKind::Bitfield,
+            Expr::Variable(_) => ExprKind::Variable,
+            Expr::Reference { .. } => ExprKind::Reference,
+            Expr::Input(_) => ExprKind::Input,
+            Expr::Arithmetic { .. } => ExprKind::Arithmetic,
             Expr::ScriptComment(_) => ExprKind::ScriptComment,
+            Expr::Define { .. } => ExprKind::Define,
+            Expr::Include(_) => ExprKind::Include,
             Expr::HPMode => ExprKind::HPMode,
             Expr::Comment(_) => ExprKind::Comment,
             Expr::Wait(_) => ExprKind::Wait,
+            Expr::Delay(_) => ExprKind::Delay,
             Expr::OpenDialog(_) => ExprKind::OpenDialog,
-            Expr::WaitDialog(_) => ExprKind::WaitDialog,
+            Expr::WaitDialog { .. } => ExprKind::WaitDialog,
+            Expr::InputDialog { .. } => ExprKind::InputDialog,
             Expr::Flush => ExprKind::Flush,
             Expr::Protocol => ExprKind::Protocol,
             Expr::Print(_) => ExprKind::Print,
@@ -148,11 +432,34 @@ impl<T: Borrow<Expr>> From<T> for ExprKind {
             Expr::USBOpen => ExprKind::USBOpen,
             Expr::USBClose => ExprKind::USBClose,
             Expr::USBPrint(_) => ExprKind::USBPrint,
+            Expr::SendHex(_) => ExprKind::SendHex,
             Expr::USBSetTimeFormat(_) => ExprKind::USBSetTimeFormat,
             Expr::USBSetTime => ExprKind::USBSetTime,
             Expr::USBSetOption { .. } => ExprKind::USBSetOption,
             Expr::USBPrinterSet(_) => ExprKind::USBPrinterSet,
             Expr::USBPrinterTest { .. } => ExprKind::USBPrinterTest,
+            Expr::ReferenceTest { .. } => ExprKind::ReferenceTest,
+            Expr::PrintImage(..) => ExprKind::PrintImage,
+            Expr::SetBaud(..) => ExprKind::SetBaud,
+            Expr::ProgramFirmware(..) => ExprKind::ProgramFirmware,
+            Expr::Confirm(..) => ExprKind::Confirm,
+            Expr::Beep => ExprKind::Beep,
+            Expr::TCUMeasure { .. } => ExprKind::TCUMeasure,
+            Expr::RatioTest { .. } => ExprKind::RatioTest,
+            Expr::StartTimer(..) => ExprKind::StartTimer,
+            Expr::StopTimer { .. } => ExprKind::StopTimer,
+            Expr::StartLatency(..) => ExprKind::StartLatency,
+            Expr::LatencyTest { .. } => ExprKind::LatencyTest,
+            Expr::NoResponse { .. } => ExprKind::NoResponse,
+            Expr::RetryBlock { .. } => ExprKind::RetryBlock,
+            Expr::RepeatBlock { .. } => ExprKind::RepeatBlock,
+            Expr::BoardBlock { .. } => ExprKind::BoardBlock,
+            Expr::AbortBlock { .. } => ExprKind::AbortBlock,
+            Expr::If { .. } => ExprKind::If,
+            Expr::LastResult { .. } => ExprKind::LastResult,
+            Expr::Break => ExprKind::Break,
+            Expr::Label(_) => ExprKind::Label,
+            Expr::Goto(_) => ExprKind::Goto,
         }
     }
 }
@@ -165,6 +472,7 @@ impl From<Expr> for ParsedExpr {
         ParsedExpr {
             expr,
             span: Range::default(),
+            annotations: Vec::new(),
         }
     }
 }
@@ -175,6 +483,7 @@ impl From<Expr> for Box<ParsedExpr> {
         Box::new(ParsedExpr {
             expr,
             span: Range::default(),
+            annotations: Vec::new(),
         })
     }
 }
@@ -188,6 +497,10 @@ impl ParsedExpr {
         &self.expr
     }
 
+    pub(crate) fn expression_mut(&mut self) -> &mut Expr {
+        &mut self.expr
+    }
+
     pub fn expression_kind(&self) -> ExprKind {
         ExprKind::from(&self.expr)
     }
@@ -195,6 +508,235 @@ impl ParsedExpr {
     pub fn span(&self) -> &Range<usize> {
         &self.span
     }
+
+    pub fn annotations(&self) -> &[Annotation] {
+        &self.annotations
+    }
+}
+
+////////////////////////////////////////////////////////////////
+// methods
+////////////////////////////////////////////////////////////////
+
+impl Expr {
+    /// Return every expression directly within the expression. i.e. It's arguments and, for a
+    /// block, it's statements, in script order.
+    ///
+    pub(crate) fn children_mut(&mut self) -> Vec<&mut ParsedExpr> {
+        match self {
+            Expr::String(_)
+            | Expr::UInt(_)
+            | Expr::Variable(_)
+            | Expr::Reference { .. }
+            | Expr::Input(_)
+            | Expr::ScriptComment(_)
+            | Expr::HPMode
+            | Expr::Flush
+            | Expr::Protocol
+            | Expr::Beep
+            | Expr::SetTime
+            | Expr::USBOpen
+            | Expr::USBClose
+            | Expr::USBSetTime
+            | Expr::LastResult { .. }
+            | Expr::Break
+            | Expr::Label(_) => Vec::new(),
+
+            Expr::Baseline(arg)
+            | Expr::Comment(arg)
+            | Expr::Wait(arg)
+            | Expr::Delay(arg)
+            | Expr::OpenDialog(arg)
+            | Expr::InputDialog { prompt: arg, .. }
+            | Expr::Confirm(arg)
+            | Expr::SetTimeFormat(arg)
+            | Expr::TCUClose(arg)
+            | Expr::TCUOpen(arg)
+            | Expr::PrinterSet(arg)
+            | Expr::IssueTest(arg)
+            | Expr::USBSetTimeFormat(arg)
+            | Expr::USBPrinterSet(arg)
+            | Expr::SendHex(arg)
+            | Expr::PrintImage(arg)
+            | Expr::SetBaud(arg)
+            | Expr::ProgramFirmware(arg)
+            | Expr::StartTimer(arg)
+            | Expr::StartLatency(arg)
+            | Expr::Define { value: arg, .. }
+            | Expr::Include(arg)
+            | Expr::Goto(arg) => vec![arg.as_mut()],
+
+            Expr::Range { min, max } => vec![min.as_mut(), max.as_mut()],
+            Expr::Tolerance { nominal, percent } => vec![nominal.as_mut(), percent.as_mut()],
+            Expr::Comparison { value, .. } => vec![value.as_mut()],
+            Expr::Stability { samples, spread } => vec![samples.as_mut(), spread.as_mut()],
+            Expr::Arithmetic { lhs, rhs, .. } => vec![lhs.as_mut(), rhs.as_mut()],
+            Expr::SetOption { option, setting } | Expr::USBSetOption { option, setting } => {
+                vec![option.as_mut(), setting.as_mut()]
+            }
+            Expr::TCUMeasure { name, channel } => vec![name.as_mut(), channel.as_mut()],
+            Expr::NoResponse { drain, command } => vec![drain.as_mut(), command.as_mut()],
+            Expr::WaitDialog { message, timeout } => std::iter::once(message.as_mut())
+                .chain(timeout.as_deref_mut())
+                .collect(),
+
+            Expr::TCUTest {
+                channel,
+                expected,
+                retries,
+                message,
+                samples,
+            }
+            | Expr::PrinterTest {
+                channel,
+                expected,
+                retries,
+                message,
+                samples,
+            }
+            | Expr::USBPrinterTest {
+                channel,
+                expected,
+                retries,
+                message,
+                samples,
+            } => [
+                channel.as_mut(),
+                expected.as_mut(),
+                retries.as_mut(),
+                message.as_mut(),
+            ]
+            .into_iter()
+            .chain(samples.as_mut().map(|(_, samples)| samples.as_mut()))
+            .collect(),
+            Expr::TestResult { min, max, message } => {
+                vec![min.as_mut(), max.as_mut(), message.as_mut()]
+            }
+            Expr::ReferenceTest {
+                channel,
+                reference,
+                tolerance,
+                retries,
+                message,
+            } => vec![
+                channel.as_mut(),
+                reference.as_mut(),
+                tolerance.as_mut(),
+                retries.as_mut(),
+                message.as_mut(),
+            ],
+            Expr::RatioTest {
+                numerator,
+                denominator,
+                expected,
+                message,
+            } => vec![
+                numerator.as_mut(),
+                denominator.as_mut(),
+                expected.as_mut(),
+                message.as_mut(),
+            ],
+            Expr::StopTimer {
+                name,
+                expected,
+                message,
+            } => vec![name.as_mut(), expected.as_mut(), message.as_mut()],
+            Expr::LatencyTest {
+                name,
+                percentile,
+                expected,
+                message,
+            } => vec![
+                name.as_mut(),
+                percentile.as_mut(),
+                expected.as_mut(),
+                message.as_mut(),
+            ],
+
+            Expr::Set(args) | Expr::Print(args) | Expr::USBPrint(args) => args.iter_mut().collect(),
+            Expr::Bitfield(fields) => fields
+                .iter_mut()
+                .flat_map(|(mask, value)| [mask, value])
+                .collect(),
+
+            Expr::RetryBlock {
+                attempts: arg,
+                body,
+            }
+            | Expr::RepeatBlock { count: arg, body }
+            | Expr::BoardBlock { id: arg, body } => std::iter::once(arg.as_mut())
+                .chain(body.iter_mut())
+                .collect(),
+            Expr::AbortBlock { body } => body.iter_mut().collect(),
+            Expr::If {
+                condition,
+                then_body,
+                else_body,
+            } => std::iter::once(condition.as_mut())
+                .chain(then_body.iter_mut())
+                .chain(else_body.iter_mut())
+                .collect(),
+        }
+    }
+
+    /// Return the statements of each section of a block. Empty if the expression isn't a block.
+    ///
+    pub(crate) fn bodies_mut(&mut self) -> Vec<&mut Vec<ParsedExpr>> {
+        match self {
+            Expr::RetryBlock { body, .. }
+            | Expr::RepeatBlock { body, .. }
+            | Expr::BoardBlock { body, .. }
+            | Expr::AbortBlock { body } => vec![body],
+            Expr::If {
+                then_body,
+                else_body,
+                ..
+            } => vec![then_body, else_body],
+            _ => Vec::new(),
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////
+
+impl ParsedExpr {
+    /// Move the expression, and every expression within it, to the given span. e.g. So statements
+    /// from an included script refer to the INCLUDE they came from.
+    ///
+    pub(crate) fn respan(&mut self, span: &Range<usize>) {
+        self.span = span.clone();
+        for child in self.expr.children_mut() {
+            child.respan(span);
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////
+
+impl Operator {
+    /// Apply the operator to two values.
+    ///
+    /// # Returns
+    /// None if the result can't be represented. i.e. It overflows, is negative or is a division by
+    /// zero.
+    ///
+    pub fn apply(&self, lhs: u32, rhs: u32) -> Option<u32> {
+        match self {
+            Operator::Add => lhs.checked_add(rhs),
+            Operator::Subtract => lhs.checked_sub(rhs),
+            Operator::Multiply => lhs.checked_mul(rhs),
+            Operator::Divide => lhs.checked_div(rhs),
+        }
+    }
+
+    /// Return how tightly the operator binds it's operands. Higher binds tighter.
+    ///
+    fn precedence(&self) -> u8 {
+        match self {
+            Operator::Add | Operator::Subtract => 0,
+            Operator::Multiply | Operator::Divide => 1,
+        }
+    }
 }
 
 ////////////////////////////////////////////////////////////////
@@ -209,3 +751,336 @@ impl std::cmp::PartialEq for ParsedExpr {
 }
 
 ////////////////////////////////////////////////////////////////
+// ...
+////////////////////////////////////////////////////////////////
+
+/// Renders the expression as script source. Parsing the rendered source gives an equal expression.
+///
+impl std::fmt::Display for Expr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.write(f, 0)
+    }
+}
+
+////////////////////////////////////////////////////////////////
+
+/// Renders the statement as script source, preceded by any annotations on lines of their own.
+///
+impl std::fmt::Display for ParsedExpr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.write(f, 0)
+    }
+}
+
+////////////////////////////////////////////////////////////////
+
+impl ParsedExpr {
+    /// Write the statement, indenting each line by `depth` levels.
+    ///
+    fn write(&self, f: &mut std::fmt::Formatter<'_>, depth: usize) -> std::fmt::Result {
+        for annotation in &self.annotations {
+            writeln!(f, "{}{annotation}", indent(depth))?;
+        }
+
+        write!(f, "{}", indent(depth))?;
+        self.expr.write(f, depth)
+    }
+}
+
+////////////////////////////////////////////////////////////////
+
+impl Expr {
+    /// Write the expression. The statements of a block are written on the following lines,
+    /// indented one level deeper than `depth`.
+    ///
+    fn write(&self, f: &mut std::fmt::Formatter<'_>, depth: usize) -> std::fmt::Result {
+        match self {
+            Expr::String(string) => write_quoted(f, string),
+            Expr::UInt(value) => write!(f, "{value}"),
+            Expr::Range { min, max } => write!(f, "{min}, {max}"),
+            Expr::Tolerance { nominal, percent } => write!(f, "{nominal} +- {percent}%"),
+            Expr::Comparison { operator, value } => write!(f, "{operator} {value}"),
+            Expr::Set(values) => write!(f, "[{}]", list(values)),
+            Expr::Stability { samples, spread } => write!(f, "STABLE {samples}, {spread}"),
+            Expr::Baseline(tolerance) => write!(f, "BASELINE {tolerance}"),
+            Expr::Bitfield(fields) => {
+                let fields: Vec<String> = fields
+                    .iter()
+                    .map(|(mask, value)| format!("{mask} = {value}"))
+                    .collect();
+                write!(f, "BITS [{}]", fields.join(", "))
+            }
+            Expr::Variable(name) => write!(f, "{name}"),
+            Expr::Reference { name, .. } | Expr::Input(name) => write!(f, "${name}"),
+            Expr::Arithmetic { operator, lhs, rhs } => {
+                // Operators are left associative so a right operand of equal precedence must also
+                // be grouped.
+                let grouped = |operand: &ParsedExpr, right: bool| match operand.expression() {
+                    Expr::Arithmetic {
+                        operator: inner, ..
+                    } => {
+                        inner.precedence() < operator.precedence()
+                            || (right && inner.precedence() == operator.precedence())
+                    }
+                    _ => false,
+                };
+
+                if grouped(lhs, false) {
+                    write!(f, "({lhs}) {operator} ")?;
+                } else {
+                    write!(f, "{lhs} {operator} ")?;
+                }
+
+                if grouped(rhs, true) {
+                    write!(f, "({rhs})")
+                } else {
+                    write!(f, "{rhs}")
+                }
+            }
+
+            // Only a block comment can span multiple lines.
+            Expr::ScriptComment(text) if text.contains('\n') => write!(f, "/*{text}*/"),
+            Expr::ScriptComment(text) => write!(f, ";{text}"),
+
+            Expr::Define { name, value } => write!(f, "SET {name} {value}"),
+            Expr::Include(path) => write!(f, "INCLUDE {path}"),
+            Expr::HPMode => write!(f, "HPMODE"),
+            Expr::Comment(message) => write!(f, "COMMENT {message}"),
+            Expr::Wait(time) => write!(f, "WAIT {time}"),
+            Expr::Delay(time) => write!(f, "DELAY {time}"),
+            Expr::OpenDialog(message) => write!(f, "OPENDIALOG {message}"),
+            Expr::WaitDialog { message, timeout } => match timeout {
+                Some(timeout) => write!(f, "WAITDIALOG {message}, {timeout}"),
+                None => write!(f, "WAITDIALOG {message}"),
+            },
+            Expr::InputDialog { prompt, target } => write!(f, "INPUTDIALOG {prompt}, ${target}"),
+            Expr::Confirm(message) => write!(f, "CONFIRM {message}"),
+            Expr::Beep => write!(f, "BEEP"),
+            Expr::Flush => write!(f, "FLUSH"),
+            Expr::Protocol => write!(f, "PROTOCOL"),
+            Expr::Print(args) if args.is_empty() => write!(f, "PRINT"),
+            Expr::Print(args) => write!(f, "PRINT {}", list(args)),
+            Expr::SetTimeFormat(format) => write!(f, "SETTIMEFORMAT {format}"),
+            Expr::SetTime => write!(f, "SETTIME"),
+            Expr::SetOption { option, setting } => write!(f, "SETOPTION {option}, {setting}"),
+            Expr::TCUClose(channel) => write!(f, "TCUCLOSE {channel}"),
+            Expr::TCUOpen(channel) => write!(f, "TCUOPEN {channel}"),
+            Expr::TCUTest {
+                channel,
+                expected,
+                retries,
+                message,
+                samples,
+            } => write!(
+                f,
+                "TCUTEST {channel}, {expected}, {retries}, {message}{}",
+                sampled(samples)
+            ),
+            Expr::PrinterSet(setting) => write!(f, "PRINTERSET {setting}"),
+            Expr::PrinterTest {
+                channel,
+                expected,
+                retries,
+                message,
+                samples,
+            } => write!(
+                f,
+                "PRINTERTEST {channel}, {expected}, {retries}, {message}{}",
+                sampled(samples)
+            ),
+            Expr::IssueTest(test) => write!(f, "ISSUETEST {test}"),
+            Expr::TestResult { min, max, message } => {
+                write!(f, "TESTRESULT {min}, {max}, {message}")
+            }
+            Expr::USBOpen => write!(f, "USBOPEN"),
+            Expr::USBClose => write!(f, "USBCLOSE"),
+            Expr::USBPrint(args) if args.is_empty() => write!(f, "USBPRINT"),
+            Expr::USBPrint(args) => write!(f, "USBPRINT {}", list(args)),
+            Expr::SendHex(bytes) => write!(f, "SENDHEX {bytes}"),
+            Expr::USBSetTimeFormat(format) => write!(f, "USBSETTIMEFORMAT {format}"),
+            Expr::USBSetTime => write!(f, "USBSETTIME"),
+            Expr::USBSetOption { option, setting } => {
+                write!(f, "USBSETOPTION {option}, {setting}")
+            }
+            Expr::USBPrinterSet(setting) => write!(f, "USBPRINTERSET {setting}"),
+            Expr::PrintImage(path) => write!(f, "PRINTIMAGE {path}"),
+            Expr::SetBaud(baud) => write!(f, "SETBAUD {baud}"),
+            Expr::ProgramFirmware(url) => write!(f, "PROGRAMFIRMWARE {url}"),
+            Expr::USBPrinterTest {
+                channel,
+                expected,
+                retries,
+                message,
+                samples,
+            } => write!(
+                f,
+                "USBPRINTERTEST {channel}, {expected}, {retries}, {message}{}",
+                sampled(samples)
+            ),
+            Expr::ReferenceTest {
+                channel,
+                reference,
+                tolerance,
+                retries,
+                message,
+            } => write!(
+                f,
+                "REFTEST {channel}, {reference}, {tolerance}, {retries}, {message}"
+            ),
+            Expr::TCUMeasure { name, channel } => write!(f, "TCUMEASURE {name}, {channel}"),
+            Expr::RatioTest {
+                numerator,
+                denominator,
+                expected,
+                message,
+            } => write!(
+                f,
+                "RATIOTEST {numerator}, {denominator}, {expected}, {message}"
+            ),
+            Expr::StartTimer(name) => write!(f, "STARTTIMER {name}"),
+            Expr::StopTimer {
+                name,
+                expected,
+                message,
+            } => write!(f, "STOPTIMER {name}, {expected}, {message}"),
+            Expr::StartLatency(name) => write!(f, "STARTLATENCY {name}"),
+            Expr::LatencyTest {
+                name,
+                percentile,
+                expected,
+                message,
+            } => write!(f, "LATENCYTEST {name}, {percentile}, {expected}, {message}"),
+            Expr::NoResponse { drain, command } => {
+                write!(f, "NORESPONSE {drain} ")?;
+                command.expr.write(f, depth)
+            }
+
+            Expr::RetryBlock { attempts, body } => {
+                write!(f, "RETRY {attempts}")?;
+                write_block(f, body, "ENDRETRY", depth)
+            }
+            Expr::RepeatBlock { count, body } => {
+                write!(f, "REPEAT {count}")?;
+                write_block(f, body, "ENDREPEAT", depth)
+            }
+            Expr::BoardBlock { id, body } => {
+                write!(f, "BOARD {id}")?;
+                write_block(f, body, "ENDBOARD", depth)
+            }
+            Expr::AbortBlock { body } => {
+                write!(f, "ONABORT")?;
+                write_block(f, body, "ENDONABORT", depth)
+            }
+            Expr::If {
+                condition,
+                then_body,
+                else_body,
+            } if else_body.is_empty() => {
+                write!(f, "IF {condition}")?;
+                write_block(f, then_body, "ENDIF", depth)
+            }
+            Expr::If {
+                condition,
+                then_body,
+                else_body,
+            } => {
+                write!(f, "IF {condition}")?;
+                write_block(f, then_body, "ELSE", depth)?;
+                write_block(f, else_body, "ENDIF", depth)
+            }
+            Expr::LastResult { passed: true } => write!(f, "LASTRESULT == PASS"),
+            Expr::LastResult { passed: false } => write!(f, "LASTRESULT == FAIL"),
+            Expr::Break => write!(f, "BREAK"),
+            Expr::Label(name) => {
+                write!(f, "LABEL ")?;
+                write_quoted(f, name)
+            }
+            Expr::Goto(label) => write!(f, "GOTO {label}"),
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////
+
+impl std::fmt::Display for Operator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Operator::Add => write!(f, "+"),
+            Operator::Subtract => write!(f, "-"),
+            Operator::Multiply => write!(f, "*"),
+            Operator::Divide => write!(f, "/"),
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////
+// helpers
+////////////////////////////////////////////////////////////////
+
+/// Return the whitespace indenting a line `depth` blocks deep.
+///
+fn indent(depth: usize) -> String {
+    " ".repeat(depth * 4)
+}
+
+////////////////////////////////////////////////////////////////
+
+/// Return the arguments as a comma seperated list.
+///
+fn list(args: &[ParsedExpr]) -> String {
+    args.iter()
+        .map(ToString::to_string)
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+////////////////////////////////////////////////////////////////
+
+/// Return a test's trailing argument giving how several measurements are reduced to the one
+/// tested, if any.
+///
+fn sampled(samples: &Option<(SampleReduction, Box<ParsedExpr>)>) -> String {
+    match samples {
+        Some((reduction, samples)) => format!(", {reduction} {samples}"),
+        None => String::new(),
+    }
+}
+
+////////////////////////////////////////////////////////////////
+
+/// Write a string delimited by `"`, escaping any characters that would otherwise end it or can't
+/// be typed in it.
+///
+fn write_quoted(f: &mut std::fmt::Formatter<'_>, string: &str) -> std::fmt::Result {
+    write!(f, "\"")?;
+    for c in string.chars() {
+        match c {
+            '\r' => write!(f, "\\r")?,
+            '\n' => write!(f, "\\n")?,
+            '\t' => write!(f, "\\t")?,
+            '\\' | '"' => write!(f, "\\{c}")?,
+            _ => write!(f, "{c}")?,
+        }
+    }
+    write!(f, "\"")
+}
+
+////////////////////////////////////////////////////////////////
+
+/// Write the statements of a block on the lines following it's opening line, then the line ending
+/// it.
+///
+fn write_block(
+    f: &mut std::fmt::Formatter<'_>,
+    body: &[ParsedExpr],
+    end: &str,
+    depth: usize,
+) -> std::fmt::Result {
+    for statement in body {
+        writeln!(f)?;
+        statement.write(f, depth + 1)?;
+    }
+    write!(f, "\n{}{end}", indent(depth))
+}
+
+////////////////////////////////////////////////////////////////
@@ -1,9 +1,14 @@
+use std::ops::Range;
+
 use chumsky::{prelude::*, text::newline};
 
-use crate::syntax::error::{Error, ErrorNote};
+use crate::{
+    execution::{Comparison, SampleReduction, BAUD_RATES},
+    syntax::error::{Error, ErrorNote},
+};
 
 use super::{
-    expression::{Expr, ParsedExpr},
+    expression::{Expr, Operator, ParsedExpr},
     parse,
 };
 
@@ -12,17 +17,35 @@ use super::{
 ////////////////////////////////////////////////////////////////
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ExprKind {
     String,
     UInt,
+    Range,
+    Tolerance,
+    Comparison,
+    Set,
+    Stability,
+    Baseline,
+    Bitfield,
+    Variable,
+    Reference,
+    Input,
+    Arithmetic,
 
     ScriptComment,
 
+    Define,
+    Include,
     HPMode,
     Comment,
     Wait,
+    Delay,
     OpenDialog,
     WaitDialog,
+    InputDialog,
+    Confirm,
+    Beep,
     Flush,
     Protocol,
     Print,
@@ -39,11 +62,60 @@ pub enum ExprKind {
     USBOpen,
     USBClose,
     USBPrint,
+    SendHex,
     USBSetTimeFormat,
     USBSetTime,
     USBSetOption,
     USBPrinterSet,
     USBPrinterTest,
+    PrintImage,
+    SetBaud,
+    ProgramFirmware,
+    ReferenceTest,
+    TCUMeasure,
+    RatioTest,
+    StartTimer,
+    StopTimer,
+    StartLatency,
+    LatencyTest,
+
+    NoResponse,
+    RetryBlock,
+    RepeatBlock,
+    BoardBlock,
+    AbortBlock,
+    If,
+    LastResult,
+    Break,
+    Label,
+    Goto,
+}
+
+////////////////////////////////////////////////////////////////
+
+/// Requirements an argument must meet. Checked as it's parsed or, for a reference to a value
+/// defined by SET, once the reference is resolved.
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Requirement {
+    String,
+    UInt,
+
+    /// An unsigned integer no greater than 255.
+    Byte,
+
+    /// An unsigned integer between 1 and 100.
+    Percentile,
+
+    /// A string of hex byte pairs. e.g. `"1B 40 0D"`.
+    HexBytes,
+
+    /// An unsigned integer that's one of [`BAUD_RATES`].
+    Baud,
+
+    /// A string that's an HTTP or HTTPS URL. e.g. `"https://example.com/firmware.bin"`.
+    HttpUrl,
 }
 
 ////////////////////////////////////////////////////////////////
@@ -53,14 +125,31 @@ impl ExprKind {
         match self {
             ExprKind::String => "String",
             ExprKind::UInt => "Unsigned Integer",
+            ExprKind::Range => "Range",
+            ExprKind::Tolerance => "Tolerance",
+            ExprKind::Comparison => "Comparison",
+            ExprKind::Set => "Set",
+            ExprKind::Stability => "Stability",
+            ExprKind::Baseline => "Baseline",
+            ExprKind::Bitfield => "Bitfield",
+            ExprKind::Variable => "Variable",
+            ExprKind::Reference => "Reference",
+            ExprKind::Input => "Operator Input",
+            ExprKind::Arithmetic => "Arithmetic",
 
             ExprKind::ScriptComment => "Script Comment",
 
+            ExprKind::Define => "Command: 'SET'",
+            ExprKind::Include => "Command: 'INCLUDE'",
             ExprKind::HPMode => "Command: 'HPMODE'",
             ExprKind::Comment => "Command: 'COMMENT'",
             ExprKind::Wait => "Command: 'WAIT'",
+            ExprKind::Delay => "Command: 'DELAY'",
             ExprKind::OpenDialog => "Command: 'OPENDIALOG'",
             ExprKind::WaitDialog => "Command: 'WAITDIALOG'",
+            ExprKind::InputDialog => "Command: 'INPUTDIALOG'",
+            ExprKind::Confirm => "Command: 'CONFIRM'",
+            ExprKind::Beep => "Command: 'BEEP'",
             ExprKind::Flush => "Command: 'FLUSH'",
             ExprKind::Protocol => "Command: 'PROTOCOL'",
             ExprKind::Print => "Command: 'PRINT'",
@@ -77,11 +166,33 @@ impl ExprKind {
             ExprKind::USBOpen => "Command: 'USBOPEN'",
             ExprKind::USBClose => "Command: 'USBCLOSE'",
             ExprKind::USBPrint => "Command: 'USBPRINT'",
+            ExprKind::SendHex => "Command: 'SENDHEX'",
             ExprKind::USBSetTimeFormat => "Command: 'USBSETTIMEFORMAT'",
             ExprKind::USBSetTime => "Command: 'USBSETTIME'",
             ExprKind::USBSetOption => "Command: 'USBSETOPTION'",
             ExprKind::USBPrinterSet => "Command: 'USBPRINTERSET'",
             ExprKind::USBPrinterTest => "Command: 'USBPRINTERTEST'",
+            ExprKind::PrintImage => "Command: 'PRINTIMAGE'",
+            ExprKind::SetBaud => "Command: 'SETBAUD'",
+            ExprKind::ProgramFirmware => "Command: 'PROGRAMFIRMWARE'",
+            ExprKind::ReferenceTest => "Command: 'REFTEST'",
+            ExprKind::TCUMeasure => "Command: 'TCUMEASURE'",
+            ExprKind::RatioTest => "Command: 'RATIOTEST'",
+            ExprKind::StartTimer => "Command: 'STARTTIMER'",
+            ExprKind::StopTimer => "Command: 'STOPTIMER'",
+            ExprKind::StartLatency => "Command: 'STARTLATENCY'",
+            ExprKind::LatencyTest => "Command: 'LATENCYTEST'",
+
+            ExprKind::NoResponse => "Command: 'NORESPONSE'",
+            ExprKind::RetryBlock => "Command: 'RETRY'",
+            ExprKind::RepeatBlock => "Command: 'REPEAT'",
+            ExprKind::BoardBlock => "Command: 'BOARD'",
+            ExprKind::AbortBlock => "Command: 'ONABORT'",
+            ExprKind::If => "Command: 'IF'",
+            ExprKind::LastResult => "Condition",
+            ExprKind::Break => "Command: 'BREAK'",
+            ExprKind::Label => "Command: 'LABEL'",
+            ExprKind::Goto => "Command: 'GOTO'",
         }
     }
 
@@ -90,33 +201,190 @@ impl ExprKind {
     pub fn parser(&self) -> impl Parser<char, ParsedExpr, Error = Error> + Clone {
         match self {
             ////////////////////////////////////////////////////////////////
-            ExprKind::String => filter(|c| *c != '"')
-                .repeated()
-                .delimited_by(just('"'), just('"'))
-                .map(String::from_iter)
-                .map(Expr::String)
-                .boxed(),
+            ExprKind::String => {
+                // Allows characters that would otherwise end the string or can't be typed in it.
+                let escape = just('\\')
+                    .ignore_then(any())
+                    .validate(|c, span, emit| match c {
+                        'r' => '\r',
+                        'n' => '\n',
+                        't' => '\t',
+                        '\\' | '"' => c,
+                        _ => {
+                            emit(Error::argument_format(
+                                span,
+                                r#"an escape sequence. i.e. \r, \n, \t, \\ or \""#,
+                            ));
+                            c
+                        }
+                    });
+
+                filter(|c| *c != '"' && *c != '\\')
+                    .or(escape)
+                    .repeated()
+                    .delimited_by(just('"'), just('"'))
+                    .map(String::from_iter)
+                    .map(Expr::String)
+                    .boxed()
+            }
 
             ////////////////////////////////////////////////////////////////
             ExprKind::UInt => {
-                let uint_dec = parse::uint(10).map(|s: String| Expr::UInt(s.parse().unwrap()));
-                let uint_hex = just("$")
+                // Reported without backtracking so an overflowing number isn't partially parsed.
+                let value = |radix| {
+                    move |digits: String, span, emit: &mut dyn FnMut(Error)| {
+                        let value = u32::from_str_radix(&digits, radix).unwrap_or_else(|_| {
+                            emit(Error::argument_format(span, "a 32 bit unsigned integer"));
+                            u32::MAX
+                        });
+                        Expr::UInt(value)
+                    }
+                };
+
+                let uint_dec = parse::uint(10).validate(value(10));
+                let uint_hex = just("$").ignore_then(parse::uint(16)).validate(value(16));
+                let uint_hex_prefixed = choice((just("0x"), just("0X")))
                     .ignore_then(parse::uint(16))
-                    .map(|s: String| Expr::UInt(u32::from_str_radix(&s, 16).unwrap()));
+                    .validate(value(16));
 
-                choice((uint_dec, uint_hex)).boxed()
+                choice((uint_hex_prefixed, uint_dec, uint_hex)).boxed()
             }
 
             ////////////////////////////////////////////////////////////////
-            ExprKind::ScriptComment => just(';')
-                .ignore_then(take_until(choice((newline(), end())).rewind()))
-                .map(|(s, _)| String::from_iter(s))
-                .map(Expr::ScriptComment)
-                .padded_by(parse::whitespace())
+            ExprKind::Range => validate_uint(argument())
+                .then_ignore(just(',').padded_by(parse::whitespace()))
+                .then(validate_uint(argument()))
+                .map(|(min, max)| Expr::Range {
+                    min: Box::new(min),
+                    max: Box::new(max),
+                })
+                .boxed(),
+
+            ExprKind::Tolerance => validate_uint(argument())
+                .then_ignore(just("+-"))
+                .then(validate_uint(argument()))
+                .then_ignore(just('%'))
+                .map(|(nominal, percent)| Expr::Tolerance {
+                    nominal: Box::new(nominal),
+                    percent: Box::new(percent),
+                })
+                .boxed(),
+
+            ExprKind::Comparison => choice((
+                just(">=").to(Comparison::GreaterEqual),
+                just("<=").to(Comparison::LessEqual),
+                just(">").to(Comparison::Greater),
+                just("<").to(Comparison::Less),
+            ))
+            .padded_by(parse::whitespace())
+            .then(validate_uint(argument()))
+            .map(|(operator, value)| Expr::Comparison {
+                operator,
+                value: Box::new(value),
+            })
+            .boxed(),
+
+            ExprKind::Set => validate_uint(argument())
+                .separated_by(just(','))
+                .at_least(1)
+                .delimited_by(
+                    just('[').padded_by(parse::whitespace()),
+                    just(']').padded_by(parse::whitespace()),
+                )
+                .map(Expr::Set)
+                .boxed(),
+
+            ExprKind::Stability => parse::keyword("STABLE")
+                .ignore_then(validate_uint(argument()))
+                .then_ignore(just(',').padded_by(parse::whitespace()))
+                .then(validate_uint(argument()))
+                .map(|(samples, spread)| Expr::Stability {
+                    samples: Box::new(samples),
+                    spread: Box::new(spread),
+                })
+                .boxed(),
+
+            ExprKind::Baseline => parse::keyword("BASELINE")
+                .ignore_then(validate_uint(argument()))
+                .map(|tolerance| Expr::Baseline(Box::new(tolerance)))
+                .boxed(),
+
+            ExprKind::Bitfield => parse::keyword("BITS")
+                .ignore_then(
+                    validate_uint(argument())
+                        .then_ignore(just('=').padded_by(parse::whitespace()))
+                        .then(validate_uint(argument()))
+                        .separated_by(just(','))
+                        .at_least(1)
+                        .delimited_by(
+                            just('[').padded_by(parse::whitespace()),
+                            just(']').padded_by(parse::whitespace()),
+                        ),
+                )
+                .map(Expr::Bitfield)
                 .boxed(),
 
+            ExprKind::Variable => text::ident().map(Expr::Variable).boxed(),
+
+            // Names made of only hex digits are left to be parsed as hex unsigned integers.
+            ExprKind::Reference => just('$')
+                .ignore_then(text::ident())
+                .try_map(|name: String, span| {
+                    if is_hex(&name) {
+                        return Err(Error::argument_format(span, "a reference"));
+                    }
+
+                    Ok(Expr::Reference {
+                        name,
+                        requirements: Vec::new(),
+                    })
+                })
+                .boxed(),
+
+            // Inputs are only created by resolving references to an INPUTDIALOG's target.
+            ExprKind::Input => unreachable!("Inputs aren't parsed"),
+
+            // Arithmetic is parsed by setting() as each operand needs it's own span.
+            ExprKind::Arithmetic => unreachable!("Arithmetic is parsed by setting()"),
+
+            ////////////////////////////////////////////////////////////////
+            ExprKind::ScriptComment => choice((
+                just(';').ignore_then(take_until(choice((newline(), end())).rewind())),
+                // A block comment may span multiple lines. It's text is kept verbatim.
+                just("/*").ignore_then(take_until(just("*/").ignored())),
+            ))
+            .map(|(s, _)| String::from_iter(s))
+            .map(Expr::ScriptComment)
+            .padded_by(parse::whitespace())
+            .boxed(),
+
             ////////////////////////////////////////////////////////////////
-            ExprKind::HPMode => text::keyword("HPMODE").to(Expr::HPMode).boxed(),
+            ExprKind::Define => parse::keyword("SET")
+                .then(parse::whitespace())
+                .ignore_then(text::ident().validate(|name: String, span, emit| {
+                    if is_hex(&name) {
+                        emit(
+                            Error::argument_format(span, "a name that isn't a hex number")
+                                .with_note(ErrorNote::Note(
+                                "References to the name would be read as a hex unsigned integer",
+                            )),
+                        );
+                    }
+
+                    name
+                }))
+                .then(argument())
+                .map(|(name, value)| Expr::Define {
+                    name,
+                    value: Box::new(value),
+                })
+                .boxed(),
+
+            ExprKind::Include => parse::command("INCLUDE", [validate_string(argument())])
+                .map(|[arg]| Expr::Include(arg))
+                .boxed(),
+
+            ExprKind::HPMode => parse::keyword("HPMODE").to(Expr::HPMode).boxed(),
 
             ExprKind::Comment => parse::command("COMMENT", [validate_string(argument())])
                 .map(|[arg]| Expr::Comment(arg))
@@ -126,17 +394,76 @@ impl ExprKind {
                 .map(|[arg]| Expr::Wait(arg))
                 .boxed(),
 
+            ExprKind::Delay => parse::command("DELAY", [validate_uint(argument())])
+                .map(|[arg]| Expr::Delay(arg))
+                .boxed(),
+
             ExprKind::OpenDialog => parse::command("OPENDIALOG", [validate_string(argument())])
                 .map(|[arg]| Expr::OpenDialog(arg))
                 .boxed(),
 
-            ExprKind::WaitDialog => parse::command("WAITDIALOG", [validate_string(argument())])
-                .map(|[arg]| Expr::WaitDialog(arg))
+            ExprKind::WaitDialog => choice((
+                parse::command(
+                    "WAITDIALOG",
+                    [validate_string(argument()), validate_uint(argument())],
+                )
+                .map(|[message, timeout]| Expr::WaitDialog {
+                    message,
+                    timeout: Some(timeout),
+                }),
+                parse::command("WAITDIALOG", [validate_string(argument())]).map(|[message]| {
+                    Expr::WaitDialog {
+                        message,
+                        timeout: None,
+                    }
+                }),
+            ))
+            .boxed(),
+
+            ExprKind::InputDialog => parse::keyword("INPUTDIALOG")
+                .then(parse::whitespace())
+                .ignore_then(validate_string(argument()))
+                .then_ignore(just(',').padded_by(parse::whitespace()))
+                .then(ExprKind::Reference.parser())
+                .map(|(prompt, target)| match target.expression() {
+                    Expr::Reference { name, .. } => Expr::InputDialog {
+                        prompt: Box::new(prompt),
+                        target: name.to_owned(),
+                    },
+                    _ => unreachable!("Expected a reference. Got: {target:?}"),
+                })
+                .boxed(),
+
+            ExprKind::Confirm => parse::command("CONFIRM", [validate_string(argument())])
+                .map(|[arg]| Expr::Confirm(arg))
+                .boxed(),
+
+            ExprKind::Beep => parse::keyword("BEEP").to(Expr::Beep).boxed(),
+
+            ExprKind::Flush => parse::keyword("FLUSH").to(Expr::Flush).boxed(),
+            ExprKind::Break => parse::keyword("BREAK").to(Expr::Break).boxed(),
+
+            ExprKind::Label => parse::command("LABEL", [ExprKind::String.parser()])
+                .map(|[name]| match name.expression() {
+                    Expr::String(name) => Expr::Label(name.to_owned()),
+                    _ => unreachable!("LABEL arg is parsed as a string"),
+                })
                 .boxed(),
 
-            ExprKind::Flush => text::keyword("FLUSH").to(Expr::Flush).boxed(),
+            ExprKind::Goto => parse::command("GOTO", [validate_string(argument())])
+                .map(|[label]| Expr::Goto(label))
+                .boxed(),
+
+            ExprKind::LastResult => parse::keyword("LASTRESULT")
+                .ignore_then(just("==").padded_by(parse::whitespace()))
+                .ignore_then(choice((
+                    parse::keyword("PASS").to(true),
+                    parse::keyword("FAIL").to(false),
+                )))
+                .map(|passed| Expr::LastResult { passed })
+                .boxed(),
 
-            ExprKind::Protocol => text::keyword("PROTOCOL").to(Expr::Protocol).boxed(),
+            ExprKind::Protocol => parse::keyword("PROTOCOL").to(Expr::Protocol).boxed(),
 
             ExprKind::Print => parse::command_variadic("PRINT", argument())
                 .map(Expr::Print)
@@ -146,11 +473,11 @@ impl ExprKind {
                 .map(|[arg]| Expr::SetTimeFormat(arg))
                 .boxed(),
 
-            ExprKind::SetTime => text::keyword("SETTIME").to(Expr::SetTime).boxed(),
+            ExprKind::SetTime => parse::keyword("SETTIME").to(Expr::SetTime).boxed(),
 
             ExprKind::SetOption => parse::command(
                 "SETOPTION",
-                [validate_byte(argument()), validate_byte(argument())],
+                [validate_byte(argument()), validate_byte(setting())],
             )
             .map(|[option, setting]| Expr::SetOption { option, setting })
             .boxed(),
@@ -167,19 +494,21 @@ impl ExprKind {
                 "TCUTEST",
                 [
                     validate_byte(argument()),
-                    validate_uint(argument()),
-                    validate_uint(argument()),
+                    measured(),
                     validate_uint(argument()),
                     validate_string(argument()),
                 ],
             )
-            .map(|[channel, min, max, retries, message]| Expr::TCUTest {
-                channel,
-                min,
-                max,
-                retries,
-                message,
-            })
+            .then(samples())
+            .map(
+                |([channel, expected, retries, message], samples)| Expr::TCUTest {
+                    channel,
+                    expected,
+                    retries,
+                    message,
+                    samples,
+                },
+            )
             .boxed(),
 
             ExprKind::PrinterSet => parse::command("PRINTERSET", [validate_byte(argument())])
@@ -190,42 +519,48 @@ impl ExprKind {
                 "PRINTERTEST",
                 [
                     validate_byte(argument()),
-                    validate_uint(argument()),
-                    validate_uint(argument()),
+                    measured(),
                     validate_uint(argument()),
                     validate_string(argument()),
                 ],
             )
-            .map(|[channel, min, max, retries, message]| Expr::PrinterTest {
-                channel,
-                min,
-                max,
-                retries,
-                message,
-            })
+            .then(samples())
+            .map(
+                |([channel, expected, retries, message], samples)| Expr::PrinterTest {
+                    channel,
+                    expected,
+                    retries,
+                    message,
+                    samples,
+                },
+            )
             .boxed(),
 
             ExprKind::IssueTest => todo!(),
             ExprKind::TestResult => todo!(),
 
-            ExprKind::USBOpen => text::keyword("USBOPEN").to(Expr::USBOpen).boxed(),
-            ExprKind::USBClose => text::keyword("USBCLOSE").to(Expr::USBClose).boxed(),
+            ExprKind::USBOpen => parse::keyword("USBOPEN").to(Expr::USBOpen).boxed(),
+            ExprKind::USBClose => parse::keyword("USBCLOSE").to(Expr::USBClose).boxed(),
 
             ExprKind::USBPrint => parse::command_variadic("USBPRINT", argument())
                 .map(Expr::USBPrint)
                 .boxed(),
 
+            ExprKind::SendHex => parse::command("SENDHEX", [validate_hex_bytes(argument())])
+                .map(|[arg]| Expr::SendHex(arg))
+                .boxed(),
+
             ExprKind::USBSetTimeFormat => {
                 parse::command("USBSETTIMEFORMAT", [validate_byte(argument())])
                     .map(|[arg]| Expr::USBSetTimeFormat(arg))
                     .boxed()
             }
 
-            ExprKind::USBSetTime => text::keyword("USBSETTIME").to(Expr::USBSetTime).boxed(),
+            ExprKind::USBSetTime => parse::keyword("USBSETTIME").to(Expr::USBSetTime).boxed(),
 
             ExprKind::USBSetOption => parse::command(
                 "USBSETOPTION",
-                [validate_byte(argument()), validate_byte(argument())],
+                [validate_byte(argument()), validate_byte(setting())],
             )
             .map(|[option, setting]| Expr::USBSetOption { option, setting })
             .boxed(),
@@ -238,22 +573,132 @@ impl ExprKind {
                 "USBPRINTERTEST",
                 [
                     validate_byte(argument()),
+                    measured(),
                     validate_uint(argument()),
+                    validate_string(argument()),
+                ],
+            )
+            .then(samples())
+            .map(
+                |([channel, expected, retries, message], samples)| Expr::USBPrinterTest {
+                    channel,
+                    expected,
+                    retries,
+                    message,
+                    samples,
+                },
+            )
+            .boxed(),
+
+            ExprKind::ReferenceTest => parse::command(
+                "REFTEST",
+                [
+                    validate_byte(argument()),
+                    validate_byte(argument()),
                     validate_uint(argument()),
                     validate_uint(argument()),
                     validate_string(argument()),
                 ],
             )
             .map(
-                |[channel, min, max, retries, message]| Expr::USBPrinterTest {
+                |[channel, reference, tolerance, retries, message]| Expr::ReferenceTest {
                     channel,
-                    min,
-                    max,
+                    reference,
+                    tolerance,
                     retries,
                     message,
                 },
             )
             .boxed(),
+
+            ExprKind::PrintImage => parse::command("PRINTIMAGE", [validate_string(argument())])
+                .map(|[path]| Expr::PrintImage(path))
+                .boxed(),
+
+            ExprKind::SetBaud => parse::command("SETBAUD", [validate_baud(argument())])
+                .map(|[baud]| Expr::SetBaud(baud))
+                .boxed(),
+
+            ExprKind::ProgramFirmware => {
+                parse::command("PROGRAMFIRMWARE", [validate_http_url(argument())])
+                    .map(|[url]| Expr::ProgramFirmware(url))
+                    .boxed()
+            }
+
+            ExprKind::TCUMeasure => parse::command(
+                "TCUMEASURE",
+                [validate_string(argument()), validate_byte(argument())],
+            )
+            .map(|[name, channel]| Expr::TCUMeasure { name, channel })
+            .boxed(),
+
+            ExprKind::RatioTest => parse::command(
+                "RATIOTEST",
+                [
+                    validate_string(argument()),
+                    validate_string(argument()),
+                    expected(),
+                    validate_string(argument()),
+                ],
+            )
+            .map(
+                |[numerator, denominator, expected, message]| Expr::RatioTest {
+                    numerator,
+                    denominator,
+                    expected,
+                    message,
+                },
+            )
+            .boxed(),
+
+            ExprKind::StartTimer => parse::command("STARTTIMER", [validate_string(argument())])
+                .map(|[name]| Expr::StartTimer(name))
+                .boxed(),
+
+            ExprKind::StopTimer => parse::command(
+                "STOPTIMER",
+                [
+                    validate_string(argument()),
+                    expected(),
+                    validate_string(argument()),
+                ],
+            )
+            .map(|[name, expected, message]| Expr::StopTimer {
+                name,
+                expected,
+                message,
+            })
+            .boxed(),
+
+            ExprKind::StartLatency => parse::command("STARTLATENCY", [validate_string(argument())])
+                .map(|[name]| Expr::StartLatency(name))
+                .boxed(),
+
+            ExprKind::LatencyTest => parse::command(
+                "LATENCYTEST",
+                [
+                    validate_string(argument()),
+                    validate_percentile(validate_uint(argument())),
+                    expected(),
+                    validate_string(argument()),
+                ],
+            )
+            .map(|[name, percentile, expected, message]| Expr::LatencyTest {
+                name,
+                percentile,
+                expected,
+                message,
+            })
+            .boxed(),
+
+            // Expressions wrapping other commands are parsed by syntax::parse as they require a
+            // parser for those commands.
+            ExprKind::NoResponse => unreachable!("NORESPONSE is parsed by syntax::parse"),
+            ExprKind::RetryBlock => unreachable!("RETRY is parsed by syntax::parse"),
+            ExprKind::RepeatBlock => unreachable!("REPEAT is parsed by syntax::parse"),
+            ExprKind::BoardBlock => unreachable!("BOARD is parsed by syntax::parse"),
+            ExprKind::AbortBlock => unreachable!("ONABORT is parsed by syntax::parse"),
+            ExprKind::If => unreachable!("IF is parsed by syntax::parse"),
         }
         .map_with_span(ParsedExpr::from_kind_and_span)
     }
@@ -261,35 +706,140 @@ impl ExprKind {
 
 ////////////////////////////////////////////////////////////////
 
-/// Parser that matches any value type. i.e. a String or UInt.
+/// Parser that matches any value type. i.e. a String or UInt, or a reference to one defined by
+/// SET.
+///
+pub fn argument() -> impl Parser<char, ParsedExpr, Error = Error> + Clone {
+    choice((
+        ExprKind::String.parser(),
+        ExprKind::Reference.parser(),
+        ExprKind::UInt.parser(),
+    ))
+    .padded_by(parse::whitespace())
+}
+
+////////////////////////////////////////////////////////////////
+
+/// Parser for the values a test's measurement is expected to take. Either a range, i.e.
+/// `<min>, <max>`, a tolerance such as `1000 +- 5%`, a comparison such as `>= 3000` or a set of
+/// values such as `[1, 3, 7]`.
+///
+pub fn expected() -> BoxedParser<'static, char, ParsedExpr, Error> {
+    choice((
+        ExprKind::Comparison.parser(),
+        ExprKind::Set.parser(),
+        ExprKind::Tolerance.parser(),
+        ExprKind::Range.parser(),
+    ))
+    .boxed()
+}
+
+////////////////////////////////////////////////////////////////
+
+/// Parser for the values a device's measurement is expected to take. As [`expected`] but also
+/// allowing a test of the measurement's stability, i.e. `STABLE <samples>, <spread>`, it's drift
+/// from a baseline, i.e. `BASELINE <tolerance>`, or of it's bitfields, i.e.
+/// `BITS [<mask> = <value>, ...]`.
 ///
-fn argument() -> impl Parser<char, ParsedExpr, Error = Error> + Clone {
-    choice((ExprKind::String.parser(), ExprKind::UInt.parser())).padded_by(parse::whitespace())
+pub fn measured() -> BoxedParser<'static, char, ParsedExpr, Error> {
+    choice((
+        ExprKind::Stability.parser(),
+        ExprKind::Baseline.parser(),
+        ExprKind::Bitfield.parser(),
+        expected(),
+    ))
+    .boxed()
+}
+
+////////////////////////////////////////////////////////////////
+
+/// Parser for the number of measurements taken by a device's test and how they're reduced to the
+/// one tested, following it's other arguments. e.g. `, AVERAGE <samples>` or `, ALL <samples>`.
+/// Optional, in which case a single measurement is tested.
+///
+pub fn samples() -> BoxedParser<'static, char, Option<(SampleReduction, Box<ParsedExpr>)>, Error> {
+    let reduction = choice((
+        parse::keyword("AVERAGE").to(SampleReduction::Average),
+        parse::keyword("MIN").to(SampleReduction::Min),
+        parse::keyword("MAX").to(SampleReduction::Max),
+        parse::keyword("ALL").to(SampleReduction::All),
+        parse::keyword("ANY").to(SampleReduction::Any),
+    ));
+
+    just(',')
+        .padded_by(parse::whitespace())
+        .ignore_then(reduction)
+        .then(validate_uint(argument()).map(Box::new))
+        .or_not()
+        .boxed()
+}
+
+////////////////////////////////////////////////////////////////
+
+/// Parser for an option's setting. Either an unsigned integer or arithmetic on unsigned integers
+/// and stored measurements. e.g. `trim * 2 + 10`.
+///
+/// Multiplication and division take precedence over addition and subtraction. Operators of equal
+/// precedence are applied left to right. Parentheses can be used to group operations.
+///
+pub fn setting() -> BoxedParser<'static, char, ParsedExpr, Error> {
+    fn operation(lhs: ParsedExpr, (operator, rhs): (Operator, ParsedExpr)) -> ParsedExpr {
+        let span = lhs.span().start..rhs.span().end;
+        let expr = Expr::Arithmetic {
+            operator,
+            lhs: Box::new(lhs),
+            rhs: Box::new(rhs),
+        };
+
+        ParsedExpr::from_kind_and_span(expr, span)
+    }
+
+    recursive(|arithmetic| {
+        let operand = choice((
+            validate_uint(ExprKind::Reference.parser()),
+            ExprKind::UInt.parser(),
+            ExprKind::Variable.parser(),
+            arithmetic.delimited_by(just('('), just(')')),
+        ))
+        .padded_by(parse::whitespace());
+
+        let product = operand
+            .clone()
+            .then(
+                choice((
+                    just('*').to(Operator::Multiply),
+                    just('/').to(Operator::Divide),
+                ))
+                .then(operand)
+                .repeated(),
+            )
+            .foldl(operation);
+
+        product
+            .clone()
+            .then(
+                choice((
+                    just('+').to(Operator::Add),
+                    just('-').to(Operator::Subtract),
+                ))
+                .then(product)
+                .repeated(),
+            )
+            .foldl(operation)
+    })
+    .boxed()
 }
 
 ////////////////////////////////////////////////////////////////
 
 /// Takes a parser and validates that the output is a String. If not, it outputs an error.
 ///
-fn validate_string<'a, 'b, P>(parser: P) -> BoxedParser<'b, char, ParsedExpr, Error>
+pub fn validate_string<'a, 'b, P>(parser: P) -> BoxedParser<'b, char, ParsedExpr, Error>
 where
     P: Parser<char, ParsedExpr, Error = Error> + 'a,
     'a: 'b,
 {
-    parser
-        .validate(|arg, span, emit| {
-            if !matches!(arg.expression(), Expr::String(_)) {
-                emit(
-                Error::argument_type(span, [ExprKind::String], arg.expression_kind())
-                    .with_note(ErrorNote::Note(
-                    "If the argument was intended to be a string it should be delimited by \"\"",
-                )),
-            )
-            }
-
-            arg
-        })
-        .boxed()
+    validate(parser, Requirement::String)
 }
 
 ////////////////////////////////////////////////////////////////
@@ -297,26 +847,25 @@ where
 /// Takes a parser and validates that the output is a Uint. If not, it outputs an error.
 /// If it isn't a string, it outputs an error.
 ///
-fn validate_uint<'a, 'b, P>(parser: P) -> BoxedParser<'b, char, ParsedExpr, Error>
+pub fn validate_uint<'a, 'b, P>(parser: P) -> BoxedParser<'b, char, ParsedExpr, Error>
 where
     P: Parser<char, ParsedExpr, Error = Error> + 'a,
     'a: 'b,
 {
-    parser.validate(|arg, span, emit| {
-        if !matches!(arg.expression(), Expr::UInt(_)) {
-            let mut error = Error::argument_type(span, [ExprKind::UInt], arg.expression_kind());
-
-            if let Expr::String(string) = arg.expression() {
-                if string.chars().all(|c| c.is_numeric()) {
-                    error = error.with_note(ErrorNote::Help("If the argument was intended to be an unsigned integer, try removing the enclosing \"\""));
-                } else if string.starts_with('$') && string.chars().skip(1).all(|c| c.is_ascii_hexdigit()) {
-                    error = error.with_note(ErrorNote::Help("If the argument was intended to be a hex unsigned integer, try removing the enclosing \"\""));
-                }
-            }
-            emit(error)
-        }
-        arg
-    }).boxed()
+    validate(parser, Requirement::UInt)
+}
+
+////////////////////////////////////////////////////////////////
+
+/// Takes a parser and validates that the output is a UInt between 1 and 100. If not, it outputs an
+/// error.
+///
+pub fn validate_percentile<'a, 'b, P>(parser: P) -> BoxedParser<'b, char, ParsedExpr, Error>
+where
+    P: Parser<char, ParsedExpr, Error = Error> + 'a,
+    'a: 'b,
+{
+    validate(parser, Requirement::Percentile)
 }
 
 ////////////////////////////////////////////////////////////////
@@ -324,17 +873,74 @@ where
 /// Takes a parser and validates that the output is a UInt < 256. If not, it outputs an error.
 /// If it isn't a string, it outputs an error.
 ///
-fn validate_byte<'a, 'b, P>(parser: P) -> BoxedParser<'b, char, ParsedExpr, Error>
+pub fn validate_byte<'a, 'b, P>(parser: P) -> BoxedParser<'b, char, ParsedExpr, Error>
+where
+    P: Parser<char, ParsedExpr, Error = Error> + 'a,
+    'a: 'b,
+{
+    validate(parser, Requirement::Byte)
+}
+
+////////////////////////////////////////////////////////////////
+
+/// Takes a parser and validates that the output is a UInt that's a supported baud rate. If not, it
+/// outputs an error.
+///
+pub fn validate_baud<'a, 'b, P>(parser: P) -> BoxedParser<'b, char, ParsedExpr, Error>
+where
+    P: Parser<char, ParsedExpr, Error = Error> + 'a,
+    'a: 'b,
+{
+    validate(parser, Requirement::Baud)
+}
+
+////////////////////////////////////////////////////////////////
+
+/// Takes a parser and validates that the output is a String of hex byte pairs. If not, it outputs
+/// an error.
+///
+pub fn validate_hex_bytes<'a, 'b, P>(parser: P) -> BoxedParser<'b, char, ParsedExpr, Error>
+where
+    P: Parser<char, ParsedExpr, Error = Error> + 'a,
+    'a: 'b,
+{
+    validate(parser, Requirement::HexBytes)
+}
+
+////////////////////////////////////////////////////////////////
+
+/// Takes a parser and validates that the output is a String that's an HTTP or HTTPS URL. If not, it
+/// outputs an error.
+///
+pub fn validate_http_url<'a, 'b, P>(parser: P) -> BoxedParser<'b, char, ParsedExpr, Error>
+where
+    P: Parser<char, ParsedExpr, Error = Error> + 'a,
+    'a: 'b,
+{
+    validate(parser, Requirement::HttpUrl)
+}
+
+////////////////////////////////////////////////////////////////
+
+/// Takes a parser and validates that the output meets the requirement. A reference's value isn't
+/// known until it's resolved so the requirement is instead recorded to be checked then.
+///
+fn validate<'a, 'b, P>(
+    parser: P,
+    requirement: Requirement,
+) -> BoxedParser<'b, char, ParsedExpr, Error>
 where
     P: Parser<char, ParsedExpr, Error = Error> + 'a,
     'a: 'b,
 {
     parser
-        .validate(|arg, span, emit| {
-            if let Expr::UInt(value) = arg.expression() {
-                if *value > 255 {
-                    emit(Error::argument_value_size(span, *value, (0, 255)))
-                }
+        .validate(move |arg, span, emit| {
+            if let Expr::Reference { .. } = arg.expression() {
+                return arg.with_requirement(requirement);
+            }
+
+            if let Some(error) = requirement.check(&arg, span) {
+                emit(error);
             }
 
             arg
@@ -343,3 +949,98 @@ where
 }
 
 ////////////////////////////////////////////////////////////////
+// methods
+////////////////////////////////////////////////////////////////
+
+impl Requirement {
+    /// Check an argument meets the requirement.
+    ///
+    /// # Returns
+    /// The error to report if it doesn't.
+    ///
+    pub fn check(&self, arg: &ParsedExpr, span: Range<usize>) -> Option<Error> {
+        match (self, arg.expression()) {
+            (Requirement::String, Expr::String(_) | Expr::Input(_)) => None,
+            (Requirement::String, _) => Some(
+                Error::argument_type(span, [ExprKind::String], arg.expression_kind())
+                    .with_note(ErrorNote::Note(
+                    "If the argument was intended to be a string it should be delimited by \"\"",
+                )),
+            ),
+
+            (Requirement::UInt, Expr::UInt(_)) => None,
+            (Requirement::UInt, _) => {
+                let mut error = Error::argument_type(span, [ExprKind::UInt], arg.expression_kind());
+
+                if let Expr::String(string) = arg.expression() {
+                    if string.chars().all(|c| c.is_numeric()) {
+                        error = error.with_note(ErrorNote::Help("If the argument was intended to be an unsigned integer, try removing the enclosing \"\""));
+                    } else if string.starts_with('$')
+                        && string.chars().skip(1).all(|c| c.is_ascii_hexdigit())
+                    {
+                        error = error.with_note(ErrorNote::Help("If the argument was intended to be a hex unsigned integer, try removing the enclosing \"\""));
+                    }
+                }
+                Some(error)
+            }
+
+            (Requirement::Percentile, Expr::UInt(value)) if !(1..=100).contains(value) => {
+                Some(Error::argument_value_size(span, *value, (1, 100)))
+            }
+            (Requirement::Byte, Expr::UInt(value)) if *value > 255 => {
+                Some(Error::argument_value_size(span, *value, (0, 255)))
+            }
+            (Requirement::Baud, Expr::UInt(value)) if !BAUD_RATES.contains(value) => Some(
+                Error::argument_format(span, "a supported baud rate").with_note(ErrorNote::Note(
+                    "Supported baud rates are 1200, 2400, 4800, 9600, 19200, 38400, 57600 and 115200",
+                )),
+            ),
+            // A computed setting's value isn't known until it's evaluated.
+            (
+                Requirement::Percentile | Requirement::Byte | Requirement::Baud,
+                Expr::Variable(_) | Expr::Arithmetic { .. },
+            ) => None,
+            (Requirement::Percentile | Requirement::Byte | Requirement::Baud, _) => {
+                Requirement::UInt.check(arg, span)
+            }
+
+            (Requirement::HexBytes, Expr::String(text)) if parse::hex_bytes(text).is_none() => {
+                Some(
+                    Error::argument_format(span, "a string of hex byte pairs").with_note(
+                        ErrorNote::Note("Pairs may be separated by whitespace. e.g. \"1B 40 0D\""),
+                    ),
+                )
+            }
+            (Requirement::HexBytes, Expr::String(_)) => None,
+            (Requirement::HexBytes, _) => Some(Error::argument_type(
+                span,
+                [ExprKind::String],
+                arg.expression_kind(),
+            )),
+
+            (Requirement::HttpUrl, Expr::String(url)) if !parse::is_http_url(url) => Some(
+                Error::argument_format(span, "an HTTP or HTTPS URL").with_note(ErrorNote::Note(
+                    "Firmware can only be downloaded over HTTP. e.g. \"https://example.com/firmware.bin\"",
+                )),
+            ),
+            (Requirement::HttpUrl, Expr::String(_)) => None,
+            (Requirement::HttpUrl, _) => Some(Error::argument_type(
+                span,
+                [ExprKind::String],
+                arg.expression_kind(),
+            )),
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////
+// helpers
+////////////////////////////////////////////////////////////////
+
+/// Return true if the text could be read as a hex unsigned integer.
+///
+fn is_hex(text: &str) -> bool {
+    text.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+////////////////////////////////////////////////////////////////
@@ -1,10 +1,13 @@
+mod annotation;
+#[allow(clippy::module_inception)]
 mod expression;
 mod kind;
 pub mod parse;
 
 ////////////////////////////////////////////////////////////////
 
-pub use expression::{Expr, ParsedExpr};
-pub use kind::ExprKind;
+pub use annotation::Annotation;
+pub use expression::{Expr, Operator, ParsedExpr};
+pub use kind::{argument, validate_uint, ExprKind};
 
 ////////////////////////////////////////////////////////////////
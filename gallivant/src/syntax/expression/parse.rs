@@ -12,7 +12,7 @@ use super::expression::ParsedExpr;
 /// # Returns
 /// A parser matching inline whitespace.
 ///   
-pub fn whitespace() -> Repeated<impl Parser<char, (), Error = Error> + Copy + Clone> {
+pub fn whitespace() -> Repeated<impl Parser<char, (), Error = Error> + Copy> {
     filter(|c: &char| c.is_inline_whitespace())
         .ignored()
         .repeated()
@@ -20,6 +20,25 @@ pub fn whitespace() -> Repeated<impl Parser<char, (), Error = Error> + Copy + Cl
 
 ////////////////////////////////////////////////////////////////
 
+/// Parser that matches a keyword, ignoring case. e.g. `HPMODE`, `HpMode` and `hpmode` all match
+/// the keyword `HPMODE`. Like chumsky's builtin text::keyword parser, the keyword must be a whole
+/// identifier so `HPMODES` doesn't match.
+///
+/// # Returns
+/// A parser matching the keyword.
+///   
+pub fn keyword<'a>(keyword: &'a str) -> impl Parser<char, (), Error = Error> + Clone + 'a {
+    text::ident().try_map(move |ident: String, span| {
+        if ident.eq_ignore_ascii_case(keyword) {
+            Ok(())
+        } else {
+            Err(Error::expected_input_found(span, None, None))
+        }
+    })
+}
+
+////////////////////////////////////////////////////////////////
+
 /// Parser that matches unsigned integers. This differs from chumsky's builtin text::int parser in
 /// that it allows leading 0's.
 ///
@@ -29,7 +48,7 @@ pub fn whitespace() -> Repeated<impl Parser<char, (), Error = Error> + Copy + Cl
 /// # Returns
 /// A parser matching unsigned integers.
 ///   
-pub fn uint(radix: u32) -> impl Parser<char, String, Error = Error> + Copy + Clone {
+pub fn uint(radix: u32) -> impl Parser<char, String, Error = Error> + Copy {
     filter(move |c: &char| c.is_digit(radix))
         .map(Some)
         .chain::<char, Vec<_>, _>(filter(move |c: &char| c.is_digit(radix)).repeated())
@@ -38,6 +57,43 @@ pub fn uint(radix: u32) -> impl Parser<char, String, Error = Error> + Copy + Clo
 
 ////////////////////////////////////////////////////////////////
 
+/// Decode a string of hex byte pairs, optionally separated by whitespace. e.g. `"1B 40 0D"` or
+/// `"1B400D"`.
+///
+/// # Returns
+/// The bytes, or None if there are none or any pair is incomplete or not hex.
+///   
+pub fn hex_bytes(text: &str) -> Option<Vec<u8>> {
+    let mut bytes = Vec::new();
+    for pairs in text.split_whitespace() {
+        if pairs.len() % 2 != 0 || !pairs.chars().all(|c| c.is_ascii_hexdigit()) {
+            return None;
+        }
+
+        for index in (0..pairs.len()).step_by(2) {
+            bytes.push(u8::from_str_radix(&pairs[index..index + 2], 16).ok()?);
+        }
+    }
+
+    (!bytes.is_empty()).then_some(bytes)
+}
+
+////////////////////////////////////////////////////////////////
+
+/// Return true if the text is an HTTP or HTTPS URL. i.e. Either scheme, in any case, followed by a
+/// host. e.g. `"https://example.com/firmware.bin"`.
+///
+pub fn is_http_url(text: &str) -> bool {
+    let Some((scheme, rest)) = text.split_once("://") else {
+        return false;
+    };
+
+    let http = scheme.eq_ignore_ascii_case("http") || scheme.eq_ignore_ascii_case("https");
+    http && !rest.is_empty() && !rest.starts_with('/')
+}
+
+////////////////////////////////////////////////////////////////
+
 /// Takes a parser that outputs an expression and outputs a parser that outputs a comma seperated
 /// list of those expressions.  
 ///
@@ -83,9 +139,9 @@ pub fn command<P, const N: usize>(
 where
     P: Parser<char, ParsedExpr, Error = Error> + 'static,
 {
-    text::keyword(cmd)
+    keyword(cmd)
         .then(whitespace())
-        .ignore_then(comma_seperated_list(parsers).map(|args| args.map(|arg| Box::new(arg))))
+        .ignore_then(comma_seperated_list(parsers).map(|args| args.map(Box::new)))
         .boxed()
 }
 
@@ -108,7 +164,7 @@ pub fn command_variadic<'a, E>(
 where
     E: Parser<char, ParsedExpr, Error = Error> + 'a,
 {
-    text::keyword(cmd)
+    keyword(cmd)
         .then(whitespace())
         .ignore_then(param_parser.separated_by(just(',').padded_by(whitespace())))
 }
@@ -2,6 +2,7 @@ mod error;
 mod evaluate;
 mod expression;
 mod parse;
+mod preprocess;
 mod state;
 
 ////////////////////////////////////////////////////////////////
@@ -10,8 +11,11 @@ mod state;
 
 pub use error::{Error, ErrorReason};
 pub use evaluate::evaluate;
-pub use expression::{Expr, ParsedExpr};
+pub use expression::{Annotation, Expr, ExprKind, Operator, ParsedExpr};
+#[cfg(test)]
 pub use parse::parse_from_str;
+pub use parse::{parse_all, parse_from_str_permissive, parse_with_includes};
+pub use preprocess::preprocess;
 pub use state::EvalState;
 
 ////////////////////////////////////////////////////////////////
@@ -1,25 +1,369 @@
+use std::{collections::BTreeMap, ops::Range};
+
 use chumsky::prelude::*;
 
 use super::{
     error::{Error, ErrorReason},
-    expression::{parse, ExprKind, ParsedExpr},
+    expression::{argument, parse, validate_uint, Annotation, Expr, ExprKind, ParsedExpr},
 };
 
 ////////////////////////////////////////////////////////////////
 
 pub fn parse_from_str(script: &str) -> Result<Vec<ParsedExpr>, Vec<Error>> {
-    parser().parse(script)
+    parse_with_includes(script, |_| None)
+}
+
+////////////////////////////////////////////////////////////////
+
+/// Parse a script, replacing each INCLUDE with the statements of the script it names. Scripts are
+/// loaded by `load`, given the path exactly as it's written in the INCLUDE, so it's up to the
+/// caller where they're loaded from. Returning None reports the script as not found.
+///
+/// Statements from an included script take the span of the INCLUDE they replace.
+///
+/// # Errors
+/// Every error found in the script. Errors within an included script are reported at the INCLUDE,
+/// along with their location within the included script.
+///
+pub fn parse_with_includes(
+    script: &str,
+    mut load: impl FnMut(&str) -> Option<String>,
+) -> Result<Vec<ParsedExpr>, Vec<Error>> {
+    let mut ast = parser().parse(script)?;
+    resolve_includes(&mut ast, &mut load, &mut Vec::new())?;
+    resolve_references(&mut ast)?;
+    Ok(ast)
+}
+
+////////////////////////////////////////////////////////////////
+
+/// Parse a script, skipping any lines that can't be parsed.
+///
+/// Each line containing an error is commented out and the script re-parsed until no errors
+/// remain. Commenting out rather than removing lines keeps the spans of the remaining statements
+/// intact.
+///
+/// # Returns
+/// The parsed statements along with the error that caused each skipped line. If an error can't be
+/// attributed to a line with anything left to skip, every error found is returned instead.
+///
+pub fn parse_from_str_permissive(
+    script: &str,
+) -> Result<(Vec<ParsedExpr>, Vec<Error>), Vec<Error>> {
+    let (ast, skipped) = parse_skipping_lines(script);
+    ast.map(|ast| (ast, skipped))
+}
+
+////////////////////////////////////////////////////////////////
+
+/// Parse a script, collecting every error found rather than stopping at the first. Lines are
+/// skipped as in [`parse_from_str_permissive`] so every statement is attempted. Intended for
+/// reporting every problem with a script at once.
+///
+/// # Returns
+/// The parsed statements along with every error found, in script order. If an error can't be
+/// recovered from by skipping lines, no statements are returned.
+///
+pub fn parse_all(script: &str) -> (Vec<ParsedExpr>, Vec<Error>) {
+    let (ast, mut errors) = parse_skipping_lines(script);
+    let ast = ast.unwrap_or_else(|unrecoverable| {
+        errors.extend(unrecoverable);
+        Vec::new()
+    });
+
+    // The same error can be reported more than once when the parser recovers at the end of input.
+    errors.sort_by_key(|error| error.reason().span().map(|span| span.start));
+    errors.dedup();
+    (ast, errors)
+}
+
+////////////////////////////////////////////////////////////////
+
+/// Parse a script, commenting out each line containing an error and re-parsing until no errors
+/// remain.
+///
+/// # Returns
+/// The result of the final parse, along with the error that caused each skipped line.
+///
+fn parse_skipping_lines(script: &str) -> (Result<Vec<ParsedExpr>, Vec<Error>>, Vec<Error>) {
+    let mut script = script.to_owned();
+    let mut skipped = Vec::new();
+
+    loop {
+        let errors = match parse_from_str(script.as_str()) {
+            Ok(ast) => return (Ok(ast), skipped),
+            Err(errors) => errors,
+        };
+
+        let Some(span) = errors.first().and_then(|error| error.reason().span()) else {
+            return (Err(errors), skipped);
+        };
+
+        let start = script[..span.start.min(script.len())]
+            .rfind('\n')
+            .map_or(0, |index| index + 1);
+        let end = script[start..]
+            .find('\n')
+            .map_or(script.len(), |index| start + index);
+
+        let line = &script[start..end];
+        if line.trim().is_empty() || line.starts_with(';') {
+            return (Err(errors), skipped);
+        }
+
+        // Replacing every byte keeps the script valid UTF-8 as both characters are a single byte.
+        let comment = format!(";{}", " ".repeat(line.len() - 1));
+        script.replace_range(start..end, &comment);
+
+        skipped.extend(errors.into_iter().take(1));
+    }
 }
 
 ////////////////////////////////////////////////////////////////
 
 fn parser() -> impl Parser<char, Vec<ParsedExpr>, Error = Error> {
-    let command = choice((
+    let statement = recursive(|statement| {
+        let command = choice((
+            simple_command(),
+            no_response(simple_command()),
+            retry_block(statement.clone()),
+            repeat_block(statement.clone()),
+            board_block(statement.clone()),
+            if_block(statement),
+        ))
+        .padded_by(parse::whitespace());
+
+        let annotated = Annotation::parser()
+            .then_ignore(text::newline().repeated())
+            .repeated()
+            .then(command)
+            .map(|(annotations, command)| command.with_annotations(annotations));
+
+        choice((
+            annotated,
+            ExprKind::UInt.parser(),
+            ExprKind::String.parser(),
+            ExprKind::ScriptComment.parser(),
+        ))
+    });
+
+    ////////////////
+
+    // Cleanup is for the whole run so can't be within another block.
+    let top_level = choice((abort_block(statement.clone()), statement));
+
+    body(top_level).then_ignore(end()).map_err(|error| {
+        if let ErrorReason::Unexpected { span, .. } = error.reason() {
+            return Error::unrecognised_command(span.clone());
+        }
+
+        error
+    })
+}
+
+////////////////////////////////////////////////////////////////
+
+/// Replace every reference with the value defined for it by the latest SET or INPUTDIALOG before it
+/// in the script, then check the value meets the requirements of the argument it's used as.
+///
+/// # Errors
+/// Every reference to an undefined value and every value that doesn't meet it's requirements.
+///
+fn resolve_references(ast: &mut [ParsedExpr]) -> Result<(), Vec<Error>> {
+    fn resolve(
+        expr: &mut ParsedExpr,
+        values: &mut BTreeMap<String, Expr>,
+        errors: &mut Vec<Error>,
+    ) {
+        let span = expr.span().clone();
+
+        if let Expr::Reference { name, requirements } = expr.expression() {
+            let Some(value) = values.get(name) else {
+                errors.push(Error::undefined_reference(span, name));
+                return;
+            };
+
+            let requirements = requirements.clone();
+            *expr.expression_mut() = value.clone();
+            errors.extend(
+                requirements
+                    .iter()
+                    .filter_map(|requirement| requirement.check(expr, span.clone())),
+            );
+            return;
+        }
+
+        for child in expr.expression_mut().children_mut() {
+            resolve(child, values, errors);
+        }
+
+        match expr.expression() {
+            Expr::Define { name, value } => {
+                values.insert(name.to_owned(), value.expression().clone());
+            }
+
+            // The value isn't known until the operator enters it when the script is run.
+            Expr::InputDialog { target, .. } => {
+                values.insert(target.to_owned(), Expr::Input(target.to_owned()));
+            }
+
+            _ => (),
+        }
+    }
+
+    let mut values = BTreeMap::new();
+    let mut errors = Vec::new();
+    for expr in ast {
+        resolve(expr, &mut values, &mut errors);
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+////////////////////////////////////////////////////////////////
+
+/// Replace every INCLUDE in the statements, including those within blocks, with the statements of
+/// the script it names.
+///
+/// # Errors
+/// Every INCLUDE that couldn't be resolved.
+///
+fn resolve_includes(
+    body: &mut Vec<ParsedExpr>,
+    load: &mut dyn FnMut(&str) -> Option<String>,
+    including: &mut Vec<String>,
+) -> Result<(), Vec<Error>> {
+    let mut resolved = Vec::with_capacity(body.len());
+    let mut errors = Vec::new();
+
+    for mut expr in body.drain(..) {
+        for statements in expr.expression_mut().bodies_mut() {
+            if let Err(block_errors) = resolve_includes(statements, load, including) {
+                errors.extend(block_errors);
+            }
+        }
+
+        let Expr::Include(path) = expr.expression() else {
+            resolved.push(expr);
+            continue;
+        };
+
+        let Expr::String(path_string) = path.expression() else {
+            errors.push(Error::argument_format(path.span().clone(), "a quoted path"));
+            continue;
+        };
+
+        match include(path_string, expr.span(), load, including) {
+            Ok(statements) => resolved.extend(statements),
+            Err(include_errors) => errors.extend(include_errors),
+        }
+    }
+
+    *body = resolved;
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+////////////////////////////////////////////////////////////////
+
+/// Load and parse an included script, resolving any INCLUDEs within it.
+///
+/// # Errors
+/// If the script can't be loaded, is already being included or contains any errors.
+///
+fn include(
+    path: &str,
+    span: &Range<usize>,
+    load: &mut dyn FnMut(&str) -> Option<String>,
+    including: &mut Vec<String>,
+) -> Result<Vec<ParsedExpr>, Vec<Error>> {
+    if including.iter().any(|included| included == path) {
+        return Err(vec![Error::include_cycle(span.clone(), path)]);
+    }
+
+    let Some(script) = load(path) else {
+        return Err(vec![Error::include_not_found(span.clone(), path)]);
+    };
+
+    let included_errors = |errors: Vec<Error>| {
+        errors
+            .into_iter()
+            .map(|error| Error::include_failed(span.clone(), path, error, &script))
+            .collect::<Vec<Error>>()
+    };
+
+    let mut ast = parser().parse(script.as_str()).map_err(included_errors)?;
+
+    including.push(path.to_owned());
+    let resolved = resolve_includes(&mut ast, load, including);
+    including.pop();
+    resolved.map_err(included_errors)?;
+
+    for statement in ast.iter_mut() {
+        statement.respan(span);
+    }
+
+    Ok(ast)
+}
+
+////////////////////////////////////////////////////////////////
+
+/// Parser for any command that doesn't contain other commands.
+///
+fn simple_command() -> impl Parser<char, ParsedExpr, Error = Error> + Clone {
+    let usb_command = choice((
+        ExprKind::USBOpen.parser(),
+        ExprKind::USBClose.parser(),
+        ExprKind::USBPrint.parser(),
+        ExprKind::SendHex.parser(),
+        ExprKind::USBSetTimeFormat.parser(),
+        ExprKind::USBSetTime.parser(),
+        ExprKind::USBSetOption.parser(),
+        ExprKind::USBPrinterSet.parser(),
+        ExprKind::USBPrinterTest.parser(),
+        ExprKind::PrintImage.parser(),
+        ExprKind::SetBaud.parser(),
+        ExprKind::ProgramFirmware.parser(),
+    ));
+
+    let timing_command = choice((
+        ExprKind::StartTimer.parser(),
+        ExprKind::StopTimer.parser(),
+        ExprKind::StartLatency.parser(),
+        ExprKind::LatencyTest.parser(),
+    ));
+
+    let directive = choice((ExprKind::Define.parser(), ExprKind::Include.parser()));
+
+    let flow = choice((
+        ExprKind::Break.parser(),
+        ExprKind::Label.parser(),
+        ExprKind::Goto.parser(),
+    ));
+
+    let dialog = choice((
+        ExprKind::OpenDialog.parser(),
+        ExprKind::WaitDialog.parser(),
+        ExprKind::InputDialog.parser(),
+        ExprKind::Confirm.parser(),
+        ExprKind::Beep.parser(),
+    ));
+
+    choice((
+        directive,
         ExprKind::HPMode.parser(),
         ExprKind::Comment.parser(),
         ExprKind::Wait.parser(),
-        ExprKind::OpenDialog.parser(),
-        ExprKind::WaitDialog.parser(),
+        ExprKind::Delay.parser(),
+        dialog,
         ExprKind::Flush.parser(),
         ExprKind::Protocol.parser(),
         ExprKind::Print.parser(),
@@ -33,44 +377,194 @@ fn parser() -> impl Parser<char, Vec<ParsedExpr>, Error = Error> {
         ExprKind::PrinterTest.parser(),
         // ExprKind::IssueTest.parser(),
         // ExprKind::TestResult.parser(),
-        ExprKind::USBOpen.parser(),
-        ExprKind::USBClose.parser(),
-        ExprKind::USBPrint.parser(),
-        ExprKind::USBSetTimeFormat.parser(),
-        ExprKind::USBSetTime.parser(),
-        ExprKind::USBSetOption.parser(),
-        ExprKind::USBPrinterSet.parser(),
-        ExprKind::USBPrinterTest.parser(),
+        usb_command,
+        ExprKind::ReferenceTest.parser(),
+        ExprKind::TCUMeasure.parser(),
+        ExprKind::RatioTest.parser(),
+        timing_command,
+        flow,
     ))
-    .padded_by(parse::whitespace());
+}
 
-    ////////////////
+////////////////////////////////////////////////////////////////
 
-    choice((
-        command,
-        ExprKind::UInt.parser(),
-        ExprKind::String.parser(),
-        ExprKind::ScriptComment.parser(),
-    ))
-    .separated_by(text::newline().repeated())
-    .padded()
-    .then_ignore(end())
-    .map_err(|error| {
-        if let ErrorReason::Unexpected { span, .. } = error.reason() {
-            return Error::unrecognised_command(span.clone());
-        }
+/// Parser for a sequence of statements seperated by newlines. i.e. A script or the body of a block.
+///
+fn body<'a, P>(statement: P) -> impl Parser<char, Vec<ParsedExpr>, Error = Error> + Clone + 'a
+where
+    P: Parser<char, ParsedExpr, Error = Error> + Clone + 'a,
+{
+    statement
+        .separated_by(text::newline().repeated())
+        .padded()
+        .boxed()
+}
 
-        error
-    })
+////////////////////////////////////////////////////////////////
+
+/// Parser for a RETRY block. i.e.
+/// ```text
+/// RETRY <attempts>
+///     <statements>
+/// ENDRETRY
+/// ```
+///
+fn retry_block<'a, P>(statement: P) -> impl Parser<char, ParsedExpr, Error = Error> + Clone + 'a
+where
+    P: Parser<char, ParsedExpr, Error = Error> + Clone + 'a,
+{
+    parse::keyword("RETRY")
+        .then(parse::whitespace())
+        .ignore_then(validate_uint(argument()))
+        .then(body(statement))
+        .then_ignore(parse::keyword("ENDRETRY"))
+        .map(|(attempts, body)| Expr::RetryBlock {
+            attempts: Box::new(attempts),
+            body,
+        })
+        .map_with_span(ParsedExpr::from_kind_and_span)
+        .boxed()
+}
+
+////////////////////////////////////////////////////////////////
+
+/// Parser for a REPEAT block. i.e.
+/// ```text
+/// REPEAT <count>
+///     <statements>
+/// ENDREPEAT
+/// ```
+///
+fn repeat_block<'a, P>(statement: P) -> impl Parser<char, ParsedExpr, Error = Error> + Clone + 'a
+where
+    P: Parser<char, ParsedExpr, Error = Error> + Clone + 'a,
+{
+    parse::keyword("REPEAT")
+        .then(parse::whitespace())
+        .ignore_then(validate_uint(argument()))
+        .then(body(statement))
+        .then_ignore(parse::keyword("ENDREPEAT"))
+        .map(|(count, body)| Expr::RepeatBlock {
+            count: Box::new(count),
+            body,
+        })
+        .map_with_span(ParsedExpr::from_kind_and_span)
+        .boxed()
+}
+
+////////////////////////////////////////////////////////////////
+
+/// Parser for a BOARD block. i.e.
+/// ```text
+/// BOARD "<id>"
+///     <statements>
+/// ENDBOARD
+/// ```
+///
+fn board_block<'a, P>(statement: P) -> impl Parser<char, ParsedExpr, Error = Error> + Clone + 'a
+where
+    P: Parser<char, ParsedExpr, Error = Error> + Clone + 'a,
+{
+    parse::keyword("BOARD")
+        .then(parse::whitespace())
+        .ignore_then(ExprKind::String.parser())
+        .then(body(statement))
+        .then_ignore(parse::keyword("ENDBOARD"))
+        .map(|(id, body)| Expr::BoardBlock {
+            id: Box::new(id),
+            body,
+        })
+        .map_with_span(ParsedExpr::from_kind_and_span)
+        .boxed()
+}
+
+////////////////////////////////////////////////////////////////
+
+/// Parser for an IF block, with or without an ELSE section. i.e.
+/// ```text
+/// IF <condition>
+///     <statements>
+/// ELSE
+///     <statements>
+/// ENDIF
+/// ```
+///
+fn if_block<'a, P>(statement: P) -> impl Parser<char, ParsedExpr, Error = Error> + Clone + 'a
+where
+    P: Parser<char, ParsedExpr, Error = Error> + Clone + 'a,
+{
+    let else_section = parse::keyword("ELSE").ignore_then(body(statement.clone()));
+
+    parse::keyword("IF")
+        .then(parse::whitespace())
+        .ignore_then(ExprKind::LastResult.parser())
+        .then(body(statement))
+        .then(else_section.or_not())
+        .then_ignore(parse::keyword("ENDIF"))
+        .map(|((condition, then_body), else_body)| Expr::If {
+            condition: Box::new(condition),
+            then_body,
+            else_body: else_body.unwrap_or_default(),
+        })
+        .map_with_span(ParsedExpr::from_kind_and_span)
+        .boxed()
+}
+
+////////////////////////////////////////////////////////////////
+
+/// Parser for an ONABORT block. i.e.
+/// ```text
+/// ONABORT
+///     <statements>
+/// ENDONABORT
+/// ```
+///
+fn abort_block<'a, P>(statement: P) -> impl Parser<char, ParsedExpr, Error = Error> + Clone + 'a
+where
+    P: Parser<char, ParsedExpr, Error = Error> + Clone + 'a,
+{
+    parse::keyword("ONABORT")
+        .ignore_then(body(statement))
+        .then_ignore(parse::keyword("ENDONABORT"))
+        .map(|body| Expr::AbortBlock { body })
+        .map_with_span(ParsedExpr::from_kind_and_span)
+        .padded_by(parse::whitespace())
+        .boxed()
+}
+
+////////////////////////////////////////////////////////////////
+
+/// Parser for a NORESPONSE command. i.e. `NORESPONSE <drain ms> <command>`.
+///
+/// # Arguments
+/// * `command` - Parser for the wrapped command.
+///
+fn no_response<'a, P>(command: P) -> impl Parser<char, ParsedExpr, Error = Error> + Clone + 'a
+where
+    P: Parser<char, ParsedExpr, Error = Error> + Clone + 'a,
+{
+    parse::keyword("NORESPONSE")
+        .then(parse::whitespace())
+        .ignore_then(validate_uint(argument()))
+        .then(command)
+        .map(|(drain, command)| Expr::NoResponse {
+            drain: Box::new(drain),
+            command: Box::new(command),
+        })
+        .map_with_span(ParsedExpr::from_kind_and_span)
+        .boxed()
 }
 
 ////////////////////////////////////////////////////////////////
-/// tests
+// tests
 ////////////////////////////////////////////////////////////////
 
 #[cfg(test)]
 mod tests {
-    use crate::syntax::Expr;
+    use crate::{
+        execution::Comparison,
+        syntax::{Expr, Operator},
+    };
 
     use super::*;
 
@@ -84,6 +578,7 @@ COMMENT "Test"
 WAIT 1234
 OPENDIALOG "Hello"
 WAITDIALOG "PLEASE WAIT"
+BEEP
 FLUSH
 PROTOCOL
 PRINT "print me"
@@ -112,7 +607,12 @@ USBPRINTERTEST 4, 133, 987, 5, "error message"
                 Expr::Comment(Expr::String("Test".to_owned()).into()).into(),
                 Expr::Wait(Expr::UInt(1234).into()).into(),
                 Expr::OpenDialog(Expr::String("Hello".to_owned()).into()).into(),
-                Expr::WaitDialog(Expr::String("PLEASE WAIT".to_owned()).into()).into(),
+                Expr::WaitDialog {
+                    message: Expr::String("PLEASE WAIT".to_owned()).into(),
+                    timeout: None,
+                }
+                .into(),
+                Expr::Beep.into(),
                 Expr::Flush.into(),
                 Expr::Protocol.into(),
                 Expr::Print(vec![Expr::String("print me".to_owned()).into()]).into(),
@@ -127,19 +627,27 @@ USBPRINTERTEST 4, 133, 987, 5, "error message"
                 Expr::TCUOpen(Expr::UInt(0xF).into()).into(),
                 Expr::TCUTest {
                     channel: Expr::UInt(5).into(),
-                    min: Expr::UInt(12000).into(),
-                    max: Expr::UInt(56000).into(),
+                    expected: Expr::Range {
+                        min: Expr::UInt(12000).into(),
+                        max: Expr::UInt(56000).into(),
+                    }
+                    .into(),
                     retries: Expr::UInt(0).into(),
                     message: Expr::String("error".to_owned()).into(),
+                    samples: None,
                 }
                 .into(),
                 Expr::PrinterSet(Expr::UInt(1).into()).into(),
                 Expr::PrinterTest {
                     channel: Expr::UInt(4).into(),
-                    min: Expr::UInt(133).into(),
-                    max: Expr::UInt(987).into(),
+                    expected: Expr::Range {
+                        min: Expr::UInt(133).into(),
+                        max: Expr::UInt(987).into(),
+                    }
+                    .into(),
                     retries: Expr::UInt(5).into(),
                     message: Expr::String("error message".to_owned()).into(),
+                    samples: None,
                 }
                 .into(),
                 Expr::USBOpen.into(),
@@ -158,10 +666,14 @@ USBPRINTERTEST 4, 133, 987, 5, "error message"
                 Expr::USBPrinterSet(Expr::UInt(6).into()).into(),
                 Expr::USBPrinterTest {
                     channel: Expr::UInt(4).into(),
-                    min: Expr::UInt(133).into(),
-                    max: Expr::UInt(987).into(),
+                    expected: Expr::Range {
+                        min: Expr::UInt(133).into(),
+                        max: Expr::UInt(987).into(),
+                    }
+                    .into(),
                     retries: Expr::UInt(5).into(),
                     message: Expr::String("error message".to_owned()).into(),
+                    samples: None,
                 }
                 .into(),
             ]
@@ -171,90 +683,964 @@ USBPRINTERTEST 4, 133, 987, 5, "error message"
     ////////////////////////////////////////////////////////////////
 
     #[test]
-    fn test_single_command() {
-        let script = r#"COMMENT "Comment 1234""#;
+    fn test_case_insensitive_commands() {
+        let script = r#"
+HPMODE
+COMMENT "Test"
+WAIT 1234
+DELAY 5
+OPENDIALOG "Hello"
+WAITDIALOG "PLEASE WAIT"
+FLUSH
+PROTOCOL
+PRINT "print me"
+SETTIMEFORMAT $A6
+SETTIME
+SETOPTION 4, 6
+TCUCLOSE 4
+TCUOPEN $F
+TCUTEST 5, 12000, 56000, 0, "error"
+PRINTERSET 1
+PRINTERTEST 4,133, 987,5,"error message"
+USBOPEN
+USBCLOSE
+USBPRINT "Look at me I can print"
+USBSETTIMEFORMAT 5
+USBSETTIME
+USBSETOPTION 5, 9
+USBPRINTERSET 6
+USBPRINTERTEST 4, 133, 987, 5, "error message"
+RETRY 2
+    REPEAT 3
+        BREAK
+    ENDREPEAT
+ENDRETRY
+        "#;
+
+        // Change the case of the command at the start of each line only, leaving it's arguments.
+        let recase = |case: fn(&str) -> String| {
+            script
+                .lines()
+                .map(|line| {
+                    let command = line.trim_start();
+                    let indent = &line[..line.len() - command.len()];
+                    let end = command.find(' ').unwrap_or(command.len());
+                    format!("{indent}{}{}", case(&command[..end]), &command[end..])
+                })
+                .collect::<Vec<String>>()
+                .join("\n")
+        };
+
+        let mixed = |command: &str| {
+            command
+                .chars()
+                .enumerate()
+                .map(|(i, c)| match i % 2 {
+                    0 => c.to_ascii_uppercase(),
+                    _ => c.to_ascii_lowercase(),
+                })
+                .collect()
+        };
+
+        let expected = parse_from_str(script).unwrap();
         assert_eq!(
-            parse_from_str(script).unwrap(),
-            [Expr::Comment(Expr::String("Comment 1234".to_owned()).into()).into()]
+            parse_from_str(&recase(str::to_lowercase)).unwrap(),
+            expected
+        );
+        assert_eq!(parse_from_str(&recase(mixed)).unwrap(), expected);
+
+        // String contents keep their case.
+        assert_eq!(
+            parse_from_str(r#"comment "MiXeD""#).unwrap(),
+            [Expr::Comment(Expr::String("MiXeD".to_owned()).into()).into()]
         );
+
+        // Only whole keywords match.
+        assert!(parse_from_str("hpmodes").is_err());
     }
 
     ////////////////////////////////////////////////////////////////
 
     #[test]
-    fn test_hex_arg_leading_0s() {
-        let script = r#"TCUOPEN $00C"#;
+    fn test_no_response() {
+        let script = r#"NORESPONSE 100 PRINT $1B, "@""#;
         assert_eq!(
             parse_from_str(script).unwrap(),
-            [Expr::TCUOpen(Expr::UInt(0x0C).into()).into()]
+            [Expr::NoResponse {
+                drain: Expr::UInt(100).into(),
+                command: Expr::Print(vec![
+                    Expr::UInt(0x1B).into(),
+                    Expr::String("@".to_owned()).into()
+                ])
+                .into(),
+            }
+            .into()]
         );
     }
 
     ////////////////////////////////////////////////////////////////
 
     #[test]
-    fn test_hex_arg_0() {
-        let script = r#"TCUOPEN $00"#;
+    fn test_repeat_block() {
+        let script = r#"
+REPEAT 2
+    PRINTERSET 1
+    REPEAT $A
+        FLUSH
+    ENDREPEAT
+ENDREPEAT
+FLUSH
+        "#;
+
         assert_eq!(
             parse_from_str(script).unwrap(),
-            [Expr::TCUOpen(Expr::UInt(0).into()).into()]
+            [
+                Expr::RepeatBlock {
+                    count: Expr::UInt(2).into(),
+                    body: vec![
+                        Expr::PrinterSet(Expr::UInt(1).into()).into(),
+                        Expr::RepeatBlock {
+                            count: Expr::UInt(10).into(),
+                            body: vec![Expr::Flush.into()],
+                        }
+                        .into(),
+                    ],
+                }
+                .into(),
+                Expr::Flush.into(),
+            ]
         );
+
+        assert!(parse_from_str("REPEAT 2\n    FLUSH\n").is_err());
+        assert!(parse_from_str("REPEAT\n    FLUSH\nENDREPEAT").is_err());
+        assert!(parse_from_str("REPEAT 2\n    FLUSH\nENDRETRY").is_err());
     }
 
     ////////////////////////////////////////////////////////////////
 
     #[test]
-    fn test_dec_arg_0() {
-        let script = r#"TCUOPEN 0"#;
+    fn test_retry_block() {
+        let script = r#"
+RETRY 3
+    PRINTERSET 1
+    ; Comment
+    RETRY 2
+        TCUTEST 5, 12000, 56000, 0, "error"
+    ENDRETRY
+ENDRETRY
+FLUSH
+        "#;
+
         assert_eq!(
             parse_from_str(script).unwrap(),
-            [Expr::TCUOpen(Expr::UInt(0).into()).into()]
+            [
+                Expr::RetryBlock {
+                    attempts: Expr::UInt(3).into(),
+                    body: vec![
+                        Expr::PrinterSet(Expr::UInt(1).into()).into(),
+                        Expr::ScriptComment(" Comment".to_owned()).into(),
+                        Expr::RetryBlock {
+                            attempts: Expr::UInt(2).into(),
+                            body: vec![Expr::TCUTest {
+                                channel: Expr::UInt(5).into(),
+                                expected: Expr::Range {
+                                    min: Expr::UInt(12000).into(),
+                                    max: Expr::UInt(56000).into(),
+                                }
+                                .into(),
+                                retries: Expr::UInt(0).into(),
+                                message: Expr::String("error".to_owned()).into(),
+                                samples: None,
+                            }
+                            .into()],
+                        }
+                        .into(),
+                    ],
+                }
+                .into(),
+                Expr::Flush.into(),
+            ]
         );
     }
 
     ////////////////////////////////////////////////////////////////
 
     #[test]
-    fn test_invalid_string_type_arg() {
-        let script = r#"COMMENT 1234"#;
-
-        let errors = parser().parse(script).unwrap_err();
+    fn test_retry_block_unclosed() {
+        let script = r#"
+RETRY 3
+    PRINTERSET 1
+        "#;
 
-        assert_eq!(errors.len(), 1);
-        assert!(matches!(
-            errors.first().unwrap().reason(),
-            ErrorReason::ArgType { .. }
-        ));
+        assert!(parse_from_str(script).is_err());
     }
 
     ////////////////////////////////////////////////////////////////
 
     #[test]
-    fn test_invalid_uint_type_arg() {
-        let script = r#"WAIT "$F54A""#;
+    fn test_board_block() {
+        let script = r#"
+BOARD "A1"
+    PRINTERSET 1
+ENDBOARD
+BOARD "A2"
+ENDBOARD
+        "#;
 
-        let errors = parser().parse(script).unwrap_err();
+        assert_eq!(
+            parse_from_str(script).unwrap(),
+            [
+                Expr::BoardBlock {
+                    id: Expr::String("A1".to_owned()).into(),
+                    body: vec![Expr::PrinterSet(Expr::UInt(1).into()).into()],
+                }
+                .into(),
+                Expr::BoardBlock {
+                    id: Expr::String("A2".to_owned()).into(),
+                    body: vec![],
+                }
+                .into(),
+            ]
+        );
 
-        assert_eq!(errors.len(), 1);
-        assert!(matches!(
-            errors.first().unwrap().reason(),
-            ErrorReason::ArgType { .. }
-        ));
+        assert!(parse_from_str("BOARD \"A1\"\n    PRINTERSET 1").is_err());
     }
 
     ////////////////////////////////////////////////////////////////
 
     #[test]
-    fn test_invalid_uint_value_arg() {
-        let script = r#"TCUCLOSE 256"#;
-
-        let errors = parser().parse(script).unwrap_err();
+    fn test_abort_block() {
+        let script = r#"
+ONABORT
+    PRINTERSET 1
+ENDONABORT
+PRINTERSET 2
+        "#;
 
-        assert_eq!(errors.len(), 1);
-        assert!(matches!(
-            errors.first().unwrap().reason(),
-            ErrorReason::ArgValue { .. }
-        ));
+        assert_eq!(
+            parse_from_str(script).unwrap(),
+            [
+                Expr::AbortBlock {
+                    body: vec![Expr::PrinterSet(Expr::UInt(1).into()).into()],
+                }
+                .into(),
+                Expr::PrinterSet(Expr::UInt(2).into()).into(),
+            ]
+        );
+
+        // Only allowed at the top level of the script.
+        let script = "BOARD \"A1\"\nONABORT\nENDONABORT\nENDBOARD";
+        assert!(parse_from_str(script).is_err());
+    }
+
+    ////////////////////////////////////////////////////////////////
+
+    #[test]
+    fn test_if_block() {
+        let script = r#"
+IF LASTRESULT == FAIL
+    PRINTERSET 1
+    IF lastresult==pass
+        FLUSH
+    ENDIF
+ELSE
+    PRINTERSET 2
+ENDIF
+FLUSH
+        "#;
+
+        assert_eq!(
+            parse_from_str(script).unwrap(),
+            [
+                Expr::If {
+                    condition: Expr::LastResult { passed: false }.into(),
+                    then_body: vec![
+                        Expr::PrinterSet(Expr::UInt(1).into()).into(),
+                        Expr::If {
+                            condition: Expr::LastResult { passed: true }.into(),
+                            then_body: vec![Expr::Flush.into()],
+                            else_body: Vec::new(),
+                        }
+                        .into(),
+                    ],
+                    else_body: vec![Expr::PrinterSet(Expr::UInt(2).into()).into()],
+                }
+                .into(),
+                Expr::Flush.into(),
+            ]
+        );
+
+        assert!(parse_from_str("IF LASTRESULT == PASS\n    FLUSH\n").is_err());
+        assert!(parse_from_str("IF LASTRESULT == PASS\nELSE\nENDIF").is_ok());
+        assert!(parse_from_str("IF LASTRESULT\n    FLUSH\nENDIF").is_err());
+        assert!(parse_from_str("IF LASTRESULT == 1\n    FLUSH\nENDIF").is_err());
+        assert!(parse_from_str("IF\n    FLUSH\nENDIF").is_err());
+    }
+
+    ////////////////////////////////////////////////////////////////
+
+    #[test]
+    fn test_test_comparisons() {
+        let script = r#"
+TCUTEST 1, >= 3000, 0, "a"
+PRINTERTEST 2, <=100, 0, "b"
+USBPRINTERTEST 3, > $10, 0, "c"
+TCUTEST 4, < 5, 0, "d"
+        "#;
+
+        let operators: Vec<Comparison> = parse_from_str(script)
+            .unwrap()
+            .iter()
+            .map(|expr| match expr.expression() {
+                Expr::TCUTest { expected, .. }
+                | Expr::PrinterTest { expected, .. }
+                | Expr::USBPrinterTest { expected, .. } => match expected.expression() {
+                    Expr::Comparison { operator, .. } => *operator,
+                    expected => panic!("Expected a comparison. Got: {expected:?}"),
+                },
+                expr => panic!("Expected a test command. Got: {expr:?}"),
+            })
+            .collect();
+
+        assert_eq!(
+            operators,
+            [
+                Comparison::GreaterEqual,
+                Comparison::LessEqual,
+                Comparison::Greater,
+                Comparison::Less
+            ]
+        );
+    }
+
+    ////////////////////////////////////////////////////////////////
+
+    #[test]
+    fn test_test_set() {
+        let script = r#"TCUTEST 1, [1, 3,$7], 0, "a""#;
+
+        let ast = parse_from_str(script).unwrap();
+        let Expr::TCUTest { expected, .. } = ast[0].expression() else {
+            panic!("Expected a TCUTEST. Got: {:?}", ast[0]);
+        };
+
+        assert_eq!(
+            expected.expression(),
+            &Expr::Set(vec![
+                Expr::UInt(1).into(),
+                Expr::UInt(3).into(),
+                Expr::UInt(7).into()
+            ])
+        );
+
+        assert!(parse_from_str(r#"TCUTEST 1, [], 0, "a""#).is_err());
+        assert!(parse_from_str(r#"TCUTEST 1, [1, "3"], 0, "a""#).is_err());
+    }
+
+    ////////////////////////////////////////////////////////////////
+
+    #[test]
+    fn test_test_tolerance() {
+        let script = r#"TCUTEST 1, 1000 +- 5%, 0, "a""#;
+
+        let ast = parse_from_str(script).unwrap();
+        let Expr::TCUTest { expected, .. } = ast[0].expression() else {
+            panic!("Expected a TCUTEST. Got: {:?}", ast[0]);
+        };
+
+        assert_eq!(
+            expected.expression(),
+            &Expr::Tolerance {
+                nominal: Box::new(Expr::UInt(1000).into()),
+                percent: Box::new(Expr::UInt(5).into()),
+            }
+        );
+
+        assert!(parse_from_str(r#"TCUTEST 1, $3E8+-5%, 0, "a""#).is_ok());
+        assert!(parse_from_str(r#"TCUTEST 1, 1000 +- 5, 0, "a""#).is_err());
+        assert!(parse_from_str(r#"TCUTEST 1, 1000 + - 5%, 0, "a""#).is_err());
+    }
+
+    ////////////////////////////////////////////////////////////////
+
+    #[test]
+    fn test_test_bitfield() {
+        let script = r#"TCUTEST 1, BITS [$08 = $08, $60= 2], 0, "a""#;
+
+        let ast = parse_from_str(script).unwrap();
+        let Expr::TCUTest { expected, .. } = ast[0].expression() else {
+            panic!("Expected a TCUTEST. Got: {:?}", ast[0]);
+        };
+
+        assert_eq!(
+            expected.expression(),
+            &Expr::Bitfield(vec![
+                (Expr::UInt(0x08).into(), Expr::UInt(0x08).into()),
+                (Expr::UInt(0x60).into(), Expr::UInt(2).into()),
+            ])
+        );
+
+        assert!(parse_from_str(r#"TCUTEST 1, BITS [], 0, "a""#).is_err());
+        assert!(parse_from_str(r#"TCUTEST 1, BITS [$08], 0, "a""#).is_err());
+    }
+
+    ////////////////////////////////////////////////////////////////
+
+    #[test]
+    fn test_computed_setting() {
+        let script = "SETOPTION 4, (trim + $10) * 2 - offset / 3";
+
+        let ast = parse_from_str(script).unwrap();
+        let Expr::SetOption { setting, .. } = ast[0].expression() else {
+            panic!("Expected a SETOPTION. Got: {:?}", ast[0]);
+        };
+
+        let operation = |operator, lhs: Expr, rhs: Expr| Expr::Arithmetic {
+            operator,
+            lhs: lhs.into(),
+            rhs: rhs.into(),
+        };
+        let variable = |name: &str| Expr::Variable(name.to_owned());
+
+        assert_eq!(
+            setting.expression(),
+            &operation(
+                Operator::Subtract,
+                operation(
+                    Operator::Multiply,
+                    operation(Operator::Add, variable("trim"), Expr::UInt(0x10)),
+                    Expr::UInt(2),
+                ),
+                operation(Operator::Divide, variable("offset"), Expr::UInt(3)),
+            )
+        );
+
+        // Operations span their operands, excluding any parentheses.
+        let Expr::Arithmetic { lhs, .. } = setting.expression() else {
+            panic!("Expected arithmetic. Got: {setting:?}");
+        };
+        assert_eq!(*lhs.span(), 14..29);
+        assert_eq!(*setting.span(), 14..script.len());
+
+        assert!(parse_from_str("SETOPTION 4, trim +").is_err());
+        assert!(parse_from_str("SETOPTION 4, 256").is_err());
+        assert!(parse_from_str(r#"SETOPTION 4, "trim""#).is_err());
+    }
+
+    ////////////////////////////////////////////////////////////////
+
+    #[test]
+    fn test_window_annotation() {
+        let script = r#"
+@window "09:00", "17:30"
+PRINTERSET 1
+PRINTERSET 2
+        "#;
+
+        let ast = parse_from_str(script).unwrap();
+        assert_eq!(
+            ast,
+            [
+                Expr::PrinterSet(Expr::UInt(1).into()).into(),
+                Expr::PrinterSet(Expr::UInt(2).into()).into(),
+            ]
+        );
+
+        let start = chrono::NaiveTime::from_hms_opt(9, 0, 0).unwrap();
+        let end = chrono::NaiveTime::from_hms_opt(17, 30, 0).unwrap();
+        assert_eq!(ast[0].annotations(), [Annotation::Window { start, end }]);
+        assert!(ast[1].annotations().is_empty());
+    }
+
+    ////////////////////////////////////////////////////////////////
+
+    #[test]
+    fn test_window_annotation_invalid_time() {
+        let script = r#"
+@window "9am", "17:30"
+PRINTERSET 1
+        "#;
+
+        assert!(parse_from_str(script).is_err());
+    }
+
+    ////////////////////////////////////////////////////////////////
+
+    #[test]
+    fn test_step_annotation() {
+        let script = r#"
+@step "Calibrating print head"
+RETRY 2
+    PRINTERSET 1
+ENDRETRY
+PRINTERSET 2
+        "#;
+
+        let ast = parse_from_str(script).unwrap();
+        assert_eq!(
+            ast[0].annotations(),
+            [Annotation::Step("Calibrating print head".to_owned())]
+        );
+        assert!(ast[1].annotations().is_empty());
+    }
+
+    ////////////////////////////////////////////////////////////////
+
+    #[test]
+    fn test_single_command() {
+        let script = r#"COMMENT "Comment 1234""#;
+        assert_eq!(
+            parse_from_str(script).unwrap(),
+            [Expr::Comment(Expr::String("Comment 1234".to_owned()).into()).into()]
+        );
+    }
+
+    ////////////////////////////////////////////////////////////////
+
+    #[test]
+    fn test_hex_arg_leading_0s() {
+        let script = r#"TCUOPEN $00C"#;
+        assert_eq!(
+            parse_from_str(script).unwrap(),
+            [Expr::TCUOpen(Expr::UInt(0x0C).into()).into()]
+        );
+    }
+
+    ////////////////////////////////////////////////////////////////
+
+    #[test]
+    fn test_hex_arg_0() {
+        let script = r#"TCUOPEN $00"#;
+        assert_eq!(
+            parse_from_str(script).unwrap(),
+            [Expr::TCUOpen(Expr::UInt(0).into()).into()]
+        );
+    }
+
+    ////////////////////////////////////////////////////////////////
+
+    #[test]
+    fn test_dec_arg_0() {
+        let script = r#"TCUOPEN 0"#;
+        assert_eq!(
+            parse_from_str(script).unwrap(),
+            [Expr::TCUOpen(Expr::UInt(0).into()).into()]
+        );
+    }
+
+    ////////////////////////////////////////////////////////////////
+
+    #[test]
+    fn test_invalid_string_type_arg() {
+        let script = r#"COMMENT 1234"#;
+
+        let errors = parser().parse(script).unwrap_err();
+
+        assert_eq!(errors.len(), 1);
+        assert!(matches!(
+            errors.first().unwrap().reason(),
+            ErrorReason::ArgType { .. }
+        ));
+    }
+
+    ////////////////////////////////////////////////////////////////
+
+    #[test]
+    fn test_parse_all() {
+        let script = "PRINTERSET 300\nWAIT 10\nBOGUS 3";
+
+        // Lines that failed to parse are blanked out rather than removed.
+        let (ast, errors) = parse_all(script);
+        assert_eq!(ast.len(), 3);
+        assert_eq!(ast[1], Expr::Wait(Expr::UInt(10).into()).into());
+        assert_eq!(
+            errors
+                .iter()
+                .map(|error| error.reason().clone())
+                .collect::<Vec<ErrorReason>>(),
+            [
+                ErrorReason::ArgValue {
+                    span: 11..14,
+                    value: 300,
+                    limits: (0, 255)
+                },
+                ErrorReason::UnrecognisedCommand { span: 23..28 },
+            ]
+        );
+
+        // Errors found before one that can't be recovered from are kept.
+        let (ast, errors) = parse_all("BOGUS 3\nRETRY 2\n    WAIT 10");
+        assert!(ast.is_empty());
+        assert_eq!(errors.len(), 2);
+        assert_eq!(
+            errors[0].reason(),
+            &ErrorReason::UnrecognisedCommand { span: 0..5 }
+        );
+
+        assert_eq!(
+            parse_all("WAIT 10"),
+            (vec![Expr::Wait(Expr::UInt(10).into()).into()], vec![])
+        );
+    }
+
+    ////////////////////////////////////////////////////////////////
+
+    #[test]
+    fn test_include() {
+        let script = "WAIT 10\nINCLUDE \"setup.txt\"\nWAIT 30";
+        let load = |path: &str| (path == "setup.txt").then(|| "DELAY 20\nHPMODE".to_owned());
+
+        let ast = parse_with_includes(script, load).unwrap();
+        assert_eq!(
+            ast,
+            [
+                Expr::Wait(Expr::UInt(10).into()).into(),
+                Expr::Delay(Expr::UInt(20).into()).into(),
+                Expr::HPMode.into(),
+                Expr::Wait(Expr::UInt(30).into()).into(),
+            ]
+        );
+
+        // Included statements take the span of the INCLUDE.
+        assert_eq!(ast[1].span(), &(8..27));
+        assert_eq!(ast[2].span(), &(8..27));
+
+        assert!(parse_from_str("INCLUDE setup").is_err());
+    }
+
+    ////////////////////////////////////////////////////////////////
+
+    #[test]
+    fn test_send_hex() {
+        let script = "SENDHEX \"1B 40 0D\"\nSET reset \"1B40\"\nSENDHEX $reset";
+
+        assert_eq!(
+            parse_from_str(script).unwrap(),
+            [
+                Expr::SendHex(Expr::String("1B 40 0D".to_owned()).into()).into(),
+                Expr::Define {
+                    name: "reset".to_owned(),
+                    value: Expr::String("1B40".to_owned()).into(),
+                }
+                .into(),
+                Expr::SendHex(Expr::String("1B40".to_owned()).into()).into(),
+            ]
+        );
+
+        for invalid in [r#""1B 4 0D""#, r#""1B4""#, r#""1G""#, r#""""#, "$1B"] {
+            let errors = parse_from_str(&format!("SENDHEX {invalid}")).unwrap_err();
+            assert_eq!(errors.len(), 1, "{invalid}");
+        }
+
+        let errors = parse_from_str("SENDHEX \"1B 40 0\"").unwrap_err();
+        assert_eq!(
+            errors[0].reason(),
+            &ErrorReason::ArgFormat {
+                span: 8..17,
+                expected: "a string of hex byte pairs"
+            }
+        );
+
+        // Checked once a reference is resolved.
+        assert!(parse_from_str("SET reset \"1B4\"\nSENDHEX $reset").is_err());
+    }
+
+    ////////////////////////////////////////////////////////////////
+
+    #[test]
+    fn test_set_baud() {
+        assert_eq!(
+            parse_from_str("SETBAUD 19200").unwrap(),
+            [Expr::SetBaud(Expr::UInt(19200).into()).into()]
+        );
+
+        let errors = parse_from_str("SETBAUD 20000").unwrap_err();
+        assert_eq!(
+            errors[0].reason(),
+            &ErrorReason::ArgFormat {
+                span: 8..13,
+                expected: "a supported baud rate"
+            }
+        );
+
+        // Checked once a reference is resolved.
+        assert!(parse_from_str("SET baud 0\nSETBAUD $baud").is_err());
+        assert!(parse_from_str("SETBAUD \"9600\"").is_err());
+    }
+
+    ////////////////////////////////////////////////////////////////
+
+    #[test]
+    fn test_program_firmware() {
+        let script = "PROGRAMFIRMWARE \"http://example.com/fw.bin\"";
+        assert_eq!(
+            parse_from_str(script).unwrap(),
+            [
+                Expr::ProgramFirmware(Expr::String("http://example.com/fw.bin".to_owned()).into())
+                    .into()
+            ]
+        );
+
+        let errors = parse_from_str("PROGRAMFIRMWARE \"ftp://example.com/fw.bin\"").unwrap_err();
+        assert_eq!(
+            errors[0].reason(),
+            &ErrorReason::ArgFormat {
+                span: 16..42,
+                expected: "an HTTP or HTTPS URL"
+            }
+        );
+
+        for invalid in [r#""https://""#, r#""https:///fw.bin""#, r#""fw.bin""#, "1"] {
+            let errors = parse_from_str(&format!("PROGRAMFIRMWARE {invalid}")).unwrap_err();
+            assert_eq!(errors.len(), 1, "{invalid}");
+        }
+
+        // Checked once a reference is resolved.
+        assert!(parse_from_str("SET url \"fw.bin\"\nPROGRAMFIRMWARE $url").is_err());
+    }
+
+    ////////////////////////////////////////////////////////////////
+
+    #[test]
+    fn test_string_escapes() {
+        let script = r#"COMMENT "a\rb\nc\td\\e\"f""#;
+
+        assert_eq!(
+            parse_from_str(script).unwrap(),
+            [Expr::Comment(Expr::String("a\rb\nc\td\\e\"f".to_owned()).into()).into()]
+        );
+
+        for (escape, character) in [
+            (r"\r", "\r"),
+            (r"\n", "\n"),
+            (r"\t", "\t"),
+            (r"\\", "\\"),
+            (r#"\""#, "\""),
+        ] {
+            let script = format!(r#"COMMENT "{escape}""#);
+            assert_eq!(
+                parse_from_str(&script).unwrap(),
+                [Expr::Comment(Expr::String(character.to_owned()).into()).into()]
+            );
+        }
+
+        let errors = parse_from_str(r#"COMMENT "ab\qc""#).unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert!(matches!(
+            errors[0].reason(),
+            ErrorReason::ArgFormat { span, .. } if *span == (11..13)
+        ));
+
+        // An escaped quote doesn't end the string.
+        assert!(parse_from_str(r#"COMMENT "ab\""#).is_err());
+    }
+
+    ////////////////////////////////////////////////////////////////
+
+    #[test]
+    fn test_hex_prefix() {
+        let script = "WAIT 0xFF\nWAIT 0x0\nWAIT 0X1f\nWAIT $FF\nWAIT 255\nWAIT 0\nWAIT 0xFFFFFFFF";
+
+        assert_eq!(
+            parse_from_str(script).unwrap(),
+            [0xFF, 0x0, 0x1F, 0xFF, 255, 0, u32::MAX]
+                .map(|value| Expr::Wait(Expr::UInt(value).into()).into())
+        );
+
+        let errors = parse_from_str("WAIT 0x100000000").unwrap_err();
+        assert_eq!(
+            errors
+                .iter()
+                .map(|error| error.reason().clone())
+                .collect::<Vec<ErrorReason>>(),
+            [ErrorReason::ArgFormat {
+                span: 5..16,
+                expected: "a 32 bit unsigned integer"
+            }]
+        );
+
+        assert!(parse_from_str("WAIT 4294967296").is_err());
+        assert!(parse_from_str("WAIT $100000000").is_err());
+        assert!(parse_from_str("WAIT 0x").is_err());
+        assert!(parse_from_str("WAIT 0xG").is_err());
+    }
+
+    ////////////////////////////////////////////////////////////////
+
+    #[test]
+    fn test_define() {
+        let script = "SET settle $1F4\nSET name \"x\"\nWAIT $settle\nPRINT $name, $FA";
+
+        assert_eq!(
+            parse_from_str(script).unwrap(),
+            [
+                Expr::Define {
+                    name: "settle".to_owned(),
+                    value: Expr::UInt(500).into(),
+                }
+                .into(),
+                Expr::Define {
+                    name: "name".to_owned(),
+                    value: Expr::String("x".to_owned()).into(),
+                }
+                .into(),
+                Expr::Wait(Expr::UInt(500).into()).into(),
+                Expr::Print(vec![
+                    Expr::String("x".to_owned()).into(),
+                    Expr::UInt(0xFA).into(),
+                ])
+                .into(),
+            ]
+        );
+
+        // Nested within a block and defined by another reference.
+        let script = "SET x 3\nRETRY 2\n    SET y $x\n    TCUCLOSE $y\nENDRETRY\nTCUOPEN $y";
+        let ast = parse_from_str(script).unwrap();
+        assert_eq!(ast[2], Expr::TCUOpen(Expr::UInt(3).into()).into());
+
+        // Names that would be read as hex numbers can't be defined.
+        assert!(parse_from_str("SET FACE 1").is_err());
+        assert!(parse_from_str("SET 1A 1").is_err());
+        assert!(parse_from_str("SET name").is_err());
+    }
+
+    ////////////////////////////////////////////////////////////////
+
+    #[test]
+    fn test_undefined_reference() {
+        let errors = parse_from_str("WAIT 10\nWAIT $settle\nPRINT $label").unwrap_err();
+
+        assert_eq!(
+            errors
+                .iter()
+                .map(|error| error.reason().clone())
+                .collect::<Vec<ErrorReason>>(),
+            [
+                ErrorReason::UndefinedReference {
+                    span: 13..20,
+                    name: "settle".to_owned()
+                },
+                ErrorReason::UndefinedReference {
+                    span: 27..33,
+                    name: "label".to_owned()
+                },
+            ]
+        );
+    }
+
+    ////////////////////////////////////////////////////////////////
+
+    #[test]
+    fn test_input_dialog() {
+        let script = "INPUTDIALOG \"Enter serial number\", $serial\nCOMMENT $serial";
+
+        assert_eq!(
+            parse_from_str(script).unwrap(),
+            [
+                Expr::InputDialog {
+                    prompt: Expr::String("Enter serial number".to_owned()).into(),
+                    target: "serial".to_owned(),
+                }
+                .into(),
+                Expr::Comment(Expr::Input("serial".to_owned()).into()).into(),
+            ]
+        );
+
+        assert!(parse_from_str(r#"INPUTDIALOG "Enter serial number", serial"#).is_err());
+        assert!(parse_from_str(r#"INPUTDIALOG "Enter serial number", $ABC"#).is_err());
+    }
+
+    ////////////////////////////////////////////////////////////////
+
+    #[test]
+    fn test_wait_dialog_timeout() {
+        let script = r#"WAITDIALOG "Unattended", 30"#;
+
+        assert_eq!(
+            parse_from_str(script).unwrap(),
+            [Expr::WaitDialog {
+                message: Expr::String("Unattended".to_owned()).into(),
+                timeout: Some(Expr::UInt(30).into()),
+            }
+            .into()]
+        );
+
+        assert!(parse_from_str(r#"WAITDIALOG "Unattended", "30""#).is_err());
+    }
+
+    ////////////////////////////////////////////////////////////////
+
+    #[test]
+    fn test_delay() {
+        let script = "WAIT 10\nDELAY 250\nDELAY $1F4";
+
+        assert_eq!(
+            parse_from_str(script).unwrap(),
+            [
+                Expr::Wait(Expr::UInt(10).into()).into(),
+                Expr::Delay(Expr::UInt(250).into()).into(),
+                Expr::Delay(Expr::UInt(500).into()).into(),
+            ]
+        );
+
+        assert!(parse_from_str(r#"DELAY "250""#).is_err());
+        assert!(parse_from_str("DELAY").is_err());
+    }
+
+    ////////////////////////////////////////////////////////////////
+
+    #[test]
+    fn test_invalid_uint_type_arg() {
+        let script = r#"WAIT "$F54A""#;
+
+        let errors = parser().parse(script).unwrap_err();
+
+        assert_eq!(errors.len(), 1);
+        assert!(matches!(
+            errors.first().unwrap().reason(),
+            ErrorReason::ArgType { .. }
+        ));
+    }
+
+    ////////////////////////////////////////////////////////////////
+
+    #[test]
+    fn test_invalid_byte_type_arg() {
+        let scripts = [
+            (r#"TCUOPEN "5""#, 8..11),
+            (r#"TCUCLOSE "a""#, 9..12),
+            (r#"PRINTERSET "2""#, 11..14),
+            (r#"TCUTEST "3", 1000, 12000, 1, "FAIL""#, 8..11),
+        ];
+
+        for (script, span) in scripts {
+            let errors = parser().parse(script).unwrap_err();
+
+            assert_eq!(errors.len(), 1, "{script}");
+            assert!(
+                matches!(
+                    errors[0].reason(),
+                    ErrorReason::ArgType { span: error_span, found, .. }
+                        if *error_span == span && *found == "String"
+                ),
+                "{script}: {:?}",
+                errors[0].reason()
+            );
+        }
+    }
+    ////////////////////////////////////////////////////////////////
+
+    #[test]
+    fn test_invalid_uint_value_arg() {
+        let script = r#"TCUCLOSE 256"#;
+
+        let errors = parser().parse(script).unwrap_err();
+
+        assert_eq!(errors.len(), 1);
+        assert!(matches!(
+            errors.first().unwrap().reason(),
+            ErrorReason::ArgValue { .. }
+        ));
     }
 
     ////////////////////////////////////////////////////////////////
@@ -320,6 +1706,39 @@ PRINT "test" ; Comment
             [Expr::ScriptComment(" PRINT \"test\"".to_owned()).into(),]
         );
     }
+
+    ////////////////////////////////////////////////////////////////
+
+    #[test]
+    fn test_block_comment() {
+        let script = "COMMENT \"a\"\n/* Fit the board to the jig.\n   Then press OK. */\nWAIT 10";
+
+        let ast = parse_from_str(script).unwrap();
+        assert_eq!(
+            ast,
+            [
+                Expr::Comment(Expr::String("a".to_owned()).into()).into(),
+                Expr::ScriptComment(" Fit the board to the jig.\n   Then press OK. ".to_owned())
+                    .into(),
+                Expr::Wait(Expr::UInt(10).into()).into(),
+            ]
+        );
+        assert_eq!(*ast[1].span(), 12..61);
+    }
+
+    ////////////////////////////////////////////////////////////////
+
+    #[test]
+    fn test_block_commented_out_commands() {
+        let script = "/*\nTCUOPEN 1\n; PRINT \"test\"\nBOGUS 3\n*/";
+
+        assert_eq!(
+            parse_from_str(script).unwrap(),
+            [Expr::ScriptComment("\nTCUOPEN 1\n; PRINT \"test\"\nBOGUS 3\n".to_owned()).into()]
+        );
+
+        assert!(parse_from_str("/* Unterminated\nTCUOPEN 1").is_err());
+    }
 }
 
 ////////////////////////////////////////////////////////////////
@@ -0,0 +1,296 @@
+use std::collections::BTreeSet;
+
+use super::error::Error;
+
+////////////////////////////////////////////////////////////////
+// preprocessing
+////////////////////////////////////////////////////////////////
+
+/// Include or exclude sections of a script based on the symbols defined. A section between
+/// `IFDEF <symbol>` and `ENDIF` is only included if the symbol is defined. Sections may be nested.
+/// Directives are case-insensitive, like any other keyword, and ignored within a block comment. An
+/// ENDIF closing an `IF` block rather than an IFDEF is left for the parser.
+///
+/// Excluded sections and the directives themselves are commented out rather than removed, so that
+/// spans within the preprocessed script are the same as in the original. Excluded sections aren't
+/// parsed so needn't be valid for the symbols defined.
+///
+/// # Errors
+/// Every IFDEF without a symbol or a matching ENDIF and every ENDIF without a matching IFDEF.
+///
+pub fn preprocess(script: &str, symbols: &BTreeSet<String>) -> Result<String, Vec<Error>> {
+    let mut preprocessed = String::with_capacity(script.len());
+    let mut errors = Vec::new();
+
+    // Each IFDEF section and IF block currently open.
+    let mut open: Vec<Section> = Vec::new();
+
+    // Whether the current line starts within a block comment.
+    let mut commented = false;
+
+    let mut start = 0;
+    for line in script.split_inclusive('\n') {
+        let length = line.chars().count();
+        let span = start..start + line.trim_end().chars().count();
+        start += length;
+
+        let within_comment = commented;
+        commented = block_comment_open(line, commented);
+
+        let included = open.iter().all(|section| match section {
+            Section::Ifdef(_, included) => *included,
+            Section::If => true,
+        });
+        // Commented out, as in a permissive parse, rather than left blank so nothing is parsed.
+        let blank = |line: &str| -> String {
+            let (content, ending) = line.split_at(line.trim_end_matches(['\r', '\n']).len());
+            match content.chars().count() {
+                0 => ending.to_owned(),
+                length => format!(";{}{ending}", " ".repeat(length - 1)),
+            }
+        };
+
+        // Any trailing script comment is ignored, as are directives within a block comment.
+        let directive = match within_comment {
+            true => "",
+            false => line.split(';').next().unwrap_or_default().trim(),
+        };
+        let mut words = directive.split_whitespace();
+        let keyword = words.next().map(str::to_ascii_uppercase);
+        match (keyword.as_deref(), words.next(), words.next()) {
+            (Some("IFDEF"), Some(symbol), None) if is_symbol(symbol) => {
+                open.push(Section::Ifdef(span, symbols.contains(symbol)));
+                preprocessed.push_str(&blank(line));
+            }
+            (Some("IFDEF"), ..) => {
+                errors.push(Error::argument_format(span, "a single symbol"));
+                preprocessed.push_str(&blank(line));
+            }
+            (Some("ENDIF"), None, None) => match open.pop() {
+                Some(Section::If) if included => preprocessed.push_str(line),
+                Some(_) => preprocessed.push_str(&blank(line)),
+                None => {
+                    errors.push(Error::unmatched_endif(span));
+                    preprocessed.push_str(&blank(line));
+                }
+            },
+            (Some("IF"), ..) => {
+                open.push(Section::If);
+                match included {
+                    true => preprocessed.push_str(line),
+                    false => preprocessed.push_str(&blank(line)),
+                }
+            }
+            _ if included => preprocessed.push_str(line),
+            _ => preprocessed.push_str(&blank(line)),
+        }
+    }
+
+    // An unclosed IF block is reported by the parser.
+    errors.extend(open.into_iter().filter_map(|section| match section {
+        Section::Ifdef(span, _) => Some(Error::unclosed_ifdef(span)),
+        Section::If => None,
+    }));
+
+    if errors.is_empty() {
+        Ok(preprocessed)
+    } else {
+        Err(errors)
+    }
+}
+
+////////////////////////////////////////////////////////////////
+// helpers
+////////////////////////////////////////////////////////////////
+
+/// Section of a script opened by a line and closed by a matching ENDIF.
+///
+enum Section {
+    /// IFDEF section with it's span and whether it's included.
+    Ifdef(std::ops::Range<usize>, bool),
+
+    /// IF block, closed by an ENDIF that's left for the parser.
+    If,
+}
+
+////////////////////////////////////////////////////////////////
+
+/// Return whether a block comment is open at the end of the line, given whether one was open at
+/// it's start. Delimiters within strings and `;` comments are ignored.
+///
+fn block_comment_open(line: &str, mut open: bool) -> bool {
+    let mut chars = line.chars().peekable();
+    let mut string = false;
+    while let Some(c) = chars.next() {
+        match c {
+            '*' if open && chars.peek() == Some(&'/') => {
+                chars.next();
+                open = false;
+            }
+            _ if open => (),
+            '"' => string = !string,
+            _ if string => (),
+            ';' => break,
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                open = true;
+            }
+            _ => (),
+        }
+    }
+
+    open
+}
+
+////////////////////////////////////////////////////////////////
+
+/// Return true if the text is a valid symbol. i.e. Letters, digits and underscores, not starting
+/// with a digit.
+///
+fn is_symbol(text: &str) -> bool {
+    let mut chars = text.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+////////////////////////////////////////////////////////////////
+// tests
+////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::syntax::ErrorReason;
+
+    ////////////////////////////////////////////////////////////////
+
+    fn symbols(symbols: &[&str]) -> BTreeSet<String> {
+        symbols.iter().map(|symbol| symbol.to_string()).collect()
+    }
+
+    ////////////////////////////////////////////////////////////////
+
+    #[test]
+    fn test_preprocess() {
+        let script = "TCUOPEN 1\nIFDEF DEBUG ; Debug only\n  BOGUS ü\nENDIF\nTCUCLOSE 1";
+
+        let excluded = preprocess(script, &symbols(&[])).unwrap();
+        let blank = |length: usize| format!(";{}", " ".repeat(length - 1));
+        assert_eq!(
+            excluded,
+            format!(
+                "TCUOPEN 1\n{}\n{}\n{}\nTCUCLOSE 1",
+                blank(24),
+                blank(9),
+                blank(5)
+            )
+        );
+        assert_eq!(excluded.chars().count(), script.chars().count());
+
+        let included = preprocess(script, &symbols(&["DEBUG"])).unwrap();
+        assert!(included.contains("  BOGUS ü\n"));
+        assert!(!included.contains("IFDEF"));
+        assert!(!included.contains("ENDIF"));
+    }
+
+    ////////////////////////////////////////////////////////////////
+
+    #[test]
+    fn test_nested() {
+        let script = "IFDEF A\nIFDEF B\nAB\nENDIF\nA\nENDIF\n";
+
+        let included = |symbols: BTreeSet<String>| -> Vec<String> {
+            preprocess(script, &symbols)
+                .unwrap()
+                .lines()
+                .filter(|line| !line.starts_with(';'))
+                .map(str::to_owned)
+                .collect()
+        };
+
+        assert!(included(symbols(&["B"])).is_empty());
+        assert_eq!(included(symbols(&["A"])), ["A"]);
+        assert_eq!(included(symbols(&["A", "B"])), ["AB", "A"]);
+    }
+
+    ////////////////////////////////////////////////////////////////
+
+    #[test]
+    fn test_if_block() {
+        let script =
+            "IFDEF A\nIF LASTRESULT == PASS\nFLUSH\nENDIF\nENDIF\nIF LASTRESULT == FAIL\nENDIF";
+
+        // Left for the parser unless excluded.
+        let included = preprocess(script, &symbols(&["A"])).unwrap();
+        assert_eq!(included.lines().filter(|line| *line == "ENDIF").count(), 2);
+
+        let excluded = preprocess(script, &symbols(&[])).unwrap();
+        assert_eq!(
+            excluded
+                .lines()
+                .filter(|line| !line.starts_with(';'))
+                .collect::<Vec<_>>(),
+            ["IF LASTRESULT == FAIL", "ENDIF"]
+        );
+    }
+
+    ////////////////////////////////////////////////////////////////
+
+    #[test]
+    fn test_lowercase() {
+        let script = "ifdef A\nif lastresult == pass\nFLUSH\nendif\nEndIf\n";
+
+        let included = preprocess(script, &symbols(&["A"])).unwrap();
+        assert_eq!(
+            included
+                .lines()
+                .filter(|line| !line.starts_with(';'))
+                .collect::<Vec<_>>(),
+            ["if lastresult == pass", "FLUSH", "endif"]
+        );
+    }
+
+    ////////////////////////////////////////////////////////////////
+
+    #[test]
+    fn test_unmatched() {
+        let script = "ENDIF\nIFDEF\nIFDEF A B\nIFDEF A\n";
+        let errors = preprocess(script, &symbols(&[])).unwrap_err();
+        let reasons: Vec<&ErrorReason> = errors.iter().map(Error::reason).collect();
+
+        assert_eq!(
+            reasons,
+            [
+                &ErrorReason::UnmatchedEndif { span: 0..5 },
+                &ErrorReason::ArgFormat {
+                    span: 6..11,
+                    expected: "a single symbol"
+                },
+                &ErrorReason::ArgFormat {
+                    span: 12..21,
+                    expected: "a single symbol"
+                },
+                &ErrorReason::UnclosedIfdef { span: 22..29 },
+            ]
+        );
+    }
+
+    ////////////////////////////////////////////////////////////////
+
+    #[test]
+    fn test_block_comment() {
+        let script =
+            "/* Not a directive\nENDIF\nIFDEF A */\nCOMMENT \"/*\" ; /*\nIFDEF A\nA\nENDIF\n";
+
+        let preprocessed = preprocess(script, &symbols(&[])).unwrap();
+        assert_eq!(
+            preprocessed.lines().take(4).collect::<Vec<_>>(),
+            script.lines().take(4).collect::<Vec<_>>()
+        );
+        assert!(!preprocessed.contains("\nA\n"));
+    }
+}
+
+////////////////////////////////////////////////////////////////
@@ -1,12 +1,125 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    ops::Range,
+    path::PathBuf,
+};
+
+use chrono::NaiveDateTime;
+
+use crate::{
+    clock::Clock,
+    diagnostic::Diagnostic,
+    execution::{
+        Capture, Checksum, Device, DeviceLog, Echo, Encoding, LatencyLog, MeasurementFormat,
+        MeasurementStore, Results, Routing, Session, SharedObserver, TimeoutAction, Transport,
+        UnitSuffix,
+    },
+};
+
 ////////////////////////////////////////////////////////////////
 // types
 ////////////////////////////////////////////////////////////////
 
 /// Contains any state that needs to persist through script evaluation.
 ///
-#[derive(Default, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Default, Clone, Debug, PartialEq, Eq)]
 pub struct EvalState {
     pub(super) hpmode: bool,
+
+    /// Record measurements taken by test commands rather than testing them.
+    pub(crate) record: bool,
+
+    /// Source of the current time for commands that depend on it.
+    pub(crate) clock: Clock,
+
+    /// Devices assigned by the frontend. If set, commands requiring an unassigned device fail.
+    pub(crate) routing: Option<Routing>,
+
+    /// How the frontend is connected to the printer. If set, commands using the other transport
+    /// fail.
+    pub(crate) transport: Option<Transport>,
+
+    /// Where transactions retain their raw exchanges, if set.
+    pub(crate) capture: Option<Capture>,
+
+    /// Where the outcomes of tests are reported, if set.
+    pub(crate) results: Option<Results>,
+
+    /// Notified of every exchange made by transactions, if set.
+    pub(crate) observer: Option<SharedObserver>,
+
+    /// How the TCU's echo is separated from the rest of it's responses.
+    pub(crate) echo: Echo,
+
+    /// Format the TCU reports it's measurements in and the unit that follows them.
+    pub(crate) format: MeasurementFormat,
+    pub(crate) unit: UnitSuffix,
+
+    /// Byte sent to prompt the TCU to take a measurement once it's echoed a test command, if any.
+    pub(crate) trigger: Option<u8>,
+
+    /// Number of times a transaction is re-transmitted after a comms error.
+    pub(crate) comms_retries: u32,
+
+    /// Encoding that printed text is converted to.
+    pub(crate) encoding: Encoding,
+
+    /// Checksum appended to each command sent to the printer, if any.
+    pub(crate) checksum: Option<Checksum>,
+
+    /// How a dialog is answered once it's timeout expires.
+    pub(crate) timeout_action: TimeoutAction,
+
+    /// Where devices being opened and closed are logged, if set.
+    pub(crate) device_log: Option<DeviceLog>,
+
+    /// Where devices are held open across runs, if set.
+    pub(crate) session: Option<Session>,
+
+    /// Directory that files referenced by the script are relative to. e.g. The script's directory.
+    pub(crate) assets: Option<PathBuf>,
+
+    /// Directory that any artifacts of the run are written to.
+    pub(crate) output: Option<PathBuf>,
+
+    /// Measurements stored by name.
+    pub(crate) measurements: MeasurementStore,
+
+    /// Baselines taken by tests with a BASELINE tolerance, by the position of the test in the
+    /// script.
+    pub(crate) baselines: MeasurementStore,
+
+    /// Text entered by the operator into each INPUTDIALOG, by the name of it's target.
+    pub(crate) inputs: BTreeMap<String, String>,
+
+    /// Target of the last INPUTDIALOG until the operator's text is entered.
+    pub(crate) awaiting_input: Option<String>,
+
+    /// Time each running timer was started, by name.
+    pub(crate) timers: BTreeMap<String, NaiveDateTime>,
+
+    /// Latencies of the responses to transactions, collected by name.
+    pub(crate) latencies: LatencyLog,
+
+    /// Whether the bytes transmitted by each transaction are reported to the frontend. Toggled by
+    /// PROTOCOL.
+    pub(crate) protocol: bool,
+
+    /// Whether the last test run passed. None until a test has been run. Checked by IF blocks.
+    pub(crate) last_result: Option<bool>,
+
+    /// Whether anything other than a CONFIRM has been evaluated.
+    pub(crate) started: bool,
+
+    /// Devices opened by the script.
+    pub(crate) open: BTreeSet<Device>,
+
+    /// TCU relays opened by the script and not yet closed, with the span of the command that
+    /// opened each.
+    pub(crate) relays: BTreeMap<u32, Range<usize>>,
+
+    /// Non-fatal issues found while evaluating the script.
+    pub(crate) diagnostics: Vec<Diagnostic>,
 }
 
 ////////////////////////////////////////////////////////////////
@@ -20,3 +133,36 @@ impl EvalState {
 }
 
 ////////////////////////////////////////////////////////////////
+// methods
+////////////////////////////////////////////////////////////////
+
+impl EvalState {
+    /// Reset any state accumulated while evaluating the script, keeping any configuration.
+    ///
+    pub fn restart(&mut self) {
+        *self = Self {
+            record: self.record,
+            clock: self.clock,
+            routing: self.routing.take(),
+            transport: self.transport,
+            capture: self.capture.take(),
+            results: self.results.take(),
+            observer: self.observer.take(),
+            echo: self.echo,
+            format: self.format,
+            unit: self.unit.clone(),
+            trigger: self.trigger,
+            comms_retries: self.comms_retries,
+            encoding: self.encoding,
+            checksum: self.checksum,
+            timeout_action: self.timeout_action,
+            device_log: self.device_log.take(),
+            session: self.session.take(),
+            assets: self.assets.take(),
+            output: self.output.take(),
+            ..Self::new()
+        };
+    }
+}
+
+////////////////////////////////////////////////////////////////
@@ -9,6 +9,9 @@ use std::{
 pub struct PortMock {
     pub rxdata: VecDeque<u8>,
     pub txdata: VecDeque<u8>,
+
+    /// Maximum number of bytes returned by each read. Unlimited if None.
+    pub read_size: Option<usize>,
 }
 
 ////////////////////////////////////////////////////////////////
@@ -18,6 +21,16 @@ impl PortMock {
         Self {
             rxdata: VecDeque::new(),
             txdata: VecDeque::new(),
+            read_size: None,
+        }
+    }
+
+    /// Create a mock that returns at most a single byte per read.
+    ///
+    pub fn byte_by_byte() -> Self {
+        Self {
+            read_size: Some(1),
+            ..Self::new()
         }
     }
 }
@@ -26,8 +39,10 @@ impl PortMock {
 
 impl Read for PortMock {
     fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let limit = self.read_size.unwrap_or(buf.len()).min(buf.len());
+
         let mut count = 0;
-        for byte in buf {
+        for byte in &mut buf[..limit] {
             if let Some(rxbyte) = self.rxdata.pop_front() {
                 *byte = rxbyte;
                 count += 1;
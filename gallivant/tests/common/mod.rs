@@ -1,14 +1,94 @@
-use gallivant::{FrontendRequest, Interpreter};
-
-pub mod mocks;
-
-////////////////////////////////////////////////////////////////
-
-pub fn interpret_script(script: &str) -> Vec<FrontendRequest> {
-    Interpreter::try_from_str(script)
-        .unwrap()
-        .map(|r| r.unwrap())
-        .collect()
-}
-
-////////////////////////////////////////////////////////////////
+#![allow(dead_code)]
+
+use gallivant::{Error, FrontendRequest, Interpreter, TransactionStatus};
+
+pub mod mocks;
+
+////////////////////////////////////////////////////////////////
+
+pub fn interpret_script(script: &str) -> Vec<FrontendRequest> {
+    Interpreter::try_from_str(script)
+        .unwrap()
+        .map(|r| r.unwrap())
+        .collect()
+}
+
+pub fn print(message: &str) -> FrontendRequest {
+    FrontendRequest::GuiPrint(message.to_owned())
+}
+
+////////////////////////////////////////////////////////////////
+
+/// Simulate the script's first transaction with the device returning each measurement in turn.
+///
+pub fn simulate(script: &str, measurements: &[u32]) -> Result<TransactionStatus, Error> {
+    let mut interpreter = Interpreter::try_from_str(script).unwrap();
+    match interpreter.next().unwrap().unwrap() {
+        FrontendRequest::TCUTransact(transaction) => {
+            transaction.simulate(measurements.iter().copied())
+        }
+        request => panic!("Expected a TCU transaction. Got: {request:?}"),
+    }
+}
+
+////////////////////////////////////////////////////////////////
+
+/// Run the script with the TCU returning each measurement in turn, recovering from any test
+/// failure the interpreter can. e.g. By retrying a block.
+///
+pub fn simulate_script(script: &str, measurements: &[u32]) -> Result<(), Error> {
+    let mut interpreter = Interpreter::try_from_str(script).unwrap();
+    let mut measurements = measurements.iter().copied();
+
+    while let Some(request) = interpreter.next() {
+        let FrontendRequest::TCUTransact(transaction) = request? else {
+            continue;
+        };
+
+        let measurement = measurements.next().expect("Ran out of measurements");
+        match transaction.simulate([measurement]) {
+            Ok(status) => assert!(matches!(status, TransactionStatus::Success { .. })),
+            Err(error) => interpreter.recover(error)?,
+        }
+    }
+
+    Ok(())
+}
+
+////////////////////////////////////////////////////////////////
+
+/// Run the script with the TCU returning each measurement in turn, until the first request other
+/// than a transaction taking a measurement.
+///
+pub fn run_until_request(
+    script: &str,
+    measurements: impl IntoIterator<Item = u32>,
+) -> Result<FrontendRequest, Error> {
+    let mut interpreter = Interpreter::try_from_str(script).unwrap();
+    next_request(&mut interpreter, &mut measurements.into_iter()).expect("Script ended")
+}
+
+/// Continue running the interpreter with the TCU returning each measurement in turn, until the
+/// next request other than a transaction taking a measurement.
+///
+/// # Returns
+/// None if the script ends first.
+///
+pub fn next_request(
+    interpreter: &mut Interpreter,
+    measurements: &mut impl Iterator<Item = u32>,
+) -> Option<Result<FrontendRequest, Error>> {
+    loop {
+        match interpreter.next()? {
+            Ok(FrontendRequest::TCUTransact(transaction)) if transaction.test().is_some() => {
+                match transaction.simulate(measurements.next()) {
+                    Ok(status) => assert!(matches!(status, TransactionStatus::Success { .. })),
+                    Err(error) => return Some(Err(error)),
+                }
+            }
+            result => return Some(result),
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////
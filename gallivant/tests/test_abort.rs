@@ -0,0 +1,138 @@
+use gallivant::{Error, ErrorReason, FrontendRequest, Interpreter, TransactionStatus};
+
+type Request = FrontendRequest;
+
+////////////////////////////////////////////////////////////////
+
+/// Run the script with the TCU returning each measurement in turn.
+///
+/// # Returns
+/// The comments printed, followed by the error that ended the run, if any.
+///
+fn run(interpreter: &mut Interpreter, measurements: &[u32]) -> (Vec<String>, Option<Error>) {
+    let mut measurements = measurements.iter().copied();
+    let mut comments = Vec::new();
+
+    while let Some(request) = interpreter.next() {
+        let result = request.and_then(|request| match request {
+            Request::GuiPrint(comment) => {
+                comments.push(comment);
+                Ok(())
+            }
+            Request::TCUTransact(transaction) => {
+                let measurement = measurements.next().expect("Ran out of measurements");
+                transaction.simulate([measurement]).map(|status| {
+                    assert!(matches!(status, TransactionStatus::Success { .. }));
+                })
+            }
+            _ => Ok(()),
+        });
+
+        if let Err(error) = result.or_else(|error| interpreter.recover(error)) {
+            return (comments, Some(error));
+        }
+    }
+
+    (comments, None)
+}
+
+////////////////////////////////////////////////////////////////
+
+const SCRIPT: &str = r#"
+ONABORT
+    COMMENT "De-energize"
+    TCUTEST 1, 0, 10, 0, "Still energized"
+    COMMENT "Home"
+ENDONABORT
+COMMENT "Start"
+TCUTEST 2, 0, 10, 0, "Out of range"
+COMMENT "End"
+ONABORT
+    COMMENT "Release"
+ENDONABORT
+"#;
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_completed() {
+    let mut interpreter = Interpreter::try_from_str(SCRIPT).unwrap();
+
+    // Cleanup is skipped when reached in the script.
+    let (comments, error) = run(&mut interpreter, &[5]);
+    assert!(error.is_none());
+    assert_eq!(comments, ["Start", "End"]);
+    assert!(!interpreter.is_aborted());
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_aborted() {
+    let mut interpreter = Interpreter::try_from_str(SCRIPT).unwrap();
+
+    let (comments, error) = run(&mut interpreter, &[20]);
+    assert_eq!(comments, ["Start"]);
+    assert!(matches!(
+        error.as_ref().map(Error::reason),
+        Some(ErrorReason::TestFailure { .. })
+    ));
+
+    // Every block is run in script order, whether or not it had been reached.
+    interpreter.abort();
+    let (comments, error) = run(&mut interpreter, &[5]);
+    assert!(error.is_none());
+    assert_eq!(comments, ["De-energize", "Home", "Release"]);
+
+    assert!(interpreter.is_aborted());
+    assert!(interpreter.next().is_none());
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_cleanup_error() {
+    let mut interpreter = Interpreter::try_from_str(SCRIPT).unwrap();
+    interpreter.abort();
+
+    // An error within the cleanup is returned without ending it.
+    let (comments, error) = run(&mut interpreter, &[20]);
+    assert_eq!(comments, ["De-energize"]);
+    assert!(error.is_some());
+
+    // Aborting again doesn't restart the cleanup.
+    interpreter.abort();
+    let (comments, error) = run(&mut interpreter, &[]);
+    assert!(error.is_none());
+    assert_eq!(comments, ["Home", "Release"]);
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_restart() {
+    let mut interpreter = Interpreter::try_from_str(SCRIPT).unwrap();
+    interpreter.abort();
+    interpreter.restart();
+
+    assert!(!interpreter.is_aborted());
+    let (comments, _) = run(&mut interpreter, &[5]);
+    assert_eq!(comments, ["Start", "End"]);
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_nested() {
+    let script = r#"
+RETRY 3
+    ONABORT
+        COMMENT "De-energize"
+    ENDONABORT
+ENDRETRY
+"#;
+
+    assert!(Interpreter::try_from_str(script).is_err());
+}
+
+////////////////////////////////////////////////////////////////
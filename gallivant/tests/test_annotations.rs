@@ -0,0 +1,237 @@
+use chrono::{NaiveDate, NaiveDateTime};
+use gallivant::{Clock, FrontendRequest, Interpreter, Shuffle};
+
+type Request = FrontendRequest;
+
+////////////////////////////////////////////////////////////////
+
+fn at(hour: u32, minute: u32) -> Clock {
+    let datetime: NaiveDateTime = NaiveDate::from_ymd_opt(2023, 6, 1)
+        .unwrap()
+        .and_hms_opt(hour, minute, 0)
+        .unwrap();
+
+    Clock::Fixed(datetime)
+}
+
+fn run(script: &str, clock: Clock) -> Vec<Request> {
+    Interpreter::try_from_str(script)
+        .unwrap()
+        .with_clock(clock)
+        .map(|r| r.unwrap())
+        .collect()
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_window_inside() {
+    let script = r#"
+@window "09:00", "17:00"
+COMMENT "Daytime"
+COMMENT "Always"
+    "#;
+
+    assert_eq!(
+        run(script, at(12, 0)),
+        [
+            Request::GuiPrint("Daytime".to_owned()),
+            Request::GuiPrint("Always".to_owned()),
+        ]
+    );
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_window_outside() {
+    let script = r#"
+@window "09:00", "17:00"
+COMMENT "Daytime"
+COMMENT "Always"
+    "#;
+
+    let requests = run(script, at(17, 0));
+    assert!(matches!(
+        &requests[..],
+        [Request::Skipped { .. }, Request::GuiPrint(_)]
+    ));
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_window_wraps_midnight() {
+    let script = r#"
+@window "22:00", "06:00"
+COMMENT "Night"
+    "#;
+
+    assert_eq!(
+        run(script, at(1, 30)),
+        [Request::GuiPrint("Night".to_owned())]
+    );
+    assert!(matches!(
+        run(script, at(12, 0))[..],
+        [Request::Skipped { .. }]
+    ));
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_window_skips_block() {
+    let script = r#"
+@window "09:00", "17:00"
+RETRY 2
+    COMMENT "One"
+    COMMENT "Two"
+ENDRETRY
+COMMENT "After"
+    "#;
+
+    let requests = run(script, at(8, 59));
+    assert!(matches!(
+        &requests[..],
+        [Request::Skipped { .. }, Request::GuiPrint(_)]
+    ));
+
+    assert_eq!(run(script, at(9, 0)).len(), 3);
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_step() {
+    let script = r#"
+@step "Calibrating print head"
+COMMENT "One"
+@step "Calibrating print head"
+COMMENT "Two"
+COMMENT "Three"
+@step "Feeding paper"
+COMMENT "Four"
+    "#;
+
+    let step = |description: &str| Request::Step(description.to_owned());
+    let print = |message: &str| Request::GuiPrint(message.to_owned());
+    assert_eq!(
+        run(script, at(12, 0)),
+        [
+            step("Calibrating print head"),
+            print("One"),
+            print("Two"),
+            print("Three"),
+            step("Feeding paper"),
+            print("Four"),
+        ]
+    );
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_step_block() {
+    let script = r#"
+@step "Calibrating print head"
+RETRY 2
+    COMMENT "One"
+    @step "Feeding paper"
+    COMMENT "Two"
+    COMMENT "Three"
+ENDRETRY
+@step "Calibrating print head"
+COMMENT "Four"
+    "#;
+
+    let step = |description: &str| Request::Step(description.to_owned());
+    let print = |message: &str| Request::GuiPrint(message.to_owned());
+    assert_eq!(
+        run(script, at(12, 0)),
+        [
+            step("Calibrating print head"),
+            print("One"),
+            step("Feeding paper"),
+            print("Two"),
+            step("Calibrating print head"),
+            print("Three"),
+            print("Four"),
+        ]
+    );
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_settime_uses_clock() {
+    let requests = run("SETTIME", at(13, 45));
+    let [Request::TCUTransact(transaction)] = &requests[..] else {
+        panic!("Unexpected requests {requests:?}");
+    };
+
+    // "13:45:00,01/06/23" in the TCU's hex format.
+    assert_eq!(
+        transaction.bytes(),
+        b"P151B747331333A34353A30302C30312F30362F3233\r"
+    );
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_independent() {
+    let script = r#"
+COMMENT "Setup"
+RETRY 2
+    @independent
+    COMMENT "A"
+    @independent
+    COMMENT "B"
+    @independent
+    COMMENT "C"
+    @independent
+    COMMENT "D"
+ENDRETRY
+COMMENT "Teardown"
+    "#;
+
+    let comments = |interpreter: Interpreter| -> Vec<String> {
+        interpreter
+            .map(|request| match request.unwrap() {
+                Request::GuiPrint(message) => message,
+                request => panic!("Expected a comment. Got: {request:?}"),
+            })
+            .collect()
+    };
+
+    let interpreter = Interpreter::try_from_str(script).unwrap();
+    assert_eq!(
+        comments(interpreter.clone()),
+        ["Setup", "A", "B", "C", "D", "Teardown"]
+    );
+
+    let orders: Vec<Vec<String>> = (0..8)
+        .map(|seed| comments(interpreter.clone().with_shuffle(Shuffle::new(seed))))
+        .collect();
+
+    for order in &orders {
+        assert_eq!(order[0], "Setup");
+        assert_eq!(order[5], "Teardown");
+
+        let mut tests = order[1..5].to_vec();
+        tests.sort();
+        assert_eq!(tests, ["A", "B", "C", "D"]);
+    }
+    assert!(orders
+        .iter()
+        .any(|order| order[1..5] != ["A", "B", "C", "D"]));
+
+    // The same seed gives the same order, including after a restart.
+    let mut shuffled = interpreter.with_shuffle(Shuffle::new(3));
+    assert_eq!(comments(shuffled.clone()), orders[3]);
+    shuffled.by_ref().for_each(drop);
+    shuffled.restart();
+    assert_eq!(comments(shuffled), orders[3]);
+}
+
+////////////////////////////////////////////////////////////////
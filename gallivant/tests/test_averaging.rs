@@ -0,0 +1,89 @@
+use gallivant::{ErrorReason, FrontendRequest, Interpreter, TransactionStatus};
+
+type Request = FrontendRequest;
+
+mod common;
+use common::simulate;
+
+////////////////////////////////////////////////////////////////
+
+const SCRIPT: &str = r#"TCUTEST 3, 1000, 2000, 0, "Supply out of range", AVERAGE 3"#;
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_average_passes() {
+    // The second reading is out of range but the average of all three isn't.
+    let status = simulate(SCRIPT, &[1500, 2400, 1200]).unwrap();
+    assert!(matches!(status, TransactionStatus::Success { .. }));
+
+    let script = r#"PRINTERTEST 3, 1000, 2000, 0, "Sensor out of range", AVERAGE 3"#;
+    let status = simulate(script, &[1500, 2400, 1200]).unwrap();
+    assert!(matches!(status, TransactionStatus::Success { .. }));
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_too_few_readings() {
+    let status = simulate(SCRIPT, &[1500, 2400]).unwrap();
+    assert!(matches!(status, TransactionStatus::Ongoing(_)));
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_average_fails() {
+    // Only the first reading is in range.
+    let error = simulate(SCRIPT, &[1500, 2400, 2300]).unwrap_err();
+    let ErrorReason::TestFailure { test, .. } = error.reason() else {
+        panic!("Expected a test failure. Got: {:?}", error.reason());
+    };
+
+    assert_eq!(test.measurement, 2066);
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_retry() {
+    // The retry takes a fresh set of readings.
+    let script = r#"TCUTEST 3, 1000, 2000, 1, "Supply out of range", AVERAGE 2"#;
+    let status = simulate(script, &[2400, 2200, 900, 1200]).unwrap();
+    assert!(matches!(status, TransactionStatus::Success { .. }));
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_recorded_average() {
+    let mut interpreter = Interpreter::try_from_str(SCRIPT)
+        .unwrap()
+        .with_record_mode(true);
+    let Some(Ok(Request::TCUTransact(transaction))) = interpreter.next() else {
+        panic!("Expected a TCU transaction");
+    };
+
+    assert!(matches!(
+        transaction.simulate([10, 20, 40]),
+        Ok(TransactionStatus::Recorded {
+            measurement: 23,
+            ..
+        })
+    ));
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_no_samples_warned() {
+    let script = r#"TCUTEST 3, 1000, 2000, 0, "Supply out of range", AVERAGE 0"#;
+    let evaluation = Interpreter::try_from_str(script).unwrap().evaluate();
+    assert_eq!(evaluation.diagnostics.len(), 1);
+
+    // A single measurement is tested.
+    let status = simulate(script, &[1500]).unwrap();
+    assert!(matches!(status, TransactionStatus::Success { .. }));
+}
+
+////////////////////////////////////////////////////////////////
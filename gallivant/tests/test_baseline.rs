@@ -0,0 +1,76 @@
+use gallivant::{ErrorReason, Expected, FrontendRequest, Interpreter};
+
+type Request = FrontendRequest;
+
+mod common;
+use common::simulate_script;
+
+////////////////////////////////////////////////////////////////
+
+/// Soak test. The second test fails until the third attempt, re-running the baseline test.
+///
+const SCRIPT: &str = r#"
+RETRY 3
+    TCUTEST 3, BASELINE 2, 0, "Drifted"
+    TCUTEST 4, 1, 1, 0, "Not settled"
+ENDRETRY
+"#;
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_baseline_captured() {
+    // Any first reading is accepted as the baseline.
+    simulate_script(SCRIPT, &[5, 1]).unwrap();
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_within_tolerance() {
+    simulate_script(SCRIPT, &[1000, 0, 1020, 0, 980, 1]).unwrap();
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_drift() {
+    let error = simulate_script(SCRIPT, &[1000, 0, 1015, 0, 1021]).unwrap_err();
+    let ErrorReason::TestFailure { test, .. } = error.reason() else {
+        panic!("Expected a test failure. Got: {:?}", error.reason());
+    };
+
+    assert_eq!(test.measurement, 1021);
+    assert_eq!(
+        test.expected,
+        Expected::Baseline {
+            tolerance: 2,
+            baseline: Some(1000)
+        }
+    );
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_baseline_per_run() {
+    // Each run takes it's own baseline.
+    let mut interpreter = Interpreter::try_from_str(SCRIPT).unwrap();
+    for measurement in [1000, 2000] {
+        let Some(Ok(Request::TCUTransact(transaction))) = interpreter.next() else {
+            panic!("Expected a TCU transaction");
+        };
+        assert_eq!(
+            transaction.test().map(|test| &test.expected),
+            Some(&Expected::Baseline {
+                tolerance: 2,
+                baseline: None
+            })
+        );
+
+        transaction.simulate([measurement]).unwrap();
+        interpreter.restart();
+    }
+}
+
+////////////////////////////////////////////////////////////////
@@ -0,0 +1,48 @@
+use gallivant::{ErrorReason, Expected, Interpreter};
+
+mod common;
+use common::simulate_script;
+
+////////////////////////////////////////////////////////////////
+
+/// Latched fault flags. Bit 3 must be set and bits 5-6 must equal 2.
+///
+const SCRIPT: &str = r#"TCUTEST 7, BITS [$08 = $08, $60 = $40], 0, "Fault latched""#;
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_bitfield_pass() {
+    simulate_script(SCRIPT, &[0x48]).unwrap();
+
+    // Bits outside of the masks are ignored.
+    simulate_script(SCRIPT, &[0xFFFF_FFDF]).unwrap();
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_bitfield_fail() {
+    let error = simulate_script(SCRIPT, &[0x68]).unwrap_err();
+    let ErrorReason::TestFailure { test, .. } = error.reason() else {
+        panic!("Expected a test failure. Got: {:?}", error.reason());
+    };
+
+    assert_eq!(test.measurement, 0x60);
+    assert_eq!(test.expected, Expected::Bitfield(vec![(0x60, 0x40)]));
+    assert_eq!(test.message, "Fault latched");
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_bitfield_unsatisfiable() {
+    let script = r#"TCUTEST 7, BITS [$60 = $80], 0, "Fault latched""#;
+    let diagnostics = Interpreter::try_from_str(script).unwrap().analyze();
+
+    assert!(diagnostics
+        .iter()
+        .any(|diagnostic| diagnostic.message().contains("outside of it's mask")));
+}
+
+////////////////////////////////////////////////////////////////
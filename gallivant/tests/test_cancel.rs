@@ -0,0 +1,61 @@
+use gallivant::{FrontendRequest, Interpreter};
+
+type Request = FrontendRequest;
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_cancel() {
+    let script = r#"TCUOPEN 1
+TCUOPEN 2
+TCUCLOSE 1
+COMMENT "a"
+COMMENT "b""#;
+    let mut interpreter = Interpreter::try_from_str(script).unwrap();
+
+    for _ in 0..4 {
+        interpreter.next().unwrap().unwrap();
+    }
+
+    // Only the relay left open is closed.
+    let requests = interpreter.cancel();
+    assert!(interpreter.is_cancelled());
+    assert!(matches!(
+        &requests[..],
+        [Request::TCUTransact(close)] if close.bytes() == b"C02\r"
+    ));
+
+    assert!(interpreter.next().is_none());
+    assert!(interpreter.next().is_none());
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_cancel_closes_usb() {
+    let script = r#"TCUOPEN 3
+USBOPEN
+COMMENT "a""#;
+    let mut interpreter = Interpreter::try_from_str(script).unwrap();
+
+    for _ in 0..2 {
+        interpreter.next().unwrap().unwrap();
+    }
+
+    let requests = interpreter.cancel();
+    assert!(matches!(
+        &requests[..],
+        [Request::TCUTransact(close), Request::PrinterClose] if close.bytes() == b"C03\r"
+    ));
+    assert!(interpreter.next().is_none());
+
+    // Nothing is left open to close again.
+    assert!(interpreter.cancel().is_empty());
+
+    // Runs again once restarted.
+    interpreter.restart();
+    assert!(!interpreter.is_cancelled());
+    assert!(interpreter.next().is_some());
+}
+
+////////////////////////////////////////////////////////////////
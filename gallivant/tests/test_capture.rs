@@ -0,0 +1,41 @@
+use gallivant::{Capture, FrontendRequest, Interpreter, TransactionStatus};
+
+type Request = FrontendRequest;
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_capture_by_transaction_index() {
+    let script = "PRINTERSET 1\nTCUTEST 3, 10, 20, 1, \"Out of range\"";
+
+    let capture = Capture::new();
+    let requests: Vec<Request> = Interpreter::try_from_str(script)
+        .unwrap()
+        .with_capture(capture.clone())
+        .map(|r| r.unwrap())
+        .collect();
+
+    for (request, measurement) in requests.into_iter().zip([None, Some([0u32, 15])]) {
+        let Request::TCUTransact(transaction) = request else {
+            panic!("Expected a TCU transaction");
+        };
+
+        let status = match measurement {
+            Some(measurements) => transaction.simulate(measurements),
+            None => transaction.simulate([]),
+        };
+        assert!(!matches!(status, Ok(TransactionStatus::Ongoing(_))));
+    }
+
+    assert_eq!(capture.len(), 2);
+    assert_eq!(capture.get(0).unwrap().sent(), b"P051B005301\r");
+    assert_eq!(capture.get(0).unwrap().received(), b"P051B005301\r");
+
+    // The retry's exchange is retained alongside the original.
+    let test = capture.get(1).unwrap();
+    assert_eq!(test.sent(), b"M03\rM03\r");
+    assert_eq!(test.received(), b"M03\r0000\rM03\r000F\r");
+    assert!(!test.truncated());
+}
+
+////////////////////////////////////////////////////////////////
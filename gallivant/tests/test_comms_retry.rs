@@ -0,0 +1,235 @@
+use std::{
+    collections::VecDeque,
+    io::{self, Read, Write},
+    time::Duration,
+};
+
+use gallivant::{
+    Error, ErrorKind, ErrorNote, ErrorReason, FrontendRequest, Interpreter, Transaction,
+    TransactionStatus,
+};
+
+////////////////////////////////////////////////////////////////
+
+/// TCU that echoes each command and responds with a measurement, but times out or garbles the
+/// echo the given number of times first.
+///
+struct FlakyTCU {
+    rxdata: VecDeque<u8>,
+    measurement: &'static [u8],
+    timeouts: u32,
+    bad_echoes: u32,
+}
+
+impl FlakyTCU {
+    fn new(measurement: &'static [u8]) -> Self {
+        Self {
+            rxdata: VecDeque::new(),
+            measurement,
+            timeouts: 0,
+            bad_echoes: 0,
+        }
+    }
+}
+
+impl Read for FlakyTCU {
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        if self.timeouts > 0 {
+            self.timeouts -= 1;
+            return Err(io::ErrorKind::TimedOut.into());
+        }
+
+        let count = buffer.len().min(self.rxdata.len());
+        for (byte, rxbyte) in buffer.iter_mut().zip(self.rxdata.drain(..count)) {
+            *byte = rxbyte;
+        }
+        Ok(count)
+    }
+}
+
+impl Write for FlakyTCU {
+    fn write(&mut self, buffer: &[u8]) -> io::Result<usize> {
+        if self.bad_echoes > 0 {
+            self.bad_echoes -= 1;
+            self.rxdata.extend(b"X03\r");
+        } else {
+            self.rxdata.extend(buffer);
+        }
+
+        self.rxdata.extend(self.measurement);
+        Ok(buffer.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+////////////////////////////////////////////////////////////////
+
+fn tcu_transaction(script: &str, comms_retries: u32) -> Transaction {
+    let mut interpreter = Interpreter::try_from_str(script)
+        .unwrap()
+        .with_comms_retries(comms_retries);
+
+    match interpreter.next().unwrap().unwrap() {
+        FrontendRequest::TCUTransact(transaction) => transaction,
+        request => panic!("Expected a TCU transaction. Got: {request:?}"),
+    }
+}
+
+fn run(mut transaction: Transaction, port: &mut FlakyTCU) -> Result<(), Error> {
+    loop {
+        transaction = match transaction.process(port)? {
+            TransactionStatus::Ongoing(transaction) => transaction,
+            _ => return Ok(()),
+        };
+    }
+}
+
+////////////////////////////////////////////////////////////////
+
+const SCRIPT: &str = r#"TCUTEST 3, 0, 16, 0, "FAIL""#;
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_timeout_retried() {
+    let mut port = FlakyTCU::new(b"0010\r");
+    port.timeouts = 2;
+
+    assert!(run(tcu_transaction(SCRIPT, 2), &mut port).is_ok());
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_timeout_retries_exhausted() {
+    let mut port = FlakyTCU::new(b"0010\r");
+    port.timeouts = 2;
+
+    let error = run(tcu_transaction(SCRIPT, 1), &mut port).unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::Timeout);
+    assert_eq!(
+        error.notes(),
+        [ErrorNote::Note(
+            "Failed after using all of the transaction's comms retries"
+        )]
+    );
+
+    // Without comms retries the first timeout fails the transaction.
+    let mut port = FlakyTCU::new(b"0010\r");
+    port.timeouts = 1;
+
+    let error = run(tcu_transaction(SCRIPT, 0), &mut port).unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::Timeout);
+    assert!(error.notes().is_empty());
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_response_timeout_port_timeouts() {
+    // The port's own timeouts are waited through while within the response timeout.
+    let mut port = FlakyTCU::new(b"0010\r");
+    port.timeouts = 3;
+
+    let transaction = tcu_transaction(SCRIPT, 0).response_timeout(Duration::from_secs(60));
+    assert!(run(transaction, &mut port).is_ok());
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_echo_mismatch() {
+    let mut port = FlakyTCU::new(b"0010\r");
+    port.bad_echoes = 1;
+
+    let error = run(tcu_transaction(SCRIPT, 0), &mut port).unwrap_err();
+    assert!(matches!(
+        error.reason(),
+        ErrorReason::EchoMismatch { expected, received, .. }
+            if expected == b"M03\r" && received == b"X03\r"
+    ));
+    assert_eq!(error.kind(), ErrorKind::Comms);
+
+    let mut port = FlakyTCU::new(b"0010\r");
+    port.bad_echoes = 1;
+
+    assert!(run(tcu_transaction(SCRIPT, 1), &mut port).is_ok());
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_echo_mismatch_untested() {
+    // Commands without a test are still only complete once correctly echoed.
+    let mut port = FlakyTCU::new(b"");
+    port.bad_echoes = 1;
+
+    let error = run(tcu_transaction("TCUOPEN 3", 0), &mut port).unwrap_err();
+    assert!(matches!(
+        error.reason(),
+        ErrorReason::EchoMismatch { expected, received, .. }
+            if expected != received && received == b"X03\r"
+    ));
+    assert_eq!(error.span(), Some(0..9));
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_invalid_measurement() {
+    let mut port = FlakyTCU::new(b"00G1\r");
+
+    let error = run(tcu_transaction(SCRIPT, 1), &mut port).unwrap_err();
+    assert!(matches!(
+        error.reason(),
+        ErrorReason::InvalidMeasurement { format: "hex", response, .. } if response == b"00G1\r"
+    ));
+    assert_eq!(error.kind(), ErrorKind::Comms);
+    assert_eq!(
+        error.notes(),
+        [ErrorNote::Note(
+            "Failed after using all of the transaction's comms retries"
+        )]
+    );
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_invalid_measurement_bcd() {
+    let mut port = FlakyTCU::new(b"\x12\x3F\r");
+
+    let transaction = tcu_transaction(SCRIPT, 0).bcd_measurement(2);
+    let error = run(transaction, &mut port).unwrap_err();
+    assert!(matches!(
+        error.reason(),
+        ErrorReason::InvalidMeasurement {
+            format: "packed BCD",
+            ..
+        }
+    ));
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_retries_separate() {
+    // Comms retries aren't used by failing measurements.
+    let script = r#"TCUTEST 3, 0, 16, 1, "FAIL""#;
+    let mut port = FlakyTCU::new(b"0020\r");
+    port.timeouts = 1;
+
+    let error = run(tcu_transaction(script, 1), &mut port).unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::TestFailure);
+    assert_eq!(
+        error.notes(),
+        [ErrorNote::Note(
+            "Failed after using all of the test's retries"
+        )]
+    );
+}
+
+////////////////////////////////////////////////////////////////
@@ -0,0 +1,109 @@
+use gallivant::{Error, ErrorReason, FrontendRequest};
+
+type Request = FrontendRequest;
+
+mod common;
+use common::run_until_request;
+
+////////////////////////////////////////////////////////////////
+
+/// Run a script, simulating the TCU returning each measurement in turn. Return the bytes sent by
+/// the first transaction that doesn't take a measurement.
+///
+fn run(script: &str, measurements: impl IntoIterator<Item = u32>) -> Result<Vec<u8>, Error> {
+    match run_until_request(script, measurements)? {
+        Request::TCUTransact(transaction) => Ok(transaction.bytes().to_vec()),
+        request => panic!("Expected a TCU transaction. Got: {request:?}"),
+    }
+}
+
+////////////////////////////////////////////////////////////////
+
+const SCRIPT: &str = r#"
+TCUMEASURE "trim", 1
+TCUMEASURE "offset", 2
+SETOPTION 7, (trim - offset) / 10 + 4
+"#;
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_computed() {
+    assert_eq!(run(SCRIPT, [1000, 500]).unwrap(), b"P061B004F0736\r");
+    assert_eq!(run(SCRIPT, [509, 500]).unwrap(), b"P061B004F0704\r");
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_negative() {
+    let error = run(SCRIPT, [500, 1000]).unwrap_err();
+    let ErrorReason::ArithmeticError { lhs, rhs, .. } = error.reason() else {
+        panic!("Expected an arithmetic error. Got: {:?}", error.reason());
+    };
+    assert_eq!((*lhs, *rhs), (500, 1000));
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_overflow() {
+    let script = r#"
+TCUMEASURE "trim", 1
+SETOPTION 7, trim * 2 / trim
+"#;
+
+    assert!(run(script, [u32::MAX / 2]).is_ok());
+
+    let error = run(script, [u32::MAX / 2 + 1]).unwrap_err();
+    assert!(matches!(
+        error.reason(),
+        ErrorReason::ArithmeticError { rhs: 2, .. }
+    ));
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_division_by_zero() {
+    let script = r#"
+TCUMEASURE "trim", 1
+SETOPTION 7, 100 / trim
+"#;
+
+    let error = run(script, [0]).unwrap_err();
+    assert!(matches!(
+        error.reason(),
+        ErrorReason::ArithmeticError {
+            lhs: 100,
+            rhs: 0,
+            ..
+        }
+    ));
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_out_of_range() {
+    let error = run(SCRIPT, [3000, 0]).unwrap_err();
+    assert!(matches!(
+        error.reason(),
+        ErrorReason::SettingOutOfRange { value: 304, .. }
+    ));
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_unknown_measurement() {
+    let script = "SETOPTION 7, trim + 1";
+
+    let error = run(script, []).unwrap_err();
+    assert!(matches!(
+        error.reason(),
+        ErrorReason::UnknownMeasurement { name, .. } if name == "trim"
+    ));
+}
+
+////////////////////////////////////////////////////////////////
@@ -0,0 +1,98 @@
+use gallivant::{Dialog, Error, ErrorReason, FrontendRequest, Interpreter};
+
+type Request = FrontendRequest;
+
+////////////////////////////////////////////////////////////////
+
+const SCRIPT: &str = r#"
+CONFIRM "Fixture clamped"
+CONFIRM "Cover closed"
+PRINTERSET 1
+"#;
+
+////////////////////////////////////////////////////////////////
+
+fn is_confirmation(result: Option<Result<Request, Error>>, expected: &str) -> bool {
+    matches!(
+        result,
+        Some(Ok(Request::GuiDialogue {
+            kind: Dialog::Confirmation,
+            message,
+        })) if message == expected
+    )
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_confirmed() {
+    let mut interpreter = Interpreter::try_from_str(SCRIPT).unwrap();
+
+    assert!(is_confirmation(interpreter.next(), "Fixture clamped"));
+    interpreter.confirm(true);
+    assert!(is_confirmation(interpreter.next(), "Cover closed"));
+    interpreter.confirm(true);
+
+    assert!(matches!(
+        interpreter.next(),
+        Some(Ok(Request::TCUTransact(_)))
+    ));
+    assert!(interpreter.next().is_none());
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_declined() {
+    let mut interpreter = Interpreter::try_from_str(SCRIPT).unwrap();
+
+    assert!(is_confirmation(interpreter.next(), "Fixture clamped"));
+    interpreter.confirm(true);
+    assert!(is_confirmation(interpreter.next(), "Cover closed"));
+    interpreter.confirm(false);
+
+    let result = interpreter.next().unwrap();
+    assert!(matches!(
+        result.as_ref().map_err(Error::reason),
+        Err(ErrorReason::Unconfirmed { .. })
+    ));
+
+    // Nothing is run once a check is declined.
+    assert!(interpreter.next().is_none());
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_unanswered() {
+    let mut interpreter = Interpreter::try_from_str(SCRIPT).unwrap();
+
+    assert!(is_confirmation(interpreter.next(), "Fixture clamped"));
+    assert!(matches!(interpreter.next(), Some(Err(_))));
+    assert!(interpreter.next().is_none());
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_late_confirmation() {
+    let script = "PRINTERSET 1\nCONFIRM \"Cover closed\"";
+    let results: Vec<Result<Request, Error>> = Interpreter::try_from_str(script).unwrap().collect();
+
+    assert!(results[0].is_ok());
+    assert!(matches!(
+        results[1].as_ref().map_err(Error::reason),
+        Err(ErrorReason::LateConfirmation { .. })
+    ));
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_evaluation_assumes_confirmed() {
+    let evaluation = Interpreter::try_from_str(SCRIPT).unwrap().evaluate();
+    assert_eq!(evaluation.requests.len(), 3);
+    assert!(evaluation.diagnostics.is_empty());
+}
+
+////////////////////////////////////////////////////////////////
@@ -0,0 +1,100 @@
+use gallivant::{CrossCheck, CrossCheckStatus, Error, ErrorReason, FrontendRequest, Results};
+
+type Request = FrontendRequest;
+
+mod common;
+use common::{interpret_script, mocks::PortMock};
+
+////////////////////////////////////////////////////////////////
+
+fn cross_check(script: &str) -> CrossCheck {
+    match interpret_script(script).remove(0) {
+        Request::CrossCheck(check) => check,
+        request => panic!("Expected a cross check. Got: {request:?}"),
+    }
+}
+
+////////////////////////////////////////////////////////////////
+
+/// Run a cross check with the TCU echoing each command followed by the next DUT measurement and
+/// the reference device responding with the next reference measurement.
+///
+fn run(mut check: CrossCheck, dut: &[&[u8]], reference: &[&[u8]]) -> Result<(), Error> {
+    let mut tcu = PortMock::new();
+    let mut meter = PortMock::new();
+    let (mut dut, mut reference) = (dut.iter(), reference.iter());
+
+    loop {
+        let (tcu_sent, meter_sent) = (tcu.txdata.len(), meter.txdata.len());
+
+        check = match check.process(&mut tcu, &mut meter)? {
+            CrossCheckStatus::Success => return Ok(()),
+            CrossCheckStatus::Ongoing(check) => check,
+            CrossCheckStatus::Recorded { .. } => panic!("Unexpected recording"),
+        };
+
+        if tcu.txdata.len() > tcu_sent {
+            let echo: Vec<u8> = tcu.txdata.drain(..).collect();
+            tcu.rxdata.extend(echo);
+            tcu.rxdata
+                .extend(*dut.next().expect("Out of DUT measurements"));
+        }
+
+        if meter.txdata.len() > meter_sent {
+            assert_eq!(meter.txdata.drain(..).collect::<Vec<u8>>(), b"M02\r");
+            meter
+                .rxdata
+                .extend(*reference.next().expect("Out of reference measurements"));
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_cross_check_within_tolerance() {
+    let check = cross_check(r#"REFTEST 1, 2, 5, 0, "Disagrees with reference""#);
+    assert!(run(check.clone(), &[b"0064\r"], &[b"0069\r"]).is_ok());
+    assert!(run(check, &[b"0069\r"], &[b"0064\r"]).is_ok());
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_cross_check_outside_tolerance() {
+    let check = cross_check(r#"REFTEST 1, 2, 5, 0, "Disagrees with reference""#);
+    let error = run(check, &[b"0064\r"], &[b"006A\r"]).unwrap_err();
+
+    let ErrorReason::TestFailure { test, .. } = error.reason() else {
+        panic!("Expected a test failure. Got: {error:?}");
+    };
+    assert_eq!(test.measurement, 100);
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_cross_check_retry() {
+    let check = cross_check(r#"REFTEST 1, 2, 5, 1, "Disagrees with reference""#);
+    let result = run(check, &[b"0000\r", b"0064\r"], &[b"0064\r", b"0064\r"]);
+    assert!(result.is_ok());
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_cross_check_reports_outcome() {
+    let results = Results::new();
+    let check =
+        cross_check(r#"REFTEST 1, 2, 5, 1, "Disagrees with reference""#).reporting(results.clone());
+
+    assert!(run(check, &[b"0064\r", b"0070\r"], &[b"0070\r", b"0070\r"]).is_ok());
+
+    let outcomes = results.outcomes();
+    assert_eq!(outcomes.len(), 1);
+    assert_eq!(outcomes[0].channel, 1);
+    assert_eq!(outcomes[0].measured, 0);
+    assert!(outcomes[0].passed);
+}
+
+////////////////////////////////////////////////////////////////
@@ -0,0 +1,93 @@
+use std::time::Duration;
+
+use gallivant::{FrontendRequest, Interpreter, SyntaxErrorReason};
+
+type Request = FrontendRequest;
+
+mod common;
+use common::interpret_script;
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_define() {
+    let script = r#"
+SET settle 250
+SET label "t"
+SET code $F3
+WAIT $settle
+PRINT $label, 123, $code
+"#;
+
+    let requests = interpret_script(script);
+    assert_eq!(requests[..3], [Request::None, Request::None, Request::None]);
+    assert_eq!(requests[3], Request::Wait(Duration::from_millis(250)));
+
+    let Request::TCUTransact(print) = &requests[4] else {
+        panic!("Expected a PRINT. Got: {:?}", requests[4]);
+    };
+    assert_eq!(print.bytes(), b"P06747BF3\r");
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_redefine() {
+    let script = "SET settle 10\nWAIT $settle\nSET settle 20\nWAIT $settle";
+
+    let requests: Vec<Request> = interpret_script(script)
+        .into_iter()
+        .filter(|request| *request != Request::None)
+        .collect();
+    assert_eq!(
+        requests,
+        [
+            Request::Wait(Duration::from_millis(10)),
+            Request::Wait(Duration::from_millis(20))
+        ]
+    );
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_undefined() {
+    let script = "WAIT $settle\nSET settle 10";
+    let errors = Interpreter::try_parse(script).unwrap_err();
+
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].span(), Some(5..12));
+    assert!(matches!(
+        errors[0].reason(),
+        SyntaxErrorReason::UndefinedReference { name, .. } if name == "settle"
+    ));
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_requirements() {
+    // A string where an unsigned integer is required.
+    let errors = Interpreter::try_parse("SET settle \"10\"\nWAIT $settle").unwrap_err();
+    assert!(matches!(
+        errors[..],
+        [ref error] if matches!(error.reason(), SyntaxErrorReason::ArgType { .. })
+    ));
+
+    // Out of range for a channel.
+    let errors = Interpreter::try_parse("SET channel 300\nTCUOPEN $channel").unwrap_err();
+    assert!(matches!(
+        errors[..],
+        [ref error] if matches!(error.reason(), SyntaxErrorReason::ArgValue { value: 300, .. })
+    ));
+    assert_eq!(errors[0].span(), Some(24..32));
+
+    // A string where a channel is required.
+    let errors = Interpreter::try_parse("SET channel \"hi\"\nTCUOPEN $channel").unwrap_err();
+    assert!(matches!(
+        errors[..],
+        [ref error] if matches!(error.reason(), SyntaxErrorReason::ArgType { .. })
+    ));
+}
+
+////////////////////////////////////////////////////////////////
@@ -0,0 +1,100 @@
+use std::io::{self, Read, Write};
+
+use chrono::NaiveDate;
+
+use gallivant::{
+    Clock, Device, DeviceAction, DeviceLog, FrontendRequest, Interpreter, Outcome,
+    TransactionStatus,
+};
+
+////////////////////////////////////////////////////////////////
+
+/// Port that fails every read and write.
+///
+struct BrokenPort;
+
+impl Read for BrokenPort {
+    fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+        Err(io::ErrorKind::BrokenPipe.into())
+    }
+}
+
+impl Write for BrokenPort {
+    fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+        Err(io::ErrorKind::BrokenPipe.into())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_device_lifecycle() {
+    let script = "USBOPEN\nTCUOPEN 1\nTCUCLOSE 1\nUSBCLOSE";
+    let time = NaiveDate::from_ymd_opt(2023, 6, 1)
+        .unwrap()
+        .and_hms_opt(12, 0, 0)
+        .unwrap();
+
+    let log = DeviceLog::new();
+    let mut interpreter = Interpreter::try_from_str(script)
+        .unwrap()
+        .with_clock(Clock::Fixed(time))
+        .with_device_log(log.clone());
+
+    while let Some(request) = interpreter.next() {
+        let status = match request.unwrap() {
+            FrontendRequest::TCUTransact(transaction) if transaction.bytes() == b"O01\r" => {
+                transaction.process(&mut BrokenPort)
+            }
+            FrontendRequest::TCUTransact(transaction) => transaction.simulate([]),
+            _ => Ok(TransactionStatus::Success { measurement: None }),
+        };
+
+        if let Err(error) = status {
+            assert!(interpreter.recover(error).is_err());
+        }
+    }
+
+    let events: Vec<(Device, DeviceAction, &str)> = log
+        .events()
+        .iter()
+        .map(|event| {
+            assert_eq!(event.time(), time);
+            let outcome = match event.outcome() {
+                Outcome::Pending => "pending",
+                Outcome::Success => "success",
+                Outcome::Failure(_) => "failure",
+            };
+            (event.device(), event.action(), outcome)
+        })
+        .collect();
+
+    assert_eq!(
+        events,
+        [
+            (Device::USB, DeviceAction::Open, "success"),
+            (Device::TCU, DeviceAction::Open, "failure"),
+            (Device::TCU, DeviceAction::Close, "success"),
+            (Device::USB, DeviceAction::Close, "success"),
+        ]
+    );
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_device_log_not_written_by_evaluation() {
+    let log = DeviceLog::new();
+    let interpreter = Interpreter::try_from_str("USBOPEN\nUSBCLOSE")
+        .unwrap()
+        .with_device_log(log.clone());
+
+    assert_eq!(interpreter.evaluate().requests.len(), 2);
+    assert!(log.is_empty());
+}
+
+////////////////////////////////////////////////////////////////
@@ -0,0 +1,131 @@
+use gallivant::{Device, Error, ErrorReason, FrontendRequest, Interpreter};
+
+////////////////////////////////////////////////////////////////
+
+fn run(script: &str) -> Vec<Result<FrontendRequest, Error>> {
+    Interpreter::try_from_str(script).unwrap().collect()
+}
+
+fn is_not_open(result: &Result<FrontendRequest, Error>) -> bool {
+    matches!(
+        result.as_ref().map_err(Error::reason),
+        Err(ErrorReason::DeviceNotOpen {
+            device: Device::USB,
+            ..
+        })
+    )
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_write_before_open() {
+    let results = run(r#"USBPRINT "test""#);
+
+    assert_eq!(results.len(), 1);
+    assert!(is_not_open(&results[0]));
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_write_after_open() {
+    let results = run(r#"
+USBOPEN
+USBPRINT "test"
+USBCLOSE
+USBPRINT "test"
+    "#);
+
+    assert!(matches!(results[0], Ok(FrontendRequest::PrinterOpen)));
+    assert!(matches!(
+        results[1],
+        Ok(FrontendRequest::PrinterTransact(_))
+    ));
+    assert!(matches!(results[2], Ok(FrontendRequest::PrinterClose)));
+    assert!(is_not_open(&results[3]));
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_close_before_open() {
+    let results = run("USBCLOSE\nUSBOPEN\nUSBCLOSE");
+
+    assert!(is_not_open(&results[0]));
+    assert!(matches!(
+        results[1..],
+        [
+            Ok(FrontendRequest::PrinterOpen),
+            Ok(FrontendRequest::PrinterClose)
+        ]
+    ));
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_tcu_always_open() {
+    let results = run("TCUOPEN 1");
+    assert!(matches!(results[..], [Ok(FrontendRequest::TCUTransact(_))]));
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_double_open() {
+    let results = run(r#"
+USBOPEN
+USBOPEN
+    "#);
+
+    assert!(matches!(results[0], Ok(FrontendRequest::PrinterOpen)));
+    assert!(matches!(
+        results[1].as_ref().map_err(Error::reason),
+        Err(ErrorReason::DeviceAlreadyOpen {
+            device: Device::USB,
+            ..
+        })
+    ));
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_reopen_after_close() {
+    let results = run(r#"
+USBOPEN
+USBCLOSE
+USBOPEN
+    "#);
+
+    assert!(matches!(
+        results[..],
+        [
+            Ok(FrontendRequest::PrinterOpen),
+            Ok(FrontendRequest::PrinterClose),
+            Ok(FrontendRequest::PrinterOpen)
+        ]
+    ));
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_relay_double_open() {
+    let results = run(r#"
+TCUOPEN 1
+TCUOPEN 2
+TCUCLOSE 1
+TCUOPEN 1
+TCUOPEN 2
+    "#);
+
+    assert!(results[..4].iter().all(Result::is_ok));
+    assert!(matches!(
+        results[4].as_ref().map_err(Error::reason),
+        Err(ErrorReason::RelayAlreadyOpen { relay: 2, .. })
+    ));
+}
+
+////////////////////////////////////////////////////////////////
@@ -0,0 +1,136 @@
+use gallivant::{Device, ExprKind, FrontendRequest, Interpreter, Severity};
+
+type Request = FrontendRequest;
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_evaluate_warnings() {
+    let script = r#"COMMENT "start"
+TCUTEST 3, 100, 10, 0, "FAIL"
+RETRY 1
+    PRINTERTEST 3, 0, 10, 0, ""
+ENDRETRY"#;
+
+    let interpreter = Interpreter::try_from_str(script).unwrap();
+    let evaluation = interpreter.evaluate();
+
+    assert_eq!(evaluation.requests.len(), 3);
+    assert_eq!(
+        evaluation.requests[0],
+        Request::GuiPrint("start".to_owned())
+    );
+
+    let diagnostics = evaluation.diagnostics;
+    assert_eq!(diagnostics.len(), 3);
+    assert!(diagnostics
+        .iter()
+        .all(|d| d.severity() == Severity::Warning));
+
+    let line = |n: usize| {
+        let start: usize = script.lines().take(n).map(|l| l.len() + 1).sum();
+        start..start + script.lines().nth(n).unwrap().trim_end().len()
+    };
+
+    assert_eq!(diagnostics[0].span(), &line(1));
+    assert!(diagnostics[0]
+        .message()
+        .contains("minimum 100 is greater than maximum 10"));
+    assert!(diagnostics[1].message().contains("never retried"));
+    assert!(diagnostics[2].message().contains("empty failure message"));
+    assert_eq!(diagnostics[2].span().start, line(3).start + 4);
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_evaluate_clean_script() {
+    let interpreter = Interpreter::try_from_str(r#"TCUTEST 3, 10, 100, 0, "FAIL""#).unwrap();
+    let evaluation = interpreter.evaluate();
+
+    assert_eq!(evaluation.requests.len(), 1);
+    assert!(evaluation.diagnostics.is_empty());
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_analyze() {
+    let script = r#"USBOPEN
+TCUTEST 3, 100, 10, 0, "FAIL"
+; Never reached.
+USBPRINT "done"
+TCUCLOSE 1"#;
+
+    let diagnostics = Interpreter::try_from_str(script).unwrap().analyze();
+    let messages: Vec<&str> = diagnostics.iter().map(|d| d.message()).collect();
+
+    assert_eq!(
+        messages,
+        [
+            "The printer is never closed",
+            "Test can never pass, minimum 100 is greater than maximum 10",
+            "Unreachable as a preceding test can never pass",
+            "TCU channel 1 is closed without being opened",
+        ]
+    );
+    assert!(!diagnostics.has_errors());
+
+    let unreachable = script.find("USBPRINT").unwrap()..script.len();
+    assert_eq!(diagnostics.iter().nth(2).unwrap().span(), &unreachable);
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_analyze_errors() {
+    let diagnostics = Interpreter::try_from_str(r#"USBPRINT "test""#)
+        .unwrap()
+        .analyze();
+
+    assert!(diagnostics.has_errors());
+    assert_eq!(diagnostics.with_severity(Severity::Error).count(), 1);
+    assert_eq!(diagnostics.with_severity(Severity::Warning).count(), 0);
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_analyze_duplicate_ids() {
+    let script = r#"@id "REQ-1"
+TCUTEST 1, 0, 10, 0, "a"
+@id "REQ-1"
+TCUTEST 2, 0, 10, 0, "b""#;
+
+    let interpreter = Interpreter::try_from_str(script).unwrap();
+    let diagnostics = interpreter.analyze();
+    assert_eq!(diagnostics.with_severity(Severity::Warning).count(), 2);
+    assert!(!diagnostics.has_errors());
+
+    let diagnostics = interpreter
+        .with_duplicate_id_severity(Severity::Error)
+        .analyze();
+    assert_eq!(diagnostics.with_severity(Severity::Error).count(), 2);
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_analyze_state_dependent() {
+    let script = r#"OPENDIALOG "Check the printer's paper"
+USBOPEN
+OPENDIALOG "Check the printer's paper"
+USBCLOSE"#;
+
+    let interpreter = Interpreter::try_from_str(script).unwrap();
+    assert!(interpreter.analyze().is_empty());
+
+    let diagnostics = interpreter
+        .with_state_dependent(ExprKind::OpenDialog, Device::USB)
+        .analyze();
+    let diagnostics: Vec<_> = diagnostics.iter().collect();
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].span(), &(0..38));
+}
+
+////////////////////////////////////////////////////////////////
@@ -0,0 +1,176 @@
+use gallivant::{Interpreter, ParsedExpr};
+
+////////////////////////////////////////////////////////////////
+
+fn parse(script: &str) -> Vec<ParsedExpr> {
+    Interpreter::try_from_str(script).unwrap().ast().to_vec()
+}
+
+fn render(ast: &[ParsedExpr]) -> String {
+    ast.iter()
+        .map(ToString::to_string)
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+/// Assert rendering the script and parsing it again gives the same statements and annotations.
+///
+fn assert_round_trip(script: &str) {
+    let ast = parse(script);
+    let rendered = render(&ast);
+    let reparsed = parse(&rendered);
+
+    assert_eq!(reparsed, ast, "{rendered}");
+    for (lhs, rhs) in reparsed.iter().zip(&ast) {
+        assert_eq!(lhs.annotations(), rhs.annotations(), "{rendered}");
+    }
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_round_trip_commands() {
+    assert_round_trip(
+        r#"
+HPMODE
+COMMENT "Test"
+WAIT 1234
+DELAY $1F4
+OPENDIALOG "Hello"
+WAITDIALOG "PLEASE WAIT"
+WAITDIALOG "Unattended", 30
+CONFIRM "Guard closed?"
+BEEP
+FLUSH
+PROTOCOL
+PRINT "print me", $1B, 64
+PRINT
+SETTIMEFORMAT $A6
+SETTIME
+SETOPTION 4, 6
+TCUCLOSE 4
+TCUOPEN $F
+PRINTERSET 1
+PRINTERTEST 4,133, 987,5,"error message"
+USBOPEN
+USBPRINT "Look at me I can print"
+SENDHEX "1B 40 0D"
+USBSETTIMEFORMAT 5
+USBSETTIME
+USBSETOPTION 5, 9
+USBPRINTERSET 6
+USBPRINTERTEST 4, 133, 987, 5, "error message"
+PRINTIMAGE "logo.bin"
+SETBAUD 57600
+PROGRAMFIRMWARE "https://example.com/firmware.bin"
+USBCLOSE
+REFTEST 1, 2, 5, 0, "Mismatch"
+STARTTIMER "boot"
+STOPTIMER "boot", <= 2000, "Slow boot"
+STARTLATENCY "print"
+LATENCYTEST "print", 95, 0, 300, "Slow"
+NORESPONSE 100 PRINT $1B, "@"
+"#,
+    );
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_round_trip_tests() {
+    assert_round_trip(
+        r#"
+TCUTEST 5, 12000, 56000, 0, "Range"
+TCUTEST 5, 1000 +- 5%, 0, "Tolerance"
+TCUTEST 5, >= 3000, 0, "Comparison"
+TCUTEST 5, [1, 3, $7], 0, "Set"
+TCUTEST 5, STABLE 5, 20, 0, "Stability"
+TCUTEST 5, BASELINE 10, 0, "Baseline"
+TCUTEST 5, 12000, 56000, 0, "Average", AVERAGE 4
+TCUTEST 5, 12000, 56000, 0, "Worst case", ALL 3
+PRINTERTEST 5, 12000, 56000, 0, "Lowest", MIN 2
+TCUTEST 5, BITS [$08 = $08, $60 = 2], 0, "Bitfield"
+TCUMEASURE "a", 1
+TCUMEASURE "b", 2
+RATIOTEST "a", "b", 950, 1050, "Ratio"
+"#,
+    );
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_round_trip_blocks() {
+    assert_round_trip(
+        r#"
+; Setup
+SET channel 4
+@step "Measure"
+@id "REQ-1"
+RETRY 3
+    /* Fit the board
+       to the jig. */
+    REPEAT 2
+        @independent
+        TCUTEST $channel, 1000, 12000, 1, "FAIL"
+        BREAK
+    ENDREPEAT
+ENDRETRY
+LABEL "boards"
+BOARD "A1"
+    @window "09:00", "17:30"
+    PRINTERSET 1
+ENDBOARD
+ONABORT
+    TCUOPEN 1
+ENDONABORT
+IF LASTRESULT == PASS
+    FLUSH
+ELSE
+    IF LASTRESULT == FAIL
+        BEEP
+    ENDIF
+ENDIF
+GOTO "boards"
+INPUTDIALOG "Enter serial number", $serial
+PRINT "SN: ", $serial
+"#,
+    );
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_round_trip_arithmetic() {
+    assert_round_trip(
+        r#"
+TCUMEASURE "trim", 1
+TCUMEASURE "offset", 2
+SETOPTION 1, (trim + $10) * 2 - offset / 3
+SETOPTION 2, trim - (offset - 1)
+SETOPTION 3, trim / (offset * 2)
+SETOPTION 4, trim - offset - 1
+"#,
+    );
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_canonical_text() {
+    let script = r#"
+retry 2
+  PRINTERTEST 4,133, 987,5,"say \"hi\"\r"
+  setoption 4, (trim + $10) * (2 - 1)
+endretry"#;
+
+    assert_eq!(
+        render(&parse(script)),
+        r#"RETRY 2
+    PRINTERTEST 4, 133, 987, 5, "say \"hi\"\r"
+    SETOPTION 4, (trim + 16) * (2 - 1)
+ENDRETRY"#
+    );
+}
+
+////////////////////////////////////////////////////////////////
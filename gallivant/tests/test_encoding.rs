@@ -0,0 +1,80 @@
+use gallivant::{Encoding, Error, ErrorReason, FrontendRequest, Interpreter};
+
+type Request = FrontendRequest;
+
+////////////////////////////////////////////////////////////////
+
+fn print(script: &str, encoding: Encoding) -> Result<Vec<u8>, Error> {
+    let mut interpreter = Interpreter::try_from_str(script)
+        .unwrap()
+        .with_encoding(encoding);
+
+    match interpreter.next().unwrap()? {
+        Request::TCUTransact(transaction) => Ok(transaction.bytes().to_vec()),
+        request => panic!("Expected a TCU transaction. Got: {request:?}"),
+    }
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_passthrough_by_default() {
+    let script = r#"PRINT "25°C""#;
+    let interpreter = Interpreter::try_from_str(script).unwrap();
+
+    let Some(Request::TCUTransact(transaction)) = interpreter.evaluate().requests.first().cloned()
+    else {
+        panic!("Expected a TCU transaction");
+    };
+    assert_eq!(transaction.bytes(), b"P0A3235C2B043\r");
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_encoded() {
+    let script = r#"PRINT "25°C ", $F8, "é""#;
+
+    assert_eq!(
+        print(script, Encoding::CP437).unwrap(),
+        b"P0E3235F84320F882\r"
+    );
+    assert_eq!(
+        print(script, Encoding::Windows1252).unwrap(),
+        b"P0E3235B04320F8E9\r"
+    );
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_unencodable() {
+    let script = r#"PRINT "Total: ", "5€""#;
+
+    let error = print(script, Encoding::Latin1).unwrap_err();
+    let ErrorReason::Unencodable {
+        argument,
+        character,
+        encoding,
+        ..
+    } = error.reason()
+    else {
+        panic!("Expected an encoding error. Got: {:?}", error.reason());
+    };
+
+    assert_eq!(*character, '€');
+    assert_eq!(*encoding, Encoding::Latin1);
+
+    // Spans are in characters rather than bytes.
+    let argument: String = script
+        .chars()
+        .skip(argument.span().start)
+        .take(argument.span().len())
+        .collect();
+    assert_eq!(argument, r#""5€""#);
+    assert_eq!(error.span(), Some(0..script.chars().count()));
+
+    assert!(print(script, Encoding::Windows1252).is_ok());
+}
+
+////////////////////////////////////////////////////////////////
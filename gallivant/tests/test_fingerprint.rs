@@ -0,0 +1,95 @@
+use gallivant::{Interpreter, Shuffle};
+
+////////////////////////////////////////////////////////////////
+
+fn fingerprint(script: &str) -> u64 {
+    Interpreter::try_from_str(script).unwrap().fingerprint()
+}
+
+////////////////////////////////////////////////////////////////
+
+const SCRIPT: &str = r#"
+COMMENT "Checking supply"
+TCUTEST 3, 1000, 12000, 1, "Supply out of range"
+WAIT 500
+PRINT "Hello"
+"#;
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_comments_and_whitespace_ignored() {
+    let script = r#"
+; Measure the supply first.
+COMMENT "Checking supply"
+
+TCUTEST   3,1000,  12000, 1, "Supply out of range" ; Volts.
+WAIT 500
+
+; Then print.
+PRINT "Hello"
+"#;
+
+    assert_eq!(fingerprint(SCRIPT), fingerprint(script));
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_behaviour_changes_detected() {
+    let changes = [
+        SCRIPT.replace("12000", "12001"),
+        SCRIPT.replace("TCUTEST 3", "TCUTEST 4"),
+        SCRIPT.replace("WAIT 500", "WAIT 501"),
+        SCRIPT.replace(r#"PRINT "Hello""#, r#"PRINT "Hello!""#),
+        SCRIPT.replace("Checking supply", "Checking the supply"),
+        SCRIPT.replace("WAIT 500\n", ""),
+        SCRIPT.replace(
+            r#""Supply out of range""#,
+            r#""Supply out of range", AVERAGE 2"#,
+        ),
+    ];
+
+    for script in changes {
+        assert_ne!(fingerprint(SCRIPT), fingerprint(&script), "{script}");
+    }
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_command_order_significant() {
+    let reordered = r#"
+COMMENT "Checking supply"
+WAIT 500
+TCUTEST 3, 1000, 12000, 1, "Supply out of range"
+PRINT "Hello"
+"#;
+
+    assert_ne!(fingerprint(SCRIPT), fingerprint(reordered));
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_shuffle_ignored() {
+    let script = (0..8)
+        .map(|channel| format!("@independent\nTCUTEST {channel}, 0, 10, 0, \"a\"\n"))
+        .collect::<String>();
+    let interpreter = Interpreter::try_from_str(&script).unwrap();
+
+    for seed in 0..4 {
+        let shuffled = interpreter.clone().with_shuffle(Shuffle::new(seed));
+        assert_eq!(shuffled.fingerprint(), interpreter.fingerprint());
+    }
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_fingerprint_stable() {
+    // Release processes gate on the fingerprint so it mustn't change between releases.
+    assert_eq!(fingerprint(SCRIPT), 14828537359457681821);
+}
+
+////////////////////////////////////////////////////////////////
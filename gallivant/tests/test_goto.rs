@@ -0,0 +1,120 @@
+use gallivant::{ErrorReason, FrontendRequest, Interpreter};
+
+type Request = FrontendRequest;
+
+mod common;
+use common::print;
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_forward_jump() {
+    let script = r#"
+COMMENT "one"
+GOTO "end"
+COMMENT "skipped"
+LABEL "end"
+COMMENT "two"
+"#;
+
+    let requests: Vec<Request> = Interpreter::try_from_str(script)
+        .unwrap()
+        .map(Result::unwrap)
+        .collect();
+    assert_eq!(requests, [print("one"), print("two")]);
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_backward_loop_bounded() {
+    let script = r#"
+LABEL "top"
+COMMENT "loop"
+GOTO "top"
+COMMENT "never run"
+"#;
+
+    let mut interpreter = Interpreter::try_from_str(script).unwrap().with_max_jumps(3);
+
+    // Run once, then once more for each jump.
+    for _ in 0..4 {
+        assert_eq!(interpreter.next().unwrap().unwrap(), print("loop"));
+    }
+
+    let Some(Err(error)) = interpreter.next() else {
+        panic!("Expected an error");
+    };
+    assert!(matches!(
+        error.reason(),
+        ErrorReason::JumpLimitExceeded { limit: 3, .. }
+    ));
+    let goto = script.find("GOTO").unwrap();
+    assert_eq!(error.span(), Some(goto..goto + 10));
+
+    // The jumps are counted afresh by a restarted run.
+    interpreter.restart();
+    assert_eq!(interpreter.next().unwrap().unwrap(), print("loop"));
+    assert_eq!(interpreter.next().unwrap().unwrap(), print("loop"));
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_undefined_label() {
+    let mut interpreter = Interpreter::try_from_str("COMMENT \"one\"\nGOTO \"missing\"").unwrap();
+
+    assert_eq!(interpreter.next().unwrap().unwrap(), print("one"));
+
+    let Some(Err(error)) = interpreter.next() else {
+        panic!("Expected an error");
+    };
+    let ErrorReason::UndefinedLabel { label, .. } = error.reason() else {
+        panic!("Expected an undefined label. Got: {:?}", error.reason());
+    };
+    assert_eq!(label, "missing");
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_jump_out_of_block() {
+    let script = r#"
+REPEAT 3
+    COMMENT "inner"
+    GOTO "after"
+    COMMENT "skipped"
+ENDREPEAT
+LABEL "after"
+COMMENT "outer"
+"#;
+
+    let requests: Vec<Request> = Interpreter::try_from_str(script)
+        .unwrap()
+        .map(Result::unwrap)
+        .collect();
+    assert_eq!(requests, [print("inner"), print("outer")]);
+
+    // Labels within a block can't be jumped to from outside it.
+    let script = "GOTO \"inner\"\nREPEAT 2\n    LABEL \"inner\"\nENDREPEAT";
+    let error = Interpreter::try_from_str(script)
+        .unwrap()
+        .next()
+        .unwrap()
+        .unwrap_err();
+    assert!(matches!(error.reason(), ErrorReason::UndefinedLabel { .. }));
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_evaluation_bounded() {
+    // Without a limit, evaluating a loop that never ends still finishes.
+    let script = "LABEL \"top\"\nCOMMENT \"loop\"\nGOTO \"top\"";
+    let evaluation = Interpreter::try_from_str(script).unwrap().evaluate();
+
+    assert_eq!(evaluation.diagnostics.len(), 1);
+    assert!(evaluation.requests.len() > 1);
+}
+
+////////////////////////////////////////////////////////////////
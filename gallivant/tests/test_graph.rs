@@ -0,0 +1,25 @@
+use gallivant::Interpreter;
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_to_dot() {
+    let script = r#"
+TCUOPEN 1
+RETRY 2
+    TCUTEST 3, 1000, 12000, 0, "Supply out of range"
+ENDRETRY
+TCUCLOSE 1
+"#;
+    let dot = Interpreter::try_from_str(script).unwrap().to_dot();
+
+    assert!(dot.starts_with("digraph script {\n"));
+    assert!(dot.contains(r#"n1 [label="Command: 'RETRY' 2"];"#));
+    assert!(dot.contains("n0 -> n1;"));
+    assert!(dot.contains("n1 -> n2;"));
+    assert!(dot.contains(r#"n2 -> n1 [style=dashed, label="retry"];"#));
+    assert!(dot.contains("n2 -> n3;"));
+    assert!(dot.contains("n3 -> end;"));
+}
+
+////////////////////////////////////////////////////////////////
@@ -34,7 +34,7 @@ SETTIMEFORMAT 5
         port.rxdata.extend(&port.txdata);
         assert!(matches!(
             transaction.process(&mut port),
-            Ok(TransactionStatus::Success)
+            Ok(TransactionStatus::Success { .. })
         ));
     }
 }
@@ -68,7 +68,7 @@ SETOPTION 6, 8
         port.rxdata.extend(&port.txdata);
         assert!(matches!(
             transaction.process(&mut port),
-            Ok(TransactionStatus::Success)
+            Ok(TransactionStatus::Success { .. })
         ));
     }
 }
@@ -100,10 +100,10 @@ PRINTERSET 2
 
         // Echo.
         port.rxdata.extend(port.txdata.iter());
-        assert_eq!(
+        assert!(matches!(
             transaction.process(&mut port).unwrap(),
-            TransactionStatus::Success
-        );
+            TransactionStatus::Success { .. }
+        ));
     }
 }
 
@@ -142,7 +142,7 @@ PRINTERTEST 3, 1000, 12000, 1, "FAIL"
             port.rxdata.extend("AA1\r".as_bytes());
             assert!(matches!(
                 tr.process(&mut port),
-                Ok(TransactionStatus::Success)
+                Ok(TransactionStatus::Success { .. })
             ))
         }
     }
@@ -154,21 +154,26 @@ PRINTERTEST 3, 1000, 12000, 1, "FAIL"
 fn test_hpmode_usbsettimeformat() {
     let script = r#"
 HPMODE
+USBOPEN
 USBSETTIMEFORMAT 6
     "#;
 
     let requests = interpret_script(script);
     assert!(matches!(
         requests[..],
-        [Request::None, Request::PrinterTransact(_)]
+        [
+            Request::None,
+            Request::PrinterOpen,
+            Request::PrinterTransact(_)
+        ]
     ));
 
-    if let Request::PrinterTransact(transaction) = requests[1].clone() {
+    if let Request::PrinterTransact(transaction) = requests[2].clone() {
         let mut port = PortMock::new();
-        assert_eq!(
+        assert!(matches!(
             transaction.process(&mut port).unwrap(),
-            TransactionStatus::Success
-        );
+            TransactionStatus::Success { .. }
+        ));
 
         assert_eq!(port.txdata, vec![0x1B, 0x00, b't', b'f', 6])
     }
@@ -180,21 +185,26 @@ USBSETTIMEFORMAT 6
 fn test_hpmode_usbsetoption() {
     let script = r#"
 HPMODE
+USBOPEN
 USBSETOPTION 6, 7
     "#;
 
     let requests = interpret_script(script);
     assert!(matches!(
         requests[..],
-        [Request::None, Request::PrinterTransact(_)]
+        [
+            Request::None,
+            Request::PrinterOpen,
+            Request::PrinterTransact(_)
+        ]
     ));
 
-    if let Request::PrinterTransact(transaction) = requests[1].clone() {
+    if let Request::PrinterTransact(transaction) = requests[2].clone() {
         let mut port = PortMock::new();
-        assert_eq!(
+        assert!(matches!(
             transaction.process(&mut port).unwrap(),
-            TransactionStatus::Success
-        );
+            TransactionStatus::Success { .. }
+        ));
 
         assert_eq!(port.txdata, vec![0x1B, 0x00, 0x00, b'O', 6, 7])
     }
@@ -206,21 +216,26 @@ USBSETOPTION 6, 7
 fn test_hpmode_usbprinterset() {
     let script = r#"
 HPMODE
+USBOPEN
 USBPRINTERSET 2
     "#;
 
     let requests = interpret_script(script);
     assert!(matches!(
         requests[..],
-        [Request::None, Request::PrinterTransact(_)]
+        [
+            Request::None,
+            Request::PrinterOpen,
+            Request::PrinterTransact(_)
+        ]
     ));
 
-    if let Request::PrinterTransact(transaction) = requests[1].clone() {
+    if let Request::PrinterTransact(transaction) = requests[2].clone() {
         let mut port = PortMock::new();
-        assert_eq!(
+        assert!(matches!(
             transaction.process(&mut port).unwrap(),
-            TransactionStatus::Success
-        );
+            TransactionStatus::Success { .. }
+        ));
 
         assert_eq!(port.txdata, vec![0x1B, 0x00, 0x00, b'S', 2])
     }
@@ -232,16 +247,21 @@ USBPRINTERSET 2
 fn test_hpmode_usbprintertest() {
     let script = r#"
 HPMODE
+USBOPEN
 USBPRINTERTEST 3, 1000, 12000, 1, "FAIL"
     "#;
 
     let requests = interpret_script(script);
     assert!(matches!(
         requests[..],
-        [Request::None, Request::PrinterTransact(_)]
+        [
+            Request::None,
+            Request::PrinterOpen,
+            Request::PrinterTransact(_)
+        ]
     ));
 
-    if let Request::PrinterTransact(mut transaction) = requests[1].clone() {
+    if let Request::PrinterTransact(mut transaction) = requests[2].clone() {
         let mut port = PortMock::new();
 
         if let Ok(TransactionStatus::Ongoing(tr)) = transaction.process(&mut port) {
@@ -255,7 +275,7 @@ USBPRINTERTEST 3, 1000, 12000, 1, "FAIL"
         port.rxdata.extend("AA1\r".as_bytes());
         assert!(matches!(
             transaction.process(&mut port),
-            Ok(TransactionStatus::Success)
+            Ok(TransactionStatus::Success { .. })
         ));
     }
 }
@@ -0,0 +1,151 @@
+use gallivant::{ErrorReason, FrontendRequest, Interpreter, TransactionStatus};
+
+type Request = FrontendRequest;
+
+////////////////////////////////////////////////////////////////
+
+const SCRIPT: &str = r#"
+TCUTEST 3, 1000, 2000, 0, "Supply out of range"
+IF LASTRESULT == PASS
+    COMMENT "passed"
+ELSE
+    COMMENT "recovering"
+    TCUCLOSE 3
+ENDIF
+COMMENT "done"
+"#;
+
+/// Run the script's first test with the device returning the measurement, recovering from any
+/// failure, then collect the rest of the requests.
+///
+fn run(script: &str, measurement: u32) -> Vec<Request> {
+    let mut interpreter = Interpreter::try_from_str(script).unwrap();
+
+    let Some(Ok(Request::TCUTransact(transaction))) = interpreter.next() else {
+        panic!("Expected a TCU transaction");
+    };
+    match transaction.simulate([measurement]) {
+        Ok(status) => assert!(matches!(status, TransactionStatus::Success { .. })),
+        Err(error) => interpreter.recover(error).unwrap(),
+    }
+
+    interpreter.map(|request| request.unwrap()).collect()
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_passed() {
+    assert_eq!(
+        run(SCRIPT, 1500),
+        [
+            Request::GuiPrint("passed".to_owned()),
+            Request::GuiPrint("done".to_owned()),
+        ]
+    );
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_failed_runs_else() {
+    let requests = run(SCRIPT, 2500);
+
+    let [Request::GuiPrint(recovering), Request::TCUTransact(_), Request::GuiPrint(done)] =
+        &requests[..]
+    else {
+        panic!("Unexpected requests: {requests:?}");
+    };
+    assert_eq!(recovering, "recovering");
+    assert_eq!(done, "done");
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_failure_not_followed_by_if() {
+    let script = "TCUTEST 3, 1000, 2000, 0, \"Supply out of range\"\nCOMMENT \"next\"\nIF LASTRESULT == FAIL\nENDIF";
+    let mut interpreter = Interpreter::try_from_str(script).unwrap();
+
+    let Some(Ok(Request::TCUTransact(transaction))) = interpreter.next() else {
+        panic!("Expected a TCU transaction");
+    };
+    let error = transaction.simulate([2500]).unwrap_err();
+    assert!(interpreter.recover(error).is_err());
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_failure_not_handled_by_if() {
+    // Nothing is run on a failure so it isn't recovered from.
+    for script in [
+        "IF LASTRESULT == PASS\n    COMMENT \"passed\"\nENDIF",
+        "IF LASTRESULT == FAIL\nELSE\n    COMMENT \"passed\"\nENDIF",
+    ] {
+        let script = format!(
+            "TCUTEST 3, 1000, 2000, 0, \"Supply out of range\"\n{script}\nCOMMENT \"done\""
+        );
+        let mut interpreter = Interpreter::try_from_str(&script).unwrap();
+
+        let Some(Ok(Request::TCUTransact(transaction))) = interpreter.next() else {
+            panic!("Expected a TCU transaction");
+        };
+        let error = transaction.simulate([2500]).unwrap_err();
+        let error = interpreter.recover(error).unwrap_err();
+        assert!(matches!(error.reason(), ErrorReason::TestFailure { .. }));
+    }
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_no_test_run() {
+    // Neither condition holds before any test has been run.
+    let script = r#"
+IF LASTRESULT == PASS
+    COMMENT "passed"
+ENDIF
+IF LASTRESULT == FAIL
+    COMMENT "failed"
+ELSE
+    COMMENT "untested"
+ENDIF
+"#;
+    let requests: Vec<Request> = Interpreter::try_from_str(script)
+        .unwrap()
+        .map(|request| request.unwrap())
+        .collect();
+
+    assert_eq!(requests, [Request::GuiPrint("untested".to_owned())]);
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_break_exits_enclosing_block() {
+    let script = r#"
+REPEAT 3
+    COMMENT "repeat"
+    IF LASTRESULT == FAIL
+    ELSE
+        BREAK
+    ENDIF
+ENDREPEAT
+COMMENT "done"
+"#;
+    let requests: Vec<Request> = Interpreter::try_from_str(script)
+        .unwrap()
+        .map(|request| request.unwrap())
+        .collect();
+
+    assert_eq!(
+        requests,
+        [
+            Request::GuiPrint("repeat".to_owned()),
+            Request::GuiPrint("done".to_owned()),
+        ]
+    );
+}
+
+////////////////////////////////////////////////////////////////
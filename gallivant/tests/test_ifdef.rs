@@ -0,0 +1,83 @@
+use gallivant::{FrontendRequest, Interpreter, SyntaxErrorReason};
+
+type Request = FrontendRequest;
+
+////////////////////////////////////////////////////////////////
+
+const SCRIPT: &str = r#"
+COMMENT "Start"
+IFDEF DEBUG
+    COMMENT "Debug"
+    IFDEF MODEL_X
+        NEWCOMMAND "Only parsed for model X"
+    ENDIF
+ENDIF
+COMMENT "End"
+"#;
+
+////////////////////////////////////////////////////////////////
+
+fn comments(symbols: &[&str]) -> Vec<String> {
+    Interpreter::try_from_str_with_symbols(SCRIPT, symbols.iter().copied())
+        .unwrap()
+        .filter_map(|request| match request.unwrap() {
+            Request::GuiPrint(message) => Some(message),
+            Request::None => None,
+            request => panic!("Expected a comment. Got: {request:?}"),
+        })
+        .collect()
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_undefined() {
+    assert_eq!(comments(&[]), ["Start", "End"]);
+    assert_eq!(comments(&["MODEL_X", "RELEASE"]), ["Start", "End"]);
+    assert!(Interpreter::try_from_str(SCRIPT).is_ok());
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_defined() {
+    assert_eq!(comments(&["DEBUG"]), ["Start", "Debug", "End"]);
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_included_section_parsed() {
+    let errors = Interpreter::try_parse_with_symbols(SCRIPT, ["DEBUG", "MODEL_X"]).unwrap_err();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].line(), Some(6));
+    assert!(matches!(
+        errors[0].reason(),
+        SyntaxErrorReason::UnrecognisedCommand { .. }
+    ));
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_unclosed() {
+    let errors = Interpreter::try_parse("IFDEF DEBUG\nCOMMENT \"Debug\"").unwrap_err();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].line(), Some(1));
+    assert_eq!(errors[0].to_string(), "1:1: IFDEF without a matching ENDIF");
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_lowercase_if_block() {
+    let script = "IFDEF A\nif lastresult == pass\n    FLUSH\nendif\nENDIF\nCOMMENT \"End\"";
+    let interpreter = Interpreter::try_from_str_with_symbols(script, ["A"]).unwrap();
+    let requests: Vec<Request> = interpreter
+        .map(|request| request.unwrap())
+        .filter(|request| *request != Request::None)
+        .collect();
+    assert_eq!(requests, [Request::GuiPrint("End".to_owned())]);
+}
+
+////////////////////////////////////////////////////////////////
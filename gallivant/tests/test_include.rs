@@ -0,0 +1,145 @@
+use std::{collections::BTreeMap, time::Duration};
+
+use gallivant::{FrontendRequest, Interpreter, ParseError, SyntaxErrorReason};
+
+type Request = FrontendRequest;
+
+////////////////////////////////////////////////////////////////
+
+fn parse(script: &str, files: &[(&str, &str)]) -> Result<Interpreter, Vec<ParseError>> {
+    let files: BTreeMap<&str, &str> = files.iter().copied().collect();
+    Interpreter::try_parse_with_includes(script, std::iter::empty::<String>(), |path| {
+        files.get(path).map(|script| script.to_string())
+    })
+}
+
+fn requests(interpreter: Interpreter) -> Vec<Request> {
+    interpreter
+        .map(Result::unwrap)
+        .filter(|request| *request != Request::None)
+        .collect()
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_include() {
+    let setup = "SET settle 250\nCOMMENT \"setup\"";
+    let script = "INCLUDE \"setup.txt\"\nWAIT $settle\nCOMMENT \"done\"";
+
+    let interpreter = parse(script, &[("setup.txt", setup)]).unwrap();
+    assert_eq!(
+        requests(interpreter),
+        [
+            Request::GuiPrint("setup".to_owned()),
+            Request::Wait(Duration::from_millis(250)),
+            Request::GuiPrint("done".to_owned()),
+        ]
+    );
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_nested_include() {
+    let files = [
+        ("outer.txt", "INCLUDE \"inner.txt\"\nCOMMENT \"outer\""),
+        ("inner.txt", "COMMENT \"inner\""),
+    ];
+    let script = "REPEAT 2\n    INCLUDE \"outer.txt\"\nENDREPEAT";
+
+    let interpreter = parse(script, &files).unwrap();
+    let iteration = [
+        Request::GuiPrint("inner".to_owned()),
+        Request::GuiPrint("outer".to_owned()),
+    ];
+    assert_eq!(
+        requests(interpreter),
+        [iteration.clone(), iteration].concat()
+    );
+
+    // The same script can be included more than once.
+    let script = "INCLUDE \"inner.txt\"\nINCLUDE \"inner.txt\"";
+    assert_eq!(requests(parse(script, &files).unwrap()).len(), 2);
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_include_cycle() {
+    let files = [
+        ("a.txt", "COMMENT \"a\"\nINCLUDE \"b.txt\""),
+        ("b.txt", "INCLUDE \"a.txt\""),
+    ];
+
+    let errors = parse("INCLUDE \"a.txt\"", &files).unwrap_err();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].span(), Some(0..15));
+
+    // Reported at the INCLUDE closing the cycle, within the scripts including it.
+    let SyntaxErrorReason::IncludeFailed { path, reason, .. } = errors[0].reason() else {
+        panic!("Expected an error in an included script. Got: {errors:?}");
+    };
+    assert_eq!(path, "a.txt");
+
+    let SyntaxErrorReason::IncludeFailed { path, reason, .. } = reason.as_ref() else {
+        panic!("Expected an error in an included script. Got: {reason:?}");
+    };
+    assert_eq!(path, "b.txt");
+    assert_eq!(
+        reason.as_ref(),
+        &SyntaxErrorReason::IncludeCycle {
+            span: 0..15,
+            path: "a.txt".to_owned()
+        }
+    );
+
+    let files = [("self.txt", "INCLUDE \"self.txt\"")];
+    let errors = parse("INCLUDE \"self.txt\"", &files).unwrap_err();
+    assert!(matches!(
+        errors[0].reason(),
+        SyntaxErrorReason::IncludeFailed { reason, .. }
+            if matches!(reason.as_ref(), SyntaxErrorReason::IncludeCycle { .. })
+    ));
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_include_errors() {
+    let errors = parse("WAIT 10\nINCLUDE \"missing.txt\"", &[]).unwrap_err();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].line(), Some(2));
+    assert_eq!(
+        errors[0].reason(),
+        &SyntaxErrorReason::IncludeNotFound {
+            span: 8..29,
+            path: "missing.txt".to_owned()
+        }
+    );
+
+    // Errors within an included script are located at the INCLUDE, along with where they are in
+    // the included script.
+    let files = [("setup.txt", "WAIT 10\n  BOGUS 3")];
+    let errors = parse("COMMENT \"start\"\nINCLUDE \"setup.txt\"", &files).unwrap_err();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].line(), Some(2));
+    assert_eq!(
+        errors[0].reason(),
+        &SyntaxErrorReason::IncludeFailed {
+            span: 16..35,
+            path: "setup.txt".to_owned(),
+            location: Some((2, 3)),
+            reason: Box::new(SyntaxErrorReason::UnrecognisedCommand { span: 10..15 }),
+        }
+    );
+
+    // Scripts can't be included without a way to load them.
+    let errors = Interpreter::try_parse("INCLUDE \"setup.txt\"").unwrap_err();
+    assert!(matches!(
+        errors[0].reason(),
+        SyntaxErrorReason::IncludeNotFound { .. }
+    ));
+}
+
+////////////////////////////////////////////////////////////////
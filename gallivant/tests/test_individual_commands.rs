@@ -1,6 +1,8 @@
 use std::time::Duration;
 
-use gallivant::{Dialog, FrontendRequest, TransactionStatus};
+use gallivant::{
+    Device, Dialog, DialogTimeout, FrontendRequest, Interpreter, TimeoutAction, TransactionStatus,
+};
 
 type Request = FrontendRequest;
 
@@ -39,6 +41,20 @@ fn test_wait() {
 
 ////////////////////////////////////////////////////////////////
 
+#[test]
+fn test_delay() {
+    let script = "DELAY 250\nDELAY 1";
+    assert_eq!(
+        interpret_script(script),
+        [
+            Request::Wait(Duration::from_millis(250)),
+            Request::Wait(Duration::from_millis(1))
+        ]
+    );
+}
+
+////////////////////////////////////////////////////////////////
+
 #[test]
 fn test_opendialog() {
     let script = r#"OPENDIALOG "Open a dialog""#;
@@ -59,7 +75,7 @@ fn test_waitdialog() {
     assert_eq!(
         interpret_script(script),
         [Request::GuiDialogue {
-            kind: Dialog::ManualInput,
+            kind: Dialog::ManualInput { timeout: None },
             message: String::from("Open a wait dialog")
         }]
     );
@@ -67,6 +83,46 @@ fn test_waitdialog() {
 
 ////////////////////////////////////////////////////////////////
 
+#[test]
+fn test_waitdialog_timeout() {
+    let script = r#"WAITDIALOG "Open a wait dialog", 30"#;
+    let expected = |action| {
+        [Request::GuiDialogue {
+            kind: Dialog::ManualInput {
+                timeout: Some(DialogTimeout {
+                    duration: Duration::from_secs(30),
+                    action,
+                }),
+            },
+            message: String::from("Open a wait dialog"),
+        }]
+    };
+
+    assert_eq!(interpret_script(script), expected(TimeoutAction::Continue));
+
+    let interpreter = Interpreter::try_from_str(script)
+        .unwrap()
+        .with_dialog_timeout_action(TimeoutAction::Fail);
+    let requests: Vec<Request> = interpreter.map(Result::unwrap).collect();
+    assert_eq!(requests, expected(TimeoutAction::Fail));
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_beep() {
+    let script = r#"BEEP
+WAITDIALOG "Test failed""#;
+    let requests = interpret_script(script);
+    assert!(matches!(
+        requests[..],
+        [Request::Beep, Request::GuiDialogue { .. }]
+    ));
+    assert!(requests[0].devices().is_empty());
+}
+
+////////////////////////////////////////////////////////////////
+
 #[test]
 fn test_flush() {
     let script = r#"FLUSH"#;
@@ -103,7 +159,7 @@ fn test_print() {
         port.rxdata.extend(&port.txdata);
         assert!(matches!(
             transaction.process(&mut port),
-            Ok(TransactionStatus::Success)
+            Ok(TransactionStatus::Success { .. })
         ));
     }
 }
@@ -130,7 +186,7 @@ fn test_settimeformat() {
         port.rxdata.extend(&port.txdata);
         assert!(matches!(
             transaction.process(&mut port),
-            Ok(TransactionStatus::Success)
+            Ok(TransactionStatus::Success { .. })
         ));
     }
 }
@@ -157,7 +213,7 @@ fn test_setoption() {
         port.rxdata.extend(&port.txdata);
         assert!(matches!(
             transaction.process(&mut port),
-            Ok(TransactionStatus::Success)
+            Ok(TransactionStatus::Success { .. })
         ));
     }
 }
@@ -184,7 +240,7 @@ fn test_tcuclose() {
         port.rxdata.extend(&port.txdata);
         assert!(matches!(
             transaction.process(&mut port),
-            Ok(TransactionStatus::Success)
+            Ok(TransactionStatus::Success { .. })
         ));
     }
 }
@@ -211,7 +267,7 @@ fn test_tcuopen() {
         port.rxdata.extend(&port.txdata);
         assert!(matches!(
             transaction.process(&mut port),
-            Ok(TransactionStatus::Success)
+            Ok(TransactionStatus::Success { .. })
         ));
     }
 }
@@ -244,7 +300,7 @@ fn test_tcutest() {
             port.rxdata.extend("AA1\r".as_bytes());
             assert!(matches!(
                 tr.process(&mut port),
-                Ok(TransactionStatus::Success)
+                Ok(TransactionStatus::Success { .. })
             ))
         }
     }
@@ -252,6 +308,19 @@ fn test_tcutest() {
 
 ////////////////////////////////////////////////////////////////
 
+#[test]
+fn test_print_escapes() {
+    let script = r#"PRINT "a\r\n\t\\\"""#;
+    let requests = interpret_script(script);
+
+    let [Request::TCUTransact(transaction)] = &requests[..] else {
+        panic!("Expected a PRINT. Got: {requests:?}");
+    };
+    assert_eq!(transaction.bytes(), b"P0C610D0A095C22\r");
+}
+
+////////////////////////////////////////////////////////////////
+
 #[test]
 fn test_printerset() {
     let script = r#"PRINTERSET 2"#;
@@ -272,7 +341,7 @@ fn test_printerset() {
         port.rxdata.extend(&port.txdata);
         assert!(matches!(
             transaction.process(&mut port),
-            Ok(TransactionStatus::Success)
+            Ok(TransactionStatus::Success { .. })
         ));
     }
 }
@@ -305,7 +374,7 @@ fn test_printertest() {
             port.rxdata.extend("AA1\r".as_bytes());
             assert!(matches!(
                 tr.process(&mut port),
-                Ok(TransactionStatus::Success)
+                Ok(TransactionStatus::Success { .. })
             ))
         }
     }
@@ -323,27 +392,34 @@ fn test_usbopen() {
 
 #[test]
 fn test_usbclose() {
-    let script = r#"USBCLOSE"#;
-    assert_eq!(interpret_script(script), [Request::PrinterClose]);
+    let script = "USBOPEN\nUSBCLOSE";
+    assert_eq!(
+        interpret_script(script),
+        [Request::PrinterOpen, Request::PrinterClose]
+    );
 }
 
 ////////////////////////////////////////////////////////////////
 
 #[test]
 fn test_usbprint() {
-    let script = r#"USBPRINT "test", 45, $D4"#;
+    let script = r#"USBOPEN
+USBPRINT "test", 45, $D4"#;
     let requests = interpret_script(script);
-    assert!(matches!(requests[..], [Request::PrinterTransact(_)]));
+    assert!(matches!(
+        requests[..],
+        [Request::PrinterOpen, Request::PrinterTransact(_)]
+    ));
 
     let mut expected = "test".as_bytes().to_owned();
     expected.extend_from_slice(&[45, 0xD4]);
 
-    if let Request::PrinterTransact(transaction) = requests[0].clone() {
+    if let Request::PrinterTransact(transaction) = requests[1].clone() {
         let mut port = PortMock::new();
-        assert_eq!(
+        assert!(matches!(
             transaction.process(&mut port).unwrap(),
-            TransactionStatus::Success
-        );
+            TransactionStatus::Success { .. }
+        ));
 
         assert_eq!(port.txdata, expected)
     }
@@ -351,18 +427,53 @@ fn test_usbprint() {
 
 ////////////////////////////////////////////////////////////////
 
+#[test]
+fn test_sendhex() {
+    let script = r#"USBOPEN
+SENDHEX "1B 40 0d"
+SENDHEX "1B40  0D""#;
+    let requests = interpret_script(script);
+    assert!(matches!(
+        requests[..],
+        [
+            Request::PrinterOpen,
+            Request::PrinterTransact(_),
+            Request::PrinterTransact(_)
+        ]
+    ));
+
+    for request in requests[1..].iter().cloned() {
+        let Request::PrinterTransact(transaction) = request else {
+            panic!("Expected a printer transaction. Got: {request:?}");
+        };
+
+        let mut port = PortMock::new();
+        assert!(matches!(
+            transaction.process(&mut port).unwrap(),
+            TransactionStatus::Success { .. }
+        ));
+        assert_eq!(port.txdata, [0x1B, 0x40, 0x0D]);
+    }
+}
+
+////////////////////////////////////////////////////////////////
+
 #[test]
 fn test_usbsettimeformat() {
-    let script = r#"USBSETTIMEFORMAT 6"#;
+    let script = r#"USBOPEN
+USBSETTIMEFORMAT 6"#;
     let requests = interpret_script(script);
-    assert!(matches!(requests[..], [Request::PrinterTransact(_)]));
+    assert!(matches!(
+        requests[..],
+        [Request::PrinterOpen, Request::PrinterTransact(_)]
+    ));
 
-    if let Request::PrinterTransact(transaction) = requests[0].clone() {
+    if let Request::PrinterTransact(transaction) = requests[1].clone() {
         let mut port = PortMock::new();
-        assert_eq!(
+        assert!(matches!(
             transaction.process(&mut port).unwrap(),
-            TransactionStatus::Success
-        );
+            TransactionStatus::Success { .. }
+        ));
 
         assert_eq!(port.txdata, vec![0x1B, b't', b'f', 6])
     }
@@ -372,16 +483,20 @@ fn test_usbsettimeformat() {
 
 #[test]
 fn test_usbsetoption() {
-    let script = r#"USBSETOPTION 6, 7"#;
+    let script = r#"USBOPEN
+USBSETOPTION 6, 7"#;
     let requests = interpret_script(script);
-    assert!(matches!(requests[..], [Request::PrinterTransact(_)]));
+    assert!(matches!(
+        requests[..],
+        [Request::PrinterOpen, Request::PrinterTransact(_)]
+    ));
 
-    if let Request::PrinterTransact(transaction) = requests[0].clone() {
+    if let Request::PrinterTransact(transaction) = requests[1].clone() {
         let mut port = PortMock::new();
-        assert_eq!(
+        assert!(matches!(
             transaction.process(&mut port).unwrap(),
-            TransactionStatus::Success
-        );
+            TransactionStatus::Success { .. }
+        ));
 
         assert_eq!(port.txdata, vec![0x1B, 0x00, b'O', 6, 7])
     }
@@ -391,16 +506,20 @@ fn test_usbsetoption() {
 
 #[test]
 fn test_usbprinterset() {
-    let script = r#"USBPRINTERSET 2"#;
+    let script = r#"USBOPEN
+USBPRINTERSET 2"#;
     let requests = interpret_script(script);
-    assert!(matches!(requests[..], [Request::PrinterTransact(_)]));
+    assert!(matches!(
+        requests[..],
+        [Request::PrinterOpen, Request::PrinterTransact(_)]
+    ));
 
-    if let Request::PrinterTransact(transaction) = requests[0].clone() {
+    if let Request::PrinterTransact(transaction) = requests[1].clone() {
         let mut port = PortMock::new();
-        assert_eq!(
+        assert!(matches!(
             transaction.process(&mut port).unwrap(),
-            TransactionStatus::Success
-        );
+            TransactionStatus::Success { .. }
+        ));
 
         assert_eq!(port.txdata, vec![0x1B, 0x00, b'S', 2])
     }
@@ -410,11 +529,15 @@ fn test_usbprinterset() {
 
 #[test]
 fn test_usbprintertest() {
-    let script = r#"USBPRINTERTEST 3, 1000, 12000, 1, "FAIL""#;
+    let script = r#"USBOPEN
+USBPRINTERTEST 3, 1000, 12000, 1, "FAIL""#;
     let requests = interpret_script(script);
-    assert!(matches!(requests[..], [Request::PrinterTransact(_)]));
+    assert!(matches!(
+        requests[..],
+        [Request::PrinterOpen, Request::PrinterTransact(_)]
+    ));
 
-    if let Request::TCUTransact(mut transaction) = requests[0].clone() {
+    if let Request::TCUTransact(mut transaction) = requests[1].clone() {
         let mut port = PortMock::new();
 
         if let Ok(TransactionStatus::Ongoing(tr)) = transaction.process(&mut port) {
@@ -428,8 +551,140 @@ fn test_usbprintertest() {
         port.rxdata.extend("AA1\r".as_bytes());
         assert!(matches!(
             transaction.process(&mut port),
-            Ok(TransactionStatus::Success)
+            Ok(TransactionStatus::Success { .. })
+        ));
+    }
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_setbaud() {
+    let script = r#"USBOPEN
+SETBAUD 115200
+SET baud 4800
+SETBAUD $baud"#;
+    let requests = interpret_script(script);
+    assert_eq!(
+        requests,
+        [
+            Request::PrinterOpen,
+            Request::Reconfigure { baud: 115200 },
+            Request::None,
+            Request::Reconfigure { baud: 4800 },
+        ]
+    );
+    assert_eq!(requests[1].devices(), [Device::USB]);
+
+    assert!(Interpreter::try_from_str("USBOPEN\nSETBAUD 9601").is_err());
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_programfirmware() {
+    let script = r#"USBOPEN
+PROGRAMFIRMWARE "https://example.com/firmware/v2.bin"
+PROGRAMFIRMWARE "HTTP://192.168.0.10:8080/fw.bin""#;
+    let requests = interpret_script(script);
+    assert_eq!(
+        requests,
+        [
+            Request::PrinterOpen,
+            Request::FetchAndProgram {
+                url: "https://example.com/firmware/v2.bin".to_owned(),
+                device: Device::USB,
+            },
+            Request::FetchAndProgram {
+                url: "HTTP://192.168.0.10:8080/fw.bin".to_owned(),
+                device: Device::USB,
+            },
+        ]
+    );
+    assert_eq!(requests[1].devices(), [Device::USB]);
+
+    for url in [
+        "ftp://example.com/fw.bin",
+        "file:///fw.bin",
+        "example.com/fw.bin",
+    ] {
+        let script = format!("USBOPEN\nPROGRAMFIRMWARE \"{url}\"");
+        assert!(Interpreter::try_from_str(&script).is_err(), "{url}");
+    }
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_usb_device() {
+    let script = r#"USBOPEN
+USBPRINT "test"
+USBPRINTERTEST 3, 1000, 12000, 1, "FAIL"
+PRINTERSET 1"#;
+    let requests = interpret_script(script);
+
+    let devices: Vec<Device> = requests
+        .iter()
+        .filter_map(|request| match request {
+            Request::TCUTransact(transaction) | Request::PrinterTransact(transaction) => {
+                Some(transaction.device())
+            }
+            _ => None,
+        })
+        .collect();
+    assert_eq!(devices, [Device::USB, Device::USB, Device::TCU]);
+
+    assert_eq!(requests[0].devices(), [Device::USB]);
+    assert_eq!(requests[1].devices(), [Device::USB]);
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_noresponse() {
+    let script = r#"NORESPONSE 0 PRINT $1B, "@""#;
+    let requests = interpret_script(script);
+    assert!(matches!(requests[..], [Request::TCUTransact(_)]));
+
+    if let Request::TCUTransact(transaction) = requests[0].clone() {
+        let mut port = PortMock::new();
+
+        // The TCU echo would normally be required but the response is ignored entirely.
+        assert!(matches!(
+            transaction.process(&mut port).unwrap(),
+            TransactionStatus::Success { .. }
+        ));
+        assert_eq!(port.txdata, b"P041B40\r");
+    }
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_noresponse_drain() {
+    let script = r#"NORESPONSE 1 PRINT $1B, "@""#;
+    let requests = interpret_script(script);
+    assert!(matches!(requests[..], [Request::TCUTransact(_)]));
+
+    if let Request::TCUTransact(mut transaction) = requests[0].clone() {
+        let mut port = PortMock::new();
+
+        if let Ok(TransactionStatus::Ongoing(tr)) = transaction.process(&mut port) {
+            transaction = tr;
+        } else {
+            panic!()
+        }
+
+        // Echo followed by some unrelated output that should be drained.
+        port.rxdata.extend(&port.txdata);
+        port.rxdata.extend(b"RESETTING\r");
+
+        std::thread::sleep(Duration::from_millis(2));
+        assert!(matches!(
+            transaction.process(&mut port).unwrap(),
+            TransactionStatus::Success { .. }
         ));
+        assert!(port.rxdata.is_empty());
     }
 }
 
@@ -0,0 +1,120 @@
+use gallivant::{Dialog, ErrorReason, FrontendRequest, Interpreter};
+
+type Request = FrontendRequest;
+
+mod common;
+use common::print;
+
+////////////////////////////////////////////////////////////////
+
+fn input(message: &str) -> Request {
+    Request::GuiDialogue {
+        kind: Dialog::Input,
+        message: message.to_owned(),
+    }
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_input_substituted() {
+    let script = r#"INPUTDIALOG "Enter serial number", $serial
+COMMENT $serial
+PRINT "SN: ", $serial"#;
+    let mut interpreter = Interpreter::try_from_str(script).unwrap();
+
+    assert_eq!(
+        interpreter.next().unwrap().unwrap(),
+        input("Enter serial number")
+    );
+    interpreter.enter_input("A1234");
+
+    assert_eq!(interpreter.next().unwrap().unwrap(), print("A1234"));
+
+    // Printed as if the text was written in the script.
+    let expected = Interpreter::try_from_str(r#"PRINT "SN: ", "A1234""#)
+        .unwrap()
+        .next()
+        .unwrap()
+        .unwrap();
+    let (Request::TCUTransact(transaction), Request::TCUTransact(expected)) =
+        (interpreter.next().unwrap().unwrap(), expected)
+    else {
+        panic!("Expected TCU transactions");
+    };
+    assert_eq!(transaction.bytes(), expected.bytes());
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_input_reentered() {
+    let script = r#"REPEAT 2
+    INPUTDIALOG "Enter serial number", $serial
+    COMMENT $serial
+ENDREPEAT"#;
+    let mut interpreter = Interpreter::try_from_str(script).unwrap();
+
+    for serial in ["A1", "B2"] {
+        assert_eq!(
+            interpreter.next().unwrap().unwrap(),
+            input("Enter serial number")
+        );
+        interpreter.enter_input(serial);
+        assert_eq!(interpreter.next().unwrap().unwrap(), print(serial));
+    }
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_input_not_entered() {
+    let script = r#"INPUTDIALOG "Enter serial number", $serial
+COMMENT $serial"#;
+    let mut interpreter = Interpreter::try_from_str(script).unwrap();
+
+    interpreter.next().unwrap().unwrap();
+
+    // Text entered after the next request is ignored.
+    let error = interpreter.next().unwrap().unwrap_err();
+    interpreter.enter_input("A1234");
+    assert!(matches!(
+        error.reason(),
+        ErrorReason::InputNotEntered { name, .. } if name == "serial"
+    ));
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_input_type() {
+    // The text can't be used where an unsigned integer is required.
+    let script = r#"INPUTDIALOG "Enter a delay", $delay
+WAIT $delay"#;
+    assert!(Interpreter::try_from_str(script).is_err());
+
+    // A reference can't be used before the dialog.
+    let script = r#"COMMENT $serial
+INPUTDIALOG "Enter serial number", $serial"#;
+    assert!(Interpreter::try_from_str(script).is_err());
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_input_evaluated() {
+    let script = r#"INPUTDIALOG "Enter serial number", $serial
+COMMENT $serial"#;
+    let interpreter = Interpreter::try_from_str(script).unwrap();
+
+    // Evaluated without an operator, so no text is entered.
+    let evaluation = interpreter.evaluate();
+    assert!(evaluation.diagnostics.is_empty());
+    assert_eq!(
+        evaluation.requests,
+        [input("Enter serial number"), print("")]
+    );
+    assert!(Interpreter::lint(script).is_empty());
+}
+
+////////////////////////////////////////////////////////////////
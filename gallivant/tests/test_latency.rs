@@ -0,0 +1,106 @@
+use std::{iter, time::Duration};
+
+use gallivant::{Error, ErrorReason, FrontendRequest, Interpreter};
+
+type Request = FrontendRequest;
+
+mod common;
+use common::next_request;
+
+////////////////////////////////////////////////////////////////
+
+/// Run the script with the TCU responding immediately to every test.
+///
+/// # Returns
+/// Every request other than a test's transaction. Stops at the first error.
+///
+fn run(script: &str) -> Result<Vec<Request>, Error> {
+    let mut interpreter = Interpreter::try_from_str(script).unwrap();
+    let mut measurements = iter::repeat(5);
+
+    let mut requests = Vec::new();
+    while let Some(request) = next_request(&mut interpreter, &mut measurements) {
+        match request? {
+            Request::None => (),
+            request => requests.push(request),
+        }
+    }
+
+    Ok(requests)
+}
+
+////////////////////////////////////////////////////////////////
+
+const SCRIPT: &str = r#"
+TCUTEST 1, 0, 10, 0, "Not collected"
+STARTLATENCY "soak"
+TCUTEST 1, 0, 10, 0, "a"
+TCUTEST 2, 0, 10, 0, "b"
+TCUTEST 3, 0, 10, 0, "c"
+"#;
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_latency_within_limit() {
+    let script = format!("{SCRIPT}LATENCYTEST \"soak\", 95, <= 1000, \"Responses too slow\"\n");
+    let requests = run(&script).unwrap();
+
+    let [Request::Latency {
+        name,
+        latency,
+        samples,
+        recorded: false,
+        ..
+    }] = requests.as_slice()
+    else {
+        panic!("Expected the latency test to pass. Got: {requests:?}");
+    };
+    assert_eq!(name, "soak");
+    assert_eq!(*samples, 3);
+    assert!(*latency < Duration::from_secs(1));
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_latency_over_limit() {
+    let script = format!("{SCRIPT}LATENCYTEST \"soak\", 50, >= 1000, \"Responses too fast\"\n");
+    let error = run(&script).unwrap_err();
+
+    let ErrorReason::TestFailure { test, .. } = error.reason() else {
+        panic!("Expected a test failure. Got: {:?}", error.reason());
+    };
+    assert!(test.measurement < 1000);
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_latency_not_collected() {
+    let script = r#"
+STARTLATENCY "soak"
+LATENCYTEST "soak", 95, < 100, "Responses too slow"
+TCUTEST 1, 0, 10, 0, "a"
+LATENCYTEST "soak", 95, < 100, "Responses too slow"
+"#;
+
+    let error = run(script).unwrap_err();
+    assert!(matches!(
+        error.reason(),
+        ErrorReason::LatencyNotCollected { name, .. } if name == "soak"
+    ));
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_invalid_percentile() {
+    let script = r#"LATENCYTEST "soak", 0, < 100, "Responses too slow""#;
+    assert!(Interpreter::try_from_str(script).is_err());
+
+    let script = r#"LATENCYTEST "soak", 101, < 100, "Responses too slow""#;
+    assert!(Interpreter::try_from_str(script).is_err());
+}
+
+////////////////////////////////////////////////////////////////
@@ -0,0 +1,46 @@
+use gallivant::{Interpreter, LineIndex};
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_locate() {
+    let script = "TCUOPEN 1\nWAIT 10\n\nCOMMENT \"done\"";
+    let index = LineIndex::new(script);
+
+    assert_eq!(index.line_count(), 4);
+    assert_eq!(index.locate(0), (1, 1));
+    assert_eq!(index.locate(8), (1, 9));
+    assert_eq!(index.locate(9), (1, 10));
+    assert_eq!(index.locate(10), (2, 1));
+    assert_eq!(index.locate(18), (3, 1));
+    assert_eq!(index.locate(19), (4, 1));
+    assert_eq!(index.locate(27), (4, 9));
+
+    assert_eq!(index.line_start(1), Some(0));
+    assert_eq!(index.line_start(4), Some(19));
+    assert_eq!(index.line_start(0), None);
+    assert_eq!(index.line_start(5), None);
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_locate_counts_characters() {
+    let script = "; Ünïcödé\nCOMMENT \"ñ\" BOGUS\nWAIT 10";
+    let index = LineIndex::new(script);
+
+    // Each accented character is two bytes but a single column.
+    assert_eq!(index.locate(9), (1, 10));
+    assert_eq!(index.locate(10), (2, 1));
+    assert_eq!(index.locate(22), (2, 13));
+    assert_eq!(index.locate(28), (3, 1));
+
+    let errors = Interpreter::try_parse(script).unwrap_err();
+    let span = errors[0].span().unwrap();
+    assert_eq!(
+        index.locate(span.start),
+        (errors[0].line().unwrap(), errors[0].column().unwrap())
+    );
+}
+
+////////////////////////////////////////////////////////////////
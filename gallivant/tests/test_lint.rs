@@ -0,0 +1,66 @@
+use gallivant::{Device, ErrorReason, Interpreter, SyntaxErrorReason};
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_lint_argument_type() {
+    let script = r#"TCUOPEN 1
+WAIT "ten"
+TCUCLOSE 1"#;
+    let errors = Interpreter::lint(script);
+
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].span(), Some(15..20));
+    assert!(matches!(
+        errors[0].reason(),
+        ErrorReason::SyntaxError(SyntaxErrorReason::ArgType { .. })
+    ));
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_lint_open_balance() {
+    let script = r#"USBPRINT "early"
+USBOPEN
+USBOPEN
+TCUOPEN 1
+TCUOPEN 1
+USBCLOSE"#;
+    let errors = Interpreter::lint(script);
+
+    assert_eq!(errors.len(), 3);
+    assert!(matches!(
+        errors[0].reason(),
+        ErrorReason::DeviceNotOpen {
+            device: Device::USB,
+            ..
+        }
+    ));
+    assert!(matches!(
+        errors[1].reason(),
+        ErrorReason::DeviceAlreadyOpen {
+            device: Device::USB,
+            ..
+        }
+    ));
+    assert!(matches!(
+        errors[2].reason(),
+        ErrorReason::RelayAlreadyOpen { relay: 1, .. }
+    ));
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_lint_valid() {
+    let script = r#"USBOPEN
+USBPRINT "test"
+USBCLOSE
+TCUOPEN 1
+WAIT 10
+TCUCLOSE 1"#;
+    assert!(Interpreter::lint(script).is_empty());
+}
+
+////////////////////////////////////////////////////////////////
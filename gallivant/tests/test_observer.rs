@@ -0,0 +1,112 @@
+use std::{
+    ops::Range,
+    sync::{Arc, Mutex},
+};
+
+use gallivant::{
+    Device, FrontendRequest, Interpreter, Transaction, TransactionObserver, TransactionStatus,
+};
+
+////////////////////////////////////////////////////////////////
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Direction {
+    Tx,
+    Rx,
+}
+
+type Exchange = (Direction, Device, Range<usize>, Vec<u8>);
+
+/// Observer recording every exchange it's notified of.
+///
+#[derive(Default)]
+struct Recorder(Mutex<Vec<Exchange>>);
+
+impl TransactionObserver for Recorder {
+    fn on_tx(&self, transaction: &Transaction, bytes: &[u8]) {
+        self.record(Direction::Tx, transaction, bytes);
+    }
+
+    fn on_rx(&self, transaction: &Transaction, bytes: &[u8]) {
+        self.record(Direction::Rx, transaction, bytes);
+    }
+}
+
+impl Recorder {
+    fn record(&self, direction: Direction, transaction: &Transaction, bytes: &[u8]) {
+        self.0.lock().unwrap().push((
+            direction,
+            transaction.device(),
+            transaction.span().clone(),
+            bytes.to_owned(),
+        ));
+    }
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_request_response_cycle() {
+    let script = "WAIT 10\nTCUTEST 3, 10, 20, 1, \"Out of range\"";
+
+    let recorder = Arc::new(Recorder::default());
+    let mut interpreter = Interpreter::try_from_str(script)
+        .unwrap()
+        .with_transaction_observer(recorder.clone());
+
+    assert!(matches!(
+        interpreter.next(),
+        Some(Ok(FrontendRequest::Wait(_)))
+    ));
+    let Some(Ok(FrontendRequest::TCUTransact(transaction))) = interpreter.next() else {
+        panic!("Expected a TCU transaction");
+    };
+
+    // The first measurement fails and is retried.
+    assert!(matches!(
+        transaction.simulate([0, 15]),
+        Ok(TransactionStatus::Success { .. })
+    ));
+
+    let span = 8..script.len();
+    let exchange = |direction, bytes: &[u8]| (direction, Device::TCU, span.clone(), bytes.to_vec());
+    assert_eq!(
+        *recorder.0.lock().unwrap(),
+        [
+            exchange(Direction::Tx, b"M03\r"),
+            exchange(Direction::Rx, b"M03\r0000\r"),
+            exchange(Direction::Tx, b"M03\r"),
+            exchange(Direction::Rx, b"M03\r000F\r"),
+        ]
+    );
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_default_methods() {
+    /// Observer only interested in transmissions.
+    #[derive(Default)]
+    struct TxOnly(Mutex<usize>);
+
+    impl TransactionObserver for TxOnly {
+        fn on_tx(&self, _: &Transaction, bytes: &[u8]) {
+            *self.0.lock().unwrap() += bytes.len();
+        }
+    }
+
+    let observer = Arc::new(TxOnly::default());
+    let requests: Vec<FrontendRequest> = Interpreter::try_from_str("PRINTERSET 1")
+        .unwrap()
+        .with_transaction_observer(observer.clone())
+        .map(|request| request.unwrap())
+        .collect();
+
+    let [FrontendRequest::TCUTransact(transaction)] = &requests[..] else {
+        panic!("Expected a TCU transaction. Got: {requests:?}");
+    };
+    assert!(transaction.clone().simulate([]).is_ok());
+    assert_eq!(*observer.0.lock().unwrap(), b"P051B005301\r".len());
+}
+
+////////////////////////////////////////////////////////////////
@@ -0,0 +1,48 @@
+use std::io::Write;
+
+use gallivant::{ErrorReason, Interpreter};
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_artifact_in_output_directory() {
+    let directory = std::env::temp_dir()
+        .join(format!("gallivant-output-{}", std::process::id()))
+        .join("run");
+
+    let interpreter = Interpreter::try_from_str("PRINTERSET 1")
+        .unwrap()
+        .with_output_directory(&directory)
+        .unwrap();
+    assert_eq!(interpreter.output_directory(), Some(directory.as_path()));
+    assert!(directory.is_dir());
+
+    let mut file = interpreter.create_artifact("recording.csv").unwrap();
+    file.write_all(b"command,min,max,samples\n").unwrap();
+    assert!(directory.join("recording.csv").is_file());
+
+    std::fs::remove_dir_all(directory.parent().unwrap()).unwrap();
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_output_directory_creation_failure() {
+    let file = std::env::temp_dir().join(format!("gallivant-not-a-dir-{}", std::process::id()));
+    std::fs::write(&file, b"").unwrap();
+
+    let result = Interpreter::try_from_str("PRINTERSET 1")
+        .unwrap()
+        .with_output_directory(file.join("run"));
+    std::fs::remove_file(&file).unwrap();
+
+    let Err(error) = result else {
+        panic!("Expected the output directory to fail");
+    };
+    match error.reason() {
+        ErrorReason::OutputError { path, .. } => assert_eq!(path, &file.join("run")),
+        reason => panic!("Unexpected error {reason:?}"),
+    }
+}
+
+////////////////////////////////////////////////////////////////
@@ -0,0 +1,70 @@
+use gallivant::{Error, ErrorReason, Interpreter, SyntaxErrorReason};
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_location() {
+    let script = "TCUOPEN 1\nWAIT 10\n  BOGUS 3\n";
+    let errors = Interpreter::try_parse(script).unwrap_err();
+
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].line(), Some(3));
+    assert_eq!(errors[0].column(), Some(3));
+    assert!(errors[0].is_recoverable());
+    assert!(matches!(
+        errors[0].reason(),
+        SyntaxErrorReason::UnrecognisedCommand { .. }
+    ));
+    assert_eq!(errors[0].to_string(), "3:3: Unrecognised command found");
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_location_counts_characters() {
+    let script = "; Ünïcödé\nBOGUS 3";
+    let errors = Interpreter::try_parse(script).unwrap_err();
+
+    assert_eq!(errors[0].span(), Some(10..15));
+    assert_eq!(errors[0].line(), Some(2));
+    assert_eq!(errors[0].column(), Some(1));
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_into_error() {
+    let script = "BOGUS 3";
+    let parse_error = Interpreter::try_parse(script).unwrap_err().remove(0);
+    let error = Interpreter::try_from_str(script).unwrap_err().remove(0);
+
+    assert_eq!(Error::from(parse_error.clone()).span(), error.span());
+    assert!(matches!(
+        error.reason(),
+        ErrorReason::SyntaxError(reason) if reason == parse_error.reason()
+    ));
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_parse_errors() {
+    let script = "TCUOPEN 1\n  BOGUS 3\nWAIT 10\nPRINTERSET 300\n";
+    let errors = Interpreter::parse_errors(script);
+
+    let locations: Vec<(Option<usize>, Option<usize>)> = errors
+        .iter()
+        .map(|error| (error.line(), error.column()))
+        .collect();
+    assert_eq!(locations, [(Some(2), Some(3)), (Some(4), Some(12))]);
+    assert!(matches!(
+        errors[1].reason(),
+        SyntaxErrorReason::ArgValue { value: 300, .. }
+    ));
+
+    // Parsing stops at the first error.
+    assert_eq!(Interpreter::try_parse(script).unwrap_err().len(), 1);
+    assert!(Interpreter::parse_errors("TCUOPEN 1\nWAIT 10\n").is_empty());
+}
+
+////////////////////////////////////////////////////////////////
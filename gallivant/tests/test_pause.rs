@@ -0,0 +1,62 @@
+use gallivant::{FrontendRequest, Interpreter};
+
+type Request = FrontendRequest;
+
+mod common;
+use common::print;
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_pause() {
+    let script = r#"COMMENT "a"
+TCUOPEN 1
+COMMENT "b""#;
+    let mut interpreter = Interpreter::try_from_str(script).unwrap();
+
+    assert_eq!(interpreter.next().unwrap().unwrap(), print("a"));
+
+    interpreter.pause();
+    assert!(interpreter.is_paused());
+    for _ in 0..3 {
+        assert_eq!(interpreter.next().unwrap().unwrap(), Request::Paused);
+    }
+
+    // Continues from the statement it was paused before.
+    interpreter.resume();
+    assert!(!interpreter.is_paused());
+
+    let requests: Vec<Request> = interpreter.map(Result::unwrap).collect();
+    assert!(matches!(
+        &requests[..],
+        [Request::TCUTransact(open), request] if open.bytes() == b"O01\r" && *request == print("b")
+    ));
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_pause_within_block() {
+    let script = r#"SET message "repeated"
+REPEAT 3
+    COMMENT $message
+ENDREPEAT
+COMMENT "done""#;
+    let mut interpreter = Interpreter::try_from_str(script).unwrap();
+
+    assert_eq!(interpreter.next().unwrap().unwrap(), Request::None);
+    assert_eq!(interpreter.next().unwrap().unwrap(), print("repeated"));
+
+    interpreter.pause();
+    assert_eq!(interpreter.next().unwrap().unwrap(), Request::Paused);
+    interpreter.resume();
+
+    // The block's remaining repetitions are still run.
+    let requests: Vec<Request> = interpreter.map(Result::unwrap).collect();
+    assert_eq!(
+        requests,
+        [print("repeated"), print("repeated"), print("done")]
+    );
+}
+
+////////////////////////////////////////////////////////////////
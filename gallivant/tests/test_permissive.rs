@@ -0,0 +1,67 @@
+use gallivant::{Expected, FrontendRequest, Interpreter, Severity};
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_unparseable_lines_skipped() {
+    let script = r#"PRINTERSET 1
+PRINTERSET 300
+RETRY 2
+    FOO 3
+    TCUTEST 3, 0, 10, 0, "Out of range"
+ENDRETRY
+PRINTERSET 2"#;
+
+    assert!(Interpreter::try_from_str(script).is_err());
+
+    let interpreter = Interpreter::try_from_str_permissive(script).unwrap();
+    assert!(interpreter.is_permissive());
+
+    let skipped: Vec<&str> = interpreter
+        .skipped_statements()
+        .iter()
+        .map(|diagnostic| {
+            assert_eq!(diagnostic.severity(), Severity::Warning);
+            let start = diagnostic.span().start;
+            script[start..].lines().next().unwrap()
+        })
+        .collect();
+    assert_eq!(skipped, ["300", "FOO 3"]);
+
+    let evaluation = interpreter.evaluate();
+    assert_eq!(evaluation.diagnostics.len(), 2);
+
+    let requests: Vec<&FrontendRequest> = evaluation
+        .requests
+        .iter()
+        .filter(|request| **request != FrontendRequest::None)
+        .collect();
+    assert_eq!(requests.len(), 3);
+
+    let FrontendRequest::TCUTransact(transaction) = requests[1] else {
+        panic!("Expected the TCUTEST to run");
+    };
+    assert_eq!(
+        transaction.test().map(|test| &test.expected),
+        Some(&Expected::Range(0..=10))
+    );
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_strict_not_permissive() {
+    let interpreter = Interpreter::try_from_str("PRINTERSET 1").unwrap();
+    assert!(!interpreter.is_permissive());
+    assert!(interpreter.skipped_statements().is_empty());
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_unclosed_block_unrecoverable() {
+    let script = "PRINTERSET 1\nRETRY 2\n    PRINTERSET 2";
+    assert!(Interpreter::try_from_str_permissive(script).is_err());
+}
+
+////////////////////////////////////////////////////////////////
@@ -0,0 +1,42 @@
+use gallivant::Interpreter;
+
+////////////////////////////////////////////////////////////////
+
+fn plan(script: &str) -> String {
+    let mut json = Vec::new();
+    Interpreter::try_from_str(script)
+        .unwrap()
+        .write_plan_json(&mut json)
+        .unwrap();
+
+    String::from_utf8(json).unwrap()
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_plan() {
+    let script = r#"COMMENT "Start"
+RETRY 2
+    TCUTEST 3, 0, 16, 1, "Out of range"
+ENDRETRY
+TCUOPEN 1
+"#;
+
+    // Only transactions are included, and blocks are evaluated once.
+    let expected = r#"[
+  {"device": "TCU", "command": "TCUTest", "bytes": "4D30330D", "test": {"expected": "0..=16", "retries": 1, "message": "Out of range"}, "span": {"start": 28, "end": 63}},
+  {"device": "TCU", "command": "TCUOpen", "bytes": "4F30310D", "test": null, "span": {"start": 73, "end": 82}}
+]
+"#;
+    assert_eq!(plan(script), expected);
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_plan_empty() {
+    assert_eq!(plan(r#"COMMENT "Nothing to do""#), "[\n]\n");
+}
+
+////////////////////////////////////////////////////////////////
@@ -0,0 +1,74 @@
+use std::path::{Path, PathBuf};
+
+use gallivant::{Error, ErrorReason, FrontendRequest, Interpreter};
+
+////////////////////////////////////////////////////////////////
+
+/// Create a directory containing a script and an image of the given size.
+///
+fn setup(name: &str, size: usize) -> PathBuf {
+    let directory =
+        std::env::temp_dir().join(format!("gallivant-image-{name}-{}", std::process::id()));
+    std::fs::create_dir_all(&directory).unwrap();
+
+    let image: Vec<u8> = (0..size).map(|i| i as u8).collect();
+    std::fs::write(directory.join("logo.bin"), image).unwrap();
+
+    directory
+}
+
+////////////////////////////////////////////////////////////////
+
+fn run(directory: &Path, script: &str) -> Result<FrontendRequest, Error> {
+    Interpreter::try_from_str(&format!("USBOPEN\n{script}"))
+        .unwrap()
+        .with_script_path(directory.join("script.txt"))
+        .nth(1)
+        .unwrap()
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_print_image() {
+    let directory = setup("ok", 300);
+    let result = run(&directory, r#"PRINTIMAGE "logo.bin""#);
+    std::fs::remove_dir_all(&directory).unwrap();
+
+    let Ok(FrontendRequest::PrinterTransact(transaction)) = result else {
+        panic!("Expected a printer transaction. Got: {result:?}");
+    };
+
+    let expected: Vec<u8> = (0..300).map(|i| i as u8).collect();
+    assert_eq!(transaction.bytes(), expected);
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_print_image_missing() {
+    let directory = setup("missing", 1);
+    let result = run(&directory, r#"PRINTIMAGE "missing.bin""#);
+    std::fs::remove_dir_all(&directory).unwrap();
+
+    assert!(matches!(
+        result.as_ref().map_err(Error::reason),
+        Err(ErrorReason::AssetError { path, .. }) if path == &directory.join("missing.bin")
+    ));
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_print_image_too_large() {
+    let directory = setup("large", 256 * 1024 + 1);
+    let result = run(&directory, r#"PRINTIMAGE "logo.bin""#);
+    std::fs::remove_dir_all(&directory).unwrap();
+
+    assert!(matches!(
+        result.as_ref().map_err(Error::reason),
+        Err(ErrorReason::AssetTooLarge { size: 262145, .. })
+    ));
+}
+
+////////////////////////////////////////////////////////////////
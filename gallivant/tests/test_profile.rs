@@ -0,0 +1,62 @@
+use gallivant::{Device, ExprKind, Interpreter, Profile, Severity};
+
+////////////////////////////////////////////////////////////////
+
+fn profile() -> Profile {
+    Profile::builder()
+        .commands([
+            ExprKind::PrinterSet,
+            ExprKind::SetOption,
+            ExprKind::TCUTest,
+            ExprKind::RetryBlock,
+        ])
+        .option(3, 0..=1)
+        .channels(Device::TCU, 4)
+        .build()
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_compatible_script() {
+    let script = r#"
+; Comments are always supported.
+PRINTERSET 1
+SETOPTION 3, 1
+RETRY 2
+    TCUTEST 3, 0, 10, 0, "Out of range"
+ENDRETRY
+    "#;
+
+    let interpreter = Interpreter::try_from_str(script).unwrap();
+    assert!(interpreter.validate(&profile()).is_empty());
+    assert!(interpreter.validate(&Profile::default()).is_empty());
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_incompatible_script() {
+    let script = r#"PRINTERSET 1
+USBOPEN
+SETOPTION 4, 0
+SETOPTION 3, 2
+RETRY 2
+    TCUTEST 4, 0, 10, 0, "Out of range"
+ENDRETRY"#;
+
+    let interpreter = Interpreter::try_from_str(script).unwrap();
+    let diagnostics = interpreter.validate(&profile());
+
+    let found: Vec<&str> = diagnostics
+        .iter()
+        .map(|diagnostic| {
+            assert_eq!(diagnostic.severity(), Severity::Error);
+            &script[diagnostic.span().clone()]
+        })
+        .collect();
+
+    assert_eq!(found, ["USBOPEN", "4", "2", "4"]);
+}
+
+////////////////////////////////////////////////////////////////
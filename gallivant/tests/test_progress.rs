@@ -0,0 +1,76 @@
+use gallivant::{FrontendRequest, Interpreter};
+
+type Request = FrontendRequest;
+
+mod common;
+use common::print;
+
+////////////////////////////////////////////////////////////////
+
+fn progress(current: usize, total: usize) -> Request {
+    Request::Progress { current, total }
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_progress() {
+    let script = r#"COMMENT "a"
+REPEAT 2
+    COMMENT "b"
+ENDREPEAT
+COMMENT "c""#;
+
+    let requests: Vec<Request> = Interpreter::try_from_str(script)
+        .unwrap()
+        .with_progress(true)
+        .map(Result::unwrap)
+        .collect();
+
+    // A block is completed once it's finished running.
+    assert_eq!(
+        requests,
+        [
+            print("a"),
+            progress(1, 3),
+            print("b"),
+            print("b"),
+            progress(2, 3),
+            print("c"),
+            progress(3, 3),
+        ]
+    );
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_progress_restart() {
+    let mut interpreter = Interpreter::try_from_str("COMMENT \"a\"\nCOMMENT \"b\"")
+        .unwrap()
+        .with_progress(true);
+
+    assert_eq!(interpreter.next().unwrap().unwrap(), print("a"));
+    assert_eq!(interpreter.next().unwrap().unwrap(), progress(1, 2));
+
+    interpreter.restart();
+    let requests: Vec<Request> = interpreter.map(Result::unwrap).collect();
+    assert_eq!(
+        requests,
+        [print("a"), progress(1, 2), print("b"), progress(2, 2)]
+    );
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_no_progress() {
+    let requests: Vec<Request> = Interpreter::try_from_str("COMMENT \"a\"")
+        .unwrap()
+        .map(Result::unwrap)
+        .collect();
+
+    assert_eq!(requests, [print("a")]);
+}
+
+////////////////////////////////////////////////////////////////
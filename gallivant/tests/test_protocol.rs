@@ -0,0 +1,96 @@
+use gallivant::{Device, FrontendRequest, Interpreter};
+
+type Request = FrontendRequest;
+
+////////////////////////////////////////////////////////////////
+
+fn dump(device: Device, dump: &str) -> Request {
+    Request::Protocol {
+        device,
+        dump: dump.to_owned(),
+    }
+}
+
+fn requests(script: &str) -> Vec<Request> {
+    Interpreter::try_from_str(script)
+        .unwrap()
+        .map(|request| request.unwrap())
+        .collect()
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_toggled() {
+    let requests = requests(
+        r#"TCUCLOSE 3
+PROTOCOL
+TCUCLOSE 3
+PROTOCOL
+TCUCLOSE 3"#,
+    );
+
+    let kinds: Vec<&str> = requests
+        .iter()
+        .map(|request| match request {
+            Request::TCUTransact(_) => "transact",
+            Request::Protocol { .. } => "protocol",
+            _ => "other",
+        })
+        .collect();
+    assert_eq!(
+        kinds,
+        ["transact", "other", "protocol", "transact", "other", "transact"]
+    );
+
+    // Reported before the transaction it's of.
+    assert_eq!(requests[2], dump(Device::TCU, "43 30 33 0D"));
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_usb() {
+    let requests = requests(
+        r#"PROTOCOL
+USBOPEN
+USBPRINT "A", $1B"#,
+    );
+
+    let [Request::None, Request::PrinterOpen, dumped, Request::PrinterTransact(transaction)] =
+        &requests[..]
+    else {
+        panic!("Unexpected requests: {requests:?}");
+    };
+
+    assert_eq!(*dumped, dump(Device::USB, "41 1B"));
+    assert_eq!(transaction.bytes(), b"A\x1B");
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_reset_on_restart() {
+    let mut interpreter = Interpreter::try_from_str("PROTOCOL\nTCUCLOSE 3").unwrap();
+
+    assert_eq!(interpreter.next().unwrap().unwrap(), Request::None);
+    assert!(matches!(
+        interpreter.next().unwrap().unwrap(),
+        Request::Protocol { .. }
+    ));
+
+    // The held back transaction is abandoned along with the mode.
+    interpreter.restart();
+    interpreter.next();
+    assert!(matches!(
+        interpreter.next().unwrap().unwrap(),
+        Request::Protocol { .. }
+    ));
+    assert!(matches!(
+        interpreter.next().unwrap().unwrap(),
+        Request::TCUTransact(_)
+    ));
+    assert!(interpreter.next().is_none());
+}
+
+////////////////////////////////////////////////////////////////
@@ -0,0 +1,75 @@
+use gallivant::{ErrorReason, FrontendRequest};
+
+type Request = FrontendRequest;
+
+mod common;
+use common::run_until_request;
+
+////////////////////////////////////////////////////////////////
+
+const SCRIPT: &str = r#"
+TCUMEASURE "ch1", 1
+TCUMEASURE "ch2", 2
+RATIOTEST "ch2", "ch1", 950, 1050, "Channels unbalanced"
+"#;
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_ratio_in_range() {
+    assert!(matches!(
+        run_until_request(SCRIPT, [2000, 1900]),
+        Ok(Request::Ratio { ratio: 950, .. })
+    ));
+
+    assert!(matches!(
+        run_until_request(SCRIPT, [2000, 2100]),
+        Ok(Request::Ratio { ratio: 1050, .. })
+    ));
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_ratio_out_of_range() {
+    let error = run_until_request(SCRIPT, [2000, 2200]).unwrap_err();
+    let ErrorReason::TestFailure { test, .. } = error.reason() else {
+        panic!("Expected a test failure. Got: {:?}", error.reason());
+    };
+    assert_eq!(test.measurement, 1100);
+
+    let error = run_until_request(SCRIPT, [2000, 1898]).unwrap_err();
+    let ErrorReason::TestFailure { test, .. } = error.reason() else {
+        panic!("Expected a test failure. Got: {:?}", error.reason());
+    };
+    assert_eq!(test.measurement, 949);
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_ratio_zero_denominator() {
+    let error = run_until_request(SCRIPT, [0, 1000]).unwrap_err();
+    assert!(matches!(
+        error.reason(),
+        ErrorReason::ZeroDenominator { name, .. } if name == "ch1"
+    ));
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_ratio_unknown_measurement() {
+    let script = r#"
+TCUMEASURE "ch1", 1
+RATIOTEST "ch3", "ch1", 950, 1050, "Channels unbalanced"
+"#;
+
+    let error = run_until_request(script, [1000]).unwrap_err();
+    assert!(matches!(
+        error.reason(),
+        ErrorReason::UnknownMeasurement { name, .. } if name == "ch3"
+    ));
+}
+
+////////////////////////////////////////////////////////////////
@@ -0,0 +1,87 @@
+use gallivant::{FrontendRequest, Interpreter, Recording, TransactionStatus};
+
+type Request = FrontendRequest;
+
+mod common;
+use common::mocks::PortMock;
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_record_mode_out_of_range() {
+    let script = r#"TCUTEST 3, 0, 0, 0, "FAIL""#;
+    let requests: Vec<Request> = Interpreter::try_from_str(script)
+        .unwrap()
+        .with_record_mode(true)
+        .map(|r| r.unwrap())
+        .collect();
+
+    assert!(matches!(requests[..], [Request::TCUTransact(_)]));
+
+    if let Request::TCUTransact(mut transaction) = requests[0].clone() {
+        let mut port = PortMock::new();
+
+        if let Ok(TransactionStatus::Ongoing(tr)) = transaction.process(&mut port) {
+            transaction = tr;
+        } else {
+            panic!()
+        }
+
+        // Echo and a measurement outside of the placeholder range.
+        port.rxdata.extend(&port.txdata);
+        port.rxdata.extend(b"AA1\r");
+
+        let mut recording = Recording::new();
+        loop {
+            transaction = match transaction.process(&mut port).unwrap() {
+                TransactionStatus::Ongoing(tr) => tr,
+                TransactionStatus::Recorded { span, measurement } => {
+                    recording.add(span, measurement);
+                    break;
+                }
+                TransactionStatus::Success { .. } => {
+                    panic!("Expected the measurement to be recorded")
+                }
+            }
+        }
+
+        assert_eq!(recording.tests().len(), 1);
+        assert_eq!(recording.tests()[0].samples(), [0xAA1]);
+    }
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_record_mode_survives_restart() {
+    let script = r#"TCUTEST 3, 0, 0, 0, "FAIL""#;
+    let mut interpreter = Interpreter::try_from_str(script)
+        .unwrap()
+        .with_record_mode(true);
+
+    interpreter.by_ref().for_each(drop);
+    interpreter.restart();
+
+    if let Some(Ok(Request::TCUTransact(mut transaction))) = interpreter.next() {
+        let mut port = PortMock::new();
+        transaction = match transaction.process(&mut port) {
+            Ok(TransactionStatus::Ongoing(tr)) => tr,
+            _ => panic!(),
+        };
+
+        port.rxdata.extend(&port.txdata);
+        port.rxdata.extend(b"0010\r");
+
+        assert!(matches!(
+            transaction.process(&mut port),
+            Ok(TransactionStatus::Recorded {
+                measurement: 0x10,
+                ..
+            })
+        ));
+    } else {
+        panic!()
+    }
+}
+
+////////////////////////////////////////////////////////////////
@@ -0,0 +1,97 @@
+use gallivant::{FrontendRequest, Interpreter};
+
+type Request = FrontendRequest;
+
+mod common;
+use common::print;
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_repeat_block() {
+    let script = r#"
+REPEAT 20
+    TCUTEST 3, 0, 16, 0, "FAIL"
+ENDREPEAT
+COMMENT "done"
+"#;
+
+    let evaluation = Interpreter::try_from_str(script).unwrap().evaluate();
+    assert!(evaluation.diagnostics.is_empty());
+
+    let (tests, rest) = evaluation.requests.split_at(20);
+    for test in tests {
+        let Request::TCUTransact(test) = test else {
+            panic!("Expected a TCUTEST. Got: {test:?}");
+        };
+        assert_eq!(test.bytes(), b"M03\r");
+    }
+    assert_eq!(rest, [print("done")]);
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_nested_repeat_blocks() {
+    let script = r#"
+REPEAT 2
+    COMMENT "outer"
+    REPEAT 3
+        COMMENT "inner"
+    ENDREPEAT
+ENDREPEAT
+"#;
+
+    let requests: Vec<Request> = Interpreter::try_from_str(script)
+        .unwrap()
+        .map(Result::unwrap)
+        .collect();
+
+    let iteration = [
+        print("outer"),
+        print("inner"),
+        print("inner"),
+        print("inner"),
+    ];
+    assert_eq!(requests, [iteration.clone(), iteration].concat());
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_break_ends_repeats() {
+    let script = r#"
+REPEAT 5
+    COMMENT "one"
+    BREAK
+ENDREPEAT
+COMMENT "done"
+"#;
+
+    let requests: Vec<Request> = Interpreter::try_from_str(script)
+        .unwrap()
+        .map(Result::unwrap)
+        .collect();
+
+    assert_eq!(requests, [print("one"), print("done")]);
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_repeat_never_run() {
+    let script = r#"
+REPEAT 0
+    COMMENT "skipped"
+ENDREPEAT
+COMMENT "done"
+"#;
+
+    let evaluation = Interpreter::try_from_str(script).unwrap().evaluate();
+    assert_eq!(evaluation.requests, [print("done")]);
+
+    assert_eq!(evaluation.diagnostics.len(), 1);
+    assert!(evaluation.diagnostics[0].message().contains("never run"));
+}
+
+////////////////////////////////////////////////////////////////
@@ -0,0 +1,160 @@
+use gallivant::{Device, FrontendRequest, Interpreter, Results, TransactionStatus};
+
+type Request = FrontendRequest;
+
+////////////////////////////////////////////////////////////////
+
+const SCRIPT: &str = r#"
+TCUMEASURE "trim", 1
+TCUTEST 3, 1000, 2000, 2, "Supply out of range"
+PRINTERTEST 4, >= 50, 0, "Head too cold"
+"#;
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_outcomes_streamed() {
+    let results = Results::new();
+    let outcomes = results.subscribe();
+
+    let interpreter = Interpreter::try_from_str(SCRIPT)
+        .unwrap()
+        .with_results(results.clone());
+
+    let mut measurements = [vec![1500], vec![999, 2001, 1999], vec![49]].into_iter();
+    let mut streamed = Vec::new();
+    for request in interpreter {
+        let transaction = match request.unwrap() {
+            Request::TCUTransact(transaction) | Request::PrinterTransact(transaction) => {
+                transaction
+            }
+            request => panic!("Expected a transaction. Got: {request:?}"),
+        };
+
+        let status = transaction.simulate(measurements.next().unwrap());
+
+        // Each outcome is available as soon as the transaction completes.
+        streamed.push(outcomes.try_iter().collect::<Vec<_>>());
+
+        match status {
+            Ok(status) => assert!(matches!(status, TransactionStatus::Success { .. })),
+            Err(_) => break,
+        }
+    }
+
+    // TCUMEASURE isn't a test so doesn't report.
+    assert!(streamed[0].is_empty());
+
+    // Retries aren't reported, only the final outcome.
+    assert_eq!(streamed[1].len(), 1);
+    let outcome = &streamed[1][0];
+    assert_eq!(outcome.device, Device::TCU);
+    assert_eq!(outcome.channel, 3);
+    assert_eq!(outcome.measured, 1999);
+    assert!(outcome.passed);
+    assert_eq!(outcome.message, "Supply out of range");
+
+    assert_eq!(streamed[2].len(), 1);
+    let outcome = &streamed[2][0];
+    assert_eq!(outcome.device, Device::TCU);
+    assert_eq!(outcome.channel, 4);
+    assert_eq!(outcome.measured, 49);
+    assert!(!outcome.passed);
+
+    // Every outcome is also collected.
+    assert_eq!(
+        results.outcomes(),
+        streamed.into_iter().flatten().collect::<Vec<_>>()
+    );
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_record_mode_not_reported() {
+    let results = Results::new();
+    let mut interpreter = Interpreter::try_from_str(SCRIPT)
+        .unwrap()
+        .with_record_mode(true)
+        .with_results(results.clone());
+
+    while let Some(Ok(Request::TCUTransact(transaction) | Request::PrinterTransact(transaction))) =
+        interpreter.next()
+    {
+        transaction.simulate([1500]).unwrap();
+    }
+
+    assert!(results.outcomes().is_empty());
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_outcomes_by_board() {
+    let script = r#"
+TCUTEST 1, 0, 100, 0, "Panel supply"
+BOARD "A1"
+    TCUTEST 2, 0, 100, 0, "Rail"
+    RETRY 2
+        PRINTERTEST 3, 0, 100, 0, "Head"
+    ENDRETRY
+ENDBOARD
+BOARD "A2"
+    TCUTEST 2, 0, 100, 0, "Rail"
+ENDBOARD
+"#;
+
+    let results = Results::new();
+    let interpreter = Interpreter::try_from_str(script)
+        .unwrap()
+        .with_results(results.clone());
+
+    for request in interpreter {
+        let (Request::TCUTransact(transaction) | Request::PrinterTransact(transaction)) =
+            request.unwrap()
+        else {
+            panic!("Expected a transaction");
+        };
+        transaction.simulate([50]).unwrap();
+    }
+
+    let boards: Vec<(Option<String>, Vec<u32>)> = results
+        .by_board()
+        .into_iter()
+        .map(|(board, outcomes)| {
+            let channels = outcomes.iter().map(|outcome| outcome.channel).collect();
+            (board, channels)
+        })
+        .collect();
+
+    assert_eq!(
+        boards,
+        [
+            (None, vec![1]),
+            (Some("A1".to_owned()), vec![2, 3]),
+            (Some("A2".to_owned()), vec![2]),
+        ]
+    );
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_nested_board() {
+    let script = r#"
+BOARD "A1"
+    BOARD "A2"
+    ENDBOARD
+ENDBOARD
+"#;
+
+    let Some(Err(error)) = Interpreter::try_from_str(script).unwrap().next() else {
+        panic!("Expected an error");
+    };
+    assert!(matches!(
+        error.reason(),
+        gallivant::ErrorReason::NestedBoard { outer, .. } if outer == "A1"
+    ));
+}
+
+////////////////////////////////////////////////////////////////
@@ -0,0 +1,187 @@
+use gallivant::{Error, FrontendRequest, Interpreter, Transaction, TransactionStatus};
+
+type Request = FrontendRequest;
+
+mod common;
+use common::mocks::PortMock;
+
+////////////////////////////////////////////////////////////////
+
+/// Run a transaction to completion against a port that echoes commands and responds to any
+/// measurement with the given measurement.
+///
+fn run_transaction(mut transaction: Transaction, measurement: &[u8]) -> Result<(), Error> {
+    let mut port = PortMock::new();
+
+    loop {
+        port.txdata.clear();
+        transaction = match transaction.process(&mut port)? {
+            TransactionStatus::Ongoing(transaction) => transaction,
+            _ => return Ok(()),
+        };
+
+        if !port.txdata.is_empty() {
+            port.rxdata.extend(&port.txdata);
+            if port.txdata.front() == Some(&b'M') {
+                port.rxdata.extend(measurement);
+            }
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////
+
+const SCRIPT: &str = r#"
+RETRY 2
+    PRINTERSET 1
+    TCUTEST 3, 0, 16, 0, "FAIL"
+ENDRETRY
+COMMENT "done"
+"#;
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_retry_block_recovers() {
+    let mut interpreter = Interpreter::try_from_str(SCRIPT).unwrap();
+
+    let Some(Ok(Request::TCUTransact(printerset))) = interpreter.next() else {
+        panic!()
+    };
+    run_transaction(printerset, b"").unwrap();
+
+    let Some(Ok(Request::TCUTransact(test))) = interpreter.next() else {
+        panic!()
+    };
+    let error = run_transaction(test, b"0020\r").unwrap_err();
+    interpreter.recover(error).unwrap();
+
+    // The whole block is re-run.
+    let Some(Ok(Request::TCUTransact(printerset))) = interpreter.next() else {
+        panic!()
+    };
+    assert_eq!(printerset.bytes(), b"P051B005301\r");
+    run_transaction(printerset, b"").unwrap();
+
+    let Some(Ok(Request::TCUTransact(test))) = interpreter.next() else {
+        panic!()
+    };
+    run_transaction(test, b"0010\r").unwrap();
+
+    assert_eq!(
+        interpreter.next().unwrap().unwrap(),
+        Request::GuiPrint("done".to_owned())
+    );
+    assert!(interpreter.next().is_none());
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_retry_block_attempts_exhausted() {
+    let mut interpreter = Interpreter::try_from_str(SCRIPT).unwrap();
+
+    for _ in 0..2 {
+        let Some(Ok(Request::TCUTransact(printerset))) = interpreter.next() else {
+            panic!()
+        };
+        run_transaction(printerset, b"").unwrap();
+
+        let Some(Ok(Request::TCUTransact(test))) = interpreter.next() else {
+            panic!()
+        };
+        let error = run_transaction(test, b"0020\r").unwrap_err();
+
+        if let Err(error) = interpreter.recover(error) {
+            assert!(matches!(
+                error.reason(),
+                gallivant::ErrorReason::TestFailure { .. }
+            ));
+            return;
+        }
+    }
+
+    panic!("Expected the block to run out of attempts");
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_recover_outside_block() {
+    let mut interpreter = Interpreter::try_from_str(r#"TCUTEST 3, 0, 16, 0, "FAIL""#).unwrap();
+
+    let Some(Ok(Request::TCUTransact(test))) = interpreter.next() else {
+        panic!()
+    };
+    let error = run_transaction(test, b"0020\r").unwrap_err();
+    assert!(interpreter.recover(error).is_err());
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_break() {
+    let script = r#"
+RETRY 2
+    COMMENT "one"
+    RETRY 2
+        COMMENT "two"
+        BREAK
+        COMMENT "skipped"
+    ENDRETRY
+    COMMENT "three"
+    BREAK
+    COMMENT "skipped"
+ENDRETRY
+COMMENT "done"
+"#;
+
+    let requests: Vec<Request> = Interpreter::try_from_str(script)
+        .unwrap()
+        .map(Result::unwrap)
+        .collect();
+
+    let print = |message: &str| Request::GuiPrint(message.to_owned());
+    assert_eq!(
+        requests,
+        [print("one"), print("two"), print("three"), print("done")]
+    );
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_break_abandons_retries() {
+    let script = r#"
+RETRY 3
+    BREAK
+ENDRETRY
+TCUTEST 3, 0, 16, 0, "FAIL"
+"#;
+    let mut interpreter = Interpreter::try_from_str(script).unwrap();
+
+    let Some(Ok(Request::TCUTransact(test))) = interpreter.next() else {
+        panic!()
+    };
+    let error = run_transaction(test, b"0020\r").unwrap_err();
+    assert!(interpreter.recover(error).is_err());
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_break_outside_block() {
+    let mut interpreter = Interpreter::try_from_str("COMMENT \"one\"\nBREAK").unwrap();
+
+    assert!(matches!(interpreter.next(), Some(Ok(Request::GuiPrint(_)))));
+
+    let Some(Err(error)) = interpreter.next() else {
+        panic!("Expected an error");
+    };
+    assert!(matches!(
+        error.reason(),
+        gallivant::ErrorReason::BreakOutsideBlock { .. }
+    ));
+}
+
+////////////////////////////////////////////////////////////////
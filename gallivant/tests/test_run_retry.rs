@@ -0,0 +1,132 @@
+use std::io::{self, Read, Write};
+
+use gallivant::{Error, ErrorKind, FrontendRequest, Interpreter, RunRetry, TransactionStatus};
+
+////////////////////////////////////////////////////////////////
+
+/// Port that accepts every write and fails every read with the given error.
+///
+struct FailingPort(io::ErrorKind);
+
+impl Read for FailingPort {
+    fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+        Err(self.0.into())
+    }
+}
+
+impl Write for FailingPort {
+    fn write(&mut self, buffer: &[u8]) -> io::Result<usize> {
+        Ok(buffer.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+////////////////////////////////////////////////////////////////
+
+/// Run the script until a transaction fails, reading from a port that fails with the given error.
+///
+fn fail_run(interpreter: &mut Interpreter, error: io::ErrorKind) -> Error {
+    for request in interpreter.by_ref() {
+        if let FrontendRequest::TCUTransact(transaction) = request.unwrap() {
+            let TransactionStatus::Ongoing(transaction) =
+                transaction.process(&mut FailingPort(error)).unwrap()
+            else {
+                panic!("Expected the transaction to await a response");
+            };
+
+            return transaction.process(&mut FailingPort(error)).unwrap_err();
+        }
+    }
+
+    panic!("Expected a TCU transaction");
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_error_kind() {
+    let mut interpreter = Interpreter::try_from_str("TCUOPEN 1").unwrap();
+    assert_eq!(
+        fail_run(&mut interpreter.clone(), io::ErrorKind::TimedOut).kind(),
+        ErrorKind::Timeout
+    );
+    assert_eq!(
+        fail_run(&mut interpreter, io::ErrorKind::BrokenPipe).kind(),
+        ErrorKind::Comms
+    );
+
+    let Some(Err(error)) = Interpreter::try_from_str("USBPRINT \"test\"")
+        .unwrap()
+        .next()
+    else {
+        panic!("Expected an error");
+    };
+    assert_eq!(error.kind(), ErrorKind::Script);
+
+    assert_eq!("Test-Failure".parse(), Ok(ErrorKind::TestFailure));
+    assert!("flaky".parse::<ErrorKind>().is_err());
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_retry_transient() {
+    let script = "USBOPEN\nTCUOPEN 1";
+    let mut interpreter = Interpreter::try_from_str(script)
+        .unwrap()
+        .with_run_retry(RunRetry::new(1));
+
+    let error = fail_run(&mut interpreter, io::ErrorKind::TimedOut);
+    let cleanup = interpreter.retry_run(&error).unwrap();
+    assert!(matches!(cleanup[..], [FrontendRequest::PrinterClose]));
+    assert_eq!(interpreter.run_retries(), 1);
+
+    // The run restarts from the beginning.
+    assert!(matches!(
+        interpreter.next(),
+        Some(Ok(FrontendRequest::PrinterOpen))
+    ));
+
+    // Out of retries.
+    let error = fail_run(&mut interpreter, io::ErrorKind::TimedOut);
+    assert!(interpreter.retry_run(&error).is_none());
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_no_retry_on_test_failure() {
+    let script = r#"TCUTEST 3, 100, 200, 0, "Out of range""#;
+    let mut interpreter = Interpreter::try_from_str(script)
+        .unwrap()
+        .with_run_retry(RunRetry::new(3));
+
+    let Some(Ok(FrontendRequest::TCUTransact(transaction))) = interpreter.next() else {
+        panic!("Expected a TCU transaction");
+    };
+    let error = transaction.simulate([0]).unwrap_err();
+
+    assert_eq!(error.kind(), ErrorKind::TestFailure);
+    assert!(interpreter.retry_run(&error).is_none());
+    assert_eq!(interpreter.run_retries(), 0);
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_transient_kinds() {
+    let mut interpreter = Interpreter::try_from_str("TCUOPEN 1")
+        .unwrap()
+        .with_run_retry(RunRetry::new(1).with_transient([ErrorKind::Timeout]));
+
+    let error = fail_run(&mut interpreter.clone(), io::ErrorKind::BrokenPipe);
+    assert!(interpreter.retry_run(&error).is_none());
+
+    let error = fail_run(&mut interpreter, io::ErrorKind::TimedOut);
+    assert!(interpreter.retry_run(&error).is_some());
+}
+
+////////////////////////////////////////////////////////////////
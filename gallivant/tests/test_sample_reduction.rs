@@ -0,0 +1,91 @@
+use gallivant::{Error, ErrorReason, TransactionStatus};
+
+mod common;
+use common::simulate;
+
+////////////////////////////////////////////////////////////////
+
+/// Return a script testing three measurements, reducing them as given.
+///
+fn script(reduction: &str) -> String {
+    format!(r#"TCUTEST 3, 1000, 2000, 0, "Supply out of range", {reduction} 3"#)
+}
+
+/// Return the measurement a failed test reported.
+///
+fn failed_measurement(result: Result<TransactionStatus, Error>) -> i64 {
+    let error = result.unwrap_err();
+    let ErrorReason::TestFailure { test, .. } = error.reason() else {
+        panic!("Expected a test failure. Got: {:?}", error.reason());
+    };
+
+    test.measurement
+}
+
+////////////////////////////////////////////////////////////////
+
+/// One measurement below the range, one within and one above.
+///
+const SAMPLES: [u32; 3] = [800, 1500, 2400];
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_average() {
+    let status = simulate(&script("AVERAGE"), &SAMPLES).unwrap();
+    assert!(matches!(status, TransactionStatus::Success { .. }));
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_min() {
+    assert_eq!(failed_measurement(simulate(&script("MIN"), &SAMPLES)), 800);
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_max() {
+    assert_eq!(failed_measurement(simulate(&script("MAX"), &SAMPLES)), 2400);
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_all() {
+    // The first measurement to fail is reported.
+    assert_eq!(failed_measurement(simulate(&script("ALL"), &SAMPLES)), 800);
+
+    let status = simulate(&script("ALL"), &[1100, 1500, 1900]).unwrap();
+    assert!(matches!(status, TransactionStatus::Success { .. }));
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_any() {
+    let status = simulate(&script("ANY"), &SAMPLES).unwrap();
+    assert!(matches!(status, TransactionStatus::Success { .. }));
+
+    // None pass so the first is reported.
+    assert_eq!(
+        failed_measurement(simulate(&script("ANY"), &[800, 2400, 2100])),
+        800
+    );
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_too_few_samples() {
+    for reduction in ["AVERAGE", "MIN", "MAX", "ALL", "ANY"] {
+        let status = simulate(&script(reduction), &SAMPLES[..2]).unwrap();
+        assert!(
+            matches!(status, TransactionStatus::Ongoing(_)),
+            "{reduction}"
+        );
+    }
+}
+
+////////////////////////////////////////////////////////////////
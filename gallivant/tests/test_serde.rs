@@ -0,0 +1,59 @@
+#![cfg(feature = "serde")]
+
+use gallivant::{Interpreter, ParsedExpr};
+
+////////////////////////////////////////////////////////////////
+
+/// Assert both ASTs are equal, including the spans and annotations that [`ParsedExpr`]'s
+/// `PartialEq` ignores.
+///
+fn assert_identical(lhs: &[ParsedExpr], rhs: &[ParsedExpr]) {
+    assert_eq!(lhs, rhs);
+    for (lhs, rhs) in lhs.iter().zip(rhs) {
+        assert_eq!(lhs.span(), rhs.span());
+        assert_eq!(lhs.annotations(), rhs.annotations());
+    }
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_round_trip() {
+    let script = r#"; Setup
+SET channel 4
+@step "Measure"
+@id "REQ-1"
+RETRY 3
+    TCUMEASURE "trim", $channel
+    TCUTEST $channel, 1000, 12000, 1, "FAIL"
+    TCUTEST 5, <= 300, 0, "FAIL"
+ENDRETRY
+SETOPTION 4, (trim + $10) * 2
+NORESPONSE 100 PRINT $1B, "@"
+WAITDIALOG "Unattended", 30
+INPUTDIALOG "Enter serial number", $serial
+PRINT "SN: ", $serial"#;
+    let interpreter = Interpreter::try_from_str(script).unwrap();
+
+    let json = serde_json::to_string(interpreter.ast()).unwrap();
+    let ast: Vec<ParsedExpr> = serde_json::from_str(&json).unwrap();
+    assert_identical(&ast, interpreter.ast());
+
+    // Behaves the same as the original.
+    assert_eq!(
+        Interpreter::from_ast(ast).fingerprint(),
+        interpreter.fingerprint()
+    );
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_span_format() {
+    let interpreter = Interpreter::try_from_str("WAIT 10").unwrap();
+    let json = serde_json::to_value(&interpreter.ast()[0]).unwrap();
+
+    assert_eq!(json["span"], serde_json::json!({"start": 0, "end": 7}));
+}
+
+////////////////////////////////////////////////////////////////
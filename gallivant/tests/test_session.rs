@@ -0,0 +1,113 @@
+use gallivant::{Device, FrontendRequest, Interpreter, Session};
+
+type Request = FrontendRequest;
+
+////////////////////////////////////////////////////////////////
+
+const SCRIPT: &str = r#"
+USBOPEN
+USBPRINT "test"
+USBCLOSE
+"#;
+
+////////////////////////////////////////////////////////////////
+
+/// Run the script as part of the session.
+///
+/// # Returns
+/// The number of times the printer was opened and closed.
+///
+fn run(script: &str, session: &Session) -> (usize, usize) {
+    let requests: Vec<Request> = Interpreter::try_from_str(script)
+        .unwrap()
+        .with_session(session.clone())
+        .map(Result::unwrap)
+        .collect();
+
+    let opens = requests
+        .iter()
+        .filter(|request| matches!(request, Request::PrinterOpen))
+        .count();
+    let closes = requests
+        .iter()
+        .filter(|request| matches!(request, Request::PrinterClose))
+        .count();
+
+    (opens, closes)
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_session_keeps_open() {
+    let session = Session::new();
+
+    // Only the first run opens the printer and none close it.
+    assert_eq!(run(SCRIPT, &session), (1, 0));
+    assert!(session.is_open(Device::USB));
+    assert_eq!(run(SCRIPT, &session), (0, 0));
+    assert_eq!(run(SCRIPT, &session), (0, 0));
+
+    // Until the session ends.
+    assert!(matches!(session.close_all()[..], [Request::PrinterClose]));
+    assert!(!session.is_open(Device::USB));
+    assert_eq!(run(SCRIPT, &session), (1, 0));
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_session_reopen_within_run() {
+    let session = Session::new();
+    let script = format!("{SCRIPT}{SCRIPT}");
+
+    // Reopening within a run is skipped too.
+    assert_eq!(run(&script, &session), (1, 0));
+
+    // The script still can't write to the printer once it's closed it.
+    let results: Vec<_> = Interpreter::try_from_str("USBOPEN\nUSBCLOSE\nUSBPRINT \"test\"")
+        .unwrap()
+        .with_session(session.clone())
+        .collect();
+    assert!(results[1].is_ok());
+    assert!(results[2].is_err());
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_session_close_all() {
+    let session = Session::new();
+    let mut interpreter = Interpreter::try_from_str("USBOPEN\nUSBPRINT \"test\"")
+        .unwrap()
+        .with_session(session.clone());
+    interpreter.by_ref().for_each(drop);
+
+    // Devices held by the session are left open when a run ends early.
+    assert!(interpreter.close_all().is_empty());
+    assert_eq!(session.close_all().len(), 1);
+
+    // Without a session they're closed.
+    let mut interpreter = Interpreter::try_from_str("USBOPEN\nUSBPRINT \"test\"").unwrap();
+    interpreter.by_ref().for_each(drop);
+    assert!(matches!(
+        interpreter.close_all()[..],
+        [Request::PrinterClose]
+    ));
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_session_evaluate() {
+    // Evaluating the script doesn't open anything within the session.
+    let session = Session::new();
+    Interpreter::try_from_str(SCRIPT)
+        .unwrap()
+        .with_session(session.clone())
+        .evaluate();
+
+    assert!(!session.is_open(Device::USB));
+}
+
+////////////////////////////////////////////////////////////////
@@ -0,0 +1,75 @@
+use gallivant::{ErrorReason, FrontendRequest, Transaction, TransactionStatus};
+
+type Request = FrontendRequest;
+
+mod common;
+use common::interpret_script;
+
+////////////////////////////////////////////////////////////////
+
+fn transaction(script: &str) -> Transaction {
+    let requests = interpret_script(script);
+    let request = requests
+        .into_iter()
+        .find(|request| !matches!(request, Request::PrinterOpen))
+        .unwrap();
+
+    match request {
+        Request::TCUTransact(transaction) => transaction,
+        Request::PrinterTransact(transaction) => transaction,
+        request => panic!("Expected a transaction. Got: {request:?}"),
+    }
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_simulate_pass_and_fail() {
+    let transaction = transaction(r#"TCUTEST 3, 100, 200, 0, "Out of range""#);
+
+    let test = transaction.test().unwrap();
+    assert!(test.passes(100) && test.passes(200));
+    assert!(!test.passes(99) && !test.passes(201));
+
+    assert!(matches!(
+        transaction.clone().simulate([150]),
+        Ok(TransactionStatus::Success { .. })
+    ));
+
+    let error = transaction.simulate([201]).unwrap_err();
+    assert!(matches!(error.reason(), ErrorReason::TestFailure { .. }));
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_simulate_retries() {
+    let transaction = transaction(r#"PRINTERTEST 3, 100, 200, 2, "Out of range""#);
+
+    assert!(matches!(
+        transaction.clone().simulate([0, 0, 150]),
+        Ok(TransactionStatus::Success { .. })
+    ));
+    assert!(transaction.clone().simulate([0, 0, 0]).is_err());
+
+    // Measurements ran out before the test completed.
+    assert!(matches!(
+        transaction.simulate([0]),
+        Ok(TransactionStatus::Ongoing(_))
+    ));
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_simulate_usb_printer() {
+    let transaction = transaction("USBOPEN\nUSBPRINTERTEST 3, <= 10, 0, \"Too high\"");
+
+    assert!(matches!(
+        transaction.clone().simulate([10]),
+        Ok(TransactionStatus::Success { .. })
+    ));
+    assert!(transaction.simulate([11]).is_err());
+}
+
+////////////////////////////////////////////////////////////////
@@ -0,0 +1,55 @@
+use gallivant::{ErrorReason, Expected, TransactionStatus};
+
+mod common;
+use common::simulate;
+
+////////////////////////////////////////////////////////////////
+
+const SCRIPT: &str = r#"TCUTEST 3, STABLE 5, 20, 0, "Reading unstable""#;
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_stable() {
+    let status = simulate(SCRIPT, &[100, 112, 95, 115, 104]).unwrap();
+    assert!(matches!(status, TransactionStatus::Success { .. }));
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_too_few_samples() {
+    let status = simulate(SCRIPT, &[100, 112, 95, 115]).unwrap();
+    assert!(matches!(status, TransactionStatus::Ongoing(_)));
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_noisy() {
+    let error = simulate(SCRIPT, &[100, 112, 95, 130, 104]).unwrap_err();
+    let ErrorReason::TestFailure { test, .. } = error.reason() else {
+        panic!("Expected a test failure. Got: {:?}", error.reason());
+    };
+
+    assert_eq!(test.measurement, 35);
+    assert_eq!(
+        test.expected,
+        Expected::Stable {
+            samples: 5,
+            spread: 20
+        }
+    );
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_noisy_retry() {
+    // The retry takes a fresh set of samples.
+    let script = r#"TCUTEST 3, STABLE 3, 20, 1, "Reading unstable""#;
+    let status = simulate(script, &[100, 150, 100, 100, 110, 105]).unwrap();
+    assert!(matches!(status, TransactionStatus::Success { .. }));
+}
+
+////////////////////////////////////////////////////////////////
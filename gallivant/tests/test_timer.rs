@@ -0,0 +1,104 @@
+use std::time::Duration;
+
+use chrono::{NaiveDate, NaiveDateTime};
+
+use gallivant::{Clock, Error, ErrorReason, FrontendRequest, Interpreter};
+
+type Request = FrontendRequest;
+
+////////////////////////////////////////////////////////////////
+
+fn time(millis: u32) -> NaiveDateTime {
+    NaiveDate::from_ymd_opt(2023, 6, 1)
+        .unwrap()
+        .and_hms_milli_opt(12, 0, 0, millis)
+        .unwrap()
+}
+
+////////////////////////////////////////////////////////////////
+
+/// Run a script, advancing the clock by the given number of milliseconds before each statement.
+///
+fn run(
+    script: &str,
+    record: bool,
+    steps: impl IntoIterator<Item = u32>,
+) -> Vec<Result<Request, Error>> {
+    let mut interpreter = Interpreter::try_from_str(script)
+        .unwrap()
+        .with_record_mode(record);
+
+    let mut now = 0;
+    steps
+        .into_iter()
+        .map_while(|step| {
+            now += step;
+            interpreter.set_clock(Clock::Fixed(time(now)));
+            interpreter.next()
+        })
+        .collect()
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_timer_within_bounds() {
+    let script = r#"
+STARTTIMER "feed"
+STOPTIMER "feed", 100, 500, "Feed too slow"
+    "#;
+
+    let results = run(script, false, [0, 250]);
+    assert!(matches!(results[0], Ok(Request::None)));
+
+    let Ok(Request::Timed { name, elapsed, .. }) = &results[1] else {
+        panic!("Expected the timer to pass. Got: {:?}", results[1]);
+    };
+    assert_eq!(name, "feed");
+    assert_eq!(*elapsed, Duration::from_millis(250));
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_timer_out_of_bounds() {
+    let script = r#"
+STARTTIMER "feed"
+STOPTIMER "feed", < 500, "Feed too slow"
+    "#;
+
+    let results = run(script, false, [0, 600]);
+    let Err(error) = &results[1] else {
+        panic!("Expected the timer to fail. Got: {:?}", results[1]);
+    };
+
+    let ErrorReason::TestFailure { test, .. } = error.reason() else {
+        panic!("Expected a test failure. Got: {:?}", error.reason());
+    };
+    assert_eq!(test.measurement, 600);
+
+    // The failure isn't tested in record mode.
+    let results = run(script, true, [0, 600]);
+    assert!(matches!(
+        results[1],
+        Ok(Request::Timed { recorded: true, .. })
+    ));
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_timer_not_started() {
+    let script = r#"
+STARTTIMER "feed"
+STOPTIMER "eject", 0, 500, "Eject too slow"
+    "#;
+
+    let results = run(script, false, [0, 100]);
+    assert!(matches!(
+        results[1].as_ref().map_err(Error::reason),
+        Err(ErrorReason::TimerNotStarted { name, .. }) if name == "eject"
+    ));
+}
+
+////////////////////////////////////////////////////////////////
@@ -0,0 +1,830 @@
+use std::{
+    io::{self, Read, Write},
+    thread,
+    time::Duration,
+};
+
+use gallivant::{
+    Checksum, Comparison, Device, Echo, ErrorKind, ErrorReason, Expected, FrontendRequest,
+    Interpreter, LineEnding, Measurement, MeasurementError, MeasurementFormat, MeasurementParser,
+    Routing, Transaction, TransactionPhase, TransactionStatus, UnitSuffix,
+};
+
+type Request = FrontendRequest;
+
+mod common;
+use common::{interpret_script, mocks::PortMock};
+
+////////////////////////////////////////////////////////////////
+
+fn tcu_transaction(script: &str) -> Transaction {
+    match interpret_script(script).remove(0) {
+        Request::TCUTransact(transaction) => transaction,
+        request => panic!("Expected a TCU transaction. Got: {request:?}"),
+    }
+}
+
+////////////////////////////////////////////////////////////////
+
+fn ongoing(status: TransactionStatus) -> Transaction {
+    match status {
+        TransactionStatus::Ongoing(transaction) => transaction,
+        status => panic!("Expected an ongoing transaction. Got: {status:?}"),
+    }
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_phases() {
+    let script = r#"TCUTEST 3, 0, 16, 1, "FAIL""#;
+    let mut transaction = tcu_transaction(script);
+    let mut port = PortMock::new();
+
+    assert_eq!(transaction.span(), &(0..script.len()));
+    assert_eq!(transaction.phase(), TransactionPhase::Writing);
+
+    transaction = ongoing(transaction.process(&mut port).unwrap());
+    assert_eq!(transaction.phase(), TransactionPhase::AwaitingEcho);
+
+    port.rxdata.extend(&port.txdata);
+    transaction = ongoing(transaction.process(&mut port).unwrap());
+    assert_eq!(transaction.phase(), TransactionPhase::AwaitingMeasurement);
+
+    // Out of range measurement.
+    port.rxdata.extend(b"0020\r");
+    transaction = ongoing(transaction.process(&mut port).unwrap());
+    assert_eq!(transaction.phase(), TransactionPhase::Retrying);
+
+    port.txdata.clear();
+    transaction = ongoing(transaction.process(&mut port).unwrap());
+    assert_eq!(transaction.phase(), TransactionPhase::AwaitingEcho);
+
+    port.rxdata.extend(&port.txdata);
+    port.rxdata.extend(b"0010\r");
+    assert!(matches!(
+        transaction.process(&mut port).unwrap(),
+        TransactionStatus::Success { .. }
+    ));
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_success_measurement() {
+    let transaction = tcu_transaction(r#"TCUTEST 3, 0, 16, 0, "FAIL""#);
+    assert_eq!(
+        transaction.simulate([12]).unwrap(),
+        TransactionStatus::Success {
+            measurement: Some(Measurement::from(12u32))
+        }
+    );
+
+    // Nothing measured.
+    let transaction = tcu_transaction("TCUCLOSE 3");
+    assert_eq!(
+        transaction.simulate([]).unwrap(),
+        TransactionStatus::Success { measurement: None }
+    );
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_phase_waiting() {
+    let mut transaction = tcu_transaction(r#"NORESPONSE 1000 PRINT "reset""#);
+    let mut port = PortMock::new();
+
+    transaction = ongoing(transaction.process(&mut port).unwrap());
+    assert_eq!(transaction.phase(), TransactionPhase::Waiting);
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_unrouted_device() {
+    let routing = Routing::builder().route(Device::TCU, "COM1").build();
+    let script = "PRINTERSET 1\nUSBOPEN";
+
+    let mut interpreter = Interpreter::try_from_str(script)
+        .unwrap()
+        .with_routing(routing);
+
+    assert!(matches!(
+        interpreter.next(),
+        Some(Ok(Request::TCUTransact(_)))
+    ));
+
+    let error = interpreter.next().unwrap().unwrap_err();
+    assert!(matches!(
+        error.reason(),
+        ErrorReason::Unrouted {
+            device: Device::USB,
+            ..
+        }
+    ));
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_comparison_failure() {
+    let mut transaction = tcu_transaction(r#"TCUTEST 3, >= 3000, 0, "Too low""#);
+    let mut port = PortMock::new();
+
+    transaction = ongoing(transaction.process(&mut port).unwrap());
+    port.rxdata.extend(&port.txdata);
+    port.rxdata.extend(b"0BB7\r");
+
+    let error = transaction.process(&mut port).unwrap_err();
+    let ErrorReason::TestFailure { test, .. } = error.reason() else {
+        panic!("Expected a test failure. Got: {error:?}");
+    };
+
+    assert_eq!(test.measurement, 2999);
+    assert_eq!(
+        test.expected,
+        Expected::Comparison(Comparison::GreaterEqual, 3000)
+    );
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_signed_measurement() {
+    let script = r#"TCUTEST 3, < 10, 0, "Too high""#;
+    let mut port = PortMock::new();
+
+    let transaction = ongoing(tcu_transaction(script).process(&mut port).unwrap());
+    port.rxdata.extend(&port.txdata);
+    port.rxdata.extend(b"-00C8\r");
+    assert!(matches!(
+        transaction.process(&mut port).unwrap(),
+        TransactionStatus::Success { .. }
+    ));
+
+    let script = r#"TCUTEST 3, 0, 10, 0, "Out of range""#;
+    let mut port = PortMock::new();
+    let transaction = ongoing(tcu_transaction(script).process(&mut port).unwrap());
+    port.rxdata.extend(&port.txdata);
+    port.rxdata.extend(b"-0001\r");
+
+    let error = transaction.process(&mut port).unwrap_err();
+    let ErrorReason::TestFailure { test, .. } = error.reason() else {
+        panic!("Expected a test failure. Got: {error:?}");
+    };
+    assert_eq!(test.measurement, -1);
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_bcd_measurement() {
+    let transaction = tcu_transaction(r#"TCUTEST 3, 1234, 1234, 0, "FAIL""#).bcd_measurement(2);
+    let mut port = PortMock::new();
+
+    let transaction = ongoing(transaction.process(&mut port).unwrap());
+    port.rxdata.extend(&port.txdata);
+    port.rxdata.extend([0x12, 0x34, b'\r']);
+
+    assert!(matches!(
+        transaction.process(&mut port).unwrap(),
+        TransactionStatus::Success { .. }
+    ));
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_min_measurement_length() {
+    let transaction = tcu_transaction(r#"TCUTEST 3, 0, 16, 0, "FAIL""#).min_measurement_length(7);
+    let mut port = PortMock::new();
+
+    let mut transaction = ongoing(transaction.process(&mut port).unwrap());
+    port.rxdata.extend(&port.txdata);
+
+    // The status line's carriage return doesn't complete the measurement.
+    port.rxdata.extend(b"S\r");
+    transaction = ongoing(transaction.process(&mut port).unwrap());
+    assert_eq!(transaction.phase(), TransactionPhase::AwaitingMeasurement);
+
+    port.rxdata.extend(b"0010\r");
+    assert!(matches!(
+        transaction.process(&mut port).unwrap(),
+        TransactionStatus::Success { .. }
+    ));
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_measurement_lines() {
+    let transaction = tcu_transaction(r#"TCUTEST 3, 4660, 4660, 0, "FAIL""#).measurement_lines(2);
+    let mut port = PortMock::new();
+
+    let mut transaction = ongoing(transaction.process(&mut port).unwrap());
+    port.rxdata.extend(&port.txdata);
+
+    // High byte.
+    port.rxdata.extend(b"12\r");
+    transaction = ongoing(transaction.process(&mut port).unwrap());
+    assert_eq!(transaction.phase(), TransactionPhase::AwaitingMeasurement);
+
+    // Low byte.
+    port.rxdata.extend(b"34\r");
+    assert!(matches!(
+        transaction.process(&mut port).unwrap(),
+        TransactionStatus::Success { .. }
+    ));
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_measurement_lines_after_status() {
+    let transaction = tcu_transaction(r#"TCUTEST 3, 4660, 4660, 0, "FAIL""#)
+        .min_measurement_length(8)
+        .measurement_lines(2);
+    let mut port = PortMock::new();
+
+    let transaction = ongoing(transaction.process(&mut port).unwrap());
+    port.rxdata.extend(&port.txdata);
+    port.rxdata.extend(b"S\r12\r34\r");
+    assert!(matches!(
+        transaction.process(&mut port).unwrap(),
+        TransactionStatus::Success { .. }
+    ));
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_measurement_parser() {
+    // Measurement framed as "=<decimal>;".
+    let parser = MeasurementParser::new(|bytes| {
+        let text = std::str::from_utf8(bytes)?;
+        let text = text.trim_end_matches('\r');
+        let Some(value) = text
+            .strip_prefix('=')
+            .and_then(|text| text.strip_suffix(';'))
+        else {
+            return Err(MeasurementError::ParseError("Missing framing".into()));
+        };
+        Ok(Measurement::from(value.parse::<u32>()?))
+    });
+
+    let transaction = tcu_transaction(r#"TCUTEST 3, 1234, 1234, 0, "FAIL""#)
+        .measurement_lines(2)
+        .measurement_parser(parser);
+    let mut port = PortMock::new();
+
+    let transaction = ongoing(transaction.process(&mut port).unwrap());
+    port.rxdata.extend(&port.txdata);
+    port.rxdata.extend(b"=12\r34;\r");
+    assert!(matches!(
+        transaction.process(&mut port).unwrap(),
+        TransactionStatus::Success { .. }
+    ));
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_printer_echo() {
+    let script = "USBOPEN\nUSBPRINTERTEST 3, 0, 16, 0, \"FAIL\"";
+    let Request::PrinterTransact(transaction) = interpret_script(script).remove(1) else {
+        panic!("Expected a printer transaction");
+    };
+    let mut port = PortMock::new();
+
+    // Printer commands aren't terminated by a carriage return so the echo is the command's length.
+    let echo = Echo::FixedLength(transaction.bytes().len());
+    let transaction = transaction.expecting_echo(true).echo_format(echo);
+
+    let mut transaction = ongoing(transaction.process(&mut port).unwrap());
+    port.rxdata.extend(&port.txdata);
+    transaction = ongoing(transaction.process(&mut port).unwrap());
+    assert_eq!(transaction.phase(), TransactionPhase::AwaitingMeasurement);
+
+    port.rxdata.extend(b"0010\r");
+    assert!(matches!(
+        transaction.process(&mut port).unwrap(),
+        TransactionStatus::Success { .. }
+    ));
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_tcu_without_echo() {
+    let transaction = tcu_transaction(r#"TCUTEST 3, 0, 16, 0, "FAIL""#).expecting_echo(false);
+    let mut port = PortMock::new();
+
+    let transaction = ongoing(transaction.process(&mut port).unwrap());
+    port.rxdata.extend(b"0010\r");
+    assert!(matches!(
+        transaction.process(&mut port).unwrap(),
+        TransactionStatus::Success { .. }
+    ));
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_echo_delimited() {
+    let mut transaction = tcu_transaction(r#"TCUTEST 3, 0, 16, 0, "FAIL""#);
+    let mut port = PortMock::new();
+
+    transaction = ongoing(transaction.process(&mut port).unwrap());
+
+    // Without the carriage return the echo is incomplete.
+    port.rxdata.extend(b"M03");
+    transaction = ongoing(transaction.process(&mut port).unwrap());
+    assert_eq!(transaction.phase(), TransactionPhase::AwaitingEcho);
+
+    port.rxdata.extend(b"\r0010\r");
+    assert!(matches!(
+        transaction.process(&mut port).unwrap(),
+        TransactionStatus::Success { .. }
+    ));
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_echo_fixed_length() {
+    let mut transaction = Interpreter::try_from_str(r#"TCUTEST 3, 0, 16, 0, "FAIL""#)
+        .unwrap()
+        .with_echo_format(Echo::FixedLength(3))
+        .map(|request| match request.unwrap() {
+            Request::TCUTransact(transaction) => transaction,
+            request => panic!("Expected a TCU transaction. Got: {request:?}"),
+        })
+        .next()
+        .unwrap();
+    let mut port = PortMock::new();
+
+    transaction = ongoing(transaction.process(&mut port).unwrap());
+
+    port.rxdata.extend(b"M0");
+    transaction = ongoing(transaction.process(&mut port).unwrap());
+    assert_eq!(transaction.phase(), TransactionPhase::AwaitingEcho);
+
+    // Echo and measurement concatenated without a carriage return.
+    port.rxdata.extend(b"30010\r");
+    assert!(matches!(
+        transaction.process(&mut port).unwrap(),
+        TransactionStatus::Success { .. }
+    ));
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_measurement_byte_by_byte() {
+    let mut transaction = tcu_transaction(r#"TCUTEST 3, 0, 16, 1, "FAIL""#);
+    let mut port = PortMock::byte_by_byte();
+
+    transaction = ongoing(transaction.process(&mut port).unwrap());
+
+    // The leading digits of 0x20 would pass if parsed before the rest arrived.
+    port.rxdata.extend(b"M03\r0020\r");
+    for _ in 0..b"M03\r0020".len() {
+        transaction = ongoing(transaction.process(&mut port).unwrap());
+        assert_ne!(transaction.phase(), TransactionPhase::Retrying);
+    }
+
+    transaction = ongoing(transaction.process(&mut port).unwrap());
+    assert_eq!(transaction.phase(), TransactionPhase::Retrying);
+    assert!(port.rxdata.is_empty());
+
+    port.txdata.clear();
+    transaction = ongoing(transaction.process(&mut port).unwrap());
+    port.rxdata.extend(b"M03\r0010\r");
+
+    let mut status = transaction.process(&mut port).unwrap();
+    while let TransactionStatus::Ongoing(transaction) = status {
+        status = transaction.process(&mut port).unwrap();
+    }
+
+    assert!(matches!(status, TransactionStatus::Success { .. }));
+    assert!(port.rxdata.is_empty());
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_measurement_byte_by_byte_fixed_length_echo() {
+    let mut transaction = Interpreter::try_from_str(r#"TCUTEST 3, 0, 16, 0, "FAIL""#)
+        .unwrap()
+        .with_echo_format(Echo::FixedLength(3))
+        .map(|request| match request.unwrap() {
+            Request::TCUTransact(transaction) => transaction,
+            request => panic!("Expected a TCU transaction. Got: {request:?}"),
+        })
+        .next()
+        .unwrap();
+    let mut port = PortMock::byte_by_byte();
+
+    transaction = ongoing(transaction.process(&mut port).unwrap());
+
+    port.rxdata.extend(b"M030020\r");
+    let mut status = transaction.process(&mut port);
+    while let Ok(TransactionStatus::Ongoing(transaction)) = status {
+        status = transaction.process(&mut port);
+    }
+
+    // Only the complete measurement is tested, which fails.
+    let error = status.unwrap_err();
+    assert!(matches!(
+        error.reason(),
+        ErrorReason::TestFailure { test, .. } if test.measurement == 0x20
+    ));
+    assert!(port.rxdata.is_empty());
+}
+
+////////////////////////////////////////////////////////////////
+
+/// TCU that echoes each command but only takes a measurement once sent the trigger byte.
+///
+struct TriggeredTCU {
+    trigger: u8,
+    measurements: Vec<&'static [u8]>,
+    triggers: usize,
+    rxdata: Vec<u8>,
+}
+
+impl Read for TriggeredTCU {
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        let count = buffer.len().min(self.rxdata.len());
+        buffer[..count].copy_from_slice(&self.rxdata[..count]);
+        self.rxdata.drain(..count);
+        Ok(count)
+    }
+}
+
+impl Write for TriggeredTCU {
+    fn write(&mut self, buffer: &[u8]) -> io::Result<usize> {
+        if buffer == [self.trigger] {
+            self.rxdata.extend(self.measurements[self.triggers]);
+            self.triggers += 1;
+        } else {
+            self.rxdata.extend(buffer);
+        }
+        Ok(buffer.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_measurement_trigger() {
+    let mut transaction = Interpreter::try_from_str(r#"TCUTEST 3, 0, 16, 1, "FAIL""#)
+        .unwrap()
+        .with_measurement_trigger(0x05)
+        .map(|request| match request.unwrap() {
+            Request::TCUTransact(transaction) => transaction,
+            request => panic!("Expected a TCU transaction. Got: {request:?}"),
+        })
+        .next()
+        .unwrap();
+    let mut port = TriggeredTCU {
+        trigger: 0x05,
+        measurements: vec![b"0020\r", b"0010\r"],
+        triggers: 0,
+        rxdata: Vec::new(),
+    };
+
+    transaction = ongoing(transaction.process(&mut port).unwrap());
+    assert_eq!(transaction.phase(), TransactionPhase::AwaitingEcho);
+
+    transaction = ongoing(transaction.process(&mut port).unwrap());
+    assert_eq!(transaction.phase(), TransactionPhase::Triggering);
+
+    transaction = ongoing(transaction.process(&mut port).unwrap());
+    assert_eq!(transaction.phase(), TransactionPhase::AwaitingMeasurement);
+    assert_eq!(port.triggers, 1);
+
+    // Out of range, so the command is re-sent and triggered again.
+    let mut status = transaction.process(&mut port).unwrap();
+    while let TransactionStatus::Ongoing(transaction) = status {
+        status = transaction.process(&mut port).unwrap();
+    }
+
+    assert!(matches!(status, TransactionStatus::Success { .. }));
+    assert_eq!(port.triggers, 2);
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_measurement_trigger_simulated() {
+    let transaction = Interpreter::try_from_str(r#"TCUTEST 3, 0, 16, 1, "FAIL""#)
+        .unwrap()
+        .with_measurement_trigger(0x05)
+        .map(|request| match request.unwrap() {
+            Request::TCUTransact(transaction) => transaction,
+            request => panic!("Expected a TCU transaction. Got: {request:?}"),
+        })
+        .next()
+        .unwrap();
+
+    assert!(matches!(
+        transaction.clone().simulate([0x20, 0x10]).unwrap(),
+        TransactionStatus::Success { .. }
+    ));
+    assert!(transaction.simulate([0x20, 0x20]).is_err());
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_read_buffer_size() {
+    let script = r#"TCUTEST 3, 0, 16, 1, "FAIL""#;
+    let mut port = PortMock::new();
+
+    // A 1 KB response, padded ahead of the measurement.
+    let mut response = vec![b' '; 1024 - b"0010\r".len()];
+    response.extend(b"0010\r");
+
+    let transaction = ongoing(tcu_transaction(script).process(&mut port).unwrap());
+    let echo = port.txdata.clone();
+    port.rxdata.extend(&echo);
+    port.rxdata.extend(&response);
+
+    // The default buffer needs several reads to receive it.
+    let transaction = ongoing(transaction.process(&mut port).unwrap());
+    assert_eq!(transaction.phase(), TransactionPhase::AwaitingMeasurement);
+
+    let mut status = transaction.process(&mut port).unwrap();
+    while let TransactionStatus::Ongoing(transaction) = status {
+        status = transaction.process(&mut port).unwrap();
+    }
+    assert!(matches!(status, TransactionStatus::Success { .. }));
+
+    // A large enough buffer receives it in one read.
+    let mut port = PortMock::new();
+    let transaction = tcu_transaction(script).read_buffer_size(echo.len() + response.len());
+    let transaction = ongoing(transaction.process(&mut port).unwrap());
+    port.rxdata.extend(&echo);
+    port.rxdata.extend(&response);
+
+    assert!(matches!(
+        transaction.process(&mut port).unwrap(),
+        TransactionStatus::Success { .. }
+    ));
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_response_timeout() {
+    let script = r#"TCUTEST 3, 0, 16, 1, "FAIL""#;
+    let mut port = PortMock::new();
+    let timeout = Duration::from_millis(20);
+
+    let transaction = tcu_transaction(script).response_timeout(timeout);
+    assert_eq!(transaction.timeout(), Some(timeout));
+    let transaction = ongoing(transaction.process(&mut port).unwrap());
+
+    // Waits while within the timeout.
+    let transaction = ongoing(transaction.process(&mut port).unwrap());
+    assert_eq!(transaction.phase(), TransactionPhase::AwaitingEcho);
+
+    // The device never responds.
+    thread::sleep(timeout);
+    let error = transaction.process(&mut port).unwrap_err();
+    let ErrorReason::Timeout { elapsed, .. } = error.reason() else {
+        panic!("Expected a timeout. Got: {:?}", error.reason());
+    };
+    assert!(*elapsed >= timeout);
+    assert_eq!(error.kind(), ErrorKind::Timeout);
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_line_endings() {
+    let script = r#"TCUTEST 3, 0, 16, 1, "FAIL""#;
+
+    for (ending, terminator) in [
+        (LineEnding::Cr, &b"\r"[..]),
+        (LineEnding::CrLf, &b"\r\n"[..]),
+        (LineEnding::Lf, &b"\n"[..]),
+    ] {
+        let mut port = PortMock::new();
+        let transaction = tcu_transaction(script).line_ending(ending);
+        let transaction = ongoing(transaction.process(&mut port).unwrap());
+
+        let command: Vec<u8> = port
+            .txdata
+            .iter()
+            .copied()
+            .take_while(|&b| b != b'\r')
+            .collect();
+        port.rxdata.extend(&command);
+        port.rxdata.extend(terminator);
+        port.rxdata.extend(b"0010");
+        port.rxdata.extend(terminator);
+
+        assert!(
+            matches!(
+                transaction.process(&mut port).unwrap(),
+                TransactionStatus::Success { .. }
+            ),
+            "{ending:?}"
+        );
+    }
+
+    // Responses with a different line ending are never complete.
+    let mut port = PortMock::new();
+    let transaction = ongoing(tcu_transaction(script).process(&mut port).unwrap());
+    port.rxdata.extend(b"M03\n0010\n");
+
+    let transaction = ongoing(transaction.process(&mut port).unwrap());
+    assert_eq!(transaction.phase(), TransactionPhase::AwaitingEcho);
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_line_ending_lines() {
+    // A status line precedes the measurement, which is split over two lines.
+    let script = r#"TCUTEST 3, 0, 16, 1, "FAIL""#;
+    let mut port = PortMock::new();
+
+    let transaction = tcu_transaction(script)
+        .line_ending(LineEnding::CrLf)
+        .measurement_lines(2)
+        .min_measurement_length(10);
+    let transaction = ongoing(transaction.process(&mut port).unwrap());
+
+    port.rxdata.extend(b"M03\r\nOK\r\n00\r\n");
+    let transaction = ongoing(transaction.process(&mut port).unwrap());
+
+    port.rxdata.extend(b"10\r\n");
+    assert!(matches!(
+        transaction.process(&mut port).unwrap(),
+        TransactionStatus::Success { .. }
+    ));
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_measurement_format() {
+    let script = r#"TCUTEST 3, 250, 260, 0, "FAIL""#;
+    let respond = |transaction: Transaction, measurement: &[u8]| {
+        let mut port = PortMock::new();
+        let transaction = ongoing(transaction.process(&mut port).unwrap());
+        let echo = port.txdata.clone();
+        port.rxdata.extend(echo);
+        port.rxdata.extend(measurement);
+        transaction.process(&mut port)
+    };
+
+    // As decimal 255 is in range, but as hex it's 597.
+    let transaction = Interpreter::try_from_str(script)
+        .unwrap()
+        .with_measurement_format(MeasurementFormat::DecU32)
+        .find_map(|request| match request.unwrap() {
+            Request::TCUTransact(transaction) => Some(transaction),
+            _ => None,
+        })
+        .unwrap();
+    assert!(matches!(
+        respond(transaction, b"255\r").unwrap(),
+        TransactionStatus::Success { .. }
+    ));
+    assert!(respond(tcu_transaction(script), b"255\r").is_err());
+
+    // Signed decimal measurements are below every limit when negative.
+    let transaction = tcu_transaction(script).measurement_format(MeasurementFormat::DecI32);
+    let error = respond(transaction, b"-12\r").unwrap_err();
+    let ErrorReason::TestFailure { test, .. } = error.reason() else {
+        panic!("Expected a test failure. Got: {:?}", error.reason());
+    };
+    assert_eq!(test.measurement, -12);
+
+    // Unsigned decimal measurements can't be negative.
+    let transaction = tcu_transaction(script).measurement_format(MeasurementFormat::DecU32);
+    let error = respond(transaction, b"-12\r").unwrap_err();
+    assert!(matches!(
+        error.reason(),
+        ErrorReason::InvalidMeasurement {
+            format: "decimal",
+            ..
+        }
+    ));
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_measurement_unit() {
+    let script = r#"TCUTEST 3, 1000, 1300, 0, "FAIL""#;
+    let respond = |transaction: Transaction, measurement: &[u8]| {
+        let mut port = PortMock::new();
+        let transaction = ongoing(transaction.process(&mut port).unwrap());
+        let echo = port.txdata.clone();
+        port.rxdata.extend(echo);
+        port.rxdata.extend(measurement);
+        transaction.process(&mut port)
+    };
+    let transaction = |unit| {
+        Interpreter::try_from_str(script)
+            .unwrap()
+            .with_measurement_format(MeasurementFormat::DecU32)
+            .with_measurement_unit(unit)
+            .find_map(|request| match request.unwrap() {
+                Request::TCUTransact(transaction) => Some(transaction),
+                _ => None,
+            })
+            .unwrap()
+    };
+
+    assert_eq!(
+        respond(transaction(UnitSuffix::Any), b"1200mV\r").unwrap(),
+        TransactionStatus::Success {
+            measurement: Some(Measurement::from(1200u32))
+        }
+    );
+    assert!(respond(transaction(UnitSuffix::Unitless), b"1200mV\r").is_err());
+
+    // The wrong unit is an invalid measurement rather than a failed test.
+    let millivolts = UnitSuffix::Expected("mV".to_owned());
+    let error = respond(transaction(millivolts), b"55Hz\r").unwrap_err();
+    assert!(matches!(
+        error.reason(),
+        ErrorReason::InvalidMeasurement { .. }
+    ));
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_checksum() {
+    let payload = b"123456789";
+    assert_eq!(Checksum::Xor8.calculate(payload), [0x31]);
+    assert_eq!(Checksum::Sum8.calculate(payload), [0xDD]);
+    assert_eq!(Checksum::Crc16.calculate(payload), [0x29, 0xB1]);
+    assert_eq!(Checksum::Crc16.calculate(b""), [0xFF, 0xFF]);
+
+    // Inserted before the terminating carriage return.
+    let transaction = tcu_transaction("PRINT \"123456789\"");
+    let command = transaction.bytes().strip_suffix(b"\r").unwrap().to_owned();
+    assert_eq!(
+        transaction.checksum(Checksum::Xor8).bytes(),
+        [
+            command.clone(),
+            Checksum::Xor8.calculate(&command),
+            b"\r".to_vec()
+        ]
+        .concat()
+    );
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_printer_checksum() {
+    let printer_bytes = |checksum: Option<Checksum>| {
+        let interpreter = Interpreter::try_from_str("HPMODE\nUSBOPEN\nUSBSETTIMEFORMAT 6").unwrap();
+        let interpreter = match checksum {
+            Some(checksum) => interpreter.with_printer_checksum(checksum),
+            None => interpreter,
+        };
+
+        interpreter
+            .map(Result::unwrap)
+            .find_map(|request| match request {
+                Request::PrinterTransact(transaction) => Some(transaction.bytes().to_owned()),
+                _ => None,
+            })
+            .unwrap()
+    };
+
+    let payload = printer_bytes(None);
+    for checksum in [Checksum::Xor8, Checksum::Sum8, Checksum::Crc16] {
+        assert_eq!(
+            printer_bytes(Some(checksum)),
+            [payload.clone(), checksum.calculate(&payload)].concat()
+        );
+    }
+
+    // Only commands sent to the printer have a checksum.
+    let interpreter = Interpreter::try_from_str("TCUCLOSE 4")
+        .unwrap()
+        .with_printer_checksum(Checksum::Sum8);
+    let requests: Vec<Request> = interpreter.map(Result::unwrap).collect();
+    let Request::TCUTransact(transaction) = &requests[0] else {
+        panic!("Expected a TCU transaction. Got: {requests:?}");
+    };
+    assert_eq!(transaction.bytes(), b"C04\r");
+}
+
+////////////////////////////////////////////////////////////////
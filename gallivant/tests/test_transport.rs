@@ -0,0 +1,63 @@
+use gallivant::{Error, ErrorReason, ExprKind, FrontendRequest, Interpreter, Transport};
+
+////////////////////////////////////////////////////////////////
+
+fn run(script: &str, transport: Transport) -> Vec<Result<FrontendRequest, Error>> {
+    Interpreter::try_from_str(script)
+        .unwrap()
+        .with_transport(transport)
+        .collect()
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_serial_command_under_usb() {
+    let results = run("USBOPEN\nUSBPRINTERSET 1\nPRINTERSET 1", Transport::USB);
+
+    assert!(results[1].is_ok());
+    assert!(matches!(
+        results[2].as_ref().map_err(Error::reason),
+        Err(ErrorReason::Unsupported {
+            kind: ExprKind::PrinterSet,
+            transport: Transport::USB,
+            ..
+        })
+    ));
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_usb_command_under_serial() {
+    let results = run("PRINTERSET 1\nUSBOPEN", Transport::Serial);
+
+    assert!(results[0].is_ok());
+    assert!(matches!(
+        results[1].as_ref().map_err(Error::reason),
+        Err(ErrorReason::Unsupported {
+            kind: ExprKind::USBOpen,
+            transport: Transport::Serial,
+            ..
+        })
+    ));
+}
+
+////////////////////////////////////////////////////////////////
+
+#[test]
+fn test_unsupported_message() {
+    let error = Interpreter::try_from_str("TCUOPEN 1")
+        .unwrap()
+        .with_transport(Transport::USB)
+        .next()
+        .unwrap()
+        .unwrap_err();
+
+    assert_eq!(
+        error.reason().message(),
+        "Command: 'TCUOPEN' is unsupported by the USB transport"
+    );
+}
+
+////////////////////////////////////////////////////////////////